@@ -1,9 +1,14 @@
 use crate::encryption;
+use crate::engine::blocks::BlockIndex;
 use crate::engine::frontmatter::{self, Frontmatter};
+use crate::engine::links::{self, LinkChange};
 use crate::engine::settings::{self, Settings};
-use crate::engine::search::SearchResult;
+use crate::engine::search::{SearchIndex, SearchResult};
+use crate::features::properties::{self, PropertyIssue, PropertyRegistry};
+use crate::features::query;
 use crate::state::AppState;
-use chrono::Local;
+use chrono::{DateTime, Local};
+use notify::{RecursiveMode, Watcher};
 use tauri::State;
 
 // Use full paths for vault and markdown to avoid name collisions with command functions
@@ -28,12 +33,47 @@ pub fn read_note(state: State<AppState>, path: String) -> Result<String, String>
     Ok(content)
 }
 
+/// Save a note. Returns frontmatter values that do not match their registered
+/// property types; the note is saved regardless so no edit is ever lost.
+/// With `files.auto_timestamps` on, created/modified frontmatter is kept up to date.
+/// With `files_links.auto_update_internal_links` on, the note as it was before
+/// this round of edits is remembered so `commit_link_updates` can later rewrite
+/// links to renamed headings and moved blocks.
 #[tauri::command]
-pub fn save_note(state: State<AppState>, path: String, content: String) -> Result<(), String> {
+pub fn save_note(state: State<AppState>, path: String, content: String) -> Result<Vec<PropertyIssue>, String> {
     let vault_path = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
     let password = state.vault_password.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
     let settings = settings::load_settings(&vault_path);
 
+    let is_note = path.ends_with(".md");
+    let previous = if is_note && (settings.files.auto_timestamps || settings.files_links.auto_update_internal_links) {
+        vault_ops::read_note(&vault_path, &path).ok()
+            .and_then(|c| encryption::plaintext_content(c, password.as_deref()))
+    } else {
+        None
+    };
+
+    // Autosaves land mid-edit, so links are only rewritten once the edit is committed
+    if let (Some(previous), true) = (&previous, settings.files_links.auto_update_internal_links) {
+        let mut baselines = state.link_baselines.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+        baselines.entry(path.clone()).or_insert_with(|| previous.clone());
+    }
+
+    let content = if settings.files.auto_timestamps && is_note {
+        // Birth time of an existing note that has no `created` yet
+        let created_fallback = std::fs::metadata(std::path::Path::new(&*vault_path).join(&path)).ok()
+            .and_then(|m| m.created().or_else(|_| m.modified()).ok())
+            .map(|t| DateTime::<Local>::from(t).naive_local());
+        let opts = frontmatter::TimestampOptions {
+            created_key: settings.files.created_key.trim(),
+            modified_key: settings.files.modified_key.trim(),
+            format: &settings.files.timestamp_format,
+        };
+        frontmatter::apply_timestamps(&content, previous.as_deref(), &opts, Local::now().naive_local(), created_fallback)
+    } else {
+        content
+    };
+
     // Create snapshot before saving (file recovery)
     crate::features::file_recovery::create_snapshot(&vault_path, &path).ok();
 
@@ -48,29 +88,19 @@ pub fn save_note(state: State<AppState>, path: String, content: String) -> Resul
         vault_ops::save_note(&vault_path, &path, &content)?;
     }
 
-    let mut search = state.search_index.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
-    search.index_note(&vault_path, &path, &content)?;
-
-    if let Ok(mut cache) = state.meta_cache.lock() {
-        cache.update_file(&path, &content);
-    }
+    state.reindex_note(&vault_path, &path, &content)?;
 
-    Ok(())
+    let issues = PropertyRegistry::load(&vault_path)
+        .validate_content(&content)
+        .unwrap_or_default();
+    Ok(issues)
 }
 
 #[tauri::command]
 pub fn delete_note(state: State<AppState>, path: String) -> Result<(), String> {
     let vault_path = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
     vault_ops::delete_note(&vault_path, &path)?;
-
-    if let Ok(mut cache) = state.meta_cache.lock() {
-        cache.remove_file(&path);
-    }
-
-    if let Ok(mut search) = state.search_index.lock() {
-        let _ = search.delete_path(&path);
-    }
-
+    state.forget_note(&vault_path, &path);
     Ok(())
 }
 
@@ -101,15 +131,26 @@ pub fn create_daily_note(state: State<AppState>) -> Result<String, String> {
     Ok(relative_path)
 }
 
+/// Render a note to HTML, evaluating any ```query blocks against the vault.
 #[tauri::command]
-pub fn render_markdown(content: String) -> Result<String, String> {
-    Ok(md::render_markdown(&content))
+pub fn render_markdown(state: State<AppState>, content: String) -> Result<String, String> {
+    if !md::has_query_blocks(&content) {
+        return Ok(md::render_markdown(&content));
+    }
+    let vault_path = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    let mut cache = state.meta_cache.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    cache.ensure_built(&vault_path);
+    let properties = state.property_index.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    let tasks = state.task_index.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    let ctx = query::QueryContext::new(&vault_path, &cache, &properties, &tasks);
+    Ok(query::render_markdown(&content, &ctx))
 }
 
 #[tauri::command]
 pub fn search_notes(state: State<AppState>, query: String) -> Result<Vec<SearchResult>, String> {
     let search = state.search_index.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
-    search.search(&query, 20)
+    let props = state.property_index.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    properties::search_with_fields(&search, &props, &query, 20)
 }
 
 #[tauri::command]
@@ -121,18 +162,7 @@ pub fn get_vault_path(state: State<AppState>) -> Result<String, String> {
 #[tauri::command]
 pub fn set_vault_path(state: State<AppState>, path: String) -> Result<(), String> {
     std::fs::create_dir_all(&path).map_err(|e| format!("Failed to create directory: {}", e))?;
-
-    let mut vault_path = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
-    *vault_path = path.clone();
-
-    let mut search = state.search_index.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
-    search.reindex_vault(&path)?;
-
-    if let Ok(mut cache) = state.meta_cache.lock() {
-        cache.rebuild(&path);
-    }
-
-    Ok(())
+    open_vault(&state, &path)
 }
 
 #[tauri::command]
@@ -146,36 +176,32 @@ pub fn rename_file(state: State<AppState>, old_path: String, new_path: String) -
     let vault_path = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
     vault_ops::rename_file(&vault_path, &old_path, &new_path)?;
 
-    // Update search index: delete old, index new
-    if let Ok(mut search) = state.search_index.lock() {
-        let _ = search.delete_path(&old_path);
-        if new_path.ends_with(".md") {
-            if let Ok(content) = vault_ops::read_note(&vault_path, &new_path) {
-                let _ = search.index_note(&vault_path, &new_path, &content);
-            }
+    // Move the note's entries: drop the old path, index the new one
+    let password = state.vault_password.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    state.forget_note(&vault_path, &old_path);
+    if new_path.ends_with(".md") {
+        let content = vault_ops::read_note(&vault_path, &new_path).ok()
+            .and_then(|c| encryption::plaintext_content(c, password.as_deref()));
+        if let Some(content) = content {
+            state.reindex_note(&vault_path, &new_path, &content)?;
         }
     }
-
     Ok(())
 }
 
 #[tauri::command]
 pub fn get_tags(state: State<AppState>) -> Result<Vec<String>, String> {
+    let vault_path = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
     let mut cache = state.meta_cache.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
-    if cache.is_stale(30) {
-        let vault_path = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
-        cache.rebuild(&vault_path);
-    }
+    cache.ensure_built(&vault_path);
     Ok(cache.all_tags())
 }
 
 #[tauri::command]
 pub fn get_backlinks(state: State<AppState>, note_path: String) -> Result<Vec<String>, String> {
+    let vault_path = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
     let mut cache = state.meta_cache.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
-    if cache.is_stale(30) {
-        let vault_path = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
-        cache.rebuild(&vault_path);
-    }
+    cache.ensure_built(&vault_path);
     Ok(cache.find_backlinks(&note_path))
 }
 
@@ -206,7 +232,13 @@ pub fn get_settings(state: State<AppState>) -> Result<Settings, String> {
 #[tauri::command]
 pub fn save_settings(state: State<AppState>, settings: Settings) -> Result<(), String> {
     let vault_path = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
-    settings::save_settings(&vault_path, &settings)
+    settings::save_settings(&vault_path, &settings)?;
+
+    // Rebuild the search index if the tokenizer language changed
+    let password = state.vault_password.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    let mut search = state.search_index.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    search.apply_settings(&vault_path, &settings.search, password.as_deref())?;
+    Ok(())
 }
 
 #[tauri::command]
@@ -238,6 +270,27 @@ pub fn unlock_vault(state: State<AppState>, password: String) -> Result<bool, St
     let mut locked = state.vault_locked.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
     *locked = false;
 
+    // Build the in-memory search index from decrypted notes
+    let vault_settings = settings::load_settings(&vault_path);
+    let mut search = state.search_index.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    if vault_settings.vault.encryption_enabled && !search.is_in_memory() {
+        *search = SearchIndex::in_memory(&vault_path, &vault_settings.search)?;
+    }
+    search.reindex_vault_with_password(&vault_path, pwd.as_deref())?;
+
+    if let Ok(mut cache) = state.meta_cache.lock() {
+        cache.rebuild_with_password(&vault_path, pwd.as_deref());
+    }
+    if let Ok(mut props) = state.property_index.lock() {
+        props.build_from_vault(&vault_path, pwd.as_deref());
+    }
+    if let Ok(mut tasks) = state.task_index.lock() {
+        tasks.build_from_vault(&vault_path, pwd.as_deref());
+    }
+    if let Ok(mut blocks) = state.block_index.lock() {
+        *blocks = BlockIndex::load_or_build(&vault_path, pwd.as_deref(), !vault_settings.vault.encryption_enabled);
+    }
+
     Ok(true)
 }
 
@@ -247,6 +300,34 @@ pub fn lock_vault(state: State<AppState>) -> Result<(), String> {
     *pwd = None;
     let mut locked = state.vault_locked.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
     *locked = true;
+
+    // Forget decrypted note contents held by the in-memory index
+    let mut search = state.search_index.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    if search.is_in_memory() {
+        search.clear()?;
+    }
+    drop(search);
+    drop(locked);
+    drop(pwd);
+
+    // Same for metadata, properties, tasks and blocks of encrypted notes
+    let vault_path = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    if let Ok(mut cache) = state.meta_cache.lock() {
+        cache.rebuild(&vault_path);
+    }
+    if let Ok(mut props) = state.property_index.lock() {
+        props.build_from_vault(&vault_path, None);
+    }
+    if let Ok(mut tasks) = state.task_index.lock() {
+        tasks.build_from_vault(&vault_path, None);
+    }
+    if let Ok(mut blocks) = state.block_index.lock() {
+        let persist = !settings::load_settings(&vault_path).vault.encryption_enabled;
+        *blocks = BlockIndex::load_or_build(&vault_path, None, persist);
+    }
+    if let Ok(mut baselines) = state.link_baselines.lock() {
+        baselines.clear();
+    }
     Ok(())
 }
 
@@ -278,6 +359,16 @@ pub fn setup_encryption(state: State<AppState>, password: String) -> Result<(),
     s.vault.encryption_enabled = true;
     settings::save_settings(&vault_path, &s)?;
 
+    // Move search off disk: drop `.search_index` and index in memory instead
+    let mut search = state.search_index.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    *search = SearchIndex::in_memory(&vault_path, &s.search)?;
+    search.reindex_vault_with_password(&vault_path, pwd.as_deref())?;
+
+    // Same for blocks: removes `.oxidian/block-index.json`, which holds block text
+    if let Ok(mut blocks) = state.block_index.lock() {
+        *blocks = BlockIndex::load_or_build(&vault_path, pwd.as_deref(), false);
+    }
+
     Ok(())
 }
 
@@ -387,6 +478,16 @@ pub fn disable_encryption(state: State<AppState>) -> Result<(), String> {
     s.vault.encryption_enabled = false;
     settings::save_settings(&vault_path, &s)?;
 
+    // Notes are plaintext again, so the index can go back to disk
+    {
+        let mut search = state.search_index.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+        *search = SearchIndex::with_settings(&vault_path, &s.search)?;
+        search.reindex_vault(&vault_path)?;
+    }
+    if let Ok(mut blocks) = state.block_index.lock() {
+        *blocks = BlockIndex::load_or_build(&vault_path, None, true);
+    }
+
     drop(password);
     let mut pwd_state = state.vault_password.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
     *pwd_state = None;
@@ -422,17 +523,11 @@ pub fn setup_vault(state: State<AppState>, path: String) -> Result<(), String> {
     std::fs::create_dir_all(&path).map_err(|e| format!("Failed to create vault: {}", e))?;
     std::fs::create_dir_all(format!("{}/daily", path)).ok();
 
-    let mut vault_path = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
-    *vault_path = path.clone();
-
     let mut default_settings = Settings::default();
     default_settings.general.vault_path = path.clone();
     settings::save_settings(&path, &default_settings)?;
 
-    let mut search = state.search_index.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
-    search.reindex_vault(&path)?;
-
-    Ok(())
+    open_vault(&state, &path)
 }
 
 // ─── Auto-Update Commands ────────────────────────────────────────────
@@ -476,26 +571,75 @@ pub fn generate_block_id() -> String {
 
 #[tauri::command]
 pub fn find_block(state: State<AppState>, block_id: String) -> Result<Option<crate::engine::blocks::BlockResult>, String> {
-    let vault_path = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
-    Ok(crate::engine::blocks::find_block_by_id(&vault_path, &block_id))
+    let blocks = state.block_index.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    Ok(blocks.find(&block_id).cloned())
 }
 
 #[tauri::command]
 pub fn get_block_content(state: State<AppState>, note_path: String, block_id: String) -> Result<String, String> {
-    let vault_path = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
-    crate::engine::blocks::get_block_content(&vault_path, &note_path, &block_id)
+    let blocks = state.block_index.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    blocks.find_in_note(&note_path, &block_id)
+        .map(|b| b.content.clone())
+        .ok_or_else(|| format!("Block ^{} not found in {}", block_id, note_path))
 }
 
 #[tauri::command]
 pub fn list_block_ids(state: State<AppState>, note_path: String) -> Result<Vec<crate::engine::blocks::BlockResult>, String> {
-    let vault_path = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
-    crate::engine::blocks::list_block_ids(&vault_path, &note_path)
+    let blocks = state.block_index.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    Ok(blocks.note_blocks(&note_path).to_vec())
 }
 
 #[tauri::command]
 pub fn list_all_block_ids(state: State<AppState>) -> Result<Vec<crate::engine::blocks::BlockResult>, String> {
+    let blocks = state.block_index.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    Ok(blocks.all())
+}
+
+/// Block autocomplete: ids starting with `query`, then blocks containing it.
+#[tauri::command]
+pub fn search_blocks(state: State<AppState>, query: String, limit: Option<usize>) -> Result<Vec<crate::engine::blocks::BlockResult>, String> {
+    let blocks = state.block_index.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    Ok(blocks.search(&query, limit.unwrap_or(20)))
+}
+
+/// Attach a `^block-id` to the block at `line` (1-based) or at the start of
+/// the byte `range`, reusing the block's id if it has one. Returns the link.
+#[tauri::command]
+pub fn assign_block_id(
+    state: State<AppState>,
+    note_path: String,
+    line: Option<usize>,
+    range: Option<(usize, usize)>,
+    block_id: Option<String>,
+) -> Result<crate::engine::blocks::BlockAssignment, String> {
     let vault_path = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
-    Ok(crate::engine::blocks::list_all_block_ids(&vault_path))
+    let password = state.vault_password.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    let raw = vault_ops::read_note(&vault_path, &note_path)?;
+    let encrypted = encryption::is_encrypted_content(&raw);
+    let content = encryption::plaintext_content(raw, password.as_deref())
+        .ok_or_else(|| "Vault is locked — cannot read encrypted note".to_string())?;
+    let line = match (line, range) {
+        (Some(line), _) => line,
+        (None, Some((start, _))) => crate::engine::blocks::line_at_offset(&content, start),
+        (None, None) => return Err("Either line or range is required".to_string()),
+    };
+
+    let assignment = {
+        let blocks = state.block_index.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+        crate::engine::blocks::assign_block_id(&note_path, &content, line, block_id.as_deref(), |id| {
+            blocks.notes_with(id).iter().any(|p| p != &note_path)
+        })?
+    };
+    if assignment.created {
+        crate::features::file_recovery::create_snapshot(&vault_path, &note_path).ok();
+        let stored = match (encrypted, password.as_deref()) {
+            (true, Some(pwd)) => encryption::encrypt_file_content(&assignment.content, pwd)?,
+            _ => assignment.content.clone(),
+        };
+        vault_ops::save_note(&vault_path, &note_path, &stored)?;
+        state.reindex_note(&vault_path, &note_path, &assignment.content)?;
+    }
+    Ok(assignment)
 }
 
 // ===== Markdown Parsing Commands =====
@@ -518,6 +662,12 @@ pub fn parse_frontmatter(content: String) -> Result<Option<Frontmatter>, String>
     Ok(fm)
 }
 
+/// Inline `key:: value` fields of a note with their line positions.
+#[tauri::command]
+pub fn parse_inline_fields(content: String) -> Result<Vec<frontmatter::InlineField>, String> {
+    Ok(frontmatter::parse_inline_fields(&content))
+}
+
 #[tauri::command]
 pub fn stringify_frontmatter(fm: Frontmatter, body: String) -> Result<String, String> {
     Ok(frontmatter::serialize_frontmatter(&fm, &body))
@@ -544,15 +694,16 @@ pub fn get_field(content: String, field: String) -> Result<Option<serde_json::Va
 
 #[tauri::command]
 pub fn set_field(content: String, field: String, value: serde_json::Value) -> Result<String, String> {
-    let (fm, body) = frontmatter::parse_frontmatter(&content)?;
-    let mut fm = fm.unwrap_or_default();
-    match field.as_str() {
-        "title" => fm.title = value.as_str().map(|s| s.to_string()),
-        "created" => fm.created = value.as_str().map(|s| s.to_string()),
-        "modified" => fm.modified = value.as_str().map(|s| s.to_string()),
-        _ => { fm.extra.insert(field, value); }
+    // Validate first so a broken block is reported rather than edited
+    frontmatter::parse_frontmatter(&content)?;
+    let value = frontmatter::coerce_known_field(&field, value);
+    let mut editor = frontmatter::FrontmatterEditor::parse(&content);
+    if value.is_null() && matches!(field.as_str(), "title" | "created" | "modified") {
+        editor.remove(&field);
+    } else {
+        editor.set(&field, &value)?;
     }
-    Ok(frontmatter::serialize_frontmatter(&fm, body))
+    Ok(editor.render())
 }
 
 // ===== Links Commands =====
@@ -572,7 +723,7 @@ pub fn resolve_link(state: State<AppState>, link: String) -> Result<Vec<crate::e
 pub fn update_links_on_rename(state: State<AppState>, old_name: String, new_name: String) -> Result<u32, String> {
     let vault_path = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
     let escaped = regex::escape(&old_name);
-    let re = regex::Regex::new(&format!(r"\[\[([^\]|]*/)?{}(\|[^\]]+)?\]\]", escaped))
+    let re = regex::Regex::new(&format!(r"(!?)\[\[([^\]|#]*/)?{}(#[^\]|]*)?(\|[^\]]+)?\]\]", escaped))
         .map_err(|e| format!("Regex error: {}", e))?;
     let mut count = 0u32;
     for entry in walkdir::WalkDir::new(&*vault_path).into_iter().filter_map(|e| e.ok()) {
@@ -580,9 +731,11 @@ pub fn update_links_on_rename(state: State<AppState>, old_name: String, new_name
         if path.extension().map(|e| e == "md").unwrap_or(false) {
             if let Ok(content) = std::fs::read_to_string(path) {
                 let new_content = re.replace_all(&content, |caps: &regex::Captures| {
-                    let prefix = caps.get(1).map(|m| m.as_str()).unwrap_or("");
-                    let alias = caps.get(2).map(|m| m.as_str()).unwrap_or("");
-                    format!("[[{}{}{}]]", prefix, new_name, alias)
+                    let embed = caps.get(1).map(|m| m.as_str()).unwrap_or("");
+                    let prefix = caps.get(2).map(|m| m.as_str()).unwrap_or("");
+                    let subpath = caps.get(3).map(|m| m.as_str()).unwrap_or("");
+                    let alias = caps.get(4).map(|m| m.as_str()).unwrap_or("");
+                    format!("{}[[{}{}{}{}]]", embed, prefix, new_name, subpath, alias)
                 }).to_string();
                 if new_content != content {
                     std::fs::write(path, &new_content).map_err(|e| format!("Write error: {}", e))?;
@@ -594,6 +747,83 @@ pub fn update_links_on_rename(state: State<AppState>, old_name: String, new_name
     Ok(count)
 }
 
+/// Links a pending edit of `path` would rewrite elsewhere in the vault
+/// (renamed headings, moved blocks), without saving anything. The edit is
+/// compared with the note as it was before its uncommitted saves.
+#[tauri::command]
+pub fn preview_link_updates(state: State<AppState>, path: String, content: String) -> Result<Vec<LinkChange>, String> {
+    let vault_path = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    let password = state.vault_password.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    let baseline = state.link_baselines.lock().map_err(|e| format!("Lock poisoned: {}", e))?
+        .get(&path)
+        .cloned();
+    let previous = baseline
+        .or_else(|| {
+            vault_ops::read_note(&vault_path, &path).ok()
+                .and_then(|c| encryption::plaintext_content(c, password.as_deref()))
+        })
+        .unwrap_or_default();
+    let rules = {
+        let blocks = state.block_index.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+        links::detect_link_rules(&path, &previous, &content, |id| blocks.notes_with(id))
+    };
+    let updates = links::plan_link_updates(&vault_path, password.as_deref(), &rules, Some(&path));
+    Ok(updates.into_iter().flat_map(|u| u.changes).collect())
+}
+
+/// Rewrite links elsewhere in the vault to headings renamed and blocks moved
+/// in `path` since its last commit. The editor calls this when it leaves a
+/// note, so headings half-typed between autosaves never reach other notes.
+#[tauri::command]
+pub fn commit_link_updates(state: State<AppState>, path: String) -> Result<Vec<LinkChange>, String> {
+    let vault_path = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    let password = state.vault_password.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    let baseline = state.link_baselines.lock().map_err(|e| format!("Lock poisoned: {}", e))?.remove(&path);
+    let Some(baseline) = baseline else { return Ok(Vec::new()) };
+    let Some(current) = vault_ops::read_note(&vault_path, &path).ok()
+        .and_then(|c| encryption::plaintext_content(c, password.as_deref()))
+    else {
+        return Ok(Vec::new());
+    };
+
+    let rules = {
+        let blocks = state.block_index.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+        links::detect_link_rules(&path, &baseline, &current, |id| blocks.notes_with(id))
+    };
+    // The committed note itself is left as the editor saved it
+    let updates = links::plan_link_updates(&vault_path, password.as_deref(), &rules, Some(&path));
+    links::apply_link_updates(&vault_path, password.as_deref(), &updates)?;
+    for update in &updates {
+        state.reindex_note(&vault_path, &update.path, &update.content)?;
+    }
+    Ok(updates.into_iter().flat_map(|u| u.changes).collect())
+}
+
+/// Rename or move a note and rewrite every link to it, including heading and
+/// block links and embeds. With `dry_run`, only report the changes.
+#[tauri::command]
+pub fn rename_with_links(state: State<AppState>, old_path: String, new_path: String, dry_run: Option<bool>) -> Result<Vec<LinkChange>, String> {
+    let vault_path = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    let password = state.vault_password.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    let dry_run = dry_run.unwrap_or(false);
+    let updates = links::rename_note(&vault_path, password.as_deref(), &old_path, &new_path, dry_run)?;
+    if dry_run {
+        return Ok(updates.into_iter().flat_map(|u| u.changes).collect());
+    }
+
+    state.forget_note(&vault_path, &old_path);
+    if let Some(content) = vault_ops::read_note(&vault_path, &new_path).ok()
+        .and_then(|c| encryption::plaintext_content(c, password.as_deref()))
+    {
+        state.reindex_note(&vault_path, &new_path, &content)?;
+    }
+
+    for update in &updates {
+        state.reindex_note(&vault_path, &update.path, &update.content)?;
+    }
+    Ok(updates.into_iter().flat_map(|u| u.changes).collect())
+}
+
 // ===== Search Commands =====
 
 #[tauri::command]
@@ -648,14 +878,7 @@ pub fn move_entry(state: State<AppState>, source_path: String, dest_dir: String)
 pub fn trash_entry(state: State<AppState>, path: String) -> Result<(), String> {
     let vault_path = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
     vault_ops::trash_entry(&vault_path, &path)?;
-
-    if let Ok(mut search) = state.search_index.lock() {
-        let _ = search.delete_path(&path);
-    }
-    if let Ok(mut cache) = state.meta_cache.lock() {
-        cache.remove_file(&path);
-    }
-
+    state.forget_note(&vault_path, &path);
     Ok(())
 }
 
@@ -712,37 +935,84 @@ pub fn remove_vault(name: String) -> Result<(), String> {
     vault_manager::remove_vault(&name)
 }
 
-#[tauri::command]
-pub fn switch_vault(state: State<AppState>, path: String) -> Result<(), String> {
-    // Validate path exists
-    if !std::path::Path::new(&path).exists() {
-        return Err(format!("Vault path does not exist: {}", path));
+/// Make `path` the open vault: point the watcher at it and rebuild every
+/// index from it. Shared by `setup_vault`, `set_vault_path` and `switch_vault`.
+fn open_vault(state: &State<AppState>, path: &str) -> Result<(), String> {
+    // Point the watcher at the new vault. The vault path lock is not held
+    // here: the watcher thread takes it while handling events.
+    {
+        let old_path = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?.clone();
+        let mut watcher = state.watcher.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+        if let Some(w) = watcher.as_mut() {
+            w.unwatch(std::path::Path::new(&old_path)).ok();
+            w.watch(std::path::Path::new(path), RecursiveMode::Recursive)
+                .map_err(|e| format!("Failed to watch vault: {}", e))?;
+        }
     }
 
     // Update vault path
     {
         let mut vp = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
-        *vp = path.clone();
+        *vp = path.to_string();
     }
 
     // Rebuild meta cache
     {
         let mut cache = state.meta_cache.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
-        cache.rebuild(&path);
+        cache.rebuild(path);
     }
 
-    // Reindex search
+    // Reopen and reindex search with the new vault's settings
+    let vault_settings = settings::load_settings(path);
     {
         let mut idx = state.search_index.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
-        let _ = idx.reindex_vault(&path);
+        *idx = SearchIndex::for_vault(path, &vault_settings)?;
+        let _ = idx.reindex_vault(path);
     }
 
     // Rebuild tag index
     {
         let mut tags = state.tag_index.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
-        tags.build_from_vault(&path);
+        tags.build_from_vault(path);
+    }
+
+    // Rebuild property index
+    {
+        let mut props = state.property_index.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+        props.build_from_vault(path, None);
     }
 
+    // Rebuild task index
+    {
+        let mut tasks = state.task_index.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+        tasks.build_from_vault(path, None);
+    }
+
+    // Load the new vault's block index
+    {
+        let mut blocks = state.block_index.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+        *blocks = BlockIndex::load_or_build(path, None, !vault_settings.vault.encryption_enabled);
+    }
+
+    // Graph metrics and uncommitted heading edits belong to the old vault
+    if let Ok(mut analytics) = state.graph_analytics.lock() {
+        analytics.clear();
+    }
+    if let Ok(mut baselines) = state.link_baselines.lock() {
+        baselines.clear();
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn switch_vault(state: State<AppState>, path: String) -> Result<(), String> {
+    // Validate path exists
+    if !std::path::Path::new(&path).exists() {
+        return Err(format!("Vault path does not exist: {}", path));
+    }
+
+    open_vault(&state, &path)?;
+
     // Touch last_opened
     vault_manager::touch_vault(&path).ok();
 
@@ -1,6 +1,6 @@
 use crate::state::AppState;
-use crate::features::graph;
-use crate::features::canvas::Canvas;
+use crate::features::graph::{self, analytics::GraphAnalytics, export::ExportFormat, layout::LayoutOptions, paths::{NotePath, PathOptions}, query::{GraphQuery, QueryInputs}, timelapse::Timelapse};
+use crate::features::canvas::{export::{self as canvas_export, CanvasExportFormat}, layout::CanvasLayoutOptions, ops::{CanvasOp, CanvasSession, HistoryStep}, validate::{self as canvas_validate, CanvasIssue}, Canvas, CanvasEdge, CanvasNode, CanvasNodeType};
 
 fn validate_canvas_path(path: &str) -> Result<(), String> {
     if path.contains("..") || path.starts_with('/') || path.starts_with('\\') {
@@ -12,6 +12,13 @@ use crate::features::bookmarks::Bookmark;
 use crate::features::daily_notes::{DailyNotes, DailyNotesConfig};
 use crate::features::templates::{TemplateManager, TemplateInfo};
 use crate::features::tags::TagEntry;
+use crate::features::transclusion;
+use crate::features::bulk_properties::{self, BulkEditReport, PropertyOp, PropertySelection};
+use crate::features::query::{self, QueryContext};
+use crate::features::properties::{self, PropertyIssue, PropertyKeyInfo, PropertyRegistry, PropertyType, PropertyValueCount};
+use crate::features::related::{self, LinkCandidate};
+use crate::features::tasks::{self, Task, TaskQuery, ToggleResult};
+use crate::engine::search::SimilarNote;
 use serde::Serialize;
 use tauri::State;
 
@@ -21,6 +28,15 @@ use tauri::State;
 pub struct GraphNode {
     pub id: String,
     pub name: String,
+    /// Community index from the graph analytics, or the `color_by` value
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    pub kind: graph::NodeKind,
+    /// Layout position, present when the graph was laid out in Rust
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub x: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub y: Option<f64>,
 }
 
 #[derive(Debug, Serialize)]
@@ -35,25 +51,149 @@ pub struct GraphData {
     pub edges: Vec<GraphEdge>,
 }
 
-#[tauri::command]
-pub fn get_graph_data(state: State<AppState>) -> Result<GraphData, String> {
+impl From<graph::GraphData> for GraphData {
+    fn from(gd: graph::GraphData) -> Self {
+        GraphData {
+            nodes: gd.nodes.into_iter().map(|n| GraphNode { id: n.id, name: n.name, group: n.group, kind: n.kind, x: None, y: None }).collect(),
+            edges: gd.edges.into_iter().map(|e| GraphEdge { source: e.source, target: e.target }).collect(),
+        }
+    }
+}
+
+/// The vault graph narrowed and decorated by `query`. Nodes are grouped by
+/// the `color_by` property when set, otherwise by community.
+fn query_vault_graph(state: &AppState, vault_path: &str, query: &GraphQuery) -> Result<graph::GraphData, String> {
+    let search = state.search_index.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
     let mut cache = state.meta_cache.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
-    if cache.is_stale(30) {
-        let vault_path = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
-        cache.rebuild(&vault_path);
+    cache.ensure_built(vault_path);
+    let props = state.property_index.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+
+    let limit = cache.entries.len().max(1);
+    let hits = |q: Option<&str>| -> Result<Option<std::collections::HashSet<String>>, String> {
+        q.map(|q| Ok(properties::search_with_fields(&search, &props, q, limit)?.into_iter().map(|r| r.path).collect()))
+            .transpose()
+    };
+    let color_by = query.color_by.as_deref().map(str::trim).filter(|k| !k.is_empty());
+    let inputs = QueryInputs {
+        search_hits: hits(query.search_query())?,
+        excluded_hits: hits(query.exclude_search_query())?,
+        files: if query.needs_files() { graph::query::vault_files(vault_path) } else { Vec::new() },
+        groups: color_by.map(|key| graph::query::property_groups(&props, cache.entries.keys(), key)).unwrap_or_default(),
+    };
+    let mut gd = graph::query::query_graph(&cache, query, &inputs);
+
+    if color_by.is_none() {
+        // Communities come from the whole vault so colours stay put while filtering
+        let full = graph::compute_graph(&cache);
+        let mut analytics = state.graph_analytics.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+        graph::analytics::apply_communities(&mut gd, analytics.get(&full));
     }
+    Ok(gd)
+}
 
-    let gd = graph::compute_graph(&cache);
+#[tauri::command]
+pub fn get_graph_data(state: State<AppState>, query: Option<GraphQuery>) -> Result<GraphData, String> {
+    let vault_path = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?.clone();
+    Ok(query_vault_graph(&state, &vault_path, &query.unwrap_or_default())?.into())
+}
+
+/// The vault graph with node positions from the Barnes–Hut layout. Saved
+/// positions are reused as they are unless `layout` options ask for a new
+/// run, new nodes need placing, or `reset` starts over.
+#[tauri::command]
+pub fn compute_graph(state: State<AppState>, layout: Option<LayoutOptions>, reset: Option<bool>, query: Option<GraphQuery>) -> Result<GraphData, String> {
+    let vault_path = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?.clone();
+    let gd = query_vault_graph(&state, &vault_path, &query.unwrap_or_default())?;
+    let positions = graph::layout::layout_vault_graph(&vault_path, &gd, layout.as_ref(), reset.unwrap_or(false))?;
 
     Ok(GraphData {
-        nodes: gd.nodes.into_iter().map(|n| GraphNode { id: n.id, name: n.name }).collect(),
+        nodes: gd.nodes.into_iter().map(|n| {
+            let p = positions.get(&n.id).copied();
+            GraphNode { x: p.map(|p| p[0]), y: p.map(|p| p[1]), id: n.id, name: n.name, group: n.group, kind: n.kind }
+        }).collect(),
         edges: gd.edges.into_iter().map(|e| GraphEdge { source: e.source, target: e.target }).collect(),
     })
 }
 
+/// Centrality, communities, components, orphans and dead ends of the link
+/// graph. Cached; recomputed incrementally when links change.
 #[tauri::command]
-pub fn compute_graph(state: State<AppState>) -> Result<GraphData, String> {
-    get_graph_data(state)
+pub fn get_graph_analytics(state: State<AppState>) -> Result<GraphAnalytics, String> {
+    let vault_path = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    let mut cache = state.meta_cache.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    cache.ensure_built(&vault_path);
+    let gd = graph::compute_graph(&cache);
+    let mut analytics = state.graph_analytics.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    Ok(analytics.get(&gd).clone())
+}
+
+/// Writes the graph (narrowed by `query`) as GraphML, GEXF or DOT to `path`
+/// in the vault, or to the export folder. Returns the written path.
+#[tauri::command]
+pub fn export_graph(state: State<AppState>, format: ExportFormat, path: Option<String>, query: Option<GraphQuery>) -> Result<String, String> {
+    let vault_path = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?.clone();
+    let gd = query_vault_graph(&state, &vault_path, &query.unwrap_or_default())?;
+    let cache = state.meta_cache.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    let full = graph::compute_graph(&cache);
+    let mut analytics = state.graph_analytics.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    let nodes = graph::export::export_nodes(&gd, &cache, Some(analytics.get(&full)));
+    drop(analytics);
+    drop(cache);
+    let relative = graph::export::export_graph(&vault_path, path.as_deref(), format, &gd, &nodes)?;
+    reindex_export(&state, &vault_path, &relative);
+    Ok(relative)
+}
+
+/// Exports land inside the vault; one written as a note is indexed like any
+/// other so it shows up in search, links and the graph.
+fn reindex_export(state: &AppState, vault_path: &str, relative: &str) {
+    if !relative.ends_with(".md") {
+        return;
+    }
+    let Ok(raw) = std::fs::read_to_string(std::path::Path::new(vault_path).join(relative)) else { return };
+    let password = state.vault_password.lock().ok().and_then(|p| p.clone());
+    if let Some(content) = crate::encryption::plaintext_content(raw, password.as_deref()) {
+        state.reindex_note(vault_path, relative, &content).ok();
+    }
+}
+
+/// Shortest link paths from `from` to `to`, each hop with its linking line.
+#[tauri::command]
+pub fn find_note_paths(state: State<AppState>, from: String, to: String, options: Option<PathOptions>) -> Result<Vec<NotePath>, String> {
+    let vault_path = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    let password = state.vault_password.lock().map_err(|e| format!("Lock poisoned: {}", e))?.clone();
+    let mut cache = state.meta_cache.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    cache.ensure_built(&vault_path);
+    graph::paths::find_paths(&vault_path, &cache, &from, &to, &options.unwrap_or_default(), password.as_deref())
+}
+
+fn parse_day(date: &str) -> Result<chrono::NaiveDate, String> {
+    chrono::NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d").map_err(|e| format!("Invalid date '{}': {}", date, e))
+}
+
+/// The graph of the notes created before `date` (YYYY-MM-DD). Creation comes
+/// from the `property` frontmatter field (default `created`), birth time or
+/// snapshot history.
+#[tauri::command]
+pub fn get_graph_as_of(state: State<AppState>, date: String, property: Option<String>, query: Option<GraphQuery>) -> Result<GraphData, String> {
+    let before = parse_day(&date)?;
+    let vault_path = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?.clone();
+    let gd = query_vault_graph(&state, &vault_path, &query.unwrap_or_default())?;
+    let props = state.property_index.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    let property = property.as_deref().unwrap_or(graph::timelapse::DEFAULT_CREATED_PROPERTY);
+    let dates = graph::timelapse::creation_dates(&vault_path, &gd, &props, property);
+    Ok(graph::timelapse::graph_as_of(&gd, &dates, before).into())
+}
+
+/// Day-by-day growth frames (node and edge additions) for animating the graph.
+#[tauri::command]
+pub fn get_graph_timelapse(state: State<AppState>, property: Option<String>, query: Option<GraphQuery>) -> Result<Timelapse, String> {
+    let vault_path = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?.clone();
+    let gd = query_vault_graph(&state, &vault_path, &query.unwrap_or_default())?;
+    let props = state.property_index.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    let property = property.as_deref().unwrap_or(graph::timelapse::DEFAULT_CREATED_PROPERTY);
+    let dates = graph::timelapse::creation_dates(&vault_path, &gd, &props, property);
+    Ok(graph::timelapse::growth_frames(&gd, &dates))
 }
 
 // ===== Theme Commands =====
@@ -106,12 +246,31 @@ pub fn load_canvas(state: State<AppState>, path: String) -> Result<Canvas, Strin
     Canvas::load(&full_path)
 }
 
+/// Saves a whole canvas from the editor. The op log stays, so undo keeps
+/// working on whatever its ops still fit.
 #[tauri::command]
 pub fn save_canvas(state: State<AppState>, path: String, canvas: Canvas) -> Result<(), String> {
+    let mut session = canvas_session(&state, &path)?;
+    session.replace(canvas);
+    session.save()
+}
+
+/// JSON Canvas 1.0 spec issues of a .canvas file; empty when it conforms.
+#[tauri::command]
+pub fn validate_canvas(state: State<AppState>, path: String) -> Result<Vec<CanvasIssue>, String> {
     validate_canvas_path(&path)?;
     let vault_path = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
     let full_path = std::path::Path::new(&*vault_path).join(&path);
-    canvas.save(&full_path)
+    let content = std::fs::read_to_string(&full_path)
+        .map_err(|e| format!("Failed to read canvas file: {}", e))?;
+    canvas_validate::validate_json(&content)
+}
+
+/// Opens the canvas at `path` with its op log.
+fn canvas_session(state: &AppState, path: &str) -> Result<CanvasSession, String> {
+    validate_canvas_path(path)?;
+    let vault_path = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    CanvasSession::open(&vault_path, path)
 }
 
 #[tauri::command]
@@ -125,43 +284,81 @@ pub fn canvas_add_node(
     url: Option<String>,
     label: Option<String>,
 ) -> Result<String, String> {
-    validate_canvas_path(&path)?;
-    let vault_path = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
-    let full_path = std::path::Path::new(&*vault_path).join(&path);
-    let mut canvas = if full_path.exists() { Canvas::load(&full_path)? } else { Canvas::new() };
-
-    let id = match node_type.as_str() {
-        "text" => canvas.add_text_node(x, y, width, height, text.as_deref().unwrap_or("")),
-        "file" => canvas.add_file_node(x, y, width, height, file.as_deref().unwrap_or(""), None),
-        "link" => canvas.add_link_node(x, y, width, height, url.as_deref().unwrap_or("")),
-        "group" => canvas.add_group_node(x, y, width, height, label.as_deref()),
+    let mut session = canvas_session(&state, &path)?;
+    let node = match node_type.as_str() {
+        "text" => CanvasNode { text: Some(text.unwrap_or_default()), ..CanvasNode::new(CanvasNodeType::Text, x, y, width, height) },
+        "file" => CanvasNode { file: Some(file.unwrap_or_default()), ..CanvasNode::new(CanvasNodeType::File, x, y, width, height) },
+        "link" => CanvasNode { url: Some(url.unwrap_or_default()), ..CanvasNode::new(CanvasNodeType::Link, x, y, width, height) },
+        "group" => CanvasNode { label, ..CanvasNode::new(CanvasNodeType::Group, x, y, width, height) },
         _ => return Err(format!("Unknown node type: {}", node_type)),
     };
-
-    canvas.save(&full_path)?;
+    let id = node.id.clone();
+    let op = CanvasOp::add_node(&session.canvas, node);
+    let applied = session.perform(op, chrono::Utc::now().timestamp_millis());
+    session.finish(applied)?;
     Ok(id)
 }
 
+/// Moves a node; consecutive moves while dragging undo as one step.
 #[tauri::command]
 pub fn canvas_move_node(state: State<AppState>, path: String, node_id: String, x: f64, y: f64) -> Result<bool, String> {
-    validate_canvas_path(&path)?;
-    let vault_path = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
-    let full_path = std::path::Path::new(&*vault_path).join(&path);
-    let mut canvas = Canvas::load(&full_path)?;
-    let result = canvas.move_node(&node_id, x, y);
-    canvas.save(&full_path)?;
-    Ok(result)
+    let mut session = canvas_session(&state, &path)?;
+    let applied = match CanvasOp::move_node(&session.canvas, &node_id, x, y) {
+        Some(op) => session.perform(op, chrono::Utc::now().timestamp_millis()),
+        None => false,
+    };
+    session.finish(applied)?;
+    Ok(applied)
 }
 
 #[tauri::command]
 pub fn canvas_delete_node(state: State<AppState>, path: String, node_id: String) -> Result<bool, String> {
+    let mut session = canvas_session(&state, &path)?;
+    let applied = match CanvasOp::remove_node(&session.canvas, &node_id) {
+        Some(op) => session.perform(op, chrono::Utc::now().timestamp_millis()),
+        None => false,
+    };
+    session.finish(applied)?;
+    Ok(applied)
+}
+
+/// Reverts the latest canvas op, even from an earlier session.
+#[tauri::command]
+pub fn canvas_undo(state: State<AppState>, path: String) -> Result<HistoryStep, String> {
+    let mut session = canvas_session(&state, &path)?;
+    let applied = session.undo();
+    session.finish(applied)
+}
+
+#[tauri::command]
+pub fn canvas_redo(state: State<AppState>, path: String) -> Result<HistoryStep, String> {
+    let mut session = canvas_session(&state, &path)?;
+    let applied = session.redo();
+    session.finish(applied)
+}
+
+/// Lays out the given nodes, or the whole canvas, as one undoable step.
+#[tauri::command]
+pub fn canvas_layout(state: State<AppState>, path: String, options: Option<CanvasLayoutOptions>, selection: Option<Vec<String>>) -> Result<HistoryStep, String> {
+    let mut session = canvas_session(&state, &path)?;
+    let mut laid_out = session.canvas.clone();
+    laid_out.apply_layout(selection.as_deref(), &options.unwrap_or_default());
+    let applied = match CanvasOp::reshape(&session.canvas, &laid_out) {
+        Some(op) => session.perform(op, chrono::Utc::now().timestamp_millis()),
+        None => false,
+    };
+    session.finish(applied)
+}
+
+/// Writes the canvas at `path` as SVG or a Markdown outline to `target` in
+/// the vault, or to the export folder. Returns the written path.
+#[tauri::command]
+pub fn export_canvas(state: State<AppState>, path: String, format: CanvasExportFormat, target: Option<String>) -> Result<String, String> {
     validate_canvas_path(&path)?;
     let vault_path = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
-    let full_path = std::path::Path::new(&*vault_path).join(&path);
-    let mut canvas = Canvas::load(&full_path)?;
-    let result = canvas.delete_node(&node_id);
-    canvas.save(&full_path)?;
-    Ok(result)
+    let relative = canvas_export::export_canvas(&vault_path, &path, target.as_deref(), format)?;
+    reindex_export(&state, &vault_path, &relative);
+    Ok(relative)
 }
 
 // ===== Bookmarks Commands =====
@@ -239,6 +436,87 @@ pub fn search_tags(state: State<AppState>, query: String) -> Result<Vec<String>,
     Ok(idx.search(&query))
 }
 
+// ===== Properties =====
+
+#[tauri::command]
+pub fn get_property_types(state: State<AppState>) -> Result<PropertyRegistry, String> {
+    let vault_path = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    Ok(PropertyRegistry::load(&vault_path))
+}
+
+/// Register a type for a property key; `property_type: null` clears it.
+#[tauri::command]
+pub fn set_property_type(state: State<AppState>, key: String, property_type: Option<PropertyType>) -> Result<(), String> {
+    let vault_path = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    let mut registry = PropertyRegistry::load(&vault_path);
+    registry.set(&key, property_type);
+    registry.save(&vault_path)
+}
+
+#[tauri::command]
+pub fn validate_properties(state: State<AppState>, content: String) -> Result<Vec<PropertyIssue>, String> {
+    let vault_path = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    PropertyRegistry::load(&vault_path).validate_content(&content)
+}
+
+#[tauri::command]
+pub fn list_property_keys(state: State<AppState>) -> Result<Vec<PropertyKeyInfo>, String> {
+    let vault_path = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    let password = state.vault_password.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    let mut idx = state.property_index.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    if idx.key_count() == 0 {
+        idx.build_from_vault(&vault_path, password.as_deref());
+    }
+    Ok(idx.keys(&PropertyRegistry::load(&vault_path)))
+}
+
+#[tauri::command]
+pub fn list_property_values(state: State<AppState>, key: String, prefix: Option<String>) -> Result<Vec<PropertyValueCount>, String> {
+    let idx = state.property_index.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    Ok(match prefix {
+        Some(p) => idx.autocomplete_value(&key, &p),
+        None => idx.values(&key),
+    })
+}
+
+#[tauri::command]
+pub fn files_with_property(state: State<AppState>, key: String, value: Option<String>) -> Result<Vec<String>, String> {
+    let idx = state.property_index.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    Ok(idx.files_with(&key, value.as_deref()))
+}
+
+/// Rename, set, remove or convert properties on a selection of notes or on
+/// the notes matched by a query. `dry_run` returns the diff without writing.
+#[tauri::command]
+pub fn bulk_edit_properties(
+    state: State<AppState>,
+    selection: PropertySelection,
+    ops: Vec<PropertyOp>,
+    dry_run: bool,
+) -> Result<BulkEditReport, String> {
+    let vault_path = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    let password = state.vault_password.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+
+    let paths = match selection {
+        PropertySelection::Paths(paths) => paths,
+        PropertySelection::Query(source) => {
+            let mut cache = state.meta_cache.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+            cache.ensure_built(&vault_path);
+            let props = state.property_index.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+            let tasks = state.task_index.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+            query::query_paths(&source, &QueryContext::new(&vault_path, &cache, &props, &tasks))?
+        }
+    };
+
+    let report = bulk_properties::bulk_edit(&vault_path, &paths, &ops, dry_run, password.as_deref())?;
+    if !dry_run {
+        for change in &report.changed {
+            state.reindex_note(&vault_path, &change.path, &change.content).ok();
+        }
+    }
+    Ok(report)
+}
+
 // ===== File Recovery =====
 
 use crate::features::file_recovery;
@@ -293,15 +571,10 @@ pub struct LocalGraphData {
 }
 
 #[tauri::command]
-pub fn get_local_graph(state: State<AppState>, path: String, depth: Option<usize>) -> Result<LocalGraphData, String> {
-    let mut cache = state.meta_cache.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
-    if cache.is_stale(30) {
-        let vault_path = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
-        cache.rebuild(&vault_path);
-    }
-
+pub fn get_local_graph(state: State<AppState>, path: String, depth: Option<usize>, query: Option<GraphQuery>) -> Result<LocalGraphData, String> {
+    let vault_path = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?.clone();
     let max_depth = depth.unwrap_or(1).min(3);
-    let full_graph = graph::compute_graph(&cache);
+    let full_graph = query_vault_graph(&state, &vault_path, &query.unwrap_or_default())?;
 
     // BFS from the center node to collect nodes within depth
     let mut visited = std::collections::HashSet::new();
@@ -331,7 +604,7 @@ pub fn get_local_graph(state: State<AppState>, path: String, depth: Option<usize
 
     let nodes: Vec<GraphNode> = full_graph.nodes.into_iter()
         .filter(|n| visited.contains(&n.id))
-        .map(|n| GraphNode { id: n.id, name: n.name })
+        .map(|n| GraphNode { id: n.id, name: n.name, group: n.group, kind: n.kind, x: None, y: None })
         .collect();
 
     let node_ids: std::collections::HashSet<&String> = nodes.iter().map(|n| &n.id).collect();
@@ -347,6 +620,52 @@ pub fn get_local_graph(state: State<AppState>, path: String, depth: Option<usize
     })
 }
 
+// ===== Related Notes =====
+
+#[tauri::command]
+pub fn find_similar_notes(state: State<AppState>, path: String, limit: Option<usize>) -> Result<Vec<SimilarNote>, String> {
+    let vault_path = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    let search = state.search_index.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    let mut cache = state.meta_cache.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    cache.ensure_built(&vault_path);
+    related::similar_notes(&search, &cache, &path, limit.unwrap_or(10))
+}
+
+#[tauri::command]
+pub fn link_candidate_report(state: State<AppState>, per_note: Option<usize>, min_score: Option<f32>) -> Result<Vec<LinkCandidate>, String> {
+    let vault_path = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    let search = state.search_index.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    let mut cache = state.meta_cache.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    cache.ensure_built(&vault_path);
+    related::link_candidates(&search, &cache, per_note.unwrap_or(5), min_score.unwrap_or(0.0))
+}
+
+// ===== Tasks =====
+
+#[tauri::command]
+pub fn query_tasks(state: State<AppState>, query: TaskQuery) -> Result<Vec<Task>, String> {
+    let tasks = state.task_index.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    tasks.query(&query, chrono::Local::now().date_naive())
+}
+
+#[tauri::command]
+pub fn get_note_tasks(state: State<AppState>, path: String) -> Result<Vec<Task>, String> {
+    let tasks = state.task_index.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    Ok(tasks.file_tasks(&path).to_vec())
+}
+
+/// Toggle the task on a 1-based `line`, rewriting only that line (plus the
+/// next occurrence of a recurring task).
+#[tauri::command]
+pub fn toggle_task(state: State<AppState>, path: String, line: usize) -> Result<ToggleResult, String> {
+    let vault_path = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    let password = state.vault_password.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    let result = tasks::toggle_task(&vault_path, &path, line, password.as_deref(), chrono::Local::now().date_naive())?;
+
+    state.reindex_note(&vault_path, &path, &result.content).ok();
+    Ok(result)
+}
+
 // ===== Navigation History =====
 
 #[tauri::command]
@@ -390,14 +709,14 @@ pub fn nav_state(state: State<AppState>) -> Result<serde_json::Value, String> {
 
 #[tauri::command]
 pub fn canvas_add_edge(state: State<AppState>, canvas_path: String, from: String, to: String) -> Result<serde_json::Value, String> {
-    validate_canvas_path(&canvas_path)?;
-    let vault_path = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
-    let full = std::path::Path::new(&*vault_path).join(&canvas_path);
-    let data = std::fs::read_to_string(&full).map_err(|e| format!("Read error: {}", e))?;
-    let mut canvas: Canvas = serde_json::from_str(&data).map_err(|e| format!("Parse error: {}", e))?;
-    let id = canvas.add_edge(&from, &to, None, None, None);
-    let json = serde_json::to_string(&canvas).map_err(|e| format!("Serialize error: {}", e))?;
-    std::fs::write(&full, json).map_err(|e| format!("Write error: {}", e))?;
+    let mut session = canvas_session(&state, &canvas_path)?;
+    let edge = CanvasEdge::new(&from, &to, None, None, None);
+    let id = edge.id.clone();
+    let op = CanvasOp::add_edge(&session.canvas, edge);
+    if !session.perform(op, chrono::Utc::now().timestamp_millis()) {
+        return Err(format!("Cannot connect {} to {}: node not found", from, to));
+    }
+    session.finish(true)?;
     Ok(serde_json::json!({ "id": id, "from": from, "to": to }))
 }
 
@@ -413,86 +732,15 @@ pub fn search_by_tag(state: State<AppState>, tag: String) -> Result<Vec<serde_js
 }
 
 #[tauri::command]
-pub fn resolve_embeds(state: State<AppState>, content: String, _current_path: String, max_depth: Option<u32>) -> Result<serde_json::Value, String> {
+pub fn resolve_embeds(state: State<AppState>, content: String, current_path: String, max_depth: Option<u32>) -> Result<serde_json::Value, String> {
     let vault_path = state.vault_path.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
-    let max_d = max_depth.unwrap_or(5);
-    let mut processed = content.clone();
-    let mut embeds = Vec::new();
-    let re = regex::Regex::new(r"!\[\[([^\]]+)\]\]").map_err(|e| format!("Regex error: {}", e))?;
-    
-    if max_d > 0 {
-        for cap in re.captures_iter(&content) {
-            let full_match = cap[0].to_string();
-            let note_ref = &cap[1];
-            let (note_path, heading) = if let Some(idx) = note_ref.find('#') {
-                (&note_ref[..idx], Some(&note_ref[idx+1..]))
-            } else {
-                (note_ref, None)
-            };
-            
-            // Try to resolve the note
-            let resolved_path = if note_path.ends_with(".md") {
-                note_path.to_string()
-            } else {
-                format!("{}.md", note_path)
-            };
-            let full_file = std::path::Path::new(&*vault_path).join(&resolved_path);
-            
-            match std::fs::read_to_string(&full_file) {
-                Ok(mut file_content) => {
-                    // Strip frontmatter
-                    if file_content.starts_with("---") {
-                        if let Some(end) = file_content[3..].find("---") {
-                            file_content = file_content[end+6..].trim_start().to_string();
-                        }
-                    }
-                    // Extract heading section if specified
-                    if let Some(h) = heading {
-                        let h_lower = h.to_lowercase();
-                        let lines: Vec<&str> = file_content.lines().collect();
-                        let mut in_section = false;
-                        let mut section_lines = Vec::new();
-                        let mut section_level = 0;
-                        for line in &lines {
-                            if line.starts_with('#') {
-                                let level = line.chars().take_while(|c| *c == '#').count();
-                                let title = line.trim_start_matches('#').trim().to_lowercase();
-                                if title == h_lower && !in_section {
-                                    in_section = true;
-                                    section_level = level;
-                                    continue;
-                                } else if in_section && level <= section_level {
-                                    break;
-                                }
-                            }
-                            if in_section {
-                                section_lines.push(*line);
-                            }
-                        }
-                        file_content = section_lines.join("\n");
-                    }
-                    embeds.push(serde_json::json!({
-                        "fullMatch": full_match,
-                        "notePath": resolved_path,
-                        "heading": heading,
-                        "content": file_content,
-                    }));
-                    processed = processed.replace(&full_match, &file_content);
-                }
-                Err(e) => {
-                    embeds.push(serde_json::json!({
-                        "fullMatch": full_match,
-                        "notePath": resolved_path,
-                        "heading": heading,
-                        "error": format!("Not found: {}", e),
-                    }));
-                }
-            }
-        }
-    }
-    
-    Ok(serde_json::json!({
-        "processed": processed,
-        "embeds": embeds,
-    }))
+    let password = state.vault_password.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+    let result = transclusion::resolve_embeds(
+        &vault_path,
+        &content,
+        &current_path,
+        max_depth.unwrap_or(5) as usize,
+        password.as_deref(),
+    );
+    serde_json::to_value(result).map_err(|e| format!("Serialize error: {}", e))
 }
@@ -83,6 +83,20 @@ pub fn decrypt_file_content(encrypted_json: &str, password: &str) -> Result<Stri
     String::from_utf8(plaintext).map_err(|e| format!("Invalid UTF-8: {}", e))
 }
 
+/// Whether note content read from disk is an encrypted `EncryptedData` blob
+pub fn is_encrypted_content(content: &str) -> bool {
+    content.starts_with('{') && content.contains("\"salt\"")
+}
+
+/// Return the plaintext of a note read from disk: unencrypted content is passed
+/// through, encrypted content is decrypted if a password is available.
+pub fn plaintext_content(content: String, password: Option<&str>) -> Option<String> {
+    if !is_encrypted_content(&content) {
+        return Some(content);
+    }
+    password.and_then(|pwd| decrypt_file_content(&content, pwd).ok())
+}
+
 /// Verify a password against encrypted data (try to decrypt)
 pub fn verify_password(encrypted_json: &str, password: &str) -> bool {
     decrypt_file_content(encrypted_json, password).is_ok()
@@ -1,10 +1,19 @@
+use crate::encryption;
+use notify::{Event, EventKind};
 use rand::Rng;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fs;
 use std::path::Path;
+use std::sync::LazyLock;
+use std::time::UNIX_EPOCH;
 use walkdir::WalkDir;
 
-#[derive(Debug, Serialize, Clone)]
+static BLOCK_ID_RE: LazyLock<regex::Regex> = LazyLock::new(|| {
+    regex::Regex::new(r"\^([a-zA-Z0-9][\w-]*)$").unwrap()
+});
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct BlockResult {
     pub note_path: String,
     pub block_id: String,
@@ -19,150 +28,486 @@ pub fn generate_block_id() -> String {
     (0..6).map(|_| chars[rng.gen_range(0..chars.len())]).collect()
 }
 
-/// Find a block by its ID across the entire vault.
-/// Block IDs appear as `^block-id` at the end of a line (paragraph, list item, etc.)
-pub fn find_block_by_id(vault_path: &str, block_id: &str) -> Option<BlockResult> {
-    let marker = format!("^{}", block_id);
-    
-    for entry in WalkDir::new(vault_path).into_iter().filter_map(|e| e.ok()) {
-        let path = entry.path();
-        if path.extension().map(|e| e == "md").unwrap_or(false) {
-            if let Ok(content) = fs::read_to_string(path) {
-                for (i, line) in content.lines().enumerate() {
-                    let trimmed = line.trim_end();
-                    if trimmed.ends_with(&marker) || trimmed == marker {
-                        let relative = path.strip_prefix(vault_path)
-                            .unwrap_or(path)
-                            .to_string_lossy()
-                            .to_string();
-                        
-                        let block_content = extract_block_content(&content, i);
-                        
-                        return Some(BlockResult {
-                            note_path: relative,
-                            block_id: block_id.to_string(),
-                            content: block_content,
-                            line_number: i + 1,
-                        });
-                    }
-                }
-            }
-        }
-    }
-    None
-}
-
-/// Get block content from a specific note by block ID
-pub fn get_block_content(vault_path: &str, note_path: &str, block_id: &str) -> Result<String, String> {
-    let full_path = Path::new(vault_path).join(note_path);
-    let content = fs::read_to_string(&full_path)
-        .map_err(|e| format!("Failed to read {}: {}", note_path, e))?;
-    
-    let marker = format!("^{}", block_id);
-    
-    for (i, line) in content.lines().enumerate() {
-        let trimmed = line.trim_end();
-        if trimmed.ends_with(&marker) || trimmed == marker {
-            return Ok(extract_block_content(&content, i));
-        }
-    }
-    
-    Err(format!("Block ^{} not found in {}", block_id, note_path))
-}
-
-/// List all block IDs in a specific note
-pub fn list_block_ids(vault_path: &str, note_path: &str) -> Result<Vec<BlockResult>, String> {
-    let full_path = Path::new(vault_path).join(note_path);
-    let content = fs::read_to_string(&full_path)
-        .map_err(|e| format!("Failed to read {}: {}", note_path, e))?;
-    
+/// All `^block-id` markers of a note's content. Markers inside fenced code
+/// are code, not block ids.
+pub fn scan_blocks(note_path: &str, content: &str) -> Vec<BlockResult> {
     let mut results = Vec::new();
-    let re = regex::Regex::new(r"\^([a-zA-Z0-9][\w-]*)$").unwrap();
-    
+    let mut in_fence = false;
     for (i, line) in content.lines().enumerate() {
+        if is_fence(line) {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
         let trimmed = line.trim_end();
-        if let Some(caps) = re.captures(trimmed) {
-            let block_id = caps.get(1).unwrap().as_str().to_string();
+        if let Some(caps) = BLOCK_ID_RE.captures(trimmed) {
             results.push(BlockResult {
                 note_path: note_path.to_string(),
-                block_id: block_id.clone(),
-                content: extract_block_content(&content, i),
+                block_id: caps[1].to_string(),
+                content: extract_block_content(content, i),
                 line_number: i + 1,
             });
         }
     }
-    
-    Ok(results)
+    results
 }
 
-/// List all block IDs across the entire vault
-pub fn list_all_block_ids(vault_path: &str) -> Vec<BlockResult> {
-    let mut results = Vec::new();
-    let re = regex::Regex::new(r"\^([a-zA-Z0-9][\w-]*)$").unwrap();
-    
-    for entry in WalkDir::new(vault_path).into_iter().filter_map(|e| e.ok()) {
-        let path = entry.path();
-        if path.extension().map(|e| e == "md").unwrap_or(false) {
-            if let Ok(content) = fs::read_to_string(path) {
-                let relative = path.strip_prefix(vault_path)
-                    .unwrap_or(path)
-                    .to_string_lossy()
-                    .to_string();
-                
-                for (i, line) in content.lines().enumerate() {
-                    let trimmed = line.trim_end();
-                    if let Some(caps) = re.captures(trimmed) {
-                        let block_id = caps.get(1).unwrap().as_str().to_string();
-                        results.push(BlockResult {
-                            note_path: relative.clone(),
-                            block_id,
-                            content: extract_block_content(&content, i),
-                            line_number: i + 1,
-                        });
-                    }
-                }
+fn is_fence(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    trimmed.starts_with("```") || trimmed.starts_with("~~~")
+}
+
+/// 0-based inclusive line span of the block a marker on `marker_line` labels.
+/// A marker on a line of its own labels the block above it, which may be set
+/// off by blank lines (the form used for tables, quotes and code blocks).
+pub(crate) fn block_span(lines: &[&str], marker_line: usize) -> Option<(usize, usize)> {
+    if !lines[marker_line].trim().starts_with('^') {
+        // Walk backwards until a blank line, stopping at headings
+        let mut start = marker_line;
+        while start > 0 {
+            let prev = lines[start - 1].trim();
+            if prev.is_empty() || prev.starts_with('#') {
+                break;
             }
+            start -= 1;
         }
+        return Some((start, marker_line));
     }
-    
-    results
+    let end = (0..marker_line).rev().find(|&i| !lines[i].trim().is_empty())?;
+    if is_fence(lines[end]) {
+        // Closing fence: the block starts at its opening fence
+        if let Some(open) = (0..end).rev().find(|&i| is_fence(lines[i])) {
+            return Some((open, end));
+        }
+    }
+    let mut start = end;
+    while start > 0 && !lines[start - 1].trim().is_empty() {
+        start -= 1;
+    }
+    Some((start, end))
 }
 
-/// Extract the block content (the paragraph/list-item containing the block ID marker).
-/// Walks backwards from the marker line to find the start of the block, then collects
-/// all lines until the next blank line or block boundary.
+/// Extract the block content (the paragraph/list-item containing the block ID marker),
+/// with the `^block-id` marker stripped.
 fn extract_block_content(content: &str, marker_line: usize) -> String {
     let lines: Vec<&str> = content.lines().collect();
-    
-    // Find start of this block (walk backwards until blank line or start)
-    let mut start = marker_line;
-    while start > 0 {
-        let prev = lines[start - 1].trim();
-        if prev.is_empty() {
-            break;
+    let Some((start, end)) = block_span(&lines, marker_line) else {
+        return String::new();
+    };
+    let mut result: Vec<String> = lines[start..=end].iter().map(|l| l.to_string()).collect();
+
+    // Remove the ^block-id from the marker line
+    if end == marker_line {
+        if let Some(last) = result.last_mut() {
+            if let Some(pos) = last.rfind(" ^") {
+                *last = last[..pos].to_string();
+            }
+        }
+    }
+
+    result.join("\n").trim().to_string()
+}
+
+// ─── Assigning block ids ─────────────────────────────────────────────
+
+/// Result of attaching a `^block-id` to a block.
+#[derive(Debug, Clone, Serialize)]
+pub struct BlockAssignment {
+    pub note_path: String,
+    pub block_id: String,
+    /// Ready-to-paste link, e.g. `[[Note#^abc123]]`
+    pub link: String,
+    /// 1-based line the marker is on
+    pub line: usize,
+    /// False when the block already had an id
+    pub created: bool,
+    #[serde(skip)]
+    pub content: String,
+}
+
+/// Where a block's marker goes.
+enum Placement {
+    /// At the end of this line (paragraphs, list items, headings)
+    Append(usize),
+    /// On its own line after the block ending on this line (tables, quotes,
+    /// code blocks)
+    After(usize),
+}
+
+fn is_list_item(line: &str) -> bool {
+    static LIST_RE: LazyLock<regex::Regex> = LazyLock::new(|| {
+        regex::Regex::new(r"^\s*(?:[-*+]|\d+[.)])(?:\s|$)").unwrap()
+    });
+    LIST_RE.is_match(line)
+}
+
+/// 1-based line containing byte `offset` of `content`.
+pub fn line_at_offset(content: &str, offset: usize) -> usize {
+    let offset = offset.min(content.len());
+    content.as_bytes()[..offset].iter().filter(|b| **b == b'\n').count() + 1
+}
+
+fn placement(lines: &[&str], line: usize) -> Result<Placement, String> {
+    // Frontmatter and fenced code first: neither can be judged line by line
+    let mut i = 0;
+    if lines.first().map(|l| l.trim_end() == "---").unwrap_or(false) {
+        if let Some(end) = (1..lines.len()).find(|&j| lines[j].trim_end() == "---") {
+            if line <= end {
+                return Err("Cannot attach a block id inside frontmatter".to_string());
+            }
+            i = end + 1;
         }
-        // Stop at headings (they start their own block)
-        if prev.starts_with('#') && start != marker_line {
+    }
+    while i < lines.len() {
+        if is_fence(lines[i]) {
+            let close = (i + 1..lines.len()).find(|&j| is_fence(lines[j])).unwrap_or(lines.len() - 1);
+            if (i..=close).contains(&line) {
+                return Ok(Placement::After(close));
+            }
+            i = close;
+        }
+        i += 1;
+    }
+
+    let text = lines[line].trim();
+    if text.is_empty() {
+        return Err(format!("Line {} is empty", line + 1));
+    }
+    let grouped = |prefix: char| {
+        let mut end = line;
+        while end + 1 < lines.len() && lines[end + 1].trim_start().starts_with(prefix) {
+            end += 1;
+        }
+        Placement::After(end)
+    };
+    if text.starts_with('|') {
+        return Ok(grouped('|'));
+    }
+    if text.starts_with('>') {
+        return Ok(grouped('>'));
+    }
+    if text.starts_with('#') || is_list_item(lines[line]) {
+        return Ok(Placement::Append(line));
+    }
+    // Continuation line of a list item belongs to that item
+    let mut up = line;
+    while up > 0 && !lines[up - 1].trim().is_empty() {
+        up -= 1;
+        if is_list_item(lines[up]) {
+            return Ok(Placement::Append(up));
+        }
+    }
+    // Paragraph: the marker goes on its last line
+    let mut end = line;
+    while end + 1 < lines.len() {
+        let next = lines[end + 1].trim();
+        if next.is_empty() || next.starts_with('#') || is_fence(next) || is_list_item(lines[end + 1]) {
             break;
         }
-        start -= 1;
+        end += 1;
+    }
+    Ok(Placement::Append(end))
+}
+
+/// Attach a `^block-id` to the block containing `line` (1-based) of the note
+/// at `note_path`, or reuse the id it already has. `id` requests a specific
+/// id; otherwise a random one is generated. `taken` reports ids already used
+/// elsewhere in the vault.
+pub fn assign_block_id<F>(note_path: &str, content: &str, line: usize, id: Option<&str>, taken: F) -> Result<BlockAssignment, String>
+where
+    F: Fn(&str) -> bool,
+{
+    let crlf = content.contains("\r\n");
+    let mut lines: Vec<&str> = content.split('\n').map(|l| l.strip_suffix('\r').unwrap_or(l)).collect();
+    if line == 0 || line > lines.len() {
+        return Err(format!("Line {} is out of range", line));
+    }
+    let placement = placement(&lines, line - 1)?;
+    let stem = Path::new(note_path).file_stem().unwrap_or_default().to_string_lossy().to_string();
+    let assignment = |block_id: &str, marker: usize, created: bool, content: String| BlockAssignment {
+        note_path: note_path.to_string(),
+        block_id: block_id.to_string(),
+        link: format!("[[{}#^{}]]", stem, block_id),
+        line: marker + 1,
+        created,
+        content,
+    };
+
+    // Reuse an existing marker
+    let existing = match placement {
+        Placement::Append(l) => Some(l),
+        Placement::After(end) => (end + 1..lines.len().min(end + 3))
+            .find(|&j| !lines[j].trim().is_empty())
+            .filter(|&j| lines[j].trim().starts_with('^')),
+    };
+    if let Some(l) = existing {
+        if let Some(caps) = BLOCK_ID_RE.captures(lines[l].trim_end()) {
+            return Ok(assignment(&caps[1], l, false, content.to_string()));
+        }
+    }
+
+    let in_note: BTreeSet<String> = scan_blocks(note_path, content).into_iter().map(|b| b.block_id).collect();
+    let block_id = match id {
+        Some(id) => {
+            let id = id.trim_start_matches('^');
+            if !BLOCK_ID_RE.find(&format!("^{}", id)).map(|m| m.start() == 0).unwrap_or(false) {
+                return Err(format!("Invalid block id: {}", id));
+            }
+            if in_note.contains(id) || taken(id) {
+                return Err(format!("Block id ^{} is already in use", id));
+            }
+            id.to_string()
+        }
+        None => loop {
+            let id = generate_block_id();
+            if !in_note.contains(&id) && !taken(&id) {
+                break id;
+            }
+        },
+    };
+
+    let marker_text = format!("^{}", block_id);
+    let appended;
+    let marker = match placement {
+        Placement::Append(l) => {
+            appended = format!("{} {}", lines[l].trim_end(), marker_text);
+            lines[l] = &appended;
+            l
+        }
+        Placement::After(end) => {
+            let mut insert = vec!["", marker_text.as_str()];
+            if lines.get(end + 1).map(|l| !l.trim().is_empty()).unwrap_or(false) {
+                insert.push("");
+            }
+            lines.splice(end + 1..end + 1, insert);
+            end + 2
+        }
+    };
+    let updated = lines.join(if crlf { "\r\n" } else { "\n" });
+    Ok(assignment(&block_id, marker, true, updated))
+}
+
+// ─── BlockIndex ──────────────────────────────────────────────────────
+
+const INDEX_FILE: &str = "block-index.json";
+
+/// Blocks of one note plus the file stamp they were read at.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct FileBlocks {
+    /// Modification time in milliseconds since the epoch
+    mtime: u64,
+    size: u64,
+    blocks: Vec<BlockResult>,
+}
+
+/// Vault-wide `^block-id` index. Built once, persisted to
+/// `.oxidian/block-index.json`, and kept current file by file. On load only
+/// notes whose size or modification time changed are re-read.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct BlockIndex {
+    files: BTreeMap<String, FileBlocks>,
+    /// block id → notes defining it
+    #[serde(skip)]
+    ids: HashMap<String, BTreeSet<String>>,
+    /// Encrypted vaults keep the index in memory only (it holds block text)
+    #[serde(skip)]
+    persist: bool,
+}
+
+fn file_stamp(path: &Path) -> Option<(u64, u64)> {
+    let meta = fs::metadata(path).ok()?;
+    let mtime = meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?.as_millis() as u64;
+    Some((mtime, meta.len()))
+}
+
+impl BlockIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the persisted index and bring it up to date with the vault:
+    /// changed notes are re-read, deleted ones dropped. With `persist` off
+    /// (encrypted vaults) nothing is read from disk, and an index file left
+    /// over from before encryption is deleted since it holds plaintext.
+    pub fn load_or_build(vault_path: &str, password: Option<&str>, persist: bool) -> Self {
+        let index_path = Path::new(vault_path).join(".oxidian").join(INDEX_FILE);
+        let mut index = if persist {
+            fs::read_to_string(&index_path).ok()
+                .and_then(|json| serde_json::from_str::<BlockIndex>(&json).ok())
+                .unwrap_or_default()
+        } else {
+            if index_path.exists() {
+                fs::remove_file(&index_path).ok();
+            }
+            BlockIndex::default()
+        };
+        index.persist = persist;
+
+        let mut seen = BTreeSet::new();
+        let mut changed = false;
+        for entry in WalkDir::new(vault_path)
+            .into_iter()
+            .filter_entry(|e| e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'))
+            .filter_map(|e| e.ok())
+        {
+            let path = entry.path();
+            if !path.is_file() || path.extension().map(|e| e != "md").unwrap_or(true) {
+                continue;
+            }
+            let relative = path.strip_prefix(vault_path).unwrap_or(path).to_string_lossy().to_string();
+            let stamp = file_stamp(path);
+            let fresh = index.files.get(&relative)
+                .is_some_and(|f| stamp == Some((f.mtime, f.size)));
+            if !fresh {
+                let content = fs::read_to_string(path).ok()
+                    .and_then(|c| encryption::plaintext_content(c, password));
+                match content {
+                    Some(content) => {
+                        index.put(&relative, stamp, scan_blocks(&relative, &content));
+                    }
+                    None => {
+                        index.files.remove(&relative);
+                    }
+                }
+                changed = true;
+            }
+            seen.insert(relative);
+        }
+        let before = index.files.len();
+        index.files.retain(|path, _| seen.contains(path));
+        changed |= index.files.len() != before;
+
+        index.rebuild_ids();
+        if changed {
+            index.save(vault_path).ok();
+        }
+        index
+    }
+
+    fn rebuild_ids(&mut self) {
+        self.ids.clear();
+        for (path, file) in &self.files {
+            for block in &file.blocks {
+                self.ids.entry(block.block_id.clone()).or_default().insert(path.clone());
+            }
+        }
+    }
+
+    /// Store a note's blocks. Returns true if its blocks changed.
+    fn put(&mut self, relative_path: &str, stamp: Option<(u64, u64)>, blocks: Vec<BlockResult>) -> bool {
+        let (mtime, size) = stamp.unwrap_or_default();
+        let old = self.files.insert(relative_path.to_string(), FileBlocks { mtime, size, blocks });
+        let new = &self.files[relative_path].blocks;
+        let unchanged = old.as_ref().is_some_and(|o| &o.blocks == new);
+        if let Some(old) = &old {
+            self.unlink_ids(relative_path, &old.blocks);
+        }
+        for block in &self.files[relative_path].blocks {
+            self.ids.entry(block.block_id.clone()).or_default().insert(relative_path.to_string());
+        }
+        !unchanged
+    }
+
+    fn unlink_ids(&mut self, relative_path: &str, blocks: &[BlockResult]) {
+        for block in blocks {
+            if let Some(paths) = self.ids.get_mut(&block.block_id) {
+                paths.remove(relative_path);
+                if paths.is_empty() {
+                    self.ids.remove(&block.block_id);
+                }
+            }
+        }
+    }
+
+    /// Re-index a note after it was written. Persists the index if the note's
+    /// blocks changed.
+    pub fn index_file(&mut self, vault_path: &str, relative_path: &str, content: &str) {
+        let stamp = file_stamp(&Path::new(vault_path).join(relative_path));
+        if self.put(relative_path, stamp, scan_blocks(relative_path, content)) {
+            self.save(vault_path).ok();
+        }
+    }
+
+    pub fn remove_file(&mut self, vault_path: &str, relative_path: &str) {
+        if let Some(old) = self.files.remove(relative_path) {
+            self.unlink_ids(relative_path, &old.blocks);
+            self.save(vault_path).ok();
+        }
+    }
+
+    /// Apply a file system event from the vault watcher. Every markdown path
+    /// in the event is re-read, or dropped if it no longer exists.
+    pub fn handle_event(&mut self, vault_path: &str, event: &Event, password: Option<&str>) {
+        if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)) {
+            return;
+        }
+        for path in &event.paths {
+            let Ok(relative) = path.strip_prefix(vault_path) else { continue };
+            if path.extension().map(|e| e != "md").unwrap_or(true) {
+                continue;
+            }
+            let relative = relative.to_string_lossy().to_string();
+            let content = if path.is_file() {
+                fs::read_to_string(path).ok().and_then(|c| encryption::plaintext_content(c, password))
+            } else {
+                None
+            };
+            match content {
+                Some(content) => self.index_file(vault_path, &relative, &content),
+                None => self.remove_file(vault_path, &relative),
+            }
+        }
+    }
+
+    /// Write the index to `.oxidian/block-index.json` (no-op for in-memory indexes).
+    pub fn save(&self, vault_path: &str) -> Result<(), String> {
+        if !self.persist {
+            return Ok(());
+        }
+        let dir = Path::new(vault_path).join(".oxidian");
+        fs::create_dir_all(&dir).map_err(|e| format!("Failed to create .oxidian dir: {}", e))?;
+        let json = serde_json::to_string(self).map_err(|e| format!("Failed to serialize block index: {}", e))?;
+        fs::write(dir.join(INDEX_FILE), json).map_err(|e| format!("Failed to write block index: {}", e))
+    }
+
+    /// Look up a block id. If several notes define it, the first path wins.
+    pub fn find(&self, block_id: &str) -> Option<&BlockResult> {
+        let path = self.ids.get(block_id)?.iter().next()?;
+        self.find_in_note(path, block_id)
+    }
+
+    /// Look up a block id within one note.
+    pub fn find_in_note(&self, note_path: &str, block_id: &str) -> Option<&BlockResult> {
+        self.files.get(note_path)?.blocks.iter().find(|b| b.block_id == block_id)
+    }
+
+    /// Every note that defines `block_id`.
+    pub fn notes_with(&self, block_id: &str) -> Vec<String> {
+        self.ids.get(block_id).map(|paths| paths.iter().cloned().collect()).unwrap_or_default()
+    }
+
+    pub fn note_blocks(&self, note_path: &str) -> &[BlockResult] {
+        self.files.get(note_path).map(|f| f.blocks.as_slice()).unwrap_or(&[])
+    }
+
+    /// Every indexed block, ordered by note path and line.
+    pub fn all(&self) -> Vec<BlockResult> {
+        self.files.values().flat_map(|f| f.blocks.iter().cloned()).collect()
+    }
+
+    /// Blocks for autocomplete: ids starting with `query` first, then blocks
+    /// whose text contains it (case-insensitive).
+    pub fn search(&self, query: &str, limit: usize) -> Vec<BlockResult> {
+        let query = query.trim_start_matches('^').to_lowercase();
+        let blocks = self.files.values().flat_map(|f| f.blocks.iter());
+        let (mut by_id, by_text): (Vec<&BlockResult>, Vec<&BlockResult>) = blocks
+            .filter(|b| b.block_id.to_lowercase().starts_with(&query) || b.content.to_lowercase().contains(&query))
+            .partition(|b| b.block_id.to_lowercase().starts_with(&query));
+        by_id.extend(by_text);
+        by_id.into_iter().take(limit).cloned().collect()
     }
-    
-    // Collect block lines, stripping the ^block-id marker from the last line
-    let block_lines: Vec<&str> = lines[start..=marker_line].to_vec();
-    let mut result: Vec<String> = block_lines.iter().map(|l| l.to_string()).collect();
-    
-    // Remove the ^block-id from the last line
-    if let Some(last) = result.last_mut() {
-        if let Some(pos) = last.rfind(" ^") {
-            *last = last[..pos].to_string();
-        } else if last.trim().starts_with('^') {
-            // The whole line is just the block ID — use preceding content
-            result.pop();
-        }
-    }
-    
-    result.join("\n").trim().to_string()
 }
 
 #[cfg(test)]
@@ -176,6 +521,13 @@ mod tests {
         assert!(id.chars().all(|c| c.is_ascii_alphanumeric()));
     }
 
+    #[test]
+    fn test_scan_blocks_skips_fenced_code() {
+        let content = "Intro ^intro\n\n```regex\nmatch ^start\n```\n\n^code\n\n~~~\nx ^tilde\n~~~\nOutro ^outro";
+        let ids: Vec<String> = scan_blocks("a.md", content).into_iter().map(|b| b.block_id).collect();
+        assert_eq!(ids, vec!["intro", "code", "outro"]);
+    }
+
     #[test]
     fn test_extract_block_content() {
         let content = "# Heading\n\nThis is a paragraph with some text ^abc123";
@@ -189,4 +541,102 @@ mod tests {
         let result = extract_block_content(content, 3);
         assert_eq!(result, "First line\nSecond line");
     }
+
+    #[test]
+    fn test_block_index_persists_and_reconciles() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let vp = dir.path().to_str().unwrap();
+        fs::write(dir.path().join("a.md"), "Intro\n\nKey claim ^claim\n\n- item ^item-1\n").unwrap();
+        fs::write(dir.path().join("b.md"), "Nothing here").unwrap();
+
+        let index = BlockIndex::load_or_build(vp, None, true);
+        assert_eq!(index.find("claim").unwrap().line_number, 3);
+        assert_eq!(index.find("item-1").unwrap().content, "- item");
+        assert!(dir.path().join(".oxidian").join(INDEX_FILE).exists());
+
+        // Offline edits are picked up on the next load
+        fs::write(dir.path().join("b.md"), "New ^fresh\n").unwrap();
+        fs::remove_file(dir.path().join("a.md")).unwrap();
+        let index = BlockIndex::load_or_build(vp, None, true);
+        assert!(index.find("claim").is_none());
+        assert_eq!(index.find("fresh").unwrap().note_path, "b.md");
+        assert_eq!(index.all().len(), 1);
+    }
+
+    #[test]
+    fn test_block_index_stays_off_disk_once_encrypted() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let vp = dir.path().to_str().unwrap();
+        let index_file = dir.path().join(".oxidian").join(INDEX_FILE);
+        fs::write(dir.path().join("a.md"), "Old claim ^old\n").unwrap();
+        BlockIndex::load_or_build(vp, None, true);
+        assert!(index_file.exists());
+
+        // Enabling encryption rebuilds in memory and drops the plaintext file
+        let mut index = BlockIndex::load_or_build(vp, Some("pw"), false);
+        assert!(!index_file.exists());
+        assert_eq!(index.find("old").unwrap().note_path, "a.md");
+
+        // Saving a note afterwards must not write it back
+        let secret = "Secret plan ^plan\n";
+        fs::write(dir.path().join("b.md"), encryption::encrypt_file_content(secret, "pw").unwrap()).unwrap();
+        index.index_file(vp, "b.md", secret);
+        assert!(index.find("plan").is_some());
+        assert!(!index_file.exists());
+
+        // Disabling encryption decrypts the notes and persists again
+        fs::write(dir.path().join("b.md"), secret).unwrap();
+        BlockIndex::load_or_build(vp, None, true);
+        assert!(fs::read_to_string(&index_file).unwrap().contains("plan"));
+    }
+
+    #[test]
+    fn test_block_index_events_and_search() {
+        use notify::event::{CreateKind, RemoveKind};
+        let dir = tempfile::TempDir::new().unwrap();
+        let vp = dir.path().to_str().unwrap();
+        let mut index = BlockIndex::load_or_build(vp, None, false);
+
+        let file = dir.path().join("n.md");
+        fs::write(&file, "Alpha paragraph ^alpha\n\nBeta about alpha ^b2\n").unwrap();
+        index.handle_event(vp, &Event::new(EventKind::Create(CreateKind::File)).add_path(file.clone()), None);
+        assert_eq!(index.note_blocks("n.md").len(), 2);
+        let hits: Vec<String> = index.search("alp", 10).into_iter().map(|b| b.block_id).collect();
+        assert_eq!(hits, vec!["alpha", "b2"]);
+
+        fs::remove_file(&file).unwrap();
+        index.handle_event(vp, &Event::new(EventKind::Remove(RemoveKind::File)).add_path(file), None);
+        assert!(index.find("alpha").is_none());
+        assert!(!dir.path().join(".oxidian").exists());
+    }
+
+    #[test]
+    fn test_assign_block_id_by_block_type() {
+        let content = "# Title\n\nFirst line\nsecond line\n\n- one\n  more of one\n- two\n\n| a | b |\n| - | - |\n| 1 | 2 |\nAfter\n\n```rust\nfn main() {}\n```\n";
+        let none = |_: &str| false;
+
+        let para = assign_block_id("notes/Doc.md", content, 3, Some("p1"), none).unwrap();
+        assert_eq!(para.link, "[[Doc#^p1]]");
+        assert_eq!(para.line, 4);
+        assert!(para.content.contains("second line ^p1\n"));
+
+        let item = assign_block_id("Doc.md", content, 7, Some("i1"), none).unwrap();
+        assert!(item.content.contains("- one ^i1\n  more of one"));
+
+        let table = assign_block_id("Doc.md", content, 11, Some("t1"), none).unwrap();
+        assert!(table.content.contains("| 1 | 2 |\n\n^t1\n\nAfter"));
+        assert_eq!(table.line, 14);
+        assert_eq!(extract_block_content(&table.content, 13), "| a | b |\n| - | - |\n| 1 | 2 |");
+
+        let code = assign_block_id("Doc.md", content, 16, Some("c1"), none).unwrap();
+        assert!(code.content.ends_with("```\n\n^c1\n"));
+        assert_eq!(extract_block_content(&code.content, 18), "```rust\nfn main() {}\n```");
+
+        // Existing ids are reused, blank lines and taken ids rejected
+        let again = assign_block_id("Doc.md", &table.content, 10, None, none).unwrap();
+        assert_eq!((again.block_id.as_str(), again.created), ("t1", false));
+        assert!(assign_block_id("Doc.md", content, 2, None, none).is_err());
+        assert!(assign_block_id("Doc.md", content, 3, Some("used"), |id| id == "used").is_err());
+        assert_eq!(line_at_offset(content, content.find("two").unwrap()), 8);
+    }
 }
@@ -1,5 +1,7 @@
+use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct Frontmatter {
@@ -39,6 +41,636 @@ pub fn serialize_frontmatter(fm: &Frontmatter, body: &str) -> String {
     }
 }
 
+// ─── Format-preserving edits ───────────────────────────────────────
+
+/// Coerce a value for the typed fields of [`Frontmatter`]: `title`,
+/// `created` and `modified` are strings (anything else clears them) and
+/// `tags`/`aliases` are lists of strings.
+pub fn coerce_known_field(key: &str, value: serde_json::Value) -> serde_json::Value {
+    use serde_json::Value;
+    let as_text = |v: &Value| match v {
+        Value::Null => None,
+        Value::String(s) => Some(s.clone()),
+        other => Some(other.to_string()),
+    };
+    match key {
+        "title" | "created" | "modified" => match value {
+            Value::String(_) => value,
+            _ => Value::Null,
+        },
+        "tags" | "aliases" => match &value {
+            Value::Array(items) => Value::from(items.iter().filter_map(as_text).collect::<Vec<_>>()),
+            other => Value::from(as_text(other).into_iter().collect::<Vec<_>>()),
+        },
+        _ => value,
+    }
+}
+
+/// How an existing value was written, so a replacement can be written the same way.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ValueStyle {
+    Plain,
+    DoubleQuoted,
+    SingleQuoted,
+    Flow,
+    Block { indent: usize },
+}
+
+/// One top-level `key: value` entry, spanning `lines[start..end]`.
+#[derive(Debug, Clone)]
+struct Entry {
+    key: String,
+    /// Key as written, including any quotes.
+    key_raw: String,
+    start: usize,
+    end: usize,
+}
+
+/// Line-based editor for the frontmatter of a note. Only the entries being
+/// changed are rewritten; key order, comments, blank lines, quoting and list
+/// style of everything else stay byte-for-byte as they were.
+#[derive(Debug, Clone)]
+pub struct FrontmatterEditor {
+    /// Everything up to and including the opening `---`.
+    head: String,
+    lines: Vec<String>,
+    /// Closing delimiter and body.
+    tail: String,
+    eol: &'static str,
+    existed: bool,
+}
+
+/// Split the key off a top-level `key: value` line.
+fn split_key(line: &str) -> Option<(String, String, &str)> {
+    let first = line.chars().next()?;
+    if first.is_whitespace() || matches!(first, '#' | '-' | '?' | '%') {
+        return None;
+    }
+    let (key, raw_len) = if first == '"' || first == '\'' {
+        let close = line[1..].find(first)? + 1;
+        let inner = &line[1..close];
+        let key = if first == '"' {
+            serde_json::from_str::<String>(&line[..=close]).unwrap_or_else(|_| inner.to_string())
+        } else {
+            inner.replace("''", "'")
+        };
+        (key, close + 1)
+    } else {
+        let colon = line.char_indices()
+            .find(|&(i, c)| c == ':' && line[i + 1..].chars().next().is_none_or(char::is_whitespace))?
+            .0;
+        (line[..colon].trim_end().to_string(), colon)
+    };
+    let rest = line[raw_len..].trim_start().strip_prefix(':')?;
+    Some((key, line[..raw_len].to_string(), rest))
+}
+
+/// Split `value  # comment` into value and comment (with its leading spaces).
+fn split_comment(rest: &str) -> (&str, &str) {
+    let mut quote: Option<char> = None;
+    let mut prev_space = true;
+    for (i, c) in rest.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == '#' && prev_space => {
+                let before = &rest[..i];
+                return (before.trim(), &rest[before.trim_end().len()..]);
+            }
+            None => {}
+        }
+        prev_space = c.is_whitespace();
+    }
+    (rest.trim(), "")
+}
+
+fn plain_key(key: &str) -> String {
+    let simple = !key.is_empty()
+        && key.chars().all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | ' ' | '.' | '/'))
+        && !key.starts_with(['-', ' '])
+        && !key.ends_with(' ');
+    if simple { key.to_string() } else { serde_json::to_string(key).unwrap_or_default() }
+}
+
+fn is_scalar(value: &serde_json::Value) -> bool {
+    !value.is_array() && !value.is_object()
+}
+
+/// Single-line YAML for a scalar, or `None` if it needs a block.
+fn yaml_scalar(value: &serde_json::Value) -> Option<String> {
+    if value.is_null() {
+        return Some(String::new());
+    }
+    let yaml = serde_yaml::to_string(value).ok()?;
+    let yaml = yaml.trim_end_matches('\n');
+    (!yaml.contains('\n')).then(|| yaml.to_string())
+}
+
+/// A scalar as it may appear inside `[a, b]`.
+fn flow_item(value: &serde_json::Value) -> String {
+    match yaml_scalar(value) {
+        Some(s) if !s.contains([',', '[', ']', '{', '}']) && !s.is_empty() => s,
+        _ => serde_json::to_string(value).unwrap_or_default(),
+    }
+}
+
+fn render_entry(key_raw: &str, value: &serde_json::Value, style: ValueStyle, comment: &str) -> Vec<String> {
+    use serde_json::Value;
+    let indent_block = |yaml: &str, pad: &str| -> Vec<String> {
+        yaml.trim_end_matches('\n').lines().map(|l| format!("{}{}", pad, l)).collect()
+    };
+    match value {
+        Value::Array(items) if items.is_empty() => vec![format!("{}: []{}", key_raw, comment)],
+        Value::Array(items) if style == ValueStyle::Flow && items.iter().all(is_scalar) => {
+            let inner: Vec<String> = items.iter().map(flow_item).collect();
+            vec![format!("{}: [{}]{}", key_raw, inner.join(", "), comment)]
+        }
+        Value::Array(items) => {
+            let indent = match style {
+                ValueStyle::Block { indent } => indent,
+                _ => 2,
+            };
+            let pad = " ".repeat(indent);
+            let mut lines = vec![format!("{}:{}", key_raw, comment)];
+            for item in items {
+                match yaml_scalar(item).filter(|_| is_scalar(item)) {
+                    Some(s) => lines.push(format!("{}- {}", pad, s).trim_end().to_string()),
+                    None => {
+                        let yaml = serde_yaml::to_string(item).unwrap_or_default();
+                        let mut block = indent_block(&yaml, &format!("{}  ", pad));
+                        if let Some(first) = block.first_mut() {
+                            *first = format!("{}- {}", pad, first.trim_start());
+                        }
+                        lines.extend(block);
+                    }
+                }
+            }
+            lines
+        }
+        Value::Object(map) if map.is_empty() => vec![format!("{}: {{}}{}", key_raw, comment)],
+        Value::Object(_) => {
+            let yaml = serde_yaml::to_string(value).unwrap_or_default();
+            let mut lines = vec![format!("{}:{}", key_raw, comment)];
+            lines.extend(indent_block(&yaml, "  "));
+            lines
+        }
+        Value::Null => vec![format!("{}:{}", key_raw, comment)],
+        Value::String(s) if !s.contains('\n') && style == ValueStyle::DoubleQuoted => {
+            vec![format!("{}: {}{}", key_raw, serde_json::to_string(s).unwrap_or_default(), comment)]
+        }
+        Value::String(s) if !s.contains('\n') && style == ValueStyle::SingleQuoted => {
+            vec![format!("{}: '{}'{}", key_raw, s.replace('\'', "''"), comment)]
+        }
+        _ => match yaml_scalar(value) {
+            Some(s) => vec![format!("{}: {}{}", key_raw, s, comment)],
+            None => {
+                // Multi-line string: serde_yaml emits a `|-` block
+                let yaml = serde_yaml::to_string(value).unwrap_or_default();
+                let mut lines: Vec<String> = yaml.trim_end_matches('\n').lines().map(String::from).collect();
+                lines[0] = format!("{}: {}{}", key_raw, lines[0], comment);
+                lines
+            }
+        },
+    }
+}
+
+impl FrontmatterEditor {
+    /// Split a note into its frontmatter lines and the rest. A note without
+    /// frontmatter gets one on the first edit.
+    pub fn parse(content: &str) -> Self {
+        let trimmed = content.trim_start();
+        let offset = content.len() - trimmed.len();
+        let closing = trimmed.strip_prefix("---").and_then(|after| after.find("\n---").map(|pos| (after, pos)));
+        let Some((after_opening, end_pos)) = closing else {
+            return FrontmatterEditor {
+                head: String::new(),
+                lines: Vec::new(),
+                tail: content.to_string(),
+                eol: if content.contains("\r\n") { "\r\n" } else { "\n" },
+                existed: false,
+            };
+        };
+
+        let mut yaml = &after_opening[..end_pos];
+        let mut tail = after_opening[end_pos..].to_string();
+        let crlf = yaml.ends_with('\r') || yaml.starts_with("\r\n");
+        if let Some(stripped) = yaml.strip_suffix('\r') {
+            yaml = stripped;
+            tail.insert(0, '\r');
+        }
+        let yaml = yaml.strip_prefix("\r\n").or_else(|| yaml.strip_prefix('\n')).unwrap_or(yaml);
+        let lines = if yaml.is_empty() && end_pos == 0 {
+            Vec::new()
+        } else {
+            yaml.split('\n')
+                .map(|l| if crlf { l.strip_suffix('\r').unwrap_or(l) } else { l }.to_string())
+                .collect()
+        };
+        FrontmatterEditor {
+            head: content[..offset + 3].to_string(),
+            lines,
+            tail,
+            eol: if crlf { "\r\n" } else { "\n" },
+            existed: true,
+        }
+    }
+
+    /// True if the note had a frontmatter block when parsed.
+    pub fn has_frontmatter(&self) -> bool {
+        self.existed
+    }
+
+    fn entries(&self) -> Vec<Entry> {
+        let mut entries: Vec<Entry> = Vec::new();
+        for (i, line) in self.lines.iter().enumerate() {
+            if let Some((key, key_raw, _)) = split_key(line) {
+                entries.push(Entry { key, key_raw, start: i, end: i + 1 });
+            } else if let Some(entry) = entries.last_mut() {
+                let continues = line.starts_with([' ', '\t']) && !line.trim().is_empty()
+                    || line.starts_with("- ")
+                    || line == "-";
+                if continues {
+                    entry.end = i + 1;
+                }
+            }
+        }
+        entries
+    }
+
+    fn entry(&self, key: &str) -> Option<Entry> {
+        self.entries().into_iter().find(|e| e.key == key)
+    }
+
+    /// Top-level keys in the order they appear.
+    pub fn keys(&self) -> Vec<String> {
+        self.entries().into_iter().map(|e| e.key).collect()
+    }
+
+    /// Current value of `key`.
+    pub fn get(&self, key: &str) -> Result<Option<serde_json::Value>, String> {
+        let Some(entry) = self.entry(key) else { return Ok(None) };
+        let text = self.lines[entry.start..entry.end].join("\n") + "\n";
+        let map: serde_yaml::Mapping = serde_yaml::from_str(&text)
+            .map_err(|e| format!("Failed to parse frontmatter YAML: {}", e))?;
+        let value = map.into_iter().next().map(|(_, v)| v).unwrap_or(serde_yaml::Value::Null);
+        serde_json::to_value(value)
+            .map(Some)
+            .map_err(|e| format!("Failed to convert frontmatter value: {}", e))
+    }
+
+    fn style_of(&self, entry: &Entry) -> (ValueStyle, String) {
+        let (_, _, rest) = split_key(&self.lines[entry.start]).unwrap_or_default();
+        let (value, comment) = split_comment(rest);
+        let style = if value.starts_with('[') {
+            ValueStyle::Flow
+        } else if value.starts_with('"') {
+            ValueStyle::DoubleQuoted
+        } else if value.starts_with('\'') {
+            ValueStyle::SingleQuoted
+        } else if value.is_empty() {
+            let item = self.lines[entry.start + 1..entry.end].iter().find(|l| l.trim_start().starts_with('-'));
+            match item {
+                Some(l) => ValueStyle::Block { indent: l.len() - l.trim_start().len() },
+                None => ValueStyle::Plain,
+            }
+        } else {
+            ValueStyle::Plain
+        };
+        (style, comment.to_string())
+    }
+
+    /// Set `key` to `value`. An existing entry is rewritten in place in its
+    /// original style; a new key is appended. Returns false if the value was
+    /// already equal.
+    pub fn set(&mut self, key: &str, value: &serde_json::Value) -> Result<bool, String> {
+        match self.entry(key) {
+            Some(entry) => {
+                if self.get(key)?.as_ref() == Some(value) {
+                    return Ok(false);
+                }
+                let (style, comment) = self.style_of(&entry);
+                let lines = render_entry(&entry.key_raw, value, style, &comment);
+                self.lines.splice(entry.start..entry.end, lines);
+            }
+            None => {
+                let lines = render_entry(&plain_key(key), value, ValueStyle::Plain, "");
+                let at = self.lines.iter().rposition(|l| !l.trim().is_empty()).map(|i| i + 1).unwrap_or(0);
+                self.lines.splice(at..at, lines);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Remove `key` and its value lines. Returns false if it wasn't present.
+    pub fn remove(&mut self, key: &str) -> bool {
+        match self.entry(key) {
+            Some(entry) => {
+                self.lines.drain(entry.start..entry.end);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Rename `from` to `to` in place, keeping its value and position.
+    pub fn rename(&mut self, from: &str, to: &str) -> Result<bool, String> {
+        if from == to {
+            return Ok(false);
+        }
+        let Some(entry) = self.entry(from) else { return Ok(false) };
+        if self.entry(to).is_some() {
+            return Err(format!("'{}' already exists", to));
+        }
+        let line = &self.lines[entry.start];
+        self.lines[entry.start] = format!("{}{}", plain_key(to), &line[entry.key_raw.len()..]);
+        Ok(true)
+    }
+
+    /// The full note with the edited frontmatter.
+    pub fn render(&self) -> String {
+        if !self.existed {
+            if self.lines.is_empty() {
+                return self.tail.clone();
+            }
+            let eol = self.eol;
+            return format!("---{eol}{}{eol}---{eol}{}", self.lines.join(eol), self.tail);
+        }
+        let mut out = self.head.clone();
+        for line in &self.lines {
+            out.push_str(self.eol);
+            out.push_str(line);
+        }
+        out.push_str(&self.tail);
+        out
+    }
+}
+
+/// Set one frontmatter field, leaving every other line of the note untouched.
+pub fn set_field_preserving(content: &str, key: &str, value: &serde_json::Value) -> Result<String, String> {
+    let mut editor = FrontmatterEditor::parse(content);
+    editor.set(key, value)?;
+    Ok(editor.render())
+}
+
+/// Remove one frontmatter field, leaving every other line of the note untouched.
+pub fn remove_field_preserving(content: &str, key: &str) -> String {
+    let mut editor = FrontmatterEditor::parse(content);
+    editor.remove(key);
+    editor.render()
+}
+
+// ─── Automatic timestamps ──────────────────────────────────────────
+
+/// Keys and format used to maintain created/modified timestamps on save.
+pub struct TimestampOptions<'a> {
+    pub created_key: &'a str,
+    pub modified_key: &'a str,
+    /// chrono strftime format
+    pub format: &'a str,
+}
+
+/// Format used when the configured one cannot render a timestamp.
+pub const DEFAULT_TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+/// `t` in a strftime format, or None if the format is invalid or asks for
+/// something a naive timestamp lacks, such as `%z`.
+pub fn format_timestamp(t: NaiveDateTime, format: &str) -> Option<String> {
+    let mut out = String::new();
+    write!(out, "{}", t.format(format)).ok()?;
+    Some(out)
+}
+
+/// Words of a note without its timestamp fields, to tell real edits from
+/// whitespace-only saves.
+fn content_words(content: &str, opts: &TimestampOptions) -> Vec<String> {
+    let mut editor = FrontmatterEditor::parse(content);
+    editor.remove(opts.created_key);
+    editor.remove(opts.modified_key);
+    let empty_frontmatter = editor.has_frontmatter() && editor.keys().is_empty();
+    let rendered = editor.render();
+    let mut words: Vec<String> = rendered.split_whitespace().map(String::from).collect();
+    if empty_frontmatter && words.len() >= 2 && words[0] == "---" && words[1] == "---" {
+        words.drain(..2);
+    }
+    words
+}
+
+/// Fill in the created/modified frontmatter fields of a note being saved.
+/// `created` is set once (kept from `previous` if the editor dropped it,
+/// otherwise `created_fallback` such as the file's birth time, or `now`);
+/// `modified` is set to `now` only when the content changed beyond
+/// whitespace. All other lines are left as they are.
+pub fn apply_timestamps(
+    content: &str,
+    previous: Option<&str>,
+    opts: &TimestampOptions,
+    now: NaiveDateTime,
+    created_fallback: Option<NaiveDateTime>,
+) -> String {
+    let stamp = |t: NaiveDateTime| serde_json::Value::String(
+        format_timestamp(t, opts.format).unwrap_or_else(|| t.format(DEFAULT_TIMESTAMP_FORMAT).to_string()),
+    );
+    let value_of = |editor: &FrontmatterEditor, key: &str| editor.get(key).ok().flatten().filter(|v| !v.is_null());
+    let previous_editor = previous.map(FrontmatterEditor::parse);
+    let previous_value = |key: &str| previous_editor.as_ref().and_then(|p| value_of(p, key));
+    let changed = previous.is_none_or(|p| content_words(content, opts) != content_words(p, opts));
+
+    let mut editor = FrontmatterEditor::parse(content);
+    if value_of(&editor, opts.created_key).is_none() {
+        let created = previous_value(opts.created_key).unwrap_or_else(|| stamp(created_fallback.unwrap_or(now)));
+        editor.set(opts.created_key, &created).ok();
+    }
+    if changed {
+        editor.set(opts.modified_key, &stamp(now)).ok();
+    } else if value_of(&editor, opts.modified_key).is_none() {
+        if let Some(modified) = previous_value(opts.modified_key) {
+            editor.set(opts.modified_key, &modified).ok();
+        }
+    }
+    editor.render()
+}
+
+// ─── Inline fields ─────────────────────────────────────────────────
+
+/// A Dataview-style inline field in the note body: a whole line
+/// `key:: value`, or `[key:: value]` / `(key:: value)` anywhere in a line.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InlineField {
+    pub key: String,
+    pub value: serde_json::Value,
+    /// The value as written.
+    pub raw: String,
+    /// 1-based line number.
+    pub line: usize,
+    /// Byte range of the field within its line.
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Field key: letters, digits, `_`, `-`, `/` and inner spaces, optionally
+/// wrapped in `**`/`__` as Obsidian users often bold them.
+fn inline_key(raw: &str) -> Option<String> {
+    let key = raw.trim();
+    let key = key.strip_prefix("**").and_then(|k| k.strip_suffix("**"))
+        .or_else(|| key.strip_prefix("__").and_then(|k| k.strip_suffix("__")))
+        .unwrap_or(key)
+        .trim();
+    let mut chars = key.chars();
+    let first = chars.next()?;
+    let valid = (first.is_alphanumeric() || first == '_')
+        && chars.all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '/' | ' '));
+    valid.then(|| key.to_string())
+}
+
+/// Typed value of an inline field: numbers and booleans are converted,
+/// everything else (dates, links, text) stays a string; empty is null.
+fn inline_value(raw: &str) -> serde_json::Value {
+    let raw = raw.trim();
+    if raw.is_empty() {
+        return serde_json::Value::Null;
+    }
+    match raw {
+        "true" => return serde_json::Value::Bool(true),
+        "false" => return serde_json::Value::Bool(false),
+        _ => {}
+    }
+    if let Ok(n) = raw.parse::<i64>() {
+        return n.into();
+    }
+    if let Some(n) = raw.parse::<f64>().ok().and_then(serde_json::Number::from_f64) {
+        return serde_json::Value::Number(n);
+    }
+    serde_json::Value::String(raw.to_string())
+}
+
+/// Byte ranges of inline code spans in a line.
+fn code_spans(line: &str) -> Vec<(usize, usize)> {
+    let ticks: Vec<usize> = line.match_indices('`').map(|(i, _)| i).collect();
+    ticks.chunks(2).filter(|c| c.len() == 2).map(|c| (c[0], c[1] + 1)).collect()
+}
+
+/// `[key:: value]` / `(key:: value)` fields in a line. Values may contain
+/// balanced brackets, e.g. `[source:: [[Some Note]]]`.
+fn bracketed_fields(line: &str, line_no: usize, out: &mut Vec<InlineField>) {
+    let spans = code_spans(line);
+    let bytes = line.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        let close = match bytes[i] {
+            b'[' => b']',
+            b'(' => b')',
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+        if spans.iter().any(|&(a, b)| i > a && i < b) {
+            i += 1;
+            continue;
+        }
+        let inner_start = i + 1;
+        let Some(sep) = line[inner_start..].find("::").map(|p| inner_start + p) else { return };
+        let key_text = &line[inner_start..sep];
+        let key = if key_text.contains(['[', ']', '(', ')']) { None } else { inline_key(key_text) };
+        let Some(key) = key else {
+            i += 1;
+            continue;
+        };
+        // Find the matching close, allowing nested brackets in the value
+        let mut depth = 0usize;
+        let mut end = None;
+        for (j, &b) in bytes.iter().enumerate().skip(sep + 2) {
+            match b {
+                b'[' | b'(' => depth += 1,
+                b']' | b')' if depth > 0 => depth -= 1,
+                b if b == close => {
+                    end = Some(j);
+                    break;
+                }
+                _ => {}
+            }
+        }
+        let Some(end) = end else {
+            i += 1;
+            continue;
+        };
+        let raw = line[sep + 2..end].trim().to_string();
+        out.push(InlineField { key, value: inline_value(&raw), raw, line: line_no, start: i, end: end + 1 });
+        i = end + 1;
+    }
+}
+
+/// Whole-line `key:: value`, also inside list items and block quotes.
+fn full_line_field(line: &str, line_no: usize) -> Option<InlineField> {
+    let mut rest = line.trim_start();
+    while let Some(r) = rest.strip_prefix('>') {
+        rest = r.trim_start();
+    }
+    if let Some(r) = rest.strip_prefix(['-', '*', '+']).filter(|r| r.starts_with(' ')) {
+        rest = r.trim_start();
+    } else {
+        let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(0);
+        if let Some(r) = rest[digits..].strip_prefix(['.', ')']).filter(|r| digits > 0 && r.starts_with(' ')) {
+            rest = r.trim_start();
+        }
+    }
+    let start = line.len() - rest.len();
+    let sep = rest.find("::")?;
+    let key = inline_key(&rest[..sep])?;
+    let raw = rest[sep + 2..].trim().to_string();
+    Some(InlineField { key, value: inline_value(&raw), raw, line: line_no, start, end: line.trim_end().len() })
+}
+
+/// All inline fields of a note, outside frontmatter and code.
+pub fn parse_inline_fields(content: &str) -> Vec<InlineField> {
+    let mut fields = Vec::new();
+    let mut lines = content.lines().enumerate().peekable();
+    if lines.peek().is_some_and(|(_, l)| l.trim_end() == "---") {
+        lines.next();
+        for (_, line) in lines.by_ref() {
+            if line.trim_end() == "---" {
+                break;
+            }
+        }
+    }
+    let mut in_code = false;
+    for (i, line) in lines {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code = !in_code;
+            continue;
+        }
+        if in_code || !line.contains("::") {
+            continue;
+        }
+        match full_line_field(line, i + 1) {
+            Some(field) => fields.push(field),
+            None => bracketed_fields(line, i + 1, &mut fields),
+        }
+    }
+    fields
+}
+
+/// Inline fields as properties: `key → value`, with a key that appears more
+/// than once collected into a list.
+pub fn inline_properties(content: &str) -> BTreeMap<String, serde_json::Value> {
+    let mut props: BTreeMap<String, serde_json::Value> = BTreeMap::new();
+    for field in parse_inline_fields(content) {
+        match props.get_mut(&field.key) {
+            None => {
+                props.insert(field.key, field.value);
+            }
+            Some(serde_json::Value::Array(items)) => items.push(field.value),
+            Some(existing) => {
+                let first = existing.take();
+                *existing = serde_json::Value::Array(vec![first, field.value]);
+            }
+        }
+    }
+    props
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -219,4 +851,160 @@ Original content"#;
         assert_eq!(fm.created, fm2.created);
         assert_eq!(body, body2);
     }
+
+    const MESSY: &str = "---\n# Project metadata\ntitle: \"My Note\"   # shown in UI\nStatus: active\ntags: [work, \"q3 plan\"]\naliases:\n- first\n- second\n\nrelated:\n    - \"[[A]]\"\n    - \"[[B]]\"\ndescription: |\n  Line one\n  Line two\nrating: 4\n'quoted key': value\nempty:\n---\n\nBody text\n";
+
+    /// Lines that differ between two texts of equal line count.
+    fn changed_lines(a: &str, b: &str) -> Vec<(String, String)> {
+        assert_eq!(a.lines().count(), b.lines().count(), "line count changed:\n{}", b);
+        a.lines().zip(b.lines())
+            .filter(|(x, y)| x != y)
+            .map(|(x, y)| (x.to_string(), y.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_editor_round_trip_is_lossless() {
+        let samples = [
+            MESSY,
+            "---\r\ntitle: Windows\r\ntags:\r\n  - a\r\n---\r\nBody\r\n",
+            "  ---\ntitle: Indented start\n---\nBody",
+            "---\n---\nEmpty frontmatter",
+            "No frontmatter at all",
+        ];
+        for sample in samples {
+            assert_eq!(FrontmatterEditor::parse(sample).render(), sample);
+        }
+    }
+
+    #[test]
+    fn test_editor_get_values() {
+        let editor = FrontmatterEditor::parse(MESSY);
+        assert_eq!(editor.keys(), vec!["title", "Status", "tags", "aliases", "related", "description", "rating", "quoted key", "empty"]);
+        assert_eq!(editor.get("title").unwrap(), Some(serde_json::json!("My Note")));
+        assert_eq!(editor.get("related").unwrap(), Some(serde_json::json!(["[[A]]", "[[B]]"])));
+        assert_eq!(editor.get("description").unwrap(), Some(serde_json::json!("Line one\nLine two\n")));
+        assert_eq!(editor.get("quoted key").unwrap(), Some(serde_json::json!("value")));
+        assert_eq!(editor.get("empty").unwrap(), Some(serde_json::Value::Null));
+        assert_eq!(editor.get("missing").unwrap(), None);
+    }
+
+    #[test]
+    fn test_set_scalar_changes_one_line() {
+        let out = set_field_preserving(MESSY, "rating", &serde_json::json!(5)).unwrap();
+        assert_eq!(changed_lines(MESSY, &out), vec![("rating: 4".into(), "rating: 5".into())]);
+
+        let out = set_field_preserving(MESSY, "title", &serde_json::json!("Renamed")).unwrap();
+        assert_eq!(changed_lines(MESSY, &out), vec![(
+            "title: \"My Note\"   # shown in UI".into(),
+            "title: \"Renamed\"   # shown in UI".into(),
+        )]);
+
+        let out = set_field_preserving(MESSY, "empty", &serde_json::json!("now set")).unwrap();
+        assert_eq!(changed_lines(MESSY, &out), vec![("empty:".into(), "empty: now set".into())]);
+        assert!(parse_frontmatter(&out).unwrap().0.is_some());
+    }
+
+    #[test]
+    fn test_set_list_keeps_style() {
+        let out = set_field_preserving(MESSY, "tags", &serde_json::json!(["work", "q3 plan", "new"])).unwrap();
+        assert_eq!(changed_lines(MESSY, &out), vec![(
+            "tags: [work, \"q3 plan\"]".into(),
+            "tags: [work, q3 plan, new]".into(),
+        )]);
+
+        let out = set_field_preserving(MESSY, "aliases", &serde_json::json!(["first", "second", "third"])).unwrap();
+        assert!(out.contains("aliases:\n- first\n- second\n- third\n\nrelated:\n    - \"[[A]]\""));
+
+        let out = set_field_preserving(MESSY, "related", &serde_json::json!(["[[C]]"])).unwrap();
+        assert!(out.contains("related:\n    - '[[C]]'\ndescription: |"));
+        let (fm, body) = parse_frontmatter(&out).unwrap();
+        assert_eq!(fm.unwrap().extra["related"], serde_json::json!(["[[C]]"]));
+        assert_eq!(body, "Body text\n");
+    }
+
+    #[test]
+    fn test_set_unchanged_value_is_noop() {
+        let mut editor = FrontmatterEditor::parse(MESSY);
+        assert!(!editor.set("tags", &serde_json::json!(["work", "q3 plan"])).unwrap());
+        assert!(!editor.set("rating", &serde_json::json!(4)).unwrap());
+        assert_eq!(editor.render(), MESSY);
+    }
+
+    #[test]
+    fn test_add_remove_and_rename() {
+        let out = set_field_preserving(MESSY, "project", &serde_json::json!("Apollo")).unwrap();
+        assert!(out.contains("empty:\nproject: Apollo\n---\n\nBody text\n"));
+
+        let out = remove_field_preserving(MESSY, "description");
+        assert!(!out.contains("Line one"));
+        assert!(out.contains("    - \"[[B]]\"\nrating: 4\n"));
+
+        let mut editor = FrontmatterEditor::parse(MESSY);
+        assert!(editor.rename("Status", "status").unwrap());
+        let out = editor.render();
+        assert_eq!(changed_lines(MESSY, &out), vec![("Status: active".into(), "status: active".into())]);
+        assert!(editor.rename("status", "title").is_err());
+    }
+
+    #[test]
+    fn test_editor_creates_frontmatter() {
+        let out = set_field_preserving("Just a body\n", "status", &serde_json::json!("draft")).unwrap();
+        assert_eq!(out, "---\nstatus: draft\n---\nJust a body\n");
+        let out = set_field_preserving("---\r\ntitle: W\r\n---\r\nBody", "n", &serde_json::json!(1)).unwrap();
+        assert_eq!(out, "---\r\ntitle: W\r\nn: 1\r\n---\r\nBody");
+        assert_eq!(remove_field_preserving("Body", "x"), "Body");
+    }
+
+    #[test]
+    fn test_inline_fields() {
+        let content = "---\nauthor:: not a field\n---\n# Book\nauthor:: Jane Doe\n- **rating**:: 4\nRead it [finished:: 2024-05-01] and (mood:: happy).\n> source:: [[Library]]\n```\ncode:: skipped\n```\nSee `[x:: y]` and [link:: [[Some Note]]] but not https://x.y or a::b::c:\ntag:: one\ntag:: two\n";
+        let fields = parse_inline_fields(content);
+        let summary: Vec<(&str, &str, usize)> = fields.iter().map(|f| (f.key.as_str(), f.raw.as_str(), f.line)).collect();
+        assert_eq!(summary, vec![
+            ("author", "Jane Doe", 5),
+            ("rating", "4", 6),
+            ("finished", "2024-05-01", 7),
+            ("mood", "happy", 7),
+            ("source", "[[Library]]", 8),
+            ("link", "[[Some Note]]", 12),
+            ("tag", "one", 13),
+            ("tag", "two", 14),
+        ]);
+        let finished = &fields[2];
+        assert_eq!(&content.lines().nth(6).unwrap()[finished.start..finished.end], "[finished:: 2024-05-01]");
+        assert_eq!(fields[1].value, serde_json::json!(4));
+
+        let props = inline_properties(content);
+        assert_eq!(props["tag"], serde_json::json!(["one", "two"]));
+        assert_eq!(props["author"], serde_json::json!("Jane Doe"));
+    }
+
+    #[test]
+    fn test_apply_timestamps() {
+        let opts = TimestampOptions { created_key: "created", modified_key: "updated", format: "%Y-%m-%d %H:%M" };
+        let t = |h: u32| chrono::NaiveDate::from_ymd_opt(2025, 3, 5).unwrap().and_hms_opt(h, 0, 0).unwrap();
+
+        // New note: created from the fallback, modified now
+        let first = apply_timestamps("# Note\nBody\n", None, &opts, t(10), Some(t(8)));
+        assert_eq!(first, "---\ncreated: 2025-03-05 08:00\nupdated: 2025-03-05 10:00\n---\n# Note\nBody\n");
+
+        // Whitespace-only change keeps the old stamp
+        let respaced = first.replace("Body\n", "Body  \n\n");
+        assert_eq!(apply_timestamps(&respaced, Some(&first), &opts, t(11), None), respaced);
+
+        // Real edit bumps `updated` but never `created`
+        let edited = first.replace("Body", "Body, edited");
+        let saved = apply_timestamps(&edited, Some(&first), &opts, t(12), None);
+        assert!(saved.contains("created: 2025-03-05 08:00\nupdated: 2025-03-05 12:00\n"));
+
+        // An editor that dropped the fields gets them back unchanged
+        assert_eq!(apply_timestamps("# Note\nBody\n", Some(&first), &opts, t(13), None), first);
+
+        // A format chrono cannot render falls back to the default
+        let bad = TimestampOptions { format: "%Y %z", ..opts };
+        let stamped = apply_timestamps("Body\n", None, &bad, t(9), None);
+        assert!(stamped.contains("created: 2025-03-05T09:00:00\n"));
+        assert_eq!(format_timestamp(t(9), "%Y %z"), None);
+    }
 }
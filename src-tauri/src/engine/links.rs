@@ -1,4 +1,13 @@
+use crate::encryption;
+use crate::engine::blocks::scan_blocks;
+use regex::{Captures, Regex};
 use serde::Serialize;
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::LazyLock;
+use walkdir::WalkDir;
+
+static LINK_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(!?)\[\[([^\]\n]+?)\]\]").unwrap());
 
 #[derive(Debug, Serialize, Clone)]
 pub struct LinkTarget {
@@ -16,6 +25,325 @@ pub fn extract_outgoing_links(content: &str) -> Vec<String> {
     crate::engine::vault::extract_wiki_links(content)
 }
 
+// ─── Heading and block link maintenance ──────────────────────────────
+
+/// A vault-wide rewrite triggered by an edit or rename.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LinkRule {
+    /// Heading `old` of `note` is now called `new`.
+    Heading { note: String, old: String, new: String },
+    /// Block `^id` now lives only in `note`.
+    Block { id: String, note: String },
+    /// Note `old` was renamed or moved to `new`.
+    Note { old: String, new: String },
+}
+
+/// One rewritten line, for previews.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LinkChange {
+    pub path: String,
+    /// 1-based line number
+    pub line: usize,
+    pub before: String,
+    pub after: String,
+}
+
+/// A note whose links change, with its rewritten content.
+#[derive(Debug, Clone, Serialize)]
+pub struct LinkUpdate {
+    pub path: String,
+    pub changes: Vec<LinkChange>,
+    #[serde(skip)]
+    pub content: String,
+    #[serde(skip)]
+    encrypted: bool,
+}
+
+fn without_md(path: &str) -> &str {
+    path.strip_suffix(".md").unwrap_or(path)
+}
+
+/// Whether link target `target`, written in `source`, points at `note`.
+/// An empty target is a link within `source` itself.
+fn links_to(target: &str, note: &str, source: &str) -> bool {
+    let target = target.trim().trim_start_matches('/');
+    if target.is_empty() {
+        return source == note;
+    }
+    let target = without_md(target).to_lowercase();
+    let note = without_md(note).to_lowercase();
+    note == target || note.ends_with(&format!("/{}", target))
+}
+
+/// Link text for `note`, keeping the path style of the link being replaced.
+fn link_text(note: &str, previous: &str) -> String {
+    if previous.contains('/') {
+        without_md(note).to_string()
+    } else {
+        Path::new(note).file_stem().unwrap_or_default().to_string_lossy().to_string()
+    }
+}
+
+fn apply_rules(inner: &str, source: &str, rules: &[LinkRule]) -> Option<String> {
+    let (link, alias) = match inner.find('|') {
+        Some(i) => (&inner[..i], &inner[i..]),
+        None => (inner, ""),
+    };
+    let (mut target, mut subpath) = match link.find('#') {
+        Some(i) => (link[..i].to_string(), Some(link[i + 1..].to_string())),
+        None => (link.to_string(), None),
+    };
+    let mut changed = false;
+    for rule in rules {
+        match rule {
+            LinkRule::Heading { note, old, new } => {
+                let Some(sub) = subpath.as_mut() else { continue };
+                if sub.starts_with('^') || !links_to(&target, note, source) {
+                    continue;
+                }
+                let segments: Vec<&str> = sub.split('#').collect();
+                if segments.iter().any(|s| s.trim().eq_ignore_ascii_case(old)) {
+                    *sub = segments.iter()
+                        .map(|s| if s.trim().eq_ignore_ascii_case(old) { new.as_str() } else { s })
+                        .collect::<Vec<_>>()
+                        .join("#");
+                    changed = true;
+                }
+            }
+            LinkRule::Block { id, note } => {
+                if subpath.as_deref() != Some(&format!("^{}", id)) || links_to(&target, note, source) {
+                    continue;
+                }
+                target = if note == source { String::new() } else { link_text(note, &target) };
+                changed = true;
+            }
+            LinkRule::Note { old, new } => {
+                if target.trim().is_empty() || !links_to(&target, old, source) {
+                    continue;
+                }
+                target = link_text(new, &target);
+                changed = true;
+            }
+        }
+    }
+    if !changed {
+        return None;
+    }
+    Some(match subpath {
+        Some(sub) => format!("{}#{}{}", target, sub, alias),
+        None => format!("{}{}", target, alias),
+    })
+}
+
+/// Rewrite the wiki links and embeds of `content` (the note at `source`)
+/// that `rules` affect. Links inside code fences are left alone.
+pub fn rewrite_links(content: &str, source: &str, rules: &[LinkRule]) -> (String, Vec<LinkChange>) {
+    let mut out = String::with_capacity(content.len());
+    let mut changes = Vec::new();
+    let mut in_fence = false;
+    for (i, line) in content.split_inclusive('\n').enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+        }
+        if in_fence || !line.contains("[[") {
+            out.push_str(line);
+            continue;
+        }
+        let rewritten = LINK_RE.replace_all(line, |caps: &Captures| {
+            match apply_rules(&caps[2], source, rules) {
+                Some(inner) => format!("{}[[{}]]", &caps[1], inner),
+                None => caps[0].to_string(),
+            }
+        });
+        if rewritten != line {
+            changes.push(LinkChange {
+                path: source.to_string(),
+                line: i + 1,
+                before: line.trim_end().to_string(),
+                after: rewritten.trim_end().to_string(),
+            });
+        }
+        out.push_str(&rewritten);
+    }
+    (out, changes)
+}
+
+/// `(level, title)` of every heading outside code fences. Headings without
+/// a title yet (`## ` while typing) are skipped.
+fn heading_list(content: &str) -> Vec<(usize, String)> {
+    let mut out = Vec::new();
+    let mut in_fence = false;
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        let level = trimmed.chars().take_while(|c| *c == '#').count();
+        if in_fence || level == 0 || level > 6 || !trimmed[level..].starts_with(' ') {
+            continue;
+        }
+        let title = trimmed[level..].trim();
+        if !title.is_empty() {
+            out.push((level, title.to_string()));
+        }
+    }
+    out
+}
+
+/// Headings renamed between two versions of a note, as `(old, new)`.
+/// Unchanged headings anchor the comparison; between two anchors, removed and
+/// added headings pair up in order when their counts and levels agree.
+pub fn detect_heading_renames(old: &str, new: &str) -> Vec<(String, String)> {
+    let a = heading_list(old);
+    let b = heading_list(new);
+    // Longest common subsequence of the two heading lists
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] { lcs[i + 1][j + 1] + 1 } else { lcs[i + 1][j].max(lcs[i][j + 1]) };
+        }
+    }
+    let old_titles: HashSet<String> = a.iter().map(|(_, t)| t.to_lowercase()).collect();
+    let new_titles: HashSet<String> = b.iter().map(|(_, t)| t.to_lowercase()).collect();
+    let mut renames = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    let (mut removed, mut added) = (Vec::new(), Vec::new());
+    let mut flush = |removed: &mut Vec<&(usize, String)>, added: &mut Vec<&(usize, String)>| {
+        if removed.len() == added.len() {
+            for (r, n) in removed.iter().zip(added.iter()) {
+                // A title that still exists (or already existed) is a move, not a rename
+                if r.0 == n.0 && !new_titles.contains(&r.1.to_lowercase()) && !old_titles.contains(&n.1.to_lowercase()) {
+                    renames.push((r.1.clone(), n.1.clone()));
+                }
+            }
+        }
+        removed.clear();
+        added.clear();
+    };
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            flush(&mut removed, &mut added);
+            i += 1;
+            j += 1;
+        } else if j < b.len() && (i == a.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            added.push(&b[j]);
+            j += 1;
+        } else {
+            removed.push(&a[i]);
+            i += 1;
+        }
+    }
+    flush(&mut removed, &mut added);
+    renames
+}
+
+/// Block ids that moved into or out of the note at `path`. `notes_with`
+/// lists the notes (other than this one) that define an id. Ids defined in
+/// more than one place are ambiguous and left alone.
+pub fn detect_block_moves<F>(path: &str, old: &str, new: &str, notes_with: F) -> Vec<LinkRule>
+where
+    F: Fn(&str) -> Vec<String>,
+{
+    let ids = |content: &str| -> HashSet<String> {
+        scan_blocks(path, content).into_iter().map(|b| b.block_id).collect()
+    };
+    let (before, after) = (ids(old), ids(new));
+    let mut rules = Vec::new();
+    for id in after.difference(&before) {
+        if notes_with(id).iter().all(|p| p == path) {
+            rules.push(LinkRule::Block { id: id.clone(), note: path.to_string() });
+        }
+    }
+    for id in before.difference(&after) {
+        let others: Vec<String> = notes_with(id).into_iter().filter(|p| p != path).collect();
+        if let [note] = others.as_slice() {
+            rules.push(LinkRule::Block { id: id.clone(), note: note.clone() });
+        }
+    }
+    rules.sort_by(|a, b| format!("{:?}", a).cmp(&format!("{:?}", b)));
+    rules
+}
+
+/// Every rule an edit of the note at `path` from `old` to `new` implies.
+pub fn detect_link_rules<F>(path: &str, old: &str, new: &str, notes_with: F) -> Vec<LinkRule>
+where
+    F: Fn(&str) -> Vec<String>,
+{
+    let mut rules: Vec<LinkRule> = detect_heading_renames(old, new)
+        .into_iter()
+        .map(|(old, new)| LinkRule::Heading { note: path.to_string(), old, new })
+        .collect();
+    rules.extend(detect_block_moves(path, old, new, notes_with));
+    rules
+}
+
+/// Notes whose links `rules` would rewrite. `skip` excludes a note, usually
+/// the one being saved, whose content the caller already holds.
+pub fn plan_link_updates(vault_path: &str, password: Option<&str>, rules: &[LinkRule], skip: Option<&str>) -> Vec<LinkUpdate> {
+    if rules.is_empty() {
+        return Vec::new();
+    }
+    let mut updates = Vec::new();
+    for entry in WalkDir::new(vault_path)
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'))
+        .filter_map(|e| e.ok())
+    {
+        let path = entry.path();
+        if !path.is_file() || path.extension().map(|e| e != "md").unwrap_or(true) {
+            continue;
+        }
+        let relative = path.strip_prefix(vault_path).unwrap_or(path).to_string_lossy().to_string();
+        if skip == Some(relative.as_str()) {
+            continue;
+        }
+        updates.extend(plan_note_update(vault_path, &relative, password, rules));
+    }
+    updates.sort_by(|a, b| a.path.cmp(&b.path));
+    updates
+}
+
+/// The update `rules` imply for a single note, if any of its links change.
+pub fn plan_note_update(vault_path: &str, relative_path: &str, password: Option<&str>, rules: &[LinkRule]) -> Option<LinkUpdate> {
+    let raw = std::fs::read_to_string(Path::new(vault_path).join(relative_path)).ok()?;
+    let encrypted = encryption::is_encrypted_content(&raw);
+    let content = encryption::plaintext_content(raw, password)?;
+    let (content, changes) = rewrite_links(&content, relative_path, rules);
+    (!changes.is_empty()).then(|| LinkUpdate { path: relative_path.to_string(), changes, content, encrypted })
+}
+
+/// Rename or move the note `old_path` to `new_path` and rewrite every link
+/// to it, the note's own `[[Old#Heading]]` links included. With `dry_run`
+/// nothing is touched and the same updates are only planned.
+pub fn rename_note(vault_path: &str, password: Option<&str>, old_path: &str, new_path: &str, dry_run: bool) -> Result<Vec<LinkUpdate>, String> {
+    let rules = [LinkRule::Note { old: old_path.to_string(), new: new_path.to_string() }];
+    if dry_run {
+        return Ok(plan_link_updates(vault_path, password, &rules, None));
+    }
+    crate::engine::vault::rename_file(vault_path, old_path, new_path)?;
+    let updates = plan_link_updates(vault_path, password, &rules, None);
+    apply_link_updates(vault_path, password, &updates)?;
+    Ok(updates)
+}
+
+/// Write planned updates back, re-encrypting notes that were encrypted.
+pub fn apply_link_updates(vault_path: &str, password: Option<&str>, updates: &[LinkUpdate]) -> Result<(), String> {
+    for update in updates {
+        let stored = match (update.encrypted, password) {
+            (true, Some(pwd)) => encryption::encrypt_file_content(&update.content, pwd)?,
+            (true, None) => return Err(format!("Cannot update links in encrypted note {}", update.path)),
+            (false, _) => update.content.clone(),
+        };
+        crate::features::file_recovery::create_snapshot(vault_path, &update.path).ok();
+        std::fs::write(Path::new(vault_path).join(&update.path), stored)
+            .map_err(|e| format!("Failed to write {}: {}", update.path, e))?;
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -107,4 +435,103 @@ mod tests {
         assert!(serialized.contains("Special"));
         assert!(!serialized.contains("null")); // Should properly escape
     }
+
+    #[test]
+    fn test_detect_heading_renames() {
+        let old = "# Title\n## Setup\ntext\n## Usage\n```\n# not a heading\n```\n## Notes\n";
+        let new = "# Title\n## Installation\ntext\n## Usage\n## Remarks\n## Extra\n";
+        // `Notes` → `Remarks` and `Extra` is ambiguous, so only Setup pairs up
+        assert_eq!(detect_heading_renames(old, new), vec![("Setup".to_string(), "Installation".to_string())]);
+        assert!(detect_heading_renames("## A\n## B\n", "## B\n## A\n").is_empty());
+        // A heading cleared to retype it is not renamed to ""
+        assert!(detect_heading_renames("# T\n## Setup\nx\n", "# T\n## \nx\n").is_empty());
+        assert!(detect_heading_renames("## Setup\n", "##   \n").is_empty());
+    }
+
+    #[test]
+    fn test_rewrite_heading_and_block_links() {
+        let rules = vec![
+            LinkRule::Heading { note: "docs/Guide.md".into(), old: "Setup".into(), new: "Installation".into() },
+            LinkRule::Block { id: "abc123".into(), note: "Archive.md".into() },
+        ];
+        let content = "See [[Guide#Setup|setup]] and [[docs/Guide#Intro#setup]].\n![[Guide#^abc123]]\n```\n[[Guide#Setup]]\n```\n[[Other#Setup]] [[Archive#^abc123]]\n";
+        let (out, changes) = rewrite_links(content, "Index.md", &rules);
+        assert_eq!(
+            out,
+            "See [[Guide#Installation|setup]] and [[docs/Guide#Intro#Installation]].\n![[Archive#^abc123]]\n```\n[[Guide#Setup]]\n```\n[[Other#Setup]] [[Archive#^abc123]]\n"
+        );
+        assert_eq!(changes.iter().map(|c| c.line).collect::<Vec<_>>(), vec![1, 2]);
+
+        // Same-note links follow the heading, and a block moved here loses its target
+        let (own, _) = rewrite_links("[[#Setup]] [[Guide#^abc123]]", "docs/Guide.md", &[
+            rules[0].clone(),
+            LinkRule::Block { id: "abc123".into(), note: "docs/Guide.md".into() },
+        ]);
+        assert_eq!(own, "[[#Installation]] [[Guide#^abc123]]");
+    }
+
+    #[test]
+    fn test_detect_block_moves_and_plan() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let vault = dir.path().to_str().unwrap();
+        std::fs::write(dir.path().join("Ref.md"), "quote ![[Source#^q1]] and [[Source#^q2]]\n").unwrap();
+
+        // q1 was cut from Source and pasted into Target; q2 stays
+        let rules = detect_block_moves("Target.md", "", "moved line ^q1\n", |id| {
+            if id == "q1" { vec!["Target.md".into()] } else { vec![] }
+        });
+        assert_eq!(rules, vec![LinkRule::Block { id: "q1".into(), note: "Target.md".into() }]);
+
+        let updates = plan_link_updates(vault, None, &rules, None);
+        assert_eq!(updates.len(), 1);
+        assert_eq!(updates[0].changes[0].after, "quote ![[Target#^q1]] and [[Source#^q2]]");
+        apply_link_updates(vault, None, &updates).unwrap();
+        let written = std::fs::read_to_string(dir.path().join("Ref.md")).unwrap();
+        assert!(written.contains("![[Target#^q1]]"));
+
+        let renamed = rewrite_links(&written, "Ref.md", &[LinkRule::Note { old: "Source.md".into(), new: "sub/Renamed.md".into() }]).0;
+        assert_eq!(renamed, "quote ![[Target#^q1]] and [[Renamed#^q2]]\n");
+    }
+
+    #[test]
+    fn test_rename_note_across_folders() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let vault = dir.path().to_str().unwrap();
+        std::fs::create_dir_all(dir.path().join("inbox")).unwrap();
+        std::fs::write(dir.path().join("inbox/Plan.md"), "# Goals\nsee [[Plan#Goals]] and [[#Goals]]\n").unwrap();
+        std::fs::write(dir.path().join("Index.md"), "[[inbox/Plan|plan]] and [[Plan#Goals]]\n").unwrap();
+
+        let preview = rename_note(vault, None, "inbox/Plan.md", "projects/Plan.md", true).unwrap();
+        assert!(dir.path().join("inbox/Plan.md").exists());
+        let updates = rename_note(vault, None, "inbox/Plan.md", "projects/Plan.md", false).unwrap();
+        let lines = |u: &[LinkUpdate]| -> Vec<String> { u.iter().flat_map(|u| u.changes.iter().map(|c| c.after.clone())).collect() };
+        assert_eq!(lines(&preview), lines(&updates));
+        assert_eq!(lines(&updates), vec!["[[projects/Plan|plan]] and [[Plan#Goals]]"]);
+        assert_eq!(std::fs::read_to_string(dir.path().join("Index.md")).unwrap(), "[[projects/Plan|plan]] and [[Plan#Goals]]\n");
+        assert!(dir.path().join("projects/Plan.md").exists());
+    }
+
+    #[test]
+    fn test_rename_note_in_encrypted_vault() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let vault = dir.path().to_str().unwrap();
+        let encrypt = |text: &str| encryption::encrypt_file_content(text, "pw").unwrap();
+        std::fs::write(dir.path().join("Old.md"), encrypt("# Intro\nback to [[Old#Intro]]\n")).unwrap();
+        std::fs::write(dir.path().join("Ref.md"), encrypt("read [[Old]]\n")).unwrap();
+
+        let preview = rename_note(vault, Some("pw"), "Old.md", "New.md", true).unwrap();
+        assert_eq!(preview.len(), 2);
+        let updates = rename_note(vault, Some("pw"), "Old.md", "New.md", false).unwrap();
+        assert_eq!(updates.iter().map(|u| u.path.as_str()).collect::<Vec<_>>(), vec!["New.md", "Ref.md"]);
+
+        let read = |name: &str| {
+            let raw = std::fs::read_to_string(dir.path().join(name)).unwrap();
+            assert!(encryption::is_encrypted_content(&raw));
+            encryption::decrypt_file_content(&raw, "pw").unwrap()
+        };
+        assert_eq!(read("New.md"), "# Intro\nback to [[New#Intro]]\n");
+        assert_eq!(read("Ref.md"), "read [[New]]\n");
+        // Without the password nothing can be read, so nothing is rewritten
+        assert!(rename_note(vault, None, "New.md", "Newer.md", false).unwrap().is_empty());
+    }
 }
@@ -8,6 +8,9 @@ static WIKI_LINK_RE: LazyLock<Regex> = LazyLock::new(|| {
 static TAG_RE: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r"(?:^|\s)#([a-zA-Z][a-zA-Z0-9_/-]*)").unwrap()
 });
+static QUERY_BLOCK_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?ms)^```query[ \t]*\r?\n(.*?)^```[ \t]*$").unwrap()
+});
 
 pub fn render_markdown(input: &str) -> String {
     let processed = preprocess_wiki_links(input);
@@ -24,13 +27,44 @@ pub fn render_markdown(input: &str) -> String {
     html_output
 }
 
+/// Render markdown, replacing each ```query block with the HTML returned by
+/// `run_query` for the block's body. Other code blocks render as usual.
+pub fn render_markdown_with_queries(input: &str, run_query: &dyn Fn(&str) -> String) -> String {
+    let mut queries: Vec<String> = Vec::new();
+    let replaced = QUERY_BLOCK_RE.replace_all(input, |caps: &regex::Captures| {
+        queries.push(caps[1].to_string());
+        format!("<!--oxidian-query-{}-->", queries.len() - 1)
+    });
+    if queries.is_empty() {
+        return render_markdown(input);
+    }
+    let mut html_output = render_markdown(&replaced);
+    for (i, query) in queries.iter().enumerate() {
+        html_output = html_output.replacen(&format!("<!--oxidian-query-{}-->", i), &run_query(query), 1);
+    }
+    html_output
+}
+
+/// True if the content contains at least one ```query block.
+pub fn has_query_blocks(input: &str) -> bool {
+    QUERY_BLOCK_RE.is_match(input)
+}
+
+/// HTML anchor for a wiki-link, as produced for `[[target|display]]`.
+pub fn wiki_link_html(target: &str, display: &str) -> String {
+    let attr = |s: &str| s.replace('&', "&amp;").replace('"', "&quot;").replace('<', "&lt;").replace('>', "&gt;");
+    // The onclick value is a JS string literal inside an HTML attribute:
+    // escape for JS first (backslash before quote), then for HTML
+    let js = target.replace('\\', "\\\\").replace('\'', "\\'");
+    let de = display.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+    format!(r#"<a class="wiki-link" data-target="{}" href="javascript:void(0)" onclick="window.navigateToNote('{}')">{}</a>"#, attr(target), attr(&js), de)
+}
+
 fn preprocess_wiki_links(input: &str) -> String {
     WIKI_LINK_RE.replace_all(input, |caps: &regex::Captures| {
         let target = &caps[1];
         let display = caps.get(2).map(|m| m.as_str()).unwrap_or(target);
-        let te = target.replace('&', "&amp;").replace('"', "&quot;").replace('<', "&lt;").replace('>', "&gt;").replace('\'', "\\'").replace('\\', "\\\\");
-        let de = display.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
-        format!(r#"<a class="wiki-link" data-target="{}" href="javascript:void(0)" onclick="window.navigateToNote('{}')">{}</a>"#, te, te, de)
+        wiki_link_html(target, display)
     }).to_string()
 }
 
@@ -115,6 +149,13 @@ mod tests {
         assert!(result.contains(r#">Display &lt; Text</a>"#));
     }
 
+    #[test]
+    fn test_wiki_link_html_escapes_quotes_and_backslashes() {
+        let html = wiki_link_html(r#"it's a\"b"#, "x");
+        assert!(html.contains(r#"data-target="it's a\&quot;b""#));
+        assert!(html.contains(r#"navigateToNote('it\'s a\\&quot;b')"#));
+    }
+
     #[test]
     fn test_preprocess_wiki_links_multiple() {
         let input = "Links to [[First Note]] and [[Second Note]]";
@@ -203,6 +244,18 @@ mod tests {
         assert!(output.contains("footnote"));
     }
 
+    #[test]
+    fn test_query_blocks_replaced() {
+        let input = "# Projects\n\n```query\nLIST FROM #project\n```\n\n```rust\nfn main() {}\n```\n\n```query\nTASK\n```\n";
+        let output = render_markdown_with_queries(input, &|q| format!("<div class=\"q\">{}</div>", q.trim()));
+        assert!(output.contains(r#"<div class="q">LIST FROM #project</div>"#));
+        assert!(output.contains(r#"<div class="q">TASK</div>"#));
+        assert!(output.contains("<code class=\"language-rust\">"));
+        assert!(!output.contains("oxidian-query"));
+        assert!(has_query_blocks(input));
+        assert!(!has_query_blocks("```rust\nquery\n```"));
+    }
+
     #[test]
     fn test_complex_markdown_integration() {
         let input = r#"# Title with [[Link]]
@@ -1,11 +1,27 @@
+use std::collections::HashSet;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use tantivy::collector::TopDocs;
-use tantivy::query::QueryParser;
+use tantivy::query::{MoreLikeThisQuery, QueryParser, TermQuery};
 use tantivy::schema::*;
-use tantivy::{doc, Index, IndexWriter, ReloadPolicy};
+use tantivy::tokenizer::{
+    Language, LowerCaser, RemoveLongFilter, SimpleTokenizer, SplitCompoundWords, Stemmer, StopWordFilter,
+    TextAnalyzer, Token, TokenStream, Tokenizer,
+};
+use tantivy::{doc, Index, IndexReader, IndexWriter, ReloadPolicy};
 use walkdir::WalkDir;
 use serde::Serialize;
+use crate::encryption;
+use crate::engine::settings::{SearchSettings, Settings};
+
+/// Name under which the vault's analyzer is registered on the index.
+const TOKENIZER_NAME: &str = "oxidian";
+/// Records the `SearchSettings` the on-disk index was built with.
+const ANALYSIS_MARKER: &str = "oxidian-analysis.json";
+/// On-disk index directory, relative to the vault root.
+const INDEX_DIR: &str = ".search_index";
+/// Where `apply_settings` builds the replacement index before swapping it in.
+const STAGING_DIR: &str = ".search_index.rebuild";
 
 #[derive(Debug, Serialize)]
 pub struct SearchResult {
@@ -15,6 +31,45 @@ pub struct SearchResult {
     pub score: f32,
 }
 
+#[derive(Debug, Serialize, Clone)]
+pub struct SimilarNote {
+    pub path: String,
+    pub title: String,
+    pub score: f32,
+}
+
+/// A `key::value` or `[key:: value]` filter in a search query, matched
+/// against note properties (frontmatter and inline fields). `value` is `None`
+/// for a bare `key::`, which only requires the field to be present.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldFilter {
+    pub key: String,
+    pub value: Option<String>,
+}
+
+/// Split field filters out of a search query, returning them and the
+/// remaining free text.
+pub fn split_field_filters(query: &str) -> (Vec<FieldFilter>, String) {
+    static FIELD_RE: std::sync::LazyLock<regex::Regex> = std::sync::LazyLock::new(|| {
+        regex::Regex::new(r"\[([^\[\]:]+?)::[ \t]*([^\]]*)\]|(?:^|\s)([\w/-]+)::(\S*)").unwrap()
+    });
+    let mut filters = Vec::new();
+    let text = FIELD_RE.replace_all(query, |c: &regex::Captures| {
+        let (key, value) = match c.get(1) {
+            Some(key) => (key.as_str(), &c[2]),
+            None => (&c[3], &c[4]),
+        };
+        let value = value.trim();
+        filters.push(FieldFilter {
+            key: key.trim().to_string(),
+            value: (!value.is_empty()).then(|| value.to_string()),
+        });
+        " "
+    });
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    (filters, text)
+}
+
 pub struct SearchIndex {
     index: Index,
     #[allow(dead_code)]
@@ -23,31 +78,112 @@ pub struct SearchIndex {
     title_field: Field,
     body_field: Field,
     writer: Option<IndexWriter>,
+    /// Shared by every query; reloaded after each commit
+    reader: IndexReader,
+    analysis: SearchSettings,
+    /// Index directory; `None` for a RAM-only index
+    dir: Option<PathBuf>,
 }
 
 impl SearchIndex {
     pub fn new(vault_path: &str) -> Result<Self, String> {
-        let mut schema_builder = Schema::builder();
-        let path_field = schema_builder.add_text_field("path", STRING | STORED);
-        let title_field = schema_builder.add_text_field("title", TEXT | STORED);
-        let body_field = schema_builder.add_text_field("body", TEXT | STORED);
-        let schema = schema_builder.build();
-        let index_path = Path::new(vault_path).join(".search_index");
-        fs::create_dir_all(&index_path).map_err(|e| format!("Failed to create index dir: {}", e))?;
+        Self::with_settings(vault_path, &SearchSettings::default())
+    }
+
+    /// Open the index appropriate for the vault: encrypted vaults never touch
+    /// disk, so note plaintext is only ever held in memory while unlocked.
+    pub fn for_vault(vault_path: &str, settings: &Settings) -> Result<Self, String> {
+        if settings.vault.encryption_enabled {
+            Self::in_memory(vault_path, &settings.search)
+        } else {
+            Self::with_settings(vault_path, &settings.search)
+        }
+    }
+
+    /// Open the on-disk index with the given text analysis. If the index on disk
+    /// was built with different settings it is wiped; callers must `reindex_vault`.
+    pub fn with_settings(vault_path: &str, analysis: &SearchSettings) -> Result<Self, String> {
+        Self::open_dir(&Path::new(vault_path).join(INDEX_DIR), analysis)
+    }
+
+    fn open_dir(index_path: &Path, analysis: &SearchSettings) -> Result<Self, String> {
+        if index_path.exists() && read_analysis_marker(index_path).as_ref() != Some(analysis) {
+            fs::remove_dir_all(index_path).map_err(|e| format!("Failed to clear stale index: {}", e))?;
+        }
+        let (schema, path_field, title_field, body_field) = build_schema();
+        fs::create_dir_all(index_path).map_err(|e| format!("Failed to create index dir: {}", e))?;
         let lock_file = index_path.join(".tantivy-writer.lock");
         if lock_file.exists() { let _ = fs::remove_file(&lock_file); }
-        let index = Index::create_in_dir(&index_path, schema.clone())
-            .or_else(|_| Index::open_in_dir(&index_path))
+        let index = Index::create_in_dir(index_path, schema.clone())
+            .or_else(|_| Index::open_in_dir(index_path))
             .map_err(|e| format!("Failed to create/open index: {}", e))?;
+        index.tokenizers().register(TOKENIZER_NAME, build_analyzer(analysis));
+        let writer = index.writer(15_000_000).map_err(|e| format!("Failed to create index writer: {}", e))?;
+        let reader = open_reader(&index)?;
+        Ok(SearchIndex { index, schema, path_field, title_field, body_field, writer: Some(writer), reader, analysis: analysis.clone(), dir: Some(index_path.to_path_buf()) })
+    }
+
+    /// Open an empty RAM-only index. Any `.search_index` left on disk is removed
+    /// so that no plaintext from before encryption was enabled survives.
+    pub fn in_memory(vault_path: &str, analysis: &SearchSettings) -> Result<Self, String> {
+        let index_path = Path::new(vault_path).join(INDEX_DIR);
+        if index_path.exists() {
+            fs::remove_dir_all(&index_path).map_err(|e| format!("Failed to remove on-disk index: {}", e))?;
+        }
+        let (schema, path_field, title_field, body_field) = build_schema();
+        let index = Index::create_in_ram(schema.clone());
+        index.tokenizers().register(TOKENIZER_NAME, build_analyzer(analysis));
         let writer = index.writer(15_000_000).map_err(|e| format!("Failed to create index writer: {}", e))?;
-        Ok(SearchIndex { index, schema, path_field, title_field, body_field, writer: Some(writer) })
+        let reader = open_reader(&index)?;
+        Ok(SearchIndex { index, schema, path_field, title_field, body_field, writer: Some(writer), reader, analysis: analysis.clone(), dir: None })
     }
 
+    pub fn is_in_memory(&self) -> bool {
+        self.dir.is_none()
+    }
+
+    /// Switch to new text analysis settings. Returns `true` if they differed
+    /// and the index was rebuilt. `password` decrypts notes of an encrypted vault.
+    /// The new index is built next to the current one and only swapped in once
+    /// complete, so a failed rebuild leaves the current index in place.
+    pub fn apply_settings(&mut self, vault_path: &str, analysis: &SearchSettings, password: Option<&str>) -> Result<bool, String> {
+        if &self.analysis == analysis { return Ok(false); }
+        if self.is_in_memory() {
+            let mut fresh = Self::in_memory(vault_path, analysis)?;
+            fresh.reindex_vault_with_password(vault_path, password)?;
+            *self = fresh;
+            return Ok(true);
+        }
+        let live = Path::new(vault_path).join(INDEX_DIR);
+        let staging = Path::new(vault_path).join(STAGING_DIR);
+        if staging.exists() {
+            fs::remove_dir_all(&staging).map_err(|e| format!("Failed to clear staging index: {}", e))?;
+        }
+        let mut fresh = Self::open_dir(&staging, analysis)?;
+        fresh.reindex_vault_with_password(vault_path, password)?;
+        drop(fresh);
+
+        // Release the current index before its directory is replaced
+        self.writer = None;
+        fs::remove_dir_all(&live).map_err(|e| format!("Failed to remove old index: {}", e))?;
+        fs::rename(&staging, &live).map_err(|e| format!("Failed to swap in rebuilt index: {}", e))?;
+        *self = Self::open_dir(&live, analysis)?;
+        Ok(true)
+    }
+
+    /// Reindex every note. Encrypted notes are skipped; see `reindex_vault_with_password`.
     pub fn reindex_vault(&mut self, vault_path: &str) -> Result<(), String> {
+        self.reindex_vault_with_password(vault_path, None)
+    }
+
+    /// Reindex every note, decrypting encrypted notes with `password`.
+    /// Encrypted notes that cannot be decrypted are left out of the index.
+    pub fn reindex_vault_with_password(&mut self, vault_path: &str, password: Option<&str>) -> Result<(), String> {
         self.writer = None;
-        let index_path = Path::new(vault_path).join(".search_index");
-        let lock_file = index_path.join(".tantivy-writer.lock");
-        if lock_file.exists() { let _ = fs::remove_file(&lock_file); }
+        if let Some(dir) = &self.dir {
+            let lock_file = dir.join(".tantivy-writer.lock");
+            if lock_file.exists() { let _ = fs::remove_file(&lock_file); }
+        }
         let mut writer: IndexWriter = self.index.writer(50_000_000).map_err(|e| format!("Failed to create index writer: {}", e))?;
         writer.delete_all_documents().map_err(|e| format!("Failed to clear index: {}", e))?;
         for entry in WalkDir::new(vault_path).into_iter()
@@ -56,7 +192,7 @@ impl SearchIndex {
         {
             let path = entry.path();
             if path.extension().map(|e| e == "md").unwrap_or(false) {
-                if let Ok(content) = fs::read_to_string(path) {
+                if let Some(content) = fs::read_to_string(path).ok().and_then(|c| encryption::plaintext_content(c, password)) {
                     let relative = path.strip_prefix(vault_path).unwrap_or(path).to_string_lossy().to_string();
                     let title = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
                     writer.add_document(doc!(self.path_field => relative, self.title_field => title, self.body_field => content))
@@ -66,10 +202,27 @@ impl SearchIndex {
         }
         writer.commit().map_err(|e| format!("Failed to commit index: {}", e))?;
         drop(writer);
+        self.reload()?;
+        // Only a fully built index may claim the current analysis settings
+        if let Some(dir) = &self.dir {
+            let marker = serde_json::to_string_pretty(&self.analysis).map_err(|e| format!("Failed to serialize analysis settings: {}", e))?;
+            fs::write(dir.join(ANALYSIS_MARKER), marker).map_err(|e| format!("Failed to write analysis marker: {}", e))?;
+        }
         self.writer = Some(self.index.writer(15_000_000).map_err(|e| format!("Failed to re-create persistent writer: {}", e))?);
         Ok(())
     }
 
+    /// Drop every document (e.g. when an encrypted vault is locked).
+    pub fn clear(&mut self) -> Result<(), String> {
+        if self.writer.is_none() {
+            self.writer = Some(self.index.writer(15_000_000).map_err(|e| format!("Failed to re-create writer: {}", e))?);
+        }
+        let writer = self.writer.as_mut().ok_or_else(|| "Index writer not available".to_string())?;
+        writer.delete_all_documents().map_err(|e| format!("Failed to clear index: {}", e))?;
+        writer.commit().map_err(|e| format!("Failed to commit index: {}", e))?;
+        self.reload()
+    }
+
     /// Remove a single document by its path from the index.
     pub fn delete_path(&mut self, path: &str) -> Result<(), String> {
         if self.writer.is_none() {
@@ -79,7 +232,7 @@ impl SearchIndex {
         let path_term = tantivy::Term::from_field_text(self.path_field, path);
         writer.delete_term(path_term);
         writer.commit().map_err(|e| format!("Failed to commit index: {}", e))?;
-        Ok(())
+        self.reload()
     }
 
     pub fn index_note(&mut self, _vault_path: &str, relative_path: &str, content: &str) -> Result<(), String> {
@@ -93,7 +246,12 @@ impl SearchIndex {
         writer.add_document(doc!(self.path_field => relative_path.to_string(), self.title_field => title, self.body_field => content.to_string()))
             .map_err(|e| format!("Failed to add document: {}", e))?;
         writer.commit().map_err(|e| format!("Failed to commit index: {}", e))?;
-        Ok(())
+        self.reload()
+    }
+
+    /// Make the last commit visible to queries.
+    fn reload(&self) -> Result<(), String> {
+        self.reader.reload().map_err(|e| format!("Failed to reload index reader: {}", e))
     }
 
     pub fn search(&self, query_str: &str, limit: usize) -> Result<Vec<SearchResult>, String> {
@@ -102,9 +260,7 @@ impl SearchIndex {
         let query_str: String = query_str.chars().map(|c| if "[]{}()~^\":\\!+-".contains(c) { ' ' } else { c }).collect();
         let query_str = query_str.trim();
         if query_str.is_empty() { return Ok(vec![]); }
-        let reader = self.index.reader_builder().reload_policy(ReloadPolicy::OnCommitWithDelay).try_into()
-            .map_err(|e| format!("Failed to create reader: {}", e))?;
-        let searcher = reader.searcher();
+        let searcher = self.reader.searcher();
         let query_parser = QueryParser::for_index(&self.index, vec![self.title_field, self.body_field]);
         let query = query_parser.parse_query(query_str).map_err(|e| format!("Failed to parse query: {}", e))?;
         let top_docs = searcher.search(&query, &TopDocs::with_limit(limit)).map_err(|e| format!("Search failed: {}", e))?;
@@ -121,6 +277,181 @@ impl SearchIndex {
     }
 }
 
+impl SearchIndex {
+    /// Notes whose content is most similar to the indexed note at `path`
+    /// (more-like-this over title and body). The note itself and any path in
+    /// `exclude` are left out. Returns nothing if `path` is not indexed.
+    pub fn more_like_this(&self, path: &str, exclude: &HashSet<String>, limit: usize) -> Result<Vec<SimilarNote>, String> {
+        let searcher = self.reader.searcher();
+        let path_query = TermQuery::new(tantivy::Term::from_field_text(self.path_field, path), IndexRecordOption::Basic);
+        let hits = searcher.search(&path_query, &TopDocs::with_limit(1)).map_err(|e| format!("Search failed: {}", e))?;
+        let Some((_, doc_address)) = hits.first() else { return Ok(vec![]) };
+        // Notes are short compared to Lucene's typical documents, so accept
+        // terms seen once in the source and shared with at least one other note.
+        let query = MoreLikeThisQuery::builder()
+            .with_min_doc_frequency(2)
+            .with_min_term_frequency(1)
+            .with_max_query_terms(25)
+            .with_min_word_length(3)
+            .with_document(*doc_address);
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(limit + exclude.len() + 1))
+            .map_err(|e| format!("Search failed: {}", e))?;
+        let mut results = Vec::new();
+        for (score, doc_address) in top_docs {
+            let doc: tantivy::TantivyDocument = searcher.doc(doc_address).map_err(|e| format!("Failed to retrieve doc: {}", e))?;
+            let doc_path = doc.get_first(self.path_field).and_then(|v| v.as_str()).unwrap_or("").to_string();
+            if doc_path == path || exclude.contains(&doc_path) { continue; }
+            let title = doc.get_first(self.title_field).and_then(|v| v.as_str()).unwrap_or("").to_string();
+            results.push(SimilarNote { path: doc_path, title, score });
+            if results.len() >= limit { break; }
+        }
+        Ok(results)
+    }
+}
+
+fn open_reader(index: &Index) -> Result<IndexReader, String> {
+    index.reader_builder().reload_policy(ReloadPolicy::Manual).try_into()
+        .map_err(|e| format!("Failed to create reader: {}", e))
+}
+
+fn build_schema() -> (Schema, Field, Field, Field) {
+    let mut schema_builder = Schema::builder();
+    let text_options = TextOptions::default()
+        .set_indexing_options(TextFieldIndexing::default()
+            .set_tokenizer(TOKENIZER_NAME)
+            .set_index_option(IndexRecordOption::WithFreqsAndPositions))
+        .set_stored();
+    let path_field = schema_builder.add_text_field("path", STRING | STORED);
+    let title_field = schema_builder.add_text_field("title", text_options.clone());
+    let body_field = schema_builder.add_text_field("body", text_options);
+    (schema_builder.build(), path_field, title_field, body_field)
+}
+
+/// Map an ISO 639-1 code to the stemmer/stopword language.
+pub fn stemmer_language(code: &str) -> Option<Language> {
+    Some(match code.to_lowercase().as_str() {
+        "ar" => Language::Arabic,
+        "da" => Language::Danish,
+        "nl" => Language::Dutch,
+        "en" => Language::English,
+        "fi" => Language::Finnish,
+        "fr" => Language::French,
+        "de" => Language::German,
+        "el" => Language::Greek,
+        "hu" => Language::Hungarian,
+        "it" => Language::Italian,
+        "no" | "nb" | "nn" => Language::Norwegian,
+        "pt" => Language::Portuguese,
+        "ro" => Language::Romanian,
+        "ru" => Language::Russian,
+        "es" => Language::Spanish,
+        "sv" => Language::Swedish,
+        "ta" => Language::Tamil,
+        "tr" => Language::Turkish,
+        _ => return None,
+    })
+}
+
+/// Build the analyzer for title/body: tokenize → remove long → lowercase →
+/// split compounds → stopwords → stem.
+fn build_analyzer(analysis: &SearchSettings) -> TextAnalyzer {
+    let mut builder = if analysis.cjk_bigrams {
+        TextAnalyzer::builder(CjkBigramTokenizer).filter_dynamic(RemoveLongFilter::limit(40))
+    } else {
+        TextAnalyzer::builder(SimpleTokenizer::default()).filter_dynamic(RemoveLongFilter::limit(40))
+    };
+    builder = builder.filter_dynamic(LowerCaser);
+    if !analysis.compound_words.is_empty() {
+        let words: Vec<String> = analysis.compound_words.iter().map(|w| w.to_lowercase()).collect();
+        match SplitCompoundWords::from_dictionary(words) {
+            Ok(filter) => builder = builder.filter_dynamic(filter),
+            Err(e) => log::warn!("Ignoring compound word dictionary: {}", e),
+        }
+    }
+    if let Some(language) = stemmer_language(&analysis.language) {
+        if let Some(stopwords) = StopWordFilter::new(language) {
+            builder = builder.filter_dynamic(stopwords);
+        }
+        builder = builder.filter_dynamic(Stemmer::new(language));
+    }
+    builder.build()
+}
+
+fn read_analysis_marker(index_path: &Path) -> Option<SearchSettings> {
+    let data = fs::read_to_string(index_path.join(ANALYSIS_MARKER)).ok()?;
+    serde_json::from_str(&data).ok()
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x3040..=0x30FF     // Hiragana, Katakana
+        | 0x3400..=0x4DBF   // CJK Extension A
+        | 0x4E00..=0x9FFF   // CJK Unified Ideographs
+        | 0xAC00..=0xD7AF   // Hangul Syllables
+        | 0xF900..=0xFAFF   // CJK Compatibility Ideographs
+        | 0x20000..=0x2FFFF // CJK Extensions B+
+    )
+}
+
+/// Like `SimpleTokenizer`, but runs of CJK characters (which have no word
+/// separators) are emitted as overlapping bigrams: "東京都" → "東京", "京都".
+#[derive(Clone, Default)]
+pub struct CjkBigramTokenizer;
+
+pub struct CjkBigramTokenStream {
+    tokens: Vec<Token>,
+    cursor: usize,
+}
+
+impl Tokenizer for CjkBigramTokenizer {
+    type TokenStream<'a> = CjkBigramTokenStream;
+    fn token_stream<'a>(&'a mut self, text: &'a str) -> CjkBigramTokenStream {
+        let mut tokens = Vec::new();
+        let mut push = |from: usize, to: usize| {
+            tokens.push(Token { offset_from: from, offset_to: to, position: tokens.len(), text: text[from..to].to_string(), position_length: 1 });
+        };
+        let chars: Vec<(usize, char)> = text.char_indices().collect();
+        let end_of = |i: usize| chars.get(i + 1).map(|(o, _)| *o).unwrap_or(text.len());
+        let mut i = 0;
+        while i < chars.len() {
+            let (start, c) = chars[i];
+            if is_cjk(c) {
+                let mut j = i;
+                while j + 1 < chars.len() && is_cjk(chars[j + 1].1) { j += 1; }
+                if i == j {
+                    push(start, end_of(i));
+                } else {
+                    for (k, &(offset, _)) in chars.iter().enumerate().take(j).skip(i) { push(offset, end_of(k + 1)); }
+                }
+                i = j + 1;
+            } else if c.is_alphanumeric() {
+                let mut j = i;
+                while j + 1 < chars.len() && chars[j + 1].1.is_alphanumeric() && !is_cjk(chars[j + 1].1) { j += 1; }
+                push(start, end_of(j));
+                i = j + 1;
+            } else {
+                i += 1;
+            }
+        }
+        CjkBigramTokenStream { tokens, cursor: 0 }
+    }
+}
+
+impl TokenStream for CjkBigramTokenStream {
+    fn advance(&mut self) -> bool {
+        self.cursor += 1;
+        self.cursor <= self.tokens.len()
+    }
+
+    fn token(&self) -> &Token {
+        &self.tokens[self.cursor - 1]
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        &mut self.tokens[self.cursor - 1]
+    }
+}
+
 fn create_snippet(body: &str, query: &str, max_len: usize) -> String {
     let lower_query = query.to_lowercase();
     let query_terms: Vec<&str> = lower_query.split_whitespace().collect();
@@ -296,6 +627,17 @@ mod tests {
                 (results_without_hash.is_empty() && results_with_hash.len() >= 0));
     }
 
+    #[test]
+    fn test_split_field_filters() {
+        let (filters, text) = split_field_filters("rust [author:: Jane Doe] status::done draft:: notes");
+        assert_eq!(filters, vec![
+            FieldFilter { key: "author".into(), value: Some("Jane Doe".into()) },
+            FieldFilter { key: "status".into(), value: Some("done".into()) },
+            FieldFilter { key: "draft".into(), value: None },
+        ]);
+        assert_eq!(text, "rust notes");
+    }
+
     #[test]
     fn test_create_snippet_basic() {
         let body = "This is a long piece of text that contains the word programming in the middle of it.";
@@ -384,6 +726,123 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    fn analyze(analysis: &SearchSettings, text: &str) -> Vec<String> {
+        let mut analyzer = build_analyzer(analysis);
+        let mut stream = analyzer.token_stream(text);
+        let mut out = Vec::new();
+        while stream.advance() { out.push(stream.token().text.clone()); }
+        out
+    }
+
+    #[test]
+    fn test_cjk_bigrams() {
+        let analysis = SearchSettings { cjk_bigrams: true, ..SearchSettings::default() };
+        assert_eq!(analyze(&analysis, "東京都 in Tokyo"), vec!["東京", "京都", "in", "tokyo"]);
+        assert_eq!(analyze(&analysis, "猫"), vec!["猫"]);
+        assert_eq!(analyze(&analysis, "rust和日本語"), vec!["rust", "和日", "日本", "本語"]);
+    }
+
+    #[test]
+    fn test_stemming_and_stopwords() {
+        let analysis = SearchSettings { language: "en".into(), ..SearchSettings::default() };
+        assert_eq!(analyze(&analysis, "The running dogs"), vec!["run", "dog"]);
+        let none = SearchSettings::default();
+        assert_eq!(analyze(&none, "The running dogs"), vec!["the", "running", "dogs"]);
+    }
+
+    #[test]
+    fn test_compound_words_split() {
+        let analysis = SearchSettings {
+            compound_words: vec!["Haus".into(), "Aufgabe".into()],
+            ..SearchSettings::default()
+        };
+        assert_eq!(analyze(&analysis, "Hausaufgabe"), vec!["haus", "aufgabe"]);
+    }
+
+    #[test]
+    fn test_search_with_language_matches_plurals() {
+        let temp_vault = create_test_vault();
+        let vault_path = temp_vault.path().to_str().unwrap();
+        fs::write(temp_vault.path().join("pets.md"), "Notes about cats and dogs.").unwrap();
+        let analysis = SearchSettings { language: "en".into(), ..SearchSettings::default() };
+        let mut index = SearchIndex::with_settings(vault_path, &analysis).expect("Failed to create index");
+        index.reindex_vault(vault_path).expect("Failed to reindex");
+        let results = index.search("cat", 10).expect("Search failed");
+        assert!(results.iter().any(|r| r.path == "pets.md"));
+    }
+
+    #[test]
+    fn test_changed_settings_rebuild_index() {
+        let temp_vault = create_test_vault();
+        let vault_path = temp_vault.path().to_str().unwrap();
+        let mut index = SearchIndex::new(vault_path).expect("Failed to create index");
+        // The marker is only written once the index has been built
+        assert_eq!(read_analysis_marker(&temp_vault.path().join(INDEX_DIR)), None);
+        index.reindex_vault(vault_path).expect("Failed to reindex");
+        assert!(!index.apply_settings(vault_path, &SearchSettings::default(), None).unwrap());
+
+        let german = SearchSettings { language: "de".into(), ..SearchSettings::default() };
+        assert!(index.apply_settings(vault_path, &german, None).unwrap());
+        let marker = read_analysis_marker(&temp_vault.path().join(INDEX_DIR));
+        assert_eq!(marker, Some(german));
+        assert!(!temp_vault.path().join(STAGING_DIR).exists());
+        assert!(!index.search("important", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_encrypted_vault_indexed_in_memory_only() {
+        let temp_vault = create_test_vault();
+        let vault_path = temp_vault.path().to_str().unwrap();
+        // A stale on-disk index from before encryption was enabled
+        SearchIndex::new(vault_path).unwrap().reindex_vault(vault_path).unwrap();
+        let secret = encryption::encrypt_file_content("classified zeppelin plans", "hunter2").unwrap();
+        fs::write(temp_vault.path().join("secret.md"), secret).unwrap();
+
+        let mut settings = Settings::default();
+        settings.vault.encryption_enabled = true;
+        let mut index = SearchIndex::for_vault(vault_path, &settings).unwrap();
+        assert!(index.is_in_memory());
+        assert!(!temp_vault.path().join(".search_index").exists());
+
+        // Locked: ciphertext is never indexed
+        index.reindex_vault(vault_path).unwrap();
+        assert!(index.search("zeppelin", 10).unwrap().is_empty());
+        assert!(index.search("salt", 10).unwrap().is_empty());
+
+        // Unlocked: plaintext is searchable, still nothing on disk
+        index.reindex_vault_with_password(vault_path, Some("hunter2")).unwrap();
+        let results = index.search("zeppelin", 10).unwrap();
+        assert_eq!(results[0].path, "secret.md");
+        assert!(!temp_vault.path().join(".search_index").exists());
+
+        // Lock: index is emptied
+        index.clear().unwrap();
+        assert!(index.search("zeppelin", 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_more_like_this() {
+        let temp_vault = create_test_vault();
+        let vault_path = temp_vault.path().to_str().unwrap();
+        fs::write(temp_vault.path().join("rust-a.md"), "Rust ownership borrowing lifetimes and the borrow checker").unwrap();
+        fs::write(temp_vault.path().join("rust-b.md"), "The borrow checker enforces ownership and lifetimes in Rust").unwrap();
+        fs::write(temp_vault.path().join("rust-c.md"), "Notes on Rust ownership").unwrap();
+        fs::write(temp_vault.path().join("garden.md"), "Tomatoes need sun, water, patience").unwrap();
+        let mut index = SearchIndex::new(vault_path).unwrap();
+        index.reindex_vault(vault_path).unwrap();
+
+        let similar = index.more_like_this("rust-a.md", &HashSet::new(), 5).unwrap();
+        assert_eq!(similar[0].path, "rust-b.md");
+        assert!(similar[0].score > 0.0);
+        assert!(similar.iter().all(|s| s.path != "rust-a.md" && s.path != "garden.md"));
+
+        let exclude: HashSet<String> = ["rust-b.md".to_string()].into_iter().collect();
+        let similar = index.more_like_this("rust-a.md", &exclude, 5).unwrap();
+        assert_eq!(similar[0].path, "rust-c.md");
+
+        assert!(index.more_like_this("missing.md", &HashSet::new(), 5).unwrap().is_empty());
+    }
+
     #[test]
     fn test_search_index_handles_unicode() {
         let temp_vault = create_test_vault();
@@ -24,6 +24,8 @@ pub struct Settings {
     pub vault: VaultSettings,
     #[serde(default)]
     pub files: FilesSettings,
+    #[serde(default)]
+    pub search: SearchSettings,
     #[serde(default, alias = "files_links")]
     pub files_links: FilesLinksSettings,
     #[serde(default)]
@@ -292,6 +294,16 @@ pub struct FilesSettings {
     pub attachment_folder: String,
     #[serde(default = "default_new_file_location")]
     pub new_file_location: String,
+    /// Maintain created/modified timestamps in frontmatter on save (opt-in)
+    #[serde(default)]
+    pub auto_timestamps: bool,
+    #[serde(default = "default_created_key")]
+    pub created_key: String,
+    #[serde(default = "default_modified_key")]
+    pub modified_key: String,
+    /// chrono strftime format, e.g. "%Y-%m-%d %H:%M"
+    #[serde(default = "default_timestamp_format")]
+    pub timestamp_format: String,
 }
 
 impl Default for FilesSettings {
@@ -300,6 +312,10 @@ impl Default for FilesSettings {
             deleted_files_behavior: "trash".into(),
             attachment_folder: "attachments".into(),
             new_file_location: "root".into(),
+            auto_timestamps: false,
+            created_key: default_created_key(),
+            modified_key: default_modified_key(),
+            timestamp_format: default_timestamp_format(),
         }
     }
 }
@@ -313,6 +329,46 @@ fn default_attachment_folder() -> String {
 fn default_new_file_location() -> String {
     "root".into()
 }
+fn default_created_key() -> String {
+    "created".into()
+}
+fn default_modified_key() -> String {
+    "modified".into()
+}
+fn default_timestamp_format() -> String {
+    crate::engine::frontmatter::DEFAULT_TIMESTAMP_FORMAT.into()
+}
+
+// ─── Search ──────────────────────────────────────────────────────────
+
+/// Text analysis for the full-text index. Changing any of these fields
+/// invalidates `.search_index`, which is then rebuilt from scratch.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SearchSettings {
+    /// ISO 639-1 code selecting stemmer + stopwords, or "none"
+    #[serde(default = "default_search_language")]
+    pub language: String,
+    /// Index runs of Chinese/Japanese/Korean characters as overlapping bigrams
+    #[serde(default)]
+    pub cjk_bigrams: bool,
+    /// Dictionary used to split compound words (e.g. German "Hausaufgabe" → "haus", "aufgabe")
+    #[serde(default)]
+    pub compound_words: Vec<String>,
+}
+
+impl Default for SearchSettings {
+    fn default() -> Self {
+        Self {
+            language: "none".into(),
+            cjk_bigrams: false,
+            compound_words: vec![],
+        }
+    }
+}
+
+fn default_search_language() -> String {
+    "none".into()
+}
 
 // ─── Files & Links (JS frontend section) ─────────────────────────────
 
@@ -589,6 +645,7 @@ impl Default for Settings {
             appearance: AppearanceSettings::default(),
             vault: VaultSettings::default(),
             files: FilesSettings::default(),
+            search: SearchSettings::default(),
             files_links: FilesLinksSettings::default(),
             plugins: PluginsSettings::default(),
             remember: RememberSettings::default(),
@@ -648,6 +705,23 @@ impl Settings {
         ) {
             issues.push("vault.default_view_mode must be edit, preview, or split".into());
         }
+        if self.search.language != "none"
+            && crate::engine::search::stemmer_language(&self.search.language).is_none()
+        {
+            issues.push("search.language must be \"none\" or a supported ISO 639-1 code".into());
+        }
+        let created_key = self.files.created_key.trim();
+        let modified_key = self.files.modified_key.trim();
+        if created_key.is_empty() || modified_key.is_empty() || created_key == modified_key {
+            issues.push("files.created_key and files.modified_key must be non-empty and different".into());
+        }
+        // Formatting a sample also catches specifiers a naive timestamp can't fill (`%z`)
+        let sample = chrono::NaiveDate::from_ymd_opt(2000, 1, 1).and_then(|d| d.and_hms_opt(0, 0, 0)).unwrap_or_default();
+        if self.files.timestamp_format.trim().is_empty()
+            || crate::engine::frontmatter::format_timestamp(sample, &self.files.timestamp_format).is_none()
+        {
+            issues.push("files.timestamp_format must be a valid strftime format".into());
+        }
 
         issues
     }
@@ -871,6 +945,30 @@ mod tests {
         assert_eq!(result["d"], 3);
     }
 
+    #[test]
+    fn test_validate_bad_search_language() {
+        let mut s = Settings::default();
+        s.search.language = "xx".into();
+        assert!(!s.validate().is_empty());
+        s.search.language = "de".into();
+        assert!(s.validate().is_empty());
+    }
+
+    #[test]
+    fn test_validate_timestamp_settings() {
+        let mut s = Settings::default();
+        assert!(!s.files.auto_timestamps);
+        s.files.timestamp_format = "%Y-%m-%d %Q".into();
+        assert!(!s.validate().is_empty());
+        s.files.timestamp_format = "%Y-%m-%d %z".into();
+        assert!(!s.validate().is_empty());
+        s.files.timestamp_format = "%d.%m.%Y".into();
+        s.files.modified_key = "created".into();
+        assert!(!s.validate().is_empty());
+        s.files.modified_key = "updated".into();
+        assert!(s.validate().is_empty());
+    }
+
     #[test]
     fn test_plugin_settings_hashmap() {
         let mut s = Settings::default();
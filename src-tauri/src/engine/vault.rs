@@ -329,60 +329,6 @@ pub fn rename_file(vault_path: &str, old_path: &str, new_path: &str) -> Result<(
     fs::rename(&old_full, &new_full).map_err(|e| format!("Failed to rename: {}", e))
 }
 
-/// Rename a file and invoke `link_updater` for every file that links to the old name.
-pub fn rename_with_link_update<F>(
-    vault_path: &str,
-    old_path: &str,
-    new_path: &str,
-    link_updater: F,
-) -> Result<(), String>
-where
-    F: Fn(&str, &str, &str) -> Result<(), String>,
-{
-    // First do the rename
-    rename_file(vault_path, old_path, new_path)?;
-
-    let old_name = Path::new(old_path)
-        .file_stem()
-        .unwrap_or_default()
-        .to_string_lossy()
-        .to_string();
-    let new_name = Path::new(new_path)
-        .file_stem()
-        .unwrap_or_default()
-        .to_string_lossy()
-        .to_string();
-
-    if old_name == new_name {
-        return Ok(());
-    }
-
-    // Walk vault and update links
-    for entry in WalkDir::new(vault_path)
-        .into_iter()
-        .filter_map(|e| e.ok())
-    {
-        let path = entry.path();
-        if path.extension().map(|e| e == "md").unwrap_or(false) {
-            let relative = path
-                .strip_prefix(vault_path)
-                .unwrap_or(path)
-                .to_string_lossy()
-                .to_string();
-            if relative == new_path {
-                continue;
-            }
-            if let Ok(content) = fs::read_to_string(path) {
-                let links = extract_wiki_links(&content);
-                if links.iter().any(|l| l == &old_name || l.ends_with(&format!("/{}", old_name))) {
-                    link_updater(&relative, &old_name, &new_name)?;
-                }
-            }
-        }
-    }
-    Ok(())
-}
-
 /// Move a file or folder to a new parent directory.
 pub fn move_entry(
     vault_path: &str,
@@ -615,17 +561,7 @@ where
     let mut watcher = RecommendedWatcher::new(
         move |res: Result<Event, notify::Error>| {
             if let Ok(event) = res {
-                // Skip events in hidden dirs
-                let dominated_by_hidden = event.paths.iter().all(|p| {
-                    p.components().any(|c| {
-                        c.as_os_str()
-                            .to_string_lossy()
-                            .starts_with('.')
-                    })
-                });
-                if !dominated_by_hidden {
-                    callback(event);
-                }
+                callback(event);
             }
         },
         Config::default(),
@@ -639,6 +575,17 @@ where
     Ok(watcher)
 }
 
+/// Whether `path` lies in a hidden directory inside the vault, or outside
+/// the vault altogether. The vault itself may live under e.g. `~/.oxidian`,
+/// so only the part below `vault_path` is checked; the watcher is reused
+/// across vault switches, so callers pass the current vault.
+pub fn is_hidden_path(vault_path: &str, path: &Path) -> bool {
+    match path.strip_prefix(vault_path) {
+        Ok(relative) => relative.components().any(|c| c.as_os_str().to_string_lossy().starts_with('.')),
+        Err(_) => true,
+    }
+}
+
 // ─── Tests ───────────────────────────────────────────────────────────
 
 #[cfg(test)]
@@ -663,6 +610,14 @@ mod tests {
         dir
     }
 
+    #[test]
+    fn test_is_hidden_path() {
+        let vault = "/home/me/.oxidian/vault";
+        assert!(!is_hidden_path(vault, Path::new("/home/me/.oxidian/vault/notes/a.md")));
+        assert!(is_hidden_path(vault, Path::new("/home/me/.oxidian/vault/.trash/a.md")));
+        assert!(is_hidden_path(vault, Path::new("/home/me/other/a.md")));
+    }
+
     #[test]
     fn test_extract_tags() {
         let tags = extract_tags("Hello #world and #rust/lang stuff #123bad");
@@ -0,0 +1,458 @@
+// Oxidian — Bulk Property Edits
+// Rename a frontmatter key, set or remove a value, or convert a value's type
+// across many notes at once. Edits go through the format-preserving
+// frontmatter editor; every run can be previewed as a dry-run diff and real
+// runs snapshot all affected notes before writing any of them.
+
+use crate::encryption;
+use crate::engine::frontmatter::{self, FrontmatterEditor};
+use crate::engine::vault as vault_ops;
+use crate::features::file_recovery;
+use crate::features::properties::{parse_date, parse_datetime, PropertyRegistry, PropertyType};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+// ─── Operations ────────────────────────────────────────────────────
+
+/// One edit applied to every selected note, in order.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum PropertyOp {
+    /// Rename `from` to `to`, keeping the value.
+    Rename { from: String, to: String },
+    /// Set `key` to `value`, adding the key where missing.
+    Set { key: String, value: Value },
+    /// Remove `key`; with `value`, only remove that value (list item or
+    /// matching scalar).
+    Remove {
+        key: String,
+        #[serde(default)]
+        value: Option<Value>,
+    },
+    /// Convert the value of `key` to another type and register that type.
+    Convert { key: String, to: PropertyType },
+}
+
+/// Which notes a bulk edit applies to.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
+pub enum PropertySelection {
+    Paths(Vec<String>),
+    /// A query as used in ```query blocks, e.g. `FROM #project`.
+    Query(String),
+}
+
+// ─── Report ────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffKind {
+    Removed,
+    Added,
+}
+
+/// A changed line; `line` is 1-based in the old (removed) or new (added) text.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct DiffLine {
+    pub kind: DiffKind,
+    pub line: usize,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PropertyEditChange {
+    pub path: String,
+    pub diff: Vec<DiffLine>,
+    /// New plaintext content, for re-indexing after a real run.
+    #[serde(skip)]
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PropertyEditSkip {
+    pub path: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BulkEditReport {
+    pub dry_run: bool,
+    pub changed: Vec<PropertyEditChange>,
+    pub skipped: Vec<PropertyEditSkip>,
+    pub unchanged: usize,
+}
+
+// ─── Line diff ─────────────────────────────────────────────────────
+
+/// Changed lines between two texts (common prefix/suffix trimmed, LCS on the rest).
+pub fn line_diff(old: &str, new: &str) -> Vec<DiffLine> {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+    let prefix = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..].iter().rev().zip(b[prefix..].iter().rev()).take_while(|(x, y)| x == y).count();
+    let a_mid = &a[prefix..a.len() - suffix];
+    let b_mid = &b[prefix..b.len() - suffix];
+
+    // lcs[i][j] = LCS length of a_mid[i..] and b_mid[j..]
+    let mut lcs = vec![vec![0usize; b_mid.len() + 1]; a_mid.len() + 1];
+    for i in (0..a_mid.len()).rev() {
+        for j in (0..b_mid.len()).rev() {
+            lcs[i][j] = if a_mid[i] == b_mid[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut diff = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a_mid.len() || j < b_mid.len() {
+        if i < a_mid.len() && j < b_mid.len() && a_mid[i] == b_mid[j] {
+            i += 1;
+            j += 1;
+        } else if i < a_mid.len() && (j == b_mid.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            diff.push(DiffLine { kind: DiffKind::Removed, line: prefix + i + 1, text: a_mid[i].to_string() });
+            i += 1;
+        } else {
+            diff.push(DiffLine { kind: DiffKind::Added, line: prefix + j + 1, text: b_mid[j].to_string() });
+            j += 1;
+        }
+    }
+    diff
+}
+
+// ─── Values ────────────────────────────────────────────────────────
+
+fn scalar_string(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(s) => Some(s.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+fn same_value(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::String(x), Value::String(y)) => x.trim() == y.trim(),
+        (Value::Number(x), Value::Number(y)) => x.as_f64() == y.as_f64(),
+        _ => a == b,
+    }
+}
+
+// ─── Type conversion ───────────────────────────────────────────────
+
+/// Convert a value to `to`, or explain why it can't be.
+pub fn convert_value(value: &Value, to: PropertyType) -> Result<Value, String> {
+    if value.is_null() {
+        return Ok(Value::Null);
+    }
+    let text = || scalar_string(value).unwrap_or_default();
+    let fail = || Err(format!("cannot convert {} to {:?}", value, to).to_lowercase());
+    match to {
+        PropertyType::Text => match value {
+            Value::Array(items) => Ok(Value::String(items.iter().filter_map(scalar_string).collect::<Vec<_>>().join(", "))),
+            Value::Object(_) => fail(),
+            other => Ok(Value::String(scalar_string(other).unwrap_or_default())),
+        },
+        PropertyType::Number => match value {
+            Value::Number(_) => Ok(value.clone()),
+            Value::Bool(b) => Ok(Value::from(*b as i64)),
+            Value::String(s) => {
+                let s = s.trim();
+                if let Ok(i) = s.parse::<i64>() {
+                    Ok(Value::from(i))
+                } else {
+                    s.parse::<f64>().ok()
+                        .and_then(serde_json::Number::from_f64)
+                        .map(Value::Number)
+                        .map_or_else(fail, Ok)
+                }
+            }
+            _ => fail(),
+        },
+        PropertyType::Checkbox => match value {
+            Value::Bool(_) => Ok(value.clone()),
+            Value::Number(n) => Ok(Value::Bool(n.as_f64() != Some(0.0))),
+            Value::String(s) => match s.trim().to_lowercase().as_str() {
+                "true" | "yes" | "y" | "1" | "x" | "done" => Ok(Value::Bool(true)),
+                "false" | "no" | "n" | "0" | "" => Ok(Value::Bool(false)),
+                _ => fail(),
+            },
+            _ => fail(),
+        },
+        PropertyType::List => match value {
+            Value::Array(_) => Ok(value.clone()),
+            Value::String(s) => Ok(Value::from(
+                s.split(',').map(|p| p.trim()).filter(|p| !p.is_empty()).map(String::from).collect::<Vec<_>>(),
+            )),
+            Value::Object(_) => fail(),
+            other => Ok(Value::Array(vec![other.clone()])),
+        },
+        PropertyType::Date => {
+            let s = text();
+            parse_date(&s)
+                .or_else(|| parse_datetime(&s).map(|dt| dt.date()))
+                .map(|d| Value::String(d.format("%Y-%m-%d").to_string()))
+                .map_or_else(fail, Ok)
+        }
+        PropertyType::Datetime => {
+            let s = text();
+            parse_datetime(&s)
+                .or_else(|| parse_date(&s).and_then(|d| d.and_hms_opt(0, 0, 0)))
+                .map(|dt| Value::String(dt.format("%Y-%m-%dT%H:%M:%S").to_string()))
+                .map_or_else(fail, Ok)
+        }
+        PropertyType::Link => match value {
+            Value::String(s) => {
+                let s = s.trim();
+                if s.is_empty() {
+                    fail()
+                } else if s.starts_with("[[") && s.ends_with("]]") {
+                    Ok(Value::String(s.to_string()))
+                } else {
+                    Ok(Value::String(format!("[[{}]]", s)))
+                }
+            }
+            _ => fail(),
+        },
+    }
+}
+
+// ─── Applying edits ────────────────────────────────────────────────
+
+/// Set `key` on the editor, keeping the typed fields well-formed.
+fn set_known(editor: &mut FrontmatterEditor, key: &str, value: Value) -> Result<(), String> {
+    let value = frontmatter::coerce_known_field(key, value);
+    if value.is_null() && matches!(key, "title" | "created" | "modified") {
+        editor.remove(key);
+    } else {
+        editor.set(key, &value)?;
+    }
+    Ok(())
+}
+
+/// Apply `ops` to one note's frontmatter. `Ok(false)` if nothing changed.
+pub fn apply_ops(editor: &mut FrontmatterEditor, ops: &[PropertyOp]) -> Result<bool, String> {
+    let before = editor.render();
+    for op in ops {
+        match op {
+            PropertyOp::Rename { from, to } => {
+                if from == to {
+                    continue;
+                }
+                let Some(value) = editor.get(from)? else { continue };
+                match editor.get(to)? {
+                    Some(existing) if same_value(&existing, &value) => { editor.remove(from); }
+                    Some(_) => return Err(format!("'{}' already exists with a different value", to)),
+                    None => { editor.rename(from, to)?; }
+                }
+            }
+            PropertyOp::Set { key, value } => set_known(editor, key, value.clone())?,
+            PropertyOp::Remove { key, value: None } => { editor.remove(key); }
+            PropertyOp::Remove { key, value: Some(target) } => match editor.get(key)? {
+                Some(Value::Array(items)) => {
+                    let kept: Vec<Value> = items.into_iter().filter(|v| !same_value(v, target)).collect();
+                    set_known(editor, key, Value::Array(kept))?;
+                }
+                Some(current) if same_value(&current, target) => { editor.remove(key); }
+                _ => {}
+            },
+            PropertyOp::Convert { key, to } => {
+                if let Some(value) = editor.get(key)? {
+                    let converted = convert_value(&value, *to).map_err(|e| format!("{}: {}", key, e))?;
+                    set_known(editor, key, converted)?;
+                }
+            }
+        }
+    }
+    Ok(editor.render() != before)
+}
+
+/// Apply `ops` to every note in `paths`. With `dry_run` nothing is written;
+/// otherwise all changed notes are snapshotted first, then written. A note
+/// is skipped as a whole if any op fails on it. Encrypted notes are edited
+/// when `password` is given and re-encrypted on write.
+pub fn bulk_edit(
+    vault_path: &str,
+    paths: &[String],
+    ops: &[PropertyOp],
+    dry_run: bool,
+    password: Option<&str>,
+) -> Result<BulkEditReport, String> {
+    let mut report = BulkEditReport { dry_run, changed: Vec::new(), skipped: Vec::new(), unchanged: 0 };
+    let creates_frontmatter = ops.iter().any(|op| matches!(op, PropertyOp::Set { .. }));
+    let mut encrypted = Vec::new();
+
+    for path in paths {
+        let skip = |reason: String| PropertyEditSkip { path: path.clone(), reason };
+        let raw = match vault_ops::read_note(vault_path, path) {
+            Ok(raw) => raw,
+            Err(e) => {
+                report.skipped.push(skip(e));
+                continue;
+            }
+        };
+        let was_encrypted = encryption::is_encrypted_content(&raw);
+        let Some(content) = encryption::plaintext_content(raw, password) else {
+            report.skipped.push(skip("Note is encrypted and the vault is locked".to_string()));
+            continue;
+        };
+        if let Err(e) = frontmatter::parse_frontmatter(&content) {
+            report.skipped.push(skip(e));
+            continue;
+        }
+        let mut editor = FrontmatterEditor::parse(&content);
+        if !editor.has_frontmatter() && !creates_frontmatter {
+            report.unchanged += 1;
+            continue;
+        }
+        match apply_ops(&mut editor, ops) {
+            Ok(true) => {}
+            Ok(false) => {
+                report.unchanged += 1;
+                continue;
+            }
+            Err(e) => {
+                report.skipped.push(skip(e));
+                continue;
+            }
+        }
+        let new_content = editor.render();
+        report.changed.push(PropertyEditChange {
+            path: path.clone(),
+            diff: line_diff(&content, &new_content),
+            content: new_content,
+        });
+        encrypted.push(was_encrypted);
+    }
+
+    if dry_run {
+        return Ok(report);
+    }
+
+    for change in &report.changed {
+        file_recovery::create_snapshot(vault_path, &change.path)?;
+    }
+    for (change, was_encrypted) in report.changed.iter().zip(encrypted) {
+        let data = match (was_encrypted, password) {
+            (true, Some(pwd)) => encryption::encrypt_file_content(&change.content, pwd)?,
+            _ => change.content.clone(),
+        };
+        vault_ops::save_note(vault_path, &change.path, &data)?;
+    }
+
+    let mut registry = PropertyRegistry::load(vault_path);
+    let mut registry_changed = false;
+    for op in ops {
+        if let PropertyOp::Convert { key, to } = op {
+            registry.set(key, Some(*to));
+            registry_changed = true;
+        }
+    }
+    if registry_changed {
+        registry.save(vault_path)?;
+    }
+    Ok(report)
+}
+
+// ─── Tests ─────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn setup() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::write(root.join("a.md"), "---\nStatus: active\npriority: \"2\"\ntags: [work, urgent]\n---\n\nBody A").unwrap();
+        fs::write(root.join("b.md"), "---\nStatus: done\nstatus: done\n---\n\nBody B").unwrap();
+        fs::write(root.join("c.md"), "No frontmatter").unwrap();
+        dir
+    }
+
+    fn paths() -> Vec<String> {
+        vec!["a.md".into(), "b.md".into(), "c.md".into()]
+    }
+
+    #[test]
+    fn test_line_diff() {
+        let diff = line_diff("a\nb\nc\nd", "a\nB\nc\nd\ne");
+        assert_eq!(diff, vec![
+            DiffLine { kind: DiffKind::Removed, line: 2, text: "b".into() },
+            DiffLine { kind: DiffKind::Added, line: 2, text: "B".into() },
+            DiffLine { kind: DiffKind::Added, line: 5, text: "e".into() },
+        ]);
+        assert!(line_diff("same\n", "same\n").is_empty());
+    }
+
+    #[test]
+    fn test_dry_run_rename_writes_nothing() {
+        let dir = setup();
+        let vp = dir.path().to_str().unwrap();
+        let ops = vec![PropertyOp::Rename { from: "Status".into(), to: "status".into() }];
+        let report = bulk_edit(vp, &paths(), &ops, true, None).unwrap();
+        assert_eq!(report.changed.len(), 2);
+        assert_eq!(report.unchanged, 1);
+        assert_eq!(report.changed[0].diff, vec![
+            DiffLine { kind: DiffKind::Removed, line: 2, text: "Status: active".into() },
+            DiffLine { kind: DiffKind::Added, line: 2, text: "status: active".into() },
+        ]);
+        // b.md already has both keys with the same value: they merge
+        assert!(report.changed[1].content.contains("status: done"));
+        assert!(!report.changed[1].content.contains("Status"));
+        assert!(fs::read_to_string(dir.path().join("a.md")).unwrap().contains("Status: active"));
+        assert!(file_recovery::list_snapshots(vp, "a.md").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_set_and_remove_with_snapshots() {
+        let dir = setup();
+        let vp = dir.path().to_str().unwrap();
+        let ops = vec![
+            PropertyOp::Set { key: "project".into(), value: json!("Apollo") },
+            PropertyOp::Remove { key: "tags".into(), value: Some(json!("urgent")) },
+        ];
+        let report = bulk_edit(vp, &paths(), &ops, false, None).unwrap();
+        assert_eq!(report.changed.len(), 3);
+
+        let a = fs::read_to_string(dir.path().join("a.md")).unwrap();
+        assert!(a.contains("project: Apollo"));
+        assert_eq!(a, "---\nStatus: active\npriority: \"2\"\ntags: [work]\nproject: Apollo\n---\n\nBody A");
+        let c = fs::read_to_string(dir.path().join("c.md")).unwrap();
+        assert_eq!(c, "---\nproject: Apollo\n---\nNo frontmatter");
+        assert_eq!(file_recovery::list_snapshots(vp, "a.md").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_convert_types() {
+        let dir = setup();
+        let vp = dir.path().to_str().unwrap();
+        let ops = vec![PropertyOp::Convert { key: "priority".into(), to: PropertyType::Number }];
+        let report = bulk_edit(vp, &["a.md".to_string()], &ops, false, None).unwrap();
+        assert_eq!(report.changed.len(), 1);
+        assert!(fs::read_to_string(dir.path().join("a.md")).unwrap().contains("priority: 2\n"));
+        assert_eq!(PropertyRegistry::load(vp).get("priority"), Some(PropertyType::Number));
+
+        assert_eq!(convert_value(&json!("yes"), PropertyType::Checkbox).unwrap(), json!(true));
+        assert_eq!(convert_value(&json!("a, b"), PropertyType::List).unwrap(), json!(["a", "b"]));
+        assert_eq!(convert_value(&json!("2024-05-01T10:30:00"), PropertyType::Date).unwrap(), json!("2024-05-01"));
+        assert_eq!(convert_value(&json!("Apollo"), PropertyType::Link).unwrap(), json!("[[Apollo]]"));
+        assert!(convert_value(&json!("soon"), PropertyType::Number).is_err());
+    }
+
+    #[test]
+    fn test_failed_op_skips_note() {
+        let dir = setup();
+        let vp = dir.path().to_str().unwrap();
+        fs::write(dir.path().join("d.md"), "---\nStatus: a\nstatus: b\n---\n").unwrap();
+        let ops = vec![PropertyOp::Rename { from: "Status".into(), to: "status".into() }];
+        let report = bulk_edit(vp, &["d.md".to_string()], &ops, false, None).unwrap();
+        assert!(report.changed.is_empty());
+        assert_eq!(report.skipped.len(), 1);
+        assert!(report.skipped[0].reason.contains("already exists"));
+    }
+}
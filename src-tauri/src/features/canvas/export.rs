@@ -0,0 +1,567 @@
+// Oxidian — Canvas export
+// Renders a canvas as a standalone SVG, or linearizes it into a Markdown
+// outline that follows its groups and edges.
+
+use super::layout::encloses;
+use super::validate::is_valid_color;
+use super::{Canvas, CanvasEdge, CanvasEnd, CanvasNode, CanvasNodeType, CanvasSide};
+use crate::features::graph::export::{escape_xml, resolve_export_path};
+use pulldown_cmark::{html, CowStr, Event, Options, Parser, Tag};
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::sync::LazyLock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CanvasExportFormat {
+    Svg,
+    Markdown,
+}
+
+impl CanvasExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            CanvasExportFormat::Svg => "svg",
+            CanvasExportFormat::Markdown => "md",
+        }
+    }
+}
+
+/// Obsidian's default colors for the presets `"1"`–`"6"`.
+const PRESET_COLORS: [&str; 6] = ["#fb464c", "#e9973f", "#e0de71", "#44cf6e", "#53dfdd", "#a882ff"];
+const DEFAULT_STROKE: &str = "#a0a0a0";
+const PADDING: f64 = 40.0;
+
+/// The hex color a canvas color stands for; invalid colors give `None`.
+pub fn resolve_color(color: Option<&str>) -> Option<&str> {
+    let color = color?;
+    if !is_valid_color(color) {
+        return None;
+    }
+    match color.parse::<usize>() {
+        Ok(preset) => PRESET_COLORS.get(preset - 1).copied(),
+        Err(_) => Some(color),
+    }
+}
+
+/// Innermost group around each node.
+fn parents(canvas: &Canvas) -> Vec<Option<usize>> {
+    let area = |i: usize| canvas.nodes[i].width * canvas.nodes[i].height;
+    (0..canvas.nodes.len())
+        .map(|i| {
+            (0..canvas.nodes.len())
+                .filter(|&g| encloses(canvas, g, i))
+                .min_by(|&a, &b| area(a).total_cmp(&area(b)))
+        })
+        .collect()
+}
+
+fn reading_order(canvas: &Canvas, nodes: &mut [usize]) {
+    nodes.sort_by(|&a, &b| {
+        let (a, b) = (&canvas.nodes[a], &canvas.nodes[b]);
+        a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x))
+    });
+}
+
+// ─── SVG ───────────────────────────────────────────────────────────
+
+/// Coordinates to at most one decimal, without a trailing `.0`.
+fn num(value: f64) -> String {
+    format!("{}", (value * 10.0).round() / 10.0)
+}
+
+fn is_safe_url(url: &str) -> bool {
+    let url = url.trim_start().to_lowercase();
+    url.starts_with("http://") || url.starts_with("https://") || url.starts_with("mailto:")
+}
+
+static WIKI_LINK_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"!?\[\[([^\]|]+)(?:\|([^\]]+))?\]\]").unwrap()
+});
+
+/// Markdown as XHTML for `<foreignObject>`: wiki links become their text,
+/// raw HTML is shown as text and only web links stay clickable.
+fn markdown_xhtml(text: &str) -> String {
+    let text = WIKI_LINK_RE.replace_all(text, |caps: &regex::Captures| {
+        caps.get(2).or_else(|| caps.get(1)).map_or("", |m| m.as_str()).to_string()
+    });
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+    options.insert(Options::ENABLE_TASKLISTS);
+    let events = Parser::new_ext(&text, options).map(|event| match event {
+        Event::Html(raw) | Event::InlineHtml(raw) => Event::Text(raw),
+        Event::Start(Tag::Link { link_type, dest_url, title, id }) if !is_safe_url(&dest_url) => {
+            Event::Start(Tag::Link { link_type, dest_url: CowStr::Borrowed("#"), title, id })
+        }
+        other => other,
+    });
+    let mut out = String::new();
+    html::push_html(&mut out, events);
+    out
+}
+
+/// Midpoint of a side and the direction pointing away from the node.
+fn anchor(node: &CanvasNode, side: &CanvasSide) -> ((f64, f64), (f64, f64)) {
+    let (cx, cy) = (node.x + node.width / 2.0, node.y + node.height / 2.0);
+    match side {
+        CanvasSide::Top => ((cx, node.y), (0.0, -1.0)),
+        CanvasSide::Right => ((node.x + node.width, cy), (1.0, 0.0)),
+        CanvasSide::Bottom => ((cx, node.y + node.height), (0.0, 1.0)),
+        CanvasSide::Left => ((node.x, cy), (-1.0, 0.0)),
+    }
+}
+
+/// The side of `node` facing `other`, for edges that do not name one.
+fn facing_side(node: &CanvasNode, other: &CanvasNode) -> CanvasSide {
+    let dx = (other.x + other.width / 2.0) - (node.x + node.width / 2.0);
+    let dy = (other.y + other.height / 2.0) - (node.y + node.height / 2.0);
+    match (dx.abs() >= dy.abs(), dx >= 0.0, dy >= 0.0) {
+        (true, true, _) => CanvasSide::Right,
+        (true, false, _) => CanvasSide::Left,
+        (false, _, true) => CanvasSide::Bottom,
+        (false, _, false) => CanvasSide::Top,
+    }
+}
+
+fn arrow_head(tip: (f64, f64), out: (f64, f64), color: &str) -> String {
+    let base = (tip.0 + out.0 * 12.0, tip.1 + out.1 * 12.0);
+    let wing = (-out.1 * 6.0, out.0 * 6.0);
+    format!(
+        "<polygon points=\"{},{} {},{} {},{}\" fill=\"{}\"/>\n",
+        num(tip.0), num(tip.1),
+        num(base.0 + wing.0), num(base.1 + wing.1),
+        num(base.0 - wing.0), num(base.1 - wing.1),
+        color,
+    )
+}
+
+fn svg_edge(canvas: &Canvas, edge: &CanvasEdge) -> Option<String> {
+    let (from, to) = (canvas.get_node(&edge.from_node)?, canvas.get_node(&edge.to_node)?);
+    let from_side = edge.from_side.clone().unwrap_or_else(|| facing_side(from, to));
+    let to_side = edge.to_side.clone().unwrap_or_else(|| facing_side(to, from));
+    let ((x1, y1), n1) = anchor(from, &from_side);
+    let ((x2, y2), n2) = anchor(to, &to_side);
+    let bend = ((x2 - x1).hypot(y2 - y1) * 0.4).clamp(40.0, 200.0);
+    let (c1, c2) = ((x1 + n1.0 * bend, y1 + n1.1 * bend), (x2 + n2.0 * bend, y2 + n2.1 * bend));
+    let color = resolve_color(edge.color.as_deref()).unwrap_or(DEFAULT_STROKE);
+
+    let mut out = format!("<g class=\"edge\" id=\"edge-{}\">\n", escape_xml(&edge.id));
+    out.push_str(&format!(
+        "<path d=\"M {} {} C {} {}, {} {}, {} {}\" fill=\"none\" stroke=\"{}\" stroke-width=\"2\"/>\n",
+        num(x1), num(y1), num(c1.0), num(c1.1), num(c2.0), num(c2.1), num(x2), num(y2), color,
+    ));
+    // Spec defaults: no end at the source, an arrow at the target
+    if edge.from_end == Some(CanvasEnd::Arrow) {
+        out.push_str(&arrow_head((x1, y1), n1, color));
+    }
+    if edge.to_end != Some(CanvasEnd::None) {
+        out.push_str(&arrow_head((x2, y2), n2, color));
+    }
+    if let Some(label) = edge.label.as_deref().filter(|l| !l.is_empty()) {
+        let mid_x = (x1 + 3.0 * c1.0 + 3.0 * c2.0 + x2) / 8.0;
+        let mid_y = (y1 + 3.0 * c1.1 + 3.0 * c2.1 + y2) / 8.0;
+        out.push_str(&format!(
+            "<text class=\"edge-label\" x=\"{}\" y=\"{}\" text-anchor=\"middle\" dominant-baseline=\"middle\">{}</text>\n",
+            num(mid_x), num(mid_y), escape_xml(label),
+        ));
+    }
+    out.push_str("</g>\n");
+    Some(out)
+}
+
+fn svg_group(node: &CanvasNode) -> String {
+    let color = resolve_color(node.color.as_deref());
+    let mut out = format!(
+        "<g class=\"group\" id=\"node-{}\">\n<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"12\" fill=\"{}\" fill-opacity=\"0.08\" stroke=\"{}\" stroke-width=\"2\"/>\n",
+        escape_xml(&node.id), num(node.x), num(node.y), num(node.width), num(node.height),
+        color.unwrap_or("#808080"), color.unwrap_or(DEFAULT_STROKE),
+    );
+    if let Some(label) = node.label.as_deref().filter(|l| !l.is_empty()) {
+        out.push_str(&format!(
+            "<text class=\"group-label\" x=\"{}\" y=\"{}\">{}</text>\n",
+            num(node.x), num(node.y - 8.0), escape_xml(label),
+        ));
+    }
+    out.push_str("</g>\n");
+    out
+}
+
+fn svg_node(node: &CanvasNode) -> String {
+    let body = match node.node_type {
+        CanvasNodeType::Text => markdown_xhtml(node.text.as_deref().unwrap_or("")),
+        CanvasNodeType::File => {
+            let file = node.file.as_deref().unwrap_or("");
+            let name = file.rsplit('/').next().unwrap_or(file);
+            let mut body = format!("<div class=\"file-name\">{}</div>", escape_xml(name.trim_end_matches(".md")));
+            if let Some(subpath) = &node.subpath {
+                body.push_str(&format!("<div class=\"subpath\">{}</div>", escape_xml(subpath)));
+            }
+            body.push_str(&format!("<div class=\"file-path\">{}</div>", escape_xml(file)));
+            body
+        }
+        CanvasNodeType::Link => {
+            let url = node.url.as_deref().unwrap_or("");
+            if is_safe_url(url) {
+                format!("<a href=\"{0}\">{0}</a>", escape_xml(url))
+            } else {
+                escape_xml(url)
+            }
+        }
+        CanvasNodeType::Group => String::new(),
+    };
+    let kind = match node.node_type {
+        CanvasNodeType::Text => "text",
+        CanvasNodeType::File => "file",
+        CanvasNodeType::Link => "link",
+        CanvasNodeType::Group => "group",
+    };
+    let inset = |size: f64| num((size - 24.0).max(0.0));
+    format!(
+        "<g class=\"node node-{}\" id=\"node-{}\">\n<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"8\" fill=\"#ffffff\" stroke=\"{}\" stroke-width=\"2\"/>\n\
+         <foreignObject x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"><div xmlns=\"http://www.w3.org/1999/xhtml\" class=\"content\">{}</div></foreignObject>\n</g>\n",
+        kind, escape_xml(&node.id), num(node.x), num(node.y), num(node.width), num(node.height),
+        resolve_color(node.color.as_deref()).unwrap_or(DEFAULT_STROKE),
+        num(node.x + 12.0), num(node.y + 12.0), inset(node.width), inset(node.height), body,
+    )
+}
+
+const SVG_STYLE: &str = "<style>\n\
+text { font-family: -apple-system, 'Segoe UI', Helvetica, Arial, sans-serif; font-size: 14px; fill: #222222; }\n\
+.group-label { font-size: 16px; font-weight: 600; }\n\
+.edge-label { paint-order: stroke; stroke: #ffffff; stroke-width: 4px; }\n\
+.content { font-family: -apple-system, 'Segoe UI', Helvetica, Arial, sans-serif; font-size: 14px; line-height: 1.4; color: #222222; overflow: hidden; height: 100%; }\n\
+.content > :first-child { margin-top: 0; }\n\
+.file-name { font-weight: 600; }\n\
+.subpath, .file-path { color: #777777; font-size: 12px; }\n\
+</style>\n";
+
+/// A standalone SVG of the whole canvas: groups at the back, then edges,
+/// then nodes, with text nodes rendered from Markdown.
+pub fn to_svg(canvas: &Canvas) -> String {
+    let (mut left, mut top, mut right, mut bottom) = (f64::MAX, f64::MAX, f64::MIN, f64::MIN);
+    for node in &canvas.nodes {
+        left = left.min(node.x);
+        top = top.min(node.y);
+        right = right.max(node.x + node.width);
+        bottom = bottom.max(node.y + node.height);
+    }
+    if canvas.nodes.is_empty() {
+        (left, top, right, bottom) = (0.0, 0.0, 0.0, 0.0);
+    }
+    let (x, y) = (left - PADDING, top - PADDING);
+    let (w, h) = (right - left + 2.0 * PADDING, bottom - top + 2.0 * PADDING);
+
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str(&format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\" width=\"{}\" height=\"{}\">\n",
+        num(x), num(y), num(w), num(h), num(w), num(h),
+    ));
+    out.push_str(SVG_STYLE);
+    out.push_str(&format!("<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#fafafa\"/>\n", num(x), num(y), num(w), num(h)));
+
+    // Larger groups first so nested groups are drawn over them
+    let mut groups: Vec<&CanvasNode> = canvas.nodes_of_type(CanvasNodeType::Group);
+    groups.sort_by(|a, b| (b.width * b.height).total_cmp(&(a.width * a.height)));
+    for group in groups {
+        out.push_str(&svg_group(group));
+    }
+    for edge in &canvas.edges {
+        out.push_str(&svg_edge(canvas, edge).unwrap_or_default());
+    }
+    for node in canvas.nodes.iter().filter(|n| n.node_type != CanvasNodeType::Group) {
+        out.push_str(&svg_node(node));
+    }
+    out.push_str("</svg>\n");
+    out
+}
+
+// ─── Markdown outline ──────────────────────────────────────────────
+
+/// One-line name of a node, for headings and the connection list.
+fn short_title(node: &CanvasNode) -> String {
+    let title = match node.node_type {
+        CanvasNodeType::Text => node.text.as_deref()
+            .and_then(|t| t.lines().map(|l| l.trim().trim_start_matches('#').trim()).find(|l| !l.is_empty()))
+            .unwrap_or("Text")
+            .to_string(),
+        CanvasNodeType::File => {
+            let file = node.file.as_deref().unwrap_or("");
+            file.rsplit('/').next().unwrap_or(file).trim_end_matches(".md").to_string()
+        }
+        CanvasNodeType::Link => node.url.clone().unwrap_or_default(),
+        CanvasNodeType::Group => node.label.clone().filter(|l| !l.is_empty()).unwrap_or_else(|| "Group".to_string()),
+    };
+    match title.char_indices().nth(60) {
+        Some((cut, _)) => format!("{}…", &title[..cut]),
+        None => title,
+    }
+}
+
+/// What a node says in the outline, possibly over several lines.
+fn outline_text(node: &CanvasNode) -> String {
+    match node.node_type {
+        CanvasNodeType::Text => node.text.clone().unwrap_or_default(),
+        CanvasNodeType::File => {
+            let file = node.file.as_deref().unwrap_or("");
+            let target = file.strip_suffix(".md").unwrap_or(file);
+            format!("[[{}{}]]", target, node.subpath.as_deref().unwrap_or(""))
+        }
+        CanvasNodeType::Link => format!("<{}>", node.url.as_deref().unwrap_or("")),
+        CanvasNodeType::Group => short_title(node),
+    }
+}
+
+struct Outline<'a> {
+    canvas: &'a Canvas,
+    parents: Vec<Option<usize>>,
+    index: std::collections::HashMap<&'a str, usize>,
+    visited: HashSet<usize>,
+    /// Edges shown by nesting; the rest are listed as connections
+    followed: HashSet<usize>,
+    out: String,
+}
+
+impl Outline<'_> {
+    fn children(&self, container: Option<usize>) -> Vec<usize> {
+        let mut nodes: Vec<usize> = (0..self.canvas.nodes.len()).filter(|&i| self.parents[i] == container).collect();
+        reading_order(self.canvas, &mut nodes);
+        nodes
+    }
+
+    fn container(&mut self, container: Option<usize>, level: usize) {
+        let children = self.children(container);
+        let (groups, items): (Vec<usize>, Vec<usize>) = children.into_iter()
+            .partition(|&i| self.canvas.nodes[i].node_type == CanvasNodeType::Group);
+
+        // Follow edges between the container's own notes, from the notes
+        // nothing points at; whatever is left sits in a cycle
+        let members: HashSet<usize> = items.iter().copied().collect();
+        let has_incoming: HashSet<usize> = self.canvas.edges.iter()
+            .filter_map(|e| Some((*self.index.get(e.from_node.as_str())?, *self.index.get(e.to_node.as_str())?)))
+            .filter(|(a, b)| a != b && members.contains(a) && members.contains(b))
+            .map(|(_, b)| b)
+            .collect();
+        let roots: Vec<usize> = items.iter().copied().filter(|i| !has_incoming.contains(i))
+            .chain(items.iter().copied())
+            .collect();
+        let before = self.out.len();
+        for root in roots {
+            if !self.visited.contains(&root) {
+                self.bullet(root, &members);
+            }
+        }
+        if self.out.len() > before {
+            self.out.push('\n');
+        }
+
+        for group in groups {
+            let heading = "#".repeat(level.min(6));
+            self.out.push_str(&format!("{} {}\n\n", heading, short_title(&self.canvas.nodes[group])));
+            self.visited.insert(group);
+            self.container(Some(group), level + 1);
+        }
+    }
+
+    /// Bullet for `root` with the notes its edges lead to nested below it,
+    /// depth first. Walks with an explicit stack so long chains cannot
+    /// overflow the call stack.
+    fn bullet(&mut self, root: usize, members: &HashSet<usize>) {
+        self.item(root, 0, None);
+        // (node, depth, next edge to look at)
+        let mut stack = vec![(root, 0, 0)];
+        let canvas = self.canvas;
+        while let Some(frame) = stack.last_mut() {
+            let (node, depth, next) = *frame;
+            let found = canvas.edges.iter().enumerate().skip(next)
+                .filter(|(_, edge)| edge.from_node == canvas.nodes[node].id)
+                .find_map(|(e, edge)| {
+                    let target = *self.index.get(edge.to_node.as_str())?;
+                    (members.contains(&target) && !self.visited.contains(&target)).then_some((e, target))
+                });
+            let Some((e, target)) = found else {
+                stack.pop();
+                continue;
+            };
+            frame.2 = e + 1;
+            self.followed.insert(e);
+            self.item(target, depth + 1, canvas.edges[e].label.as_deref());
+            stack.push((target, depth + 1, 0));
+        }
+    }
+
+    fn item(&mut self, node: usize, depth: usize, label: Option<&str>) {
+        self.visited.insert(node);
+        let indent = "  ".repeat(depth);
+        let text = outline_text(&self.canvas.nodes[node]);
+        let mut lines = text.lines();
+        let first = lines.next().unwrap_or("");
+        match label.filter(|l| !l.is_empty()) {
+            Some(label) => self.out.push_str(&format!("{}- *{}*: {}\n", indent, label, first)),
+            None => self.out.push_str(&format!("{}- {}\n", indent, first)),
+        }
+        for line in lines {
+            if line.trim().is_empty() {
+                self.out.push('\n');
+            } else {
+                self.out.push_str(&format!("{}  {}\n", indent, line));
+            }
+        }
+    }
+}
+
+/// The canvas as an outline under `title`: loose notes first, then one
+/// section per group. Edges nest their targets under their sources; edges the
+/// nesting cannot show are listed at the end.
+pub fn to_markdown(canvas: &Canvas, title: &str) -> String {
+    let mut outline = Outline {
+        canvas,
+        parents: parents(canvas),
+        index: canvas.nodes.iter().enumerate().map(|(i, n)| (n.id.as_str(), i)).collect(),
+        visited: HashSet::new(),
+        followed: HashSet::new(),
+        out: format!("# {}\n\n", title),
+    };
+    outline.container(None, 2);
+
+    let connections: Vec<String> = canvas.edges.iter()
+        .enumerate()
+        .filter(|(e, _)| !outline.followed.contains(e))
+        .filter_map(|(_, edge)| {
+            let from = short_title(canvas.get_node(&edge.from_node)?);
+            let to = short_title(canvas.get_node(&edge.to_node)?);
+            Some(match edge.label.as_deref().filter(|l| !l.is_empty()) {
+                Some(label) => format!("- {} → {}: {}\n", from, to, label),
+                None => format!("- {} → {}\n", from, to),
+            })
+        })
+        .collect();
+    let mut out = outline.out;
+    if !connections.is_empty() {
+        out.push_str("## Connections\n\n");
+        out.extend(connections);
+    }
+    format!("{}\n", out.trim_end())
+}
+
+// ─── Writing ───────────────────────────────────────────────────────
+
+pub fn render(canvas: &Canvas, format: CanvasExportFormat, title: &str) -> String {
+    match format {
+        CanvasExportFormat::Svg => to_svg(canvas),
+        CanvasExportFormat::Markdown => to_markdown(canvas, title),
+    }
+}
+
+/// Renders the canvas at `canvas_path` into the vault, next to the other
+/// exports unless `target` says otherwise. Returns the relative path.
+pub fn export_canvas(vault_path: &str, canvas_path: &str, target: Option<&str>, format: CanvasExportFormat) -> Result<String, String> {
+    let canvas = Canvas::load(&Path::new(vault_path).join(canvas_path))?;
+    let title = Path::new(canvas_path).file_stem().and_then(|s| s.to_str()).unwrap_or("Canvas");
+    let relative = resolve_export_path(vault_path, target, title, format.extension())?;
+    let full = Path::new(vault_path).join(&relative);
+    if let Some(parent) = full.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create export folder: {}", e))?;
+    }
+    fs::write(&full, render(&canvas, format, title)).map_err(|e| format!("Failed to write canvas export: {}", e))?;
+    Ok(relative)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn sample() -> Canvas {
+        Canvas::from_json(include_str!("fixtures/spec-basic.canvas")).unwrap()
+    }
+
+    #[test]
+    fn test_svg_export() {
+        let mut canvas = sample();
+        canvas.add_text_node(900.0, 300.0, 200.0, 80.0, "<script>alert(1)</script>\n\nSee [[Roadmap|the plan]] & [x](javascript:alert(1))");
+        let svg = to_svg(&canvas);
+
+        assert!(svg.starts_with("<?xml") && svg.trim_end().ends_with("</svg>"));
+        assert!(svg.contains("viewBox=\"-500 -360 1640 780\""));
+        // Markdown text, groups with labels, colors and both arrow styles
+        assert!(svg.contains("<h1>Goals</h1>") && svg.contains("<strong>beta</strong>"));
+        assert!(svg.contains(">Project kickoff</text>"));
+        assert!(svg.contains("fill=\"#44cf6e\" fill-opacity=\"0.08\""));
+        assert!(svg.contains("stroke=\"#3a7bd5\""));
+        assert_eq!(svg.matches("<polygon").count(), 3);
+        assert!(svg.contains(">tracked in</text>"));
+        assert!(svg.contains("<a href=\"https://obsidian.md\">"));
+        // Nothing from the text escapes into markup
+        assert!(svg.contains("&lt;script&gt;") && !svg.contains("<script>"));
+        assert!(svg.contains("See the plan &amp; <a href=\"#\">x</a>") && !svg.contains("javascript:"));
+
+        let empty = to_svg(&Canvas::new());
+        assert!(empty.contains("viewBox=\"-40 -40 80 80\""));
+    }
+
+    #[test]
+    fn test_markdown_outline() {
+        let mut canvas = Canvas::new();
+        let idea = canvas.add_text_node(0.0, 0.0, 200.0, 80.0, "# Idea\nLine two");
+        let step = canvas.add_file_node(0.0, 200.0, 200.0, 80.0, "Plans/Step.md", Some("#Now"));
+        let link = canvas.add_link_node(300.0, 200.0, 200.0, 80.0, "https://example.com");
+        let group = canvas.add_group_node(0.0, 500.0, 600.0, 300.0, Some("Later"));
+        let inner = canvas.add_text_node(20.0, 540.0, 200.0, 80.0, "Inside");
+        canvas.add_edge(&idea, &step, None, None, Some("leads to"));
+        canvas.add_edge(&idea, &link, None, None, None);
+        canvas.add_edge(&link, &step, None, None, None);
+        canvas.add_edge(&step, &inner, None, None, Some("then"));
+        canvas.add_edge(&group, &idea, None, None, None);
+
+        let md = to_markdown(&canvas, "Plan");
+        assert_eq!(md, "# Plan\n\n\
+            - # Idea\n  Line two\n  - *leads to*: [[Plans/Step#Now]]\n  - <https://example.com>\n\n\
+            ## Later\n\n\
+            - Inside\n\n\
+            ## Connections\n\n\
+            - https://example.com → Step\n\
+            - Step → Inside: then\n\
+            - Later → Idea\n");
+    }
+
+    #[test]
+    fn test_markdown_outline_of_a_long_chain() {
+        let mut canvas = Canvas::new();
+        let ids: Vec<String> = (0..2000).map(|i| canvas.add_text_node(0.0, i as f64 * 100.0, 200.0, 80.0, &format!("Step {}", i))).collect();
+        for pair in ids.windows(2) {
+            canvas.add_edge(&pair[0], &pair[1], None, None, None);
+        }
+        // A small stack: nesting must not recurse once per link
+        let md = std::thread::Builder::new().stack_size(64 * 1024)
+            .spawn(move || to_markdown(&canvas, "Chain"))
+            .unwrap().join().unwrap();
+        assert!(md.contains(&format!("\n{}- Step 1999\n", "  ".repeat(1999))));
+        assert!(!md.contains("## Connections"));
+    }
+
+    #[test]
+    fn test_export_canvas_writes_into_vault() {
+        let dir = TempDir::new().unwrap();
+        let vault = dir.path().to_str().unwrap();
+        sample().save(&dir.path().join("Boards/Kickoff.canvas")).unwrap();
+
+        let svg = export_canvas(vault, "Boards/Kickoff.canvas", None, CanvasExportFormat::Svg).unwrap();
+        assert_eq!(svg, "exports/Kickoff.svg");
+        let md = export_canvas(vault, "Boards/Kickoff.canvas", Some("shared/"), CanvasExportFormat::Markdown).unwrap();
+        assert_eq!(md, "shared/Kickoff.md");
+        let written = fs::read_to_string(dir.path().join(md)).unwrap();
+        assert!(written.starts_with("# Kickoff\n\n## Project kickoff\n\n- # Goals"));
+        assert!(export_canvas(vault, "Boards/Kickoff.canvas", Some("../out.svg"), CanvasExportFormat::Svg).is_err());
+
+        // Exports never replace a note or write one format under another's name
+        fs::write(dir.path().join("Plan.md"), "mine").unwrap();
+        assert!(export_canvas(vault, "Boards/Kickoff.canvas", Some("Plan.md"), CanvasExportFormat::Markdown).is_err());
+        assert!(export_canvas(vault, "Boards/Kickoff.canvas", Some("shared/"), CanvasExportFormat::Markdown).is_err());
+        assert!(export_canvas(vault, "Boards/Kickoff.canvas", Some("Plan.md"), CanvasExportFormat::Svg).is_err());
+        assert_eq!(fs::read_to_string(dir.path().join("Plan.md")).unwrap(), "mine");
+    }
+}
@@ -0,0 +1,660 @@
+// Oxidian — Canvas layout
+// Grid, tree and force-directed arrangements for a canvas or a selection.
+// Groups move as one block with everything inside them, and nodes never overlap.
+
+use super::{Canvas, CanvasNodeType};
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CanvasLayout {
+    #[default]
+    Grid,
+    /// Layered tree following edge direction
+    Tree,
+    Force,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TreeDirection {
+    #[default]
+    Down,
+    Right,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CanvasLayoutOptions {
+    pub algorithm: CanvasLayout,
+    /// Gap between nodes; tree layers are twice as far apart
+    pub spacing: f64,
+    /// Grid columns; square-ish when unset
+    pub columns: Option<usize>,
+    pub direction: TreeDirection,
+    /// Force simulation steps, at most `MAX_FORCE_ITERATIONS`
+    pub iterations: usize,
+}
+
+/// Upper bound on force simulation steps; more do not visibly improve the
+/// layout and a caller-supplied count must not stall the app.
+pub const MAX_FORCE_ITERATIONS: usize = 1000;
+
+impl Default for CanvasLayoutOptions {
+    fn default() -> Self {
+        Self { algorithm: CanvasLayout::Grid, spacing: 40.0, columns: None, direction: TreeDirection::Down, iterations: 300 }
+    }
+}
+
+/// A unit of layout: a node, or a group with everything inside it.
+struct Block {
+    /// Node indices; the first is the block's own node
+    members: Vec<usize>,
+    x: f64,
+    y: f64,
+    w: f64,
+    h: f64,
+}
+
+impl Block {
+    fn center(&self) -> (f64, f64) {
+        (self.x + self.w / 2.0, self.y + self.h / 2.0)
+    }
+}
+
+fn rect_contains(canvas: &Canvas, outer: usize, inner: usize) -> bool {
+    let (o, i) = (&canvas.nodes[outer], &canvas.nodes[inner]);
+    outer != inner
+        && o.node_type == CanvasNodeType::Group
+        && i.x >= o.x && i.y >= o.y
+        && i.x + i.width <= o.x + o.width
+        && i.y + i.height <= o.y + o.height
+}
+
+/// `outer` holds `inner`; of two groups with the same bounds the earlier one is outside.
+pub(super) fn encloses(canvas: &Canvas, outer: usize, inner: usize) -> bool {
+    rect_contains(canvas, outer, inner) && (!rect_contains(canvas, inner, outer) || outer < inner)
+}
+
+impl Canvas {
+    /// Arranges the selected nodes, or the whole canvas with `None`, and
+    /// returns the ids of nodes that moved.
+    ///
+    /// A selected group is laid out as one block and carries its contents
+    /// along. Selected nodes stay in the group they were in: each group's
+    /// share of the selection is laid out on its own and the group grows to
+    /// fit it. The arrangement keeps the top-left corner of what it replaces
+    /// unless that would cover an unselected node, in which case it moves
+    /// right until it is clear; whatever a growing group runs into is pushed
+    /// out of its way.
+    pub fn apply_layout(&mut self, selection: Option<&[String]>, options: &CanvasLayoutOptions) -> Vec<String> {
+        let selected: BTreeSet<usize> = match selection {
+            Some(ids) => {
+                let ids: HashSet<&str> = ids.iter().map(String::as_str).collect();
+                (0..self.nodes.len()).filter(|&i| ids.contains(self.nodes[i].id.as_str())).collect()
+            }
+            None => (0..self.nodes.len()).collect(),
+        };
+        // Selected nodes not inside another selected group, grouped by the
+        // innermost group around them
+        let mut partitions: BTreeMap<Option<usize>, Vec<Block>> = BTreeMap::new();
+        for &i in &selected {
+            if selected.iter().any(|&g| encloses(self, g, i)) {
+                continue;
+            }
+            partitions.entry(self.parent_group(i, None)).or_default().push(self.block(i));
+        }
+
+        let spacing = options.spacing.max(0.0).round();
+        let mut moved = BTreeSet::new();
+        for (parent, mut blocks) in partitions {
+            let edges = self.block_edges(&blocks);
+            let (origin_x, origin_y) = blocks.iter().fold((f64::MAX, f64::MAX), |(x, y), b| (x.min(b.x), y.min(b.y)));
+            let placed = match options.algorithm {
+                CanvasLayout::Grid => grid(&blocks, options.columns, spacing),
+                CanvasLayout::Tree => tree(&blocks, &edges, spacing, options.direction),
+                CanvasLayout::Force => force(&blocks, &edges, spacing, options.iterations.min(MAX_FORCE_ITERATIONS)),
+            };
+            let (min_x, min_y) = placed.iter().fold((f64::MAX, f64::MAX), |(x, y), p| (x.min(p.0), y.min(p.1)));
+            // Flooring keeps integer-sized boxes apart
+            let mut targets: Vec<(f64, f64)> = placed.iter()
+                .map(|p| ((origin_x + p.0 - min_x).floor(), (origin_y + p.1 - min_y).floor()))
+                .collect();
+            clear_of(&blocks, &mut targets, &self.obstacles(&blocks), spacing);
+            for (block, (x, y)) in blocks.iter_mut().zip(targets) {
+                self.shift_block(block, x - block.x, y - block.y, &mut moved);
+            }
+            if let Some(group) = parent {
+                self.grow_group(group, &blocks, spacing, &mut moved);
+            }
+        }
+        moved.into_iter().map(|i| self.nodes[i].id.clone()).collect()
+    }
+
+    /// Node `i` as a block, with everything inside it if it is a group.
+    fn block(&self, i: usize) -> Block {
+        let mut members = vec![i];
+        if self.nodes[i].node_type == CanvasNodeType::Group {
+            members.extend((0..self.nodes.len()).filter(|&j| encloses(self, i, j)));
+        }
+        let node = &self.nodes[i];
+        Block { members, x: node.x, y: node.y, w: node.width, h: node.height }
+    }
+
+    /// Innermost group around node `i`, not counting `skip`.
+    fn parent_group(&self, i: usize, skip: Option<usize>) -> Option<usize> {
+        (0..self.nodes.len())
+            .filter(|&g| Some(g) != skip && encloses(self, g, i))
+            .min_by(|&a, &b| area(self, a).total_cmp(&area(self, b)))
+    }
+
+    /// Boxes of the nodes that stay where they are while `blocks` move: all
+    /// but the blocks' own members and the groups around them.
+    fn obstacles(&self, blocks: &[Block]) -> Vec<(f64, f64, f64, f64)> {
+        let members: HashSet<usize> = blocks.iter().flat_map(|b| b.members.iter().copied()).collect();
+        (0..self.nodes.len())
+            .filter(|&j| !members.contains(&j) && !blocks.iter().any(|b| encloses(self, j, b.members[0])))
+            .map(|j| (self.nodes[j].x, self.nodes[j].y, self.nodes[j].width, self.nodes[j].height))
+            .collect()
+    }
+
+    fn shift_block(&mut self, block: &mut Block, dx: f64, dy: f64, moved: &mut BTreeSet<usize>) {
+        if dx == 0.0 && dy == 0.0 {
+            return;
+        }
+        for &m in &block.members {
+            self.nodes[m].x += dx;
+            self.nodes[m].y += dy;
+            moved.insert(m);
+        }
+        block.x += dx;
+        block.y += dy;
+    }
+
+    /// Edges between distinct blocks, by block index, without repeats.
+    fn block_edges(&self, blocks: &[Block]) -> Vec<(usize, usize)> {
+        let block_of: HashMap<&str, usize> = blocks.iter()
+            .enumerate()
+            .flat_map(|(b, block)| block.members.iter().map(move |&m| (m, b)))
+            .map(|(m, b)| (self.nodes[m].id.as_str(), b))
+            .collect();
+        let mut seen = HashSet::new();
+        self.edges.iter()
+            .filter_map(|e| Some((*block_of.get(e.from_node.as_str())?, *block_of.get(e.to_node.as_str())?)))
+            .filter(|(a, b)| a != b && seen.insert((*a, *b)))
+            .collect()
+    }
+
+    /// Enlarges `group` so every block sits inside it with `padding` to spare,
+    /// then pushes the neighbours it now overlaps out of the way and lets the
+    /// group around it grow in turn.
+    fn grow_group(&mut self, group: usize, blocks: &[Block], padding: f64, moved: &mut BTreeSet<usize>) {
+        let mut inside: HashSet<usize> = (0..self.nodes.len()).filter(|&j| encloses(self, group, j)).collect();
+        inside.extend(blocks.iter().flat_map(|b| b.members.iter().copied()));
+        let parent = self.parent_group(group, None);
+
+        let node = &mut self.nodes[group];
+        let before = (node.x, node.y, node.width, node.height);
+        let (mut left, mut top) = (node.x, node.y);
+        let (mut right, mut bottom) = (node.x + node.width, node.y + node.height);
+        for b in blocks {
+            left = left.min(b.x - padding);
+            top = top.min(b.y - padding);
+            right = right.max(b.x + b.w + padding);
+            bottom = bottom.max(b.y + b.h + padding);
+        }
+        node.x = left;
+        node.y = top;
+        node.width = right - left;
+        node.height = bottom - top;
+        if (node.x, node.y, node.width, node.height) == before {
+            return;
+        }
+
+        // The group's neighbours, each with its contents; the group stays put
+        // and pulls in only what it overlaps, plus whatever that then hits
+        let mut level = vec![Block { members: vec![group], x: left, y: top, w: right - left, h: bottom - top }];
+        level.extend((0..self.nodes.len())
+            .filter(|&j| j != group && !inside.contains(&j) && self.parent_group(j, Some(group)) == parent)
+            .map(|j| self.block(j)));
+        let mut crowd = vec![0];
+        loop {
+            let joined: Vec<usize> = (1..level.len())
+                .filter(|k| !crowd.contains(k) && crowd.iter().any(|&c| overlaps(&level[c], &level[*k])))
+                .collect();
+            if joined.is_empty() {
+                break;
+            }
+            crowd.extend(joined);
+            let mut pos: Vec<(f64, f64)> = crowd.iter().map(|&k| level[k].center()).collect();
+            let boxes: Vec<Block> = crowd.iter()
+                .map(|&k| Block { members: Vec::new(), ..level[k] })
+                .collect();
+            separate(&boxes, &mut pos, padding, Some(0));
+            for (&k, p) in crowd.iter().zip(pos).skip(1) {
+                let block = &mut level[k];
+                let (dx, dy) = ((p.0 - block.w / 2.0).floor() - block.x, (p.1 - block.h / 2.0).floor() - block.y);
+                self.shift_block(block, dx, dy, moved);
+            }
+        }
+        if let Some(parent) = parent {
+            self.grow_group(parent, &level, padding, moved);
+        }
+    }
+}
+
+fn overlaps(a: &Block, b: &Block) -> bool {
+    a.x < b.x + b.w && b.x < a.x + a.w && a.y < b.y + b.h && b.y < a.y + a.h
+}
+
+/// Moves the arrangement `targets` (top-left corners of `blocks`) right
+/// until no block comes within `spacing` of an obstacle box.
+fn clear_of(blocks: &[Block], targets: &mut [(f64, f64)], obstacles: &[(f64, f64, f64, f64)], spacing: f64) {
+    loop {
+        let shift = blocks.iter()
+            .zip(targets.iter())
+            .flat_map(|(b, &(x, y))| obstacles.iter()
+                .filter(move |o| x < o.0 + o.2 + spacing && o.0 < x + b.w + spacing && y < o.1 + o.3 + spacing && o.1 < y + b.h + spacing)
+                .map(move |o| (o.0 + o.2 + spacing - x).ceil()))
+            .fold(0.0f64, f64::max);
+        if shift <= 0.0 {
+            return;
+        }
+        for t in targets.iter_mut() {
+            t.0 += shift;
+        }
+    }
+}
+
+fn area(canvas: &Canvas, i: usize) -> f64 {
+    canvas.nodes[i].width * canvas.nodes[i].height
+}
+
+/// Block indices in reading order of their current position.
+fn reading_order(blocks: &[Block]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..blocks.len()).collect();
+    order.sort_by(|&a, &b| blocks[a].y.total_cmp(&blocks[b].y).then(blocks[a].x.total_cmp(&blocks[b].x)));
+    order
+}
+
+// ─── Grid ──────────────────────────────────────────────────────────
+
+/// Rows and columns as wide and tall as their largest block.
+fn grid(blocks: &[Block], columns: Option<usize>, spacing: f64) -> Vec<(f64, f64)> {
+    let n = blocks.len();
+    let columns = columns.unwrap_or_else(|| (n as f64).sqrt().ceil() as usize).clamp(1, n.max(1));
+    let order = reading_order(blocks);
+    let mut widths = vec![0.0f64; columns];
+    let mut heights = vec![0.0f64; n.div_ceil(columns)];
+    for (slot, &b) in order.iter().enumerate() {
+        widths[slot % columns] = widths[slot % columns].max(blocks[b].w);
+        heights[slot / columns] = heights[slot / columns].max(blocks[b].h);
+    }
+    let offsets = |sizes: &[f64]| -> Vec<f64> {
+        sizes.iter().scan(0.0, |at, size| { let start = *at; *at += size + spacing; Some(start) }).collect()
+    };
+    let (xs, ys) = (offsets(&widths), offsets(&heights));
+    let mut placed = vec![(0.0, 0.0); n];
+    for (slot, &b) in order.iter().enumerate() {
+        placed[b] = (xs[slot % columns], ys[slot / columns]);
+    }
+    placed
+}
+
+// ─── Tree ──────────────────────────────────────────────────────────
+
+/// Layers along edge direction. Each block is centered over the subtree of
+/// blocks first reached through it; edges closing cycles are left as they are.
+fn tree(blocks: &[Block], edges: &[(usize, usize)], spacing: f64, direction: TreeDirection) -> Vec<(f64, f64)> {
+    let n = blocks.len();
+    // Lay out top-down; for left-to-right, swap axes on the way in and out
+    let size = |b: usize| match direction {
+        TreeDirection::Down => (blocks[b].w, blocks[b].h),
+        TreeDirection::Right => (blocks[b].h, blocks[b].w),
+    };
+    let order = reading_order(blocks);
+    let rank: Vec<usize> = {
+        let mut rank = vec![0; n];
+        for (r, &b) in order.iter().enumerate() {
+            rank[b] = r;
+        }
+        rank
+    };
+    let mut out: Vec<Vec<usize>> = vec![Vec::new(); n];
+    let mut has_incoming = vec![false; n];
+    for &(a, b) in edges {
+        out[a].push(b);
+        has_incoming[b] = true;
+    }
+    for targets in &mut out {
+        targets.sort_by_key(|&t| rank[t]);
+    }
+
+    // Spanning forest by breadth-first search, roots in reading order; blocks
+    // only reachable through a cycle become roots themselves
+    let mut depth: Vec<Option<usize>> = vec![None; n];
+    let mut children: Vec<Vec<usize>> = vec![Vec::new(); n];
+    let mut roots = Vec::new();
+    let starts = order.iter().filter(|&&b| !has_incoming[b]).chain(order.iter());
+    for &start in starts {
+        if depth[start].is_some() {
+            continue;
+        }
+        roots.push(start);
+        depth[start] = Some(0);
+        let mut queue = VecDeque::from([start]);
+        while let Some(b) = queue.pop_front() {
+            for &t in &out[b] {
+                if depth[t].is_none() {
+                    depth[t] = Some(depth[b].unwrap_or(0) + 1);
+                    children[b].push(t);
+                    queue.push_back(t);
+                }
+            }
+        }
+    }
+
+    let depth: Vec<usize> = depth.into_iter().map(|d| d.unwrap_or(0)).collect();
+    let layers = depth.iter().max().map_or(0, |d| d + 1);
+    let mut layer_size = vec![0.0f64; layers];
+    for b in 0..n {
+        layer_size[depth[b]] = layer_size[depth[b]].max(size(b).1);
+    }
+    let layer_at: Vec<f64> = layer_size.iter()
+        .scan(0.0, |at, s| { let start = *at; *at += s + spacing * 2.0; Some(start) })
+        .collect();
+
+    // Width of each subtree, children before parents
+    let mut span = vec![0.0f64; n];
+    let mut by_depth: Vec<usize> = (0..n).collect();
+    by_depth.sort_by_key(|&b| std::cmp::Reverse(depth[b]));
+    for &b in &by_depth {
+        let kids: f64 = children[b].iter().map(|&c| span[c]).sum::<f64>()
+            + spacing * children[b].len().saturating_sub(1) as f64;
+        span[b] = size(b).0.max(kids);
+    }
+
+    let mut placed = vec![(0.0, 0.0); n];
+    let mut stack: Vec<(usize, f64)> = Vec::new();
+    let mut left = 0.0;
+    for &root in &roots {
+        stack.push((root, left));
+        left += span[root] + spacing;
+    }
+    while let Some((b, left)) = stack.pop() {
+        let main = left + (span[b] - size(b).0) / 2.0;
+        placed[b] = (main, layer_at[depth[b]]);
+        let kids: f64 = children[b].iter().map(|&c| span[c]).sum::<f64>()
+            + spacing * children[b].len().saturating_sub(1) as f64;
+        let mut at = left + (span[b] - kids) / 2.0;
+        for &c in &children[b] {
+            stack.push((c, at));
+            at += span[c] + spacing;
+        }
+    }
+    match direction {
+        TreeDirection::Down => placed,
+        TreeDirection::Right => placed.into_iter().map(|(a, b)| (b, a)).collect(),
+    }
+}
+
+// ─── Force-directed ────────────────────────────────────────────────
+
+/// Fruchterman–Reingold from the current positions, then overlaps are
+/// pushed apart.
+fn force(blocks: &[Block], edges: &[(usize, usize)], spacing: f64, iterations: usize) -> Vec<(f64, f64)> {
+    let n = blocks.len();
+    if n == 0 {
+        return Vec::new();
+    }
+    let ideal = blocks.iter().map(|b| b.w.max(b.h)).sum::<f64>() / n as f64 + spacing;
+    let mut pos: Vec<(f64, f64)> = blocks.iter().map(Block::center).collect();
+    // Blocks sharing a spot start on a small circle around it
+    let mut spots: HashMap<(i64, i64), usize> = HashMap::new();
+    for (i, p) in pos.iter_mut().enumerate() {
+        let seen = spots.entry((p.0 as i64, p.1 as i64)).or_insert(0);
+        if *seen > 0 {
+            let angle = i as f64 * 2.399_963;
+            p.0 += angle.cos() * ideal * 0.5;
+            p.1 += angle.sin() * ideal * 0.5;
+        }
+        *seen += 1;
+    }
+
+    let mut temperature = ideal * 2.0;
+    let cooling = temperature / iterations.max(1) as f64;
+    for _ in 0..iterations {
+        let mut shift = vec![(0.0f64, 0.0f64); n];
+        for a in 0..n {
+            for b in a + 1..n {
+                let (dx, dy) = (pos[a].0 - pos[b].0, pos[a].1 - pos[b].1);
+                let dist = (dx * dx + dy * dy).sqrt().max(0.01);
+                let push = ideal * ideal / dist;
+                let (ux, uy) = (dx / dist, dy / dist);
+                shift[a].0 += ux * push;
+                shift[a].1 += uy * push;
+                shift[b].0 -= ux * push;
+                shift[b].1 -= uy * push;
+            }
+        }
+        for &(a, b) in edges {
+            let (dx, dy) = (pos[a].0 - pos[b].0, pos[a].1 - pos[b].1);
+            let dist = (dx * dx + dy * dy).sqrt().max(0.01);
+            let pull = dist * dist / ideal;
+            let (ux, uy) = (dx / dist, dy / dist);
+            shift[a].0 -= ux * pull;
+            shift[a].1 -= uy * pull;
+            shift[b].0 += ux * pull;
+            shift[b].1 += uy * pull;
+        }
+        for (p, (sx, sy)) in pos.iter_mut().zip(shift) {
+            let len = (sx * sx + sy * sy).sqrt();
+            if len > 0.0 {
+                let step = len.min(temperature);
+                p.0 += sx / len * step;
+                p.1 += sy / len * step;
+            }
+        }
+        temperature = (temperature - cooling).max(1.0);
+    }
+
+    separate(blocks, &mut pos, spacing, None);
+    pos.iter().zip(blocks).map(|(p, b)| (p.0 - b.w / 2.0, p.1 - b.h / 2.0)).collect()
+}
+
+/// Pushes overlapping boxes (centered at `pos`) apart along their shallower
+/// axis; whatever still overlaps after that is spread out from the middle.
+/// A `pinned` box never moves and the others make way for it.
+fn separate(blocks: &[Block], pos: &mut [(f64, f64)], spacing: f64, pinned: Option<usize>) {
+    let n = blocks.len();
+    let overlap = |pos: &[(f64, f64)], a: usize, b: usize| {
+        let need_x = (blocks[a].w + blocks[b].w) / 2.0 + spacing;
+        let need_y = (blocks[a].h + blocks[b].h) / 2.0 + spacing;
+        (need_x - (pos[a].0 - pos[b].0).abs(), need_y - (pos[a].1 - pos[b].1).abs())
+    };
+    for _ in 0..100 {
+        let mut clean = true;
+        for a in 0..n {
+            for b in a + 1..n {
+                let (ox, oy) = overlap(pos, a, b);
+                if ox <= 0.0 || oy <= 0.0 {
+                    continue;
+                }
+                clean = false;
+                let (share_a, share_b) = match pinned {
+                    Some(p) if p == a => (0.0, 1.0),
+                    Some(p) if p == b => (1.0, 0.0),
+                    _ => (0.5, 0.5),
+                };
+                if ox < oy {
+                    let dir = if pos[a].0 < pos[b].0 { -1.0 } else { 1.0 };
+                    pos[a].0 += dir * ox * share_a;
+                    pos[b].0 -= dir * ox * share_b;
+                } else {
+                    let dir = if pos[a].1 < pos[b].1 { -1.0 } else { 1.0 };
+                    pos[a].1 += dir * oy * share_a;
+                    pos[b].1 -= dir * oy * share_b;
+                }
+            }
+        }
+        if clean {
+            return;
+        }
+    }
+
+    // Scaling distances from the centroid (or the pinned box) by a large
+    // enough factor always separates boxes with distinct centers
+    let (cx, cy) = match pinned {
+        Some(p) => pos[p],
+        None => pos.iter().fold((0.0, 0.0), |(x, y), p| (x + p.0 / n as f64, y + p.1 / n as f64)),
+    };
+    let mut scale = 1.0f64;
+    for a in 0..n {
+        for b in a + 1..n {
+            let (ox, oy) = overlap(pos, a, b);
+            if ox <= 0.0 || oy <= 0.0 {
+                continue;
+            }
+            let (dx, dy) = ((pos[a].0 - pos[b].0).abs(), (pos[a].1 - pos[b].1).abs());
+            let fit = |d: f64, over: f64| if d > 0.0 { (d + over) / d } else { f64::MAX };
+            scale = scale.max(fit(dx, ox).min(fit(dy, oy)));
+        }
+    }
+    if scale > 1.0 && scale < f64::MAX {
+        for p in pos.iter_mut() {
+            p.0 = cx + (p.0 - cx) * scale;
+            p.1 = cy + (p.1 - cy) * scale;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_no_overlap(canvas: &Canvas, ids: &[String]) {
+        let nodes: Vec<_> = ids.iter().map(|id| canvas.get_node(id).unwrap()).collect();
+        for (i, a) in nodes.iter().enumerate() {
+            for b in &nodes[i + 1..] {
+                let apart = a.x + a.width <= b.x || b.x + b.width <= a.x
+                    || a.y + a.height <= b.y || b.y + b.height <= a.y;
+                assert!(apart, "{} overlaps {}", a.id, b.id);
+            }
+        }
+    }
+
+    /// A pile of notes on one spot, next to a group holding two notes.
+    fn pile() -> (Canvas, Vec<String>, String, Vec<String>) {
+        let mut canvas = Canvas::new();
+        let loose: Vec<String> = (0..5)
+            .map(|i| canvas.add_text_node(600.0, 10.0, 200.0 + i as f64 * 20.0, 80.0, &format!("n{}", i)))
+            .collect();
+        let group = canvas.add_group_node(0.0, 0.0, 500.0, 300.0, Some("G"));
+        let inner = vec![
+            canvas.add_text_node(20.0, 40.0, 150.0, 60.0, "in1"),
+            canvas.add_text_node(200.0, 120.0, 150.0, 60.0, "in2"),
+        ];
+        (canvas, loose, group, inner)
+    }
+
+    #[test]
+    fn test_grid_and_force_keep_groups_whole() {
+        for algorithm in [CanvasLayout::Grid, CanvasLayout::Force] {
+            let (mut canvas, loose, group, inner) = pile();
+            let offsets = |canvas: &Canvas| -> Vec<(f64, f64)> {
+                let g = canvas.get_node(&group).unwrap();
+                inner.iter().map(|id| canvas.get_node(id).map(|n| (n.x - g.x, n.y - g.y)).unwrap()).collect()
+            };
+            let before = offsets(&canvas);
+            let options = CanvasLayoutOptions { algorithm, ..Default::default() };
+            canvas.apply_layout(None, &options);
+
+            let mut top_level = loose.clone();
+            top_level.push(group.clone());
+            assert_no_overlap(&canvas, &top_level);
+            // Members kept their place inside the group
+            assert_eq!(before, offsets(&canvas), "{:?}", algorithm);
+            assert_eq!(canvas.nodes_in_group(&group).len(), 2, "{:?}", algorithm);
+            assert!(canvas.nodes.iter().all(|n| n.x.fract() == 0.0 && n.y.fract() == 0.0));
+        }
+    }
+
+    #[test]
+    fn test_force_iterations_are_capped() {
+        let (mut canvas, loose, group, _) = pile();
+        let options = CanvasLayoutOptions { algorithm: CanvasLayout::Force, iterations: usize::MAX, ..Default::default() };
+        canvas.apply_layout(None, &options);
+        let mut top_level = loose;
+        top_level.push(group);
+        assert_no_overlap(&canvas, &top_level);
+    }
+
+    #[test]
+    fn test_tree_follows_edges() {
+        let mut canvas = Canvas::new();
+        let ids: Vec<String> = (0..4).map(|i| canvas.add_text_node(0.0, 0.0, 100.0, 50.0, &format!("{}", i))).collect();
+        canvas.add_edge(&ids[0], &ids[1], None, None, None);
+        canvas.add_edge(&ids[0], &ids[2], None, None, None);
+        canvas.add_edge(&ids[2], &ids[3], None, None, None);
+        canvas.add_edge(&ids[3], &ids[0], None, None, None);
+
+        let options = CanvasLayoutOptions { algorithm: CanvasLayout::Tree, ..Default::default() };
+        canvas.apply_layout(None, &options);
+        assert_no_overlap(&canvas, &ids);
+        let node = |i: usize| canvas.get_node(&ids[i]).unwrap();
+        assert!(node(1).y > node(0).y && node(1).y == node(2).y);
+        assert!(node(3).y > node(2).y);
+        // The root is centered over its children
+        assert_eq!(node(0).x + 50.0, (node(1).x + node(2).x + 100.0) / 2.0);
+
+        let right = CanvasLayoutOptions { direction: TreeDirection::Right, ..options };
+        canvas.apply_layout(None, &right);
+        let node = |i: usize| canvas.get_node(&ids[i]).unwrap();
+        assert!(node(1).x > node(0).x && node(1).x == node(2).x);
+    }
+
+    #[test]
+    fn test_selection_inside_group() {
+        let (mut canvas, loose, group, inner) = pile();
+        let fixed: Vec<(f64, f64)> = loose.iter().map(|id| canvas.get_node(id).map(|n| (n.x, n.y)).unwrap()).collect();
+        let options = CanvasLayoutOptions { columns: Some(1), spacing: 200.0, ..Default::default() };
+        let moved = canvas.apply_layout(Some(&inner), &options);
+
+        assert_eq!(moved, vec![inner[1].clone()]);
+        assert_no_overlap(&canvas, &inner);
+        let still: Vec<(f64, f64)> = loose.iter().map(|id| canvas.get_node(id).map(|n| (n.x, n.y)).unwrap()).collect();
+        assert_eq!(fixed, still);
+        // The group grew to keep both notes inside
+        assert_eq!(canvas.nodes_in_group(&group).len(), 2);
+        let g = canvas.get_node(&group).unwrap();
+        let bottom = canvas.get_node(&inner[1]).unwrap();
+        assert_eq!((bottom.y, g.y + g.height), (300.0, 560.0));
+    }
+
+    #[test]
+    fn test_selection_clears_unselected_nodes() {
+        let mut canvas = Canvas::new();
+        let picked: Vec<String> = (0..3).map(|i| canvas.add_text_node(0.0, i as f64 * 100.0, 200.0, 80.0, &format!("p{}", i))).collect();
+        let bystander = canvas.add_text_node(250.0, 0.0, 200.0, 80.0, "stay");
+        let options = CanvasLayoutOptions { columns: Some(3), ..Default::default() };
+        canvas.apply_layout(Some(&picked), &options);
+
+        let mut all = picked.clone();
+        all.push(bystander.clone());
+        assert_no_overlap(&canvas, &all);
+        let b = canvas.get_node(&bystander).unwrap();
+        assert_eq!((b.x, b.y), (250.0, 0.0));
+        let first = canvas.get_node(&picked[0]).unwrap();
+        assert_eq!((first.x, first.y), (490.0, 0.0));
+
+        // A group growing around its selection pushes out the note below it
+        let (mut canvas, _, group, inner) = pile();
+        let below = canvas.add_text_node(0.0, 320.0, 200.0, 80.0, "below");
+        let options = CanvasLayoutOptions { columns: Some(1), spacing: 200.0, ..Default::default() };
+        let moved = canvas.apply_layout(Some(&inner), &options);
+        assert!(moved.contains(&below));
+        assert_no_overlap(&canvas, &[group.clone(), below.clone()]);
+        assert_eq!(canvas.nodes_in_group(&group).len(), 2);
+    }
+}
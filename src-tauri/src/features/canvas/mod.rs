@@ -1,9 +1,16 @@
 // Oxidian — Canvas data model (Obsidian .canvas format compatible)
 //
 // Implements load/save/CRUD for .canvas files using Obsidian's JSON format.
-// See: https://jsoncanvas.org/ and Obsidian's canvas spec.
+// See: https://jsoncanvas.org/ and Obsidian's canvas spec. Fields this model
+// does not know (newer Obsidian versions, canvas plugins) survive a round trip.
 
-use serde::{Deserialize, Serialize};
+pub mod export;
+pub mod layout;
+pub mod ops;
+pub mod validate;
+
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::{Map, Value};
 use std::path::Path;
 
 // ─── Obsidian .canvas format types ──────────────────────────────────────────
@@ -11,8 +18,23 @@ use std::path::Path;
 /// Root canvas file structure — matches Obsidian's .canvas JSON exactly
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Canvas {
+    #[serde(default)]
     pub nodes: Vec<CanvasNode>,
+    #[serde(default)]
     pub edges: Vec<CanvasEdge>,
+    /// Top-level fields this model does not know, kept as they were read
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// JSON Canvas positions and sizes are integers; write whole numbers without
+/// a fraction so files stay byte-for-byte what Obsidian would write.
+fn serialize_coord<S: Serializer>(value: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+    if value.fract() == 0.0 && value.abs() < i64::MAX as f64 {
+        serializer.serialize_i64(*value as i64)
+    } else {
+        serializer.serialize_f64(*value)
+    }
 }
 
 /// A node on the canvas. The `type` field determines which fields are used.
@@ -20,10 +42,14 @@ pub struct Canvas {
 pub struct CanvasNode {
     pub id: String,
     /// x position
+    #[serde(serialize_with = "serialize_coord")]
     pub x: f64,
     /// y position
+    #[serde(serialize_with = "serialize_coord")]
     pub y: f64,
+    #[serde(serialize_with = "serialize_coord")]
     pub width: f64,
+    #[serde(serialize_with = "serialize_coord")]
     pub height: f64,
     /// Node type: "text", "file", "link", "group"
     #[serde(rename = "type")]
@@ -59,6 +85,32 @@ pub struct CanvasNode {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "backgroundStyle")]
     pub background_style: Option<String>,
+
+    /// Fields this model does not know, kept as they were read
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl CanvasNode {
+    /// A node of `node_type` with a fresh id and no type-specific fields
+    pub fn new(node_type: CanvasNodeType, x: f64, y: f64, width: f64, height: f64) -> Self {
+        Self {
+            id: Canvas::generate_id(),
+            x,
+            y,
+            width,
+            height,
+            node_type,
+            text: None,
+            file: None,
+            subpath: None,
+            url: None,
+            color: None,
+            label: None,
+            background_style: None,
+            extra: Map::new(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -100,6 +152,28 @@ pub struct CanvasEdge {
     /// Edge label
     #[serde(skip_serializing_if = "Option::is_none")]
     pub label: Option<String>,
+
+    /// Fields this model does not know, kept as they were read
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl CanvasEdge {
+    /// An edge with a fresh id and an arrow at the target
+    pub fn new(from_node: &str, to_node: &str, from_side: Option<CanvasSide>, to_side: Option<CanvasSide>, label: Option<&str>) -> Self {
+        Self {
+            id: Canvas::generate_id(),
+            from_node: from_node.to_string(),
+            from_side,
+            from_end: None,
+            to_node: to_node.to_string(),
+            to_side,
+            to_end: Some(CanvasEnd::Arrow),
+            color: None,
+            label: label.map(|s| s.to_string()),
+            extra: Map::new(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -194,85 +268,46 @@ impl Canvas {
 
     /// Add a text node
     pub fn add_text_node(&mut self, x: f64, y: f64, width: f64, height: f64, text: &str) -> String {
-        let id = Self::generate_id();
-        self.nodes.push(CanvasNode {
-            id: id.clone(),
-            x,
-            y,
-            width,
-            height,
-            node_type: CanvasNodeType::Text,
+        let node = CanvasNode {
             text: Some(text.to_string()),
-            file: None,
-            subpath: None,
-            url: None,
-            color: None,
-            label: None,
-            background_style: None,
-        });
+            ..CanvasNode::new(CanvasNodeType::Text, x, y, width, height)
+        };
+        let id = node.id.clone();
+        self.nodes.push(node);
         id
     }
 
     /// Add a file node (embed a vault file on the canvas)
     pub fn add_file_node(&mut self, x: f64, y: f64, width: f64, height: f64, file: &str, subpath: Option<&str>) -> String {
-        let id = Self::generate_id();
-        self.nodes.push(CanvasNode {
-            id: id.clone(),
-            x,
-            y,
-            width,
-            height,
-            node_type: CanvasNodeType::File,
-            text: None,
+        let node = CanvasNode {
             file: Some(file.to_string()),
             subpath: subpath.map(|s| s.to_string()),
-            url: None,
-            color: None,
-            label: None,
-            background_style: None,
-        });
+            ..CanvasNode::new(CanvasNodeType::File, x, y, width, height)
+        };
+        let id = node.id.clone();
+        self.nodes.push(node);
         id
     }
 
     /// Add a link node (embed a URL on the canvas)
     pub fn add_link_node(&mut self, x: f64, y: f64, width: f64, height: f64, url: &str) -> String {
-        let id = Self::generate_id();
-        self.nodes.push(CanvasNode {
-            id: id.clone(),
-            x,
-            y,
-            width,
-            height,
-            node_type: CanvasNodeType::Link,
-            text: None,
-            file: None,
-            subpath: None,
+        let node = CanvasNode {
             url: Some(url.to_string()),
-            color: None,
-            label: None,
-            background_style: None,
-        });
+            ..CanvasNode::new(CanvasNodeType::Link, x, y, width, height)
+        };
+        let id = node.id.clone();
+        self.nodes.push(node);
         id
     }
 
     /// Add a group node
     pub fn add_group_node(&mut self, x: f64, y: f64, width: f64, height: f64, label: Option<&str>) -> String {
-        let id = Self::generate_id();
-        self.nodes.push(CanvasNode {
-            id: id.clone(),
-            x,
-            y,
-            width,
-            height,
-            node_type: CanvasNodeType::Group,
-            text: None,
-            file: None,
-            subpath: None,
-            url: None,
-            color: None,
+        let node = CanvasNode {
             label: label.map(|s| s.to_string()),
-            background_style: None,
-        });
+            ..CanvasNode::new(CanvasNodeType::Group, x, y, width, height)
+        };
+        let id = node.id.clone();
+        self.nodes.push(node);
         id
     }
 
@@ -352,18 +387,9 @@ impl Canvas {
         to_side: Option<CanvasSide>,
         label: Option<&str>,
     ) -> String {
-        let id = Self::generate_id();
-        self.edges.push(CanvasEdge {
-            id: id.clone(),
-            from_node: from_node.to_string(),
-            from_side,
-            from_end: None,
-            to_node: to_node.to_string(),
-            to_side,
-            to_end: Some(CanvasEnd::Arrow),
-            color: None,
-            label: label.map(|s| s.to_string()),
-        });
+        let edge = CanvasEdge::new(from_node, to_node, from_side, to_side, label);
+        let id = edge.id.clone();
+        self.edges.push(edge);
         id
     }
 
@@ -0,0 +1,501 @@
+// Oxidian — Canvas operations
+// Invertible edits to a canvas with a persistent undo/redo log. Ops are
+// applied to the file as it is on disk, so edits made elsewhere are kept.
+
+use super::{Canvas, CanvasEdge, CanvasNode};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Moves of one node closer together than this undo as a single drag.
+pub const COALESCE_MS: i64 = 1500;
+/// Oldest entries beyond this are forgotten.
+pub const MAX_HISTORY: usize = 200;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum CanvasOp {
+    /// Inserts `node` at `index` and restores `edges` at their indices
+    AddNode {
+        node: CanvasNode,
+        index: usize,
+        #[serde(default)]
+        edges: Vec<(usize, CanvasEdge)>,
+    },
+    /// Removes `node` (found at `index`) with its `edges`
+    RemoveNode {
+        node: CanvasNode,
+        index: usize,
+        #[serde(default)]
+        edges: Vec<(usize, CanvasEdge)>,
+    },
+    MoveNode {
+        id: String,
+        from: (f64, f64),
+        to: (f64, f64),
+    },
+    /// Changes a node's `(width, height)`
+    ResizeNode {
+        id: String,
+        from: (f64, f64),
+        to: (f64, f64),
+    },
+    AddEdge {
+        edge: CanvasEdge,
+        index: usize,
+    },
+    RemoveEdge {
+        edge: CanvasEdge,
+        index: usize,
+    },
+    /// Ops applied, undone and redone together as one step
+    Batch {
+        ops: Vec<CanvasOp>,
+    },
+}
+
+impl CanvasOp {
+    pub fn add_node(canvas: &Canvas, node: CanvasNode) -> Self {
+        CanvasOp::AddNode { node, index: canvas.nodes.len(), edges: Vec::new() }
+    }
+
+    /// Removal of a node and the edges touching it, as they are now.
+    pub fn remove_node(canvas: &Canvas, id: &str) -> Option<Self> {
+        let index = canvas.nodes.iter().position(|n| n.id == id)?;
+        let edges = canvas.edges.iter()
+            .enumerate()
+            .filter(|(_, e)| e.from_node == id || e.to_node == id)
+            .map(|(i, e)| (i, e.clone()))
+            .collect();
+        Some(CanvasOp::RemoveNode { node: canvas.nodes[index].clone(), index, edges })
+    }
+
+    pub fn move_node(canvas: &Canvas, id: &str, x: f64, y: f64) -> Option<Self> {
+        let node = canvas.get_node(id)?;
+        Some(CanvasOp::MoveNode { id: id.to_string(), from: (node.x, node.y), to: (x, y) })
+    }
+
+    /// The moves and resizes that take the nodes of `before` to their places
+    /// in `after`, as one step; None if no node moved or changed size.
+    pub fn reshape(before: &Canvas, after: &Canvas) -> Option<Self> {
+        let mut ops = Vec::new();
+        for node in &after.nodes {
+            let Some(old) = before.get_node(&node.id) else { continue };
+            if (old.x, old.y) != (node.x, node.y) {
+                ops.push(CanvasOp::MoveNode { id: node.id.clone(), from: (old.x, old.y), to: (node.x, node.y) });
+            }
+            if (old.width, old.height) != (node.width, node.height) {
+                ops.push(CanvasOp::ResizeNode {
+                    id: node.id.clone(),
+                    from: (old.width, old.height),
+                    to: (node.width, node.height),
+                });
+            }
+        }
+        (!ops.is_empty()).then_some(CanvasOp::Batch { ops })
+    }
+
+    pub fn add_edge(canvas: &Canvas, edge: CanvasEdge) -> Self {
+        CanvasOp::AddEdge { edge, index: canvas.edges.len() }
+    }
+
+    pub fn remove_edge(canvas: &Canvas, id: &str) -> Option<Self> {
+        let index = canvas.edges.iter().position(|e| e.id == id)?;
+        Some(CanvasOp::RemoveEdge { edge: canvas.edges[index].clone(), index })
+    }
+
+    /// The op that takes the canvas back to where it was before this one.
+    pub fn inverse(&self) -> Self {
+        match self.clone() {
+            CanvasOp::AddNode { node, index, edges } => CanvasOp::RemoveNode { node, index, edges },
+            CanvasOp::RemoveNode { node, index, edges } => CanvasOp::AddNode { node, index, edges },
+            CanvasOp::MoveNode { id, from, to } => CanvasOp::MoveNode { id, from: to, to: from },
+            CanvasOp::ResizeNode { id, from, to } => CanvasOp::ResizeNode { id, from: to, to: from },
+            CanvasOp::AddEdge { edge, index } => CanvasOp::RemoveEdge { edge, index },
+            CanvasOp::RemoveEdge { edge, index } => CanvasOp::AddEdge { edge, index },
+            CanvasOp::Batch { ops } => CanvasOp::Batch { ops: ops.iter().rev().map(CanvasOp::inverse).collect() },
+        }
+    }
+
+    /// Applies the op if the canvas is in the state the op expects: ids to
+    /// add are free, what is removed exists, a moved node is where the move
+    /// starts. Returns false, leaving the canvas alone, on a conflict; a
+    /// batch applies whole or not at all. Restored edges whose other end is
+    /// gone are dropped.
+    pub fn apply(&self, canvas: &mut Canvas) -> bool {
+        match self {
+            CanvasOp::AddNode { node, index, edges } => {
+                if canvas.get_node(&node.id).is_some() {
+                    return false;
+                }
+                canvas.nodes.insert((*index).min(canvas.nodes.len()), node.clone());
+                for (i, edge) in edges {
+                    let ends = canvas.get_node(&edge.from_node).is_some() && canvas.get_node(&edge.to_node).is_some();
+                    if ends && canvas.get_edge(&edge.id).is_none() {
+                        canvas.edges.insert((*i).min(canvas.edges.len()), edge.clone());
+                    }
+                }
+                true
+            }
+            CanvasOp::RemoveNode { node, .. } => canvas.delete_node(&node.id),
+            CanvasOp::MoveNode { id, from, to } => match canvas.get_node_mut(id) {
+                Some(node) if (node.x, node.y) == *from => {
+                    (node.x, node.y) = *to;
+                    true
+                }
+                _ => false,
+            },
+            CanvasOp::ResizeNode { id, from, to } => match canvas.get_node_mut(id) {
+                Some(node) if (node.width, node.height) == *from => {
+                    (node.width, node.height) = *to;
+                    true
+                }
+                _ => false,
+            },
+            CanvasOp::AddEdge { edge, index } => {
+                let ends = canvas.get_node(&edge.from_node).is_some() && canvas.get_node(&edge.to_node).is_some();
+                if !ends || canvas.get_edge(&edge.id).is_some() {
+                    return false;
+                }
+                canvas.edges.insert((*index).min(canvas.edges.len()), edge.clone());
+                true
+            }
+            CanvasOp::RemoveEdge { edge, .. } => canvas.delete_edge(&edge.id),
+            CanvasOp::Batch { ops } => {
+                for (done, op) in ops.iter().enumerate() {
+                    if !op.apply(canvas) {
+                        for applied in ops[..done].iter().rev() {
+                            applied.inverse().apply(canvas);
+                        }
+                        return false;
+                    }
+                }
+                true
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub op: CanvasOp,
+    /// Milliseconds since the epoch when the op was last extended
+    pub at: i64,
+}
+
+/// Undo and redo stacks of one canvas, kept in `.oxidian/canvas-history/`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CanvasHistory {
+    #[serde(default)]
+    pub undo: Vec<HistoryEntry>,
+    #[serde(default)]
+    pub redo: Vec<HistoryEntry>,
+    /// Hash of the canvas file as last written here; anything else on disk
+    /// was changed outside the op log
+    #[serde(default)]
+    pub fingerprint: Option<String>,
+}
+
+impl CanvasHistory {
+    /// Adds an applied op, folding a drag (moves of one node, each starting
+    /// where the last ended, within [`COALESCE_MS`]) into one entry.
+    pub fn record(&mut self, op: CanvasOp, now: i64) {
+        self.redo.clear();
+        if let (CanvasOp::MoveNode { id, from, to }, Some(last)) = (&op, self.undo.last_mut()) {
+            if let CanvasOp::MoveNode { id: last_id, to: last_to, .. } = &mut last.op {
+                if last_id == id && last_to == from && now - last.at <= COALESCE_MS {
+                    *last_to = *to;
+                    last.at = now;
+                    return;
+                }
+            }
+        }
+        self.undo.push(HistoryEntry { op, at: now });
+        if self.undo.len() > MAX_HISTORY {
+            self.undo.drain(..self.undo.len() - MAX_HISTORY);
+        }
+    }
+}
+
+/// FNV-1a, 64 bit: unlike `DefaultHasher` it gives the same value on every
+/// build, so fingerprints saved by one version still match in the next.
+fn fingerprint(content: &str) -> String {
+    let hash = content.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("{:016x}", hash)
+}
+
+fn history_path(vault_path: &str, canvas_path: &str) -> PathBuf {
+    Path::new(vault_path)
+        .join(".oxidian")
+        .join("canvas-history")
+        .join(format!("{}.json", canvas_path))
+}
+
+/// Outcome of an edit, undo or redo.
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryStep {
+    pub canvas: Canvas,
+    /// False when there was nothing to do or the op conflicted
+    pub applied: bool,
+    /// The file had been changed outside the op log since the last step
+    pub external_change: bool,
+    pub can_undo: bool,
+    pub can_redo: bool,
+}
+
+/// A canvas file and its history, loaded fresh for every step.
+pub struct CanvasSession {
+    canvas_file: PathBuf,
+    history_file: PathBuf,
+    pub canvas: Canvas,
+    pub history: CanvasHistory,
+    pub external_change: bool,
+    /// The canvas differs from the file
+    changed: bool,
+}
+
+impl CanvasSession {
+    pub fn open(vault_path: &str, canvas_path: &str) -> Result<Self, String> {
+        let canvas_file = Path::new(vault_path).join(canvas_path);
+        let history_file = history_path(vault_path, canvas_path);
+        let content = match fs::read_to_string(&canvas_file) {
+            Ok(content) => Some(content),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(format!("Failed to read canvas file: {}", e)),
+        };
+        let canvas = match &content {
+            Some(content) => Canvas::from_json(content)?,
+            None => Canvas::new(),
+        };
+        let history: CanvasHistory = fs::read_to_string(&history_file)
+            .ok()
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default();
+        let external_change = match (&history.fingerprint, &content) {
+            (Some(known), Some(content)) => *known != fingerprint(content),
+            (Some(_), None) => true,
+            (None, _) => false,
+        };
+        Ok(Self { canvas_file, history_file, canvas, history, external_change, changed: false })
+    }
+
+    /// Applies `op` and records it for undo.
+    pub fn perform(&mut self, op: CanvasOp, now: i64) -> bool {
+        if !op.apply(&mut self.canvas) {
+            return false;
+        }
+        self.history.record(op, now);
+        self.changed = true;
+        true
+    }
+
+    /// Takes `canvas` as it is, e.g. saved whole by the editor, without
+    /// recording an op. Logged ops keep applying wherever they still fit.
+    pub fn replace(&mut self, canvas: Canvas) {
+        self.canvas = canvas;
+        self.changed = true;
+    }
+
+    /// Reverts the latest op. One that no longer fits the canvas, because
+    /// its nodes were changed elsewhere, is dropped instead.
+    pub fn undo(&mut self) -> bool {
+        let Some(entry) = self.history.undo.pop() else { return false };
+        if !entry.op.inverse().apply(&mut self.canvas) {
+            return false;
+        }
+        self.history.redo.push(entry);
+        self.changed = true;
+        true
+    }
+
+    /// Re-applies the latest undone op, dropping it if it no longer fits.
+    pub fn redo(&mut self) -> bool {
+        let Some(entry) = self.history.redo.pop() else { return false };
+        if !entry.op.apply(&mut self.canvas) {
+            return false;
+        }
+        self.history.undo.push(entry);
+        self.changed = true;
+        true
+    }
+
+    /// Writes the canvas, if any step changed it, and the history.
+    pub fn save(&mut self) -> Result<(), String> {
+        if self.changed {
+            self.canvas.save(&self.canvas_file)?;
+            self.changed = false;
+        }
+        if let Ok(content) = fs::read_to_string(&self.canvas_file) {
+            self.history.fingerprint = Some(fingerprint(&content));
+        }
+        if let Some(parent) = self.history_file.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create canvas history dir: {}", e))?;
+        }
+        let json = serde_json::to_string(&self.history)
+            .map_err(|e| format!("Failed to serialize canvas history: {}", e))?;
+        fs::write(&self.history_file, json).map_err(|e| format!("Failed to write canvas history: {}", e))
+    }
+
+    /// Saves and reports the last step, `applied` or not.
+    pub fn finish(mut self, applied: bool) -> Result<HistoryStep, String> {
+        self.save()?;
+        Ok(HistoryStep {
+            can_undo: !self.history.undo.is_empty(),
+            can_redo: !self.history.redo.is_empty(),
+            canvas: self.canvas,
+            applied,
+            external_change: self.external_change,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::canvas::CanvasNodeType;
+    use tempfile::TempDir;
+
+    fn session(dir: &TempDir) -> CanvasSession {
+        CanvasSession::open(dir.path().to_str().unwrap(), "boards/plan.canvas").unwrap()
+    }
+
+    #[test]
+    fn test_undo_redo_across_sessions() {
+        let dir = TempDir::new().unwrap();
+        let mut s = session(&dir);
+        let a = CanvasNode { text: Some("A".into()), ..CanvasNode::new(CanvasNodeType::Text, 0.0, 0.0, 100.0, 50.0) };
+        let b = CanvasNode { text: Some("B".into()), ..CanvasNode::new(CanvasNodeType::Text, 200.0, 0.0, 100.0, 50.0) };
+        let (a_id, b_id) = (a.id.clone(), b.id.clone());
+        assert!(s.perform(CanvasOp::add_node(&s.canvas, a), 0));
+        assert!(s.perform(CanvasOp::add_node(&s.canvas, b), 10));
+        let edge = CanvasOp::add_edge(&s.canvas, CanvasEdge::new(&a_id, &b_id, None, None, Some("next")));
+        assert!(s.perform(edge, 20));
+        s.finish(true).unwrap();
+
+        // Deleting a node takes its edge along; undo brings both back
+        let mut s = session(&dir);
+        assert!(!s.external_change);
+        let delete = CanvasOp::remove_node(&s.canvas, &a_id).unwrap();
+        assert!(s.perform(delete, 30));
+        assert!(s.canvas.edges.is_empty());
+        let step = s.finish(true).unwrap();
+        assert!(step.can_undo && !step.can_redo);
+
+        let mut s = session(&dir);
+        assert!(s.undo());
+        assert_eq!(s.canvas.nodes[0].id, a_id);
+        assert_eq!(s.canvas.edges[0].label.as_deref(), Some("next"));
+        s.finish(true).unwrap();
+
+        let mut s = session(&dir);
+        assert!(s.redo());
+        assert_eq!(s.canvas.nodes.len(), 1);
+        assert!(s.undo() && s.undo());
+        assert!(s.canvas.edges.is_empty() && s.canvas.nodes.len() == 2);
+        let step = s.finish(true).unwrap();
+        assert!(step.can_redo);
+        let saved = Canvas::load(&dir.path().join("boards/plan.canvas")).unwrap();
+        assert_eq!(saved.nodes.len(), 2);
+    }
+
+    #[test]
+    fn test_drag_moves_coalesce() {
+        let mut canvas = Canvas::new();
+        let id = canvas.add_text_node(0.0, 0.0, 100.0, 50.0, "drag me");
+        let mut history = CanvasHistory::default();
+        for (i, x) in [10.0, 20.0, 30.0].into_iter().enumerate() {
+            let op = CanvasOp::move_node(&canvas, &id, x, 0.0).unwrap();
+            assert!(op.apply(&mut canvas));
+            history.record(op, i as i64 * 100);
+        }
+        assert_eq!(history.undo.len(), 1);
+        // A later move is a separate step
+        let op = CanvasOp::move_node(&canvas, &id, 99.0, 0.0).unwrap();
+        op.apply(&mut canvas);
+        history.record(op, 300 + COALESCE_MS + 1);
+        assert_eq!(history.undo.len(), 2);
+
+        // Undoing the drag goes back to where it started
+        match &history.undo[0].op {
+            CanvasOp::MoveNode { from, to, .. } => assert_eq!((*from, *to), ((0.0, 0.0), (30.0, 0.0))),
+            other => panic!("unexpected op {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_layout_is_one_step() {
+        let dir = TempDir::new().unwrap();
+        let mut s = session(&dir);
+        let mut canvas = Canvas::new();
+        let group = canvas.add_group_node(0.0, 0.0, 300.0, 200.0, Some("G"));
+        let inner: Vec<String> = (0..3).map(|i| canvas.add_text_node(20.0, 20.0, 100.0, 50.0, &i.to_string())).collect();
+        s.replace(canvas.clone());
+
+        let mut laid_out = canvas.clone();
+        let options = crate::features::canvas::layout::CanvasLayoutOptions { columns: Some(1), ..Default::default() };
+        laid_out.apply_layout(Some(&inner), &options);
+        let op = CanvasOp::reshape(&s.canvas, &laid_out).unwrap();
+        assert!(s.perform(op, 0));
+        assert!(s.canvas.get_node(&group).unwrap().height > 200.0);
+        s.finish(true).unwrap();
+
+        // One undo puts every note back and shrinks the group again
+        let mut s = session(&dir);
+        assert!(!s.external_change);
+        assert!(s.undo());
+        assert_eq!((s.history.undo.len(), s.history.redo.len()), (0, 1));
+        assert_eq!(s.canvas.get_node(&group).map(|g| (g.x, g.y, g.width, g.height)), Some((0.0, 0.0, 300.0, 200.0)));
+        assert!(inner.iter().all(|id| s.canvas.get_node(id).map(|n| (n.x, n.y)) == Some((20.0, 20.0))));
+        assert!(CanvasOp::reshape(&canvas, &canvas).is_none());
+
+        // A batch that no longer fits leaves the canvas untouched
+        s.canvas.move_node(&inner[2], 500.0, 500.0);
+        let before = s.canvas.to_json().unwrap();
+        assert!(!s.redo());
+        assert_eq!(s.canvas.to_json().unwrap(), before);
+    }
+
+    #[test]
+    fn test_fingerprint_is_stable() {
+        assert_eq!(fingerprint(""), "cbf29ce484222325");
+        assert_eq!(fingerprint("a"), "af63dc4c8601ec8c");
+    }
+
+    #[test]
+    fn test_merges_with_external_changes() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("boards/plan.canvas");
+        let mut s = session(&dir);
+        let note = CanvasNode::new(CanvasNodeType::Text, 0.0, 0.0, 100.0, 50.0);
+        let id = note.id.clone();
+        s.perform(CanvasOp::add_node(&s.canvas, note), 0);
+        let moved = CanvasOp::move_node(&s.canvas, &id, 50.0, 50.0).unwrap();
+        s.perform(moved, 10_000);
+        s.finish(true).unwrap();
+
+        // Another app adds a node and moves ours
+        let mut outside = Canvas::load(&path).unwrap();
+        let theirs = outside.add_text_node(500.0, 0.0, 100.0, 50.0, "theirs");
+        outside.move_node(&id, 70.0, 70.0);
+        outside.save(&path).unwrap();
+
+        // New ops apply on top of their version
+        let mut s = session(&dir);
+        assert!(s.external_change);
+        let edge = CanvasOp::add_edge(&s.canvas, CanvasEdge::new(&id, &theirs, None, None, None));
+        assert!(s.perform(edge, 20_000));
+        assert!(s.undo());
+        // Our move was overridden outside, so it cannot be undone
+        let applied = s.undo();
+        assert_eq!(s.canvas.get_node(&id).map(|n| (n.x, n.y)), Some((70.0, 70.0)));
+        let step = s.finish(applied).unwrap();
+        assert!(step.external_change && !step.applied);
+        assert_eq!(step.canvas.nodes.len(), 2);
+
+        let s = session(&dir);
+        assert!(!s.external_change);
+        assert_eq!(s.history.undo.len(), 1);
+    }
+}
@@ -0,0 +1,265 @@
+// Oxidian — JSON Canvas validation
+// Checks a .canvas document against the JSON Canvas 1.0 spec: unique ids,
+// edges between existing nodes, known node types, colors, sides and ends.
+
+use super::Canvas;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+
+pub const NODE_TYPES: [&str; 4] = ["text", "file", "link", "group"];
+pub const SIDES: [&str; 4] = ["top", "right", "bottom", "left"];
+pub const ENDS: [&str; 2] = ["none", "arrow"];
+pub const BACKGROUND_STYLES: [&str; 3] = ["cover", "ratio", "repeat"];
+
+/// One place where a document departs from the spec.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct CanvasIssue {
+    /// Where the problem is, e.g. `nodes[2].color`
+    pub location: String,
+    pub message: String,
+}
+
+/// A preset (`"1"`–`"6"`) or a `#rgb` / `#rrggbb` hex color.
+pub fn is_valid_color(color: &str) -> bool {
+    if let Some(hex) = color.strip_prefix('#') {
+        return matches!(hex.len(), 3 | 6) && hex.chars().all(|c| c.is_ascii_hexdigit());
+    }
+    matches!(color, "1" | "2" | "3" | "4" | "5" | "6")
+}
+
+/// Validates raw .canvas text. Unparseable JSON is an error; an empty file is
+/// an empty, valid canvas.
+pub fn validate_json(json: &str) -> Result<Vec<CanvasIssue>, String> {
+    if json.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    let value: Value = serde_json::from_str(json)
+        .map_err(|e| format!("Failed to parse canvas JSON: {}", e))?;
+    Ok(validate_value(&value))
+}
+
+impl Canvas {
+    /// Spec issues of this canvas as it would be saved.
+    pub fn validate(&self) -> Vec<CanvasIssue> {
+        serde_json::to_value(self)
+            .map(|value| validate_value(&value))
+            .unwrap_or_default()
+    }
+}
+
+struct Checker {
+    issues: Vec<CanvasIssue>,
+}
+
+impl Checker {
+    fn issue(&mut self, location: &str, message: impl Into<String>) {
+        self.issues.push(CanvasIssue { location: location.to_string(), message: message.into() });
+    }
+
+    fn required_string<'a>(&mut self, obj: &'a Map<String, Value>, at: &str, key: &str) -> Option<&'a str> {
+        match obj.get(key) {
+            Some(Value::String(s)) => Some(s),
+            Some(_) => { self.issue(&format!("{}.{}", at, key), "must be a string"); None }
+            None => { self.issue(&format!("{}.{}", at, key), "is required"); None }
+        }
+    }
+
+    fn optional_string<'a>(&mut self, obj: &'a Map<String, Value>, at: &str, key: &str) -> Option<&'a str> {
+        match obj.get(key) {
+            Some(Value::String(s)) => Some(s),
+            Some(_) => { self.issue(&format!("{}.{}", at, key), "must be a string"); None }
+            None => None,
+        }
+    }
+
+    fn one_of(&mut self, obj: &Map<String, Value>, at: &str, key: &str, allowed: &[&str]) {
+        if let Some(value) = self.optional_string(obj, at, key) {
+            if !allowed.contains(&value) {
+                self.issue(&format!("{}.{}", at, key), format!("\"{}\" is not one of {}", value, allowed.join(", ")));
+            }
+        }
+    }
+
+    fn integer(&mut self, obj: &Map<String, Value>, at: &str, key: &str) {
+        let location = format!("{}.{}", at, key);
+        match obj.get(key) {
+            Some(Value::Number(n)) if n.is_i64() || n.is_u64() => {}
+            Some(Value::Number(n)) if n.as_f64().is_some_and(|f| f.fract() == 0.0) => {}
+            Some(Value::Number(_)) => self.issue(&location, "must be an integer"),
+            Some(_) => self.issue(&location, "must be a number"),
+            None => self.issue(&location, "is required"),
+        }
+    }
+
+    fn color(&mut self, obj: &Map<String, Value>, at: &str) {
+        if let Some(color) = self.optional_string(obj, at, "color") {
+            if !is_valid_color(color) {
+                self.issue(&format!("{}.color", at), format!("\"{}\" is neither a preset 1-6 nor a hex color", color));
+            }
+        }
+    }
+
+    fn id<'a>(&mut self, obj: &'a Map<String, Value>, at: &str, seen: &mut HashSet<&'a str>) -> Option<&'a str> {
+        let id = self.required_string(obj, at, "id")?;
+        if id.is_empty() {
+            self.issue(&format!("{}.id", at), "must not be empty");
+        } else if !seen.insert(id) {
+            self.issue(&format!("{}.id", at), format!("duplicate id \"{}\"", id));
+        }
+        Some(id)
+    }
+
+    fn node(&mut self, obj: &Map<String, Value>, at: &str) {
+        for key in ["x", "y", "width", "height"] {
+            self.integer(obj, at, key);
+        }
+        self.color(obj, at);
+        match self.required_string(obj, at, "type") {
+            Some("text") => { self.required_string(obj, at, "text"); }
+            Some("file") => {
+                self.required_string(obj, at, "file");
+                if let Some(subpath) = self.optional_string(obj, at, "subpath") {
+                    if !subpath.starts_with('#') {
+                        self.issue(&format!("{}.subpath", at), "must start with #");
+                    }
+                }
+            }
+            Some("link") => { self.required_string(obj, at, "url"); }
+            Some("group") => {
+                self.optional_string(obj, at, "label");
+                self.optional_string(obj, at, "background");
+                self.one_of(obj, at, "backgroundStyle", &BACKGROUND_STYLES);
+            }
+            Some(other) => self.issue(&format!("{}.type", at), format!("\"{}\" is not one of {}", other, NODE_TYPES.join(", "))),
+            None => {}
+        }
+    }
+
+    fn edge(&mut self, obj: &Map<String, Value>, at: &str, node_ids: &HashSet<&str>) {
+        for key in ["fromNode", "toNode"] {
+            if let Some(node) = self.required_string(obj, at, key) {
+                if !node_ids.contains(node) {
+                    self.issue(&format!("{}.{}", at, key), format!("no node with id \"{}\"", node));
+                }
+            }
+        }
+        for key in ["fromSide", "toSide"] {
+            self.one_of(obj, at, key, &SIDES);
+        }
+        for key in ["fromEnd", "toEnd"] {
+            self.one_of(obj, at, key, &ENDS);
+        }
+        self.color(obj, at);
+        self.optional_string(obj, at, "label");
+    }
+}
+
+/// Spec issues of a parsed .canvas document, in document order. Fields the
+/// spec does not define are allowed.
+pub fn validate_value(value: &Value) -> Vec<CanvasIssue> {
+    let mut checker = Checker { issues: Vec::new() };
+    let Some(root) = value.as_object() else {
+        checker.issue("", "canvas must be a JSON object");
+        return checker.issues;
+    };
+
+    let list = |checker: &mut Checker, key: &str| -> &[Value] {
+        match root.get(key) {
+            Some(Value::Array(items)) => items,
+            Some(_) => { checker.issue(key, "must be an array"); &[] }
+            None => &[],
+        }
+    };
+    let nodes = list(&mut checker, "nodes");
+    let edges = list(&mut checker, "edges");
+
+    // Ids are unique across nodes and edges alike
+    let mut ids: HashSet<&str> = HashSet::new();
+    let mut node_ids: HashSet<&str> = HashSet::new();
+    for (i, node) in nodes.iter().enumerate() {
+        let at = format!("nodes[{}]", i);
+        let Some(obj) = node.as_object() else {
+            checker.issue(&at, "must be an object");
+            continue;
+        };
+        if let Some(id) = checker.id(obj, &at, &mut ids) {
+            node_ids.insert(id);
+        }
+        checker.node(obj, &at);
+    }
+    for (i, edge) in edges.iter().enumerate() {
+        let at = format!("edges[{}]", i);
+        let Some(obj) = edge.as_object() else {
+            checker.issue(&at, "must be an object");
+            continue;
+        };
+        checker.id(obj, &at, &mut ids);
+        checker.edge(obj, &at, &node_ids);
+    }
+    checker.issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Hand-written from the JSON Canvas 1.0 spec, not saved by Obsidian: one
+    // file with every node type, one with fields of the kind plugins add.
+    const FIXTURES: [(&str, &str); 2] = [
+        ("spec-basic", include_str!("fixtures/spec-basic.canvas")),
+        ("spec-unknown-fields", include_str!("fixtures/spec-unknown-fields.canvas")),
+    ];
+
+    #[test]
+    fn test_fixtures_validate_and_round_trip() {
+        for (name, json) in FIXTURES {
+            assert_eq!(validate_json(json).unwrap(), vec![], "{}", name);
+
+            let canvas = Canvas::from_json(json).unwrap();
+            assert!(canvas.validate().is_empty(), "{}", name);
+            let original: Value = serde_json::from_str(json).unwrap();
+            let saved: Value = serde_json::from_str(&canvas.to_json().unwrap()).unwrap();
+            assert_eq!(saved, original, "{} should survive a round trip unchanged", name);
+        }
+
+        let canvas = Canvas::from_json(FIXTURES[1].1).unwrap();
+        assert_eq!(canvas.extra["metadata"]["version"], "1.0-1.0");
+        assert_eq!(canvas.nodes[0].extra["styleAttributes"]["shape"], "pill");
+        assert_eq!(canvas.edges[0].extra["styleAttributes"]["pathfindingMethod"], "square");
+    }
+
+    #[test]
+    fn test_reports_spec_violations() {
+        let json = r##"{
+            "nodes": [
+                {"id":"a","type":"text","text":"ok","x":0,"y":0,"width":100,"height":50},
+                {"id":"a","type":"text","x":0.5,"y":0,"width":100,"height":50,"color":"7"},
+                {"id":"g","type":"group","x":0,"y":0,"width":"wide","height":50,"backgroundStyle":"tile"},
+                {"id":"f","type":"file","file":"n.md","subpath":"Heading","x":0,"y":0,"width":1,"height":1,"color":"#12345g"},
+                {"id":"s","type":"sticker","x":0,"y":0,"width":1,"height":1}
+            ],
+            "edges": [
+                {"id":"e1","fromNode":"a","toNode":"missing","fromSide":"middle","toEnd":"triangle"},
+                {"id":"g","fromNode":"a","toNode":"f","color":"#ABC"}
+            ]
+        }"##;
+        let issues: Vec<String> = validate_json(json).unwrap()
+            .into_iter()
+            .map(|i| i.location)
+            .collect();
+        assert_eq!(issues, vec![
+            "nodes[1].id", "nodes[1].x", "nodes[1].color", "nodes[1].text",
+            "nodes[2].width", "nodes[2].backgroundStyle",
+            "nodes[3].color", "nodes[3].subpath",
+            "nodes[4].type",
+            "edges[0].toNode", "edges[0].fromSide", "edges[0].toEnd",
+            "edges[1].id",
+        ]);
+
+        assert!(validate_json("").unwrap().is_empty());
+        assert_eq!(validate_json("[]").unwrap().len(), 1);
+        assert!(validate_json("{nope").is_err());
+        assert!(is_valid_color("#ABC") && is_valid_color("6") && !is_valid_color("0"));
+    }
+}
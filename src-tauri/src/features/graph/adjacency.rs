@@ -0,0 +1,259 @@
+// Oxidian — Link adjacency
+// Resolved note-to-note links, kept up to date one note at a time so a file
+// event never means re-resolving the whole vault.
+
+use super::query::{best_candidate, link_target};
+use super::GraphEdge;
+use std::collections::{BTreeSet, HashMap};
+
+/// Edges that appeared or disappeared through one change.
+#[derive(Debug, Default, Clone)]
+pub struct EdgeDelta {
+    pub added: Vec<GraphEdge>,
+    pub removed: Vec<GraphEdge>,
+}
+
+/// Every note's wiki links with the note each resolves to, plus the reverse
+/// direction. Resolution follows [`super::query`]: full path first, then file
+/// name, case-insensitively.
+#[derive(Debug, Default)]
+pub struct Adjacency {
+    /// note → its wiki links in order, with the note each resolves to
+    links: HashMap<String, Vec<(String, Option<String>)>>,
+    /// note → notes linking to it
+    incoming: HashMap<String, BTreeSet<String>>,
+    /// lowercase path without `.md` → note
+    by_path: HashMap<String, String>,
+    /// lowercase file stem → notes
+    by_name: HashMap<String, BTreeSet<String>>,
+    /// lowercase last segment of a link target → notes with such a link.
+    /// These are the notes to re-resolve when a note with that name comes or goes.
+    wanted: HashMap<String, BTreeSet<String>>,
+}
+
+fn note_key(path: &str) -> String {
+    path.to_lowercase().trim_end_matches(".md").to_string()
+}
+
+fn last_segment(key: &str) -> &str {
+    key.rsplit('/').next().unwrap_or(key)
+}
+
+fn target_key(link: &str) -> Option<String> {
+    let target = link_target(link).to_lowercase();
+    let target = target.trim_end_matches(".md");
+    (!target.is_empty()).then(|| target.to_string())
+}
+
+impl Adjacency {
+    /// Builds the adjacency for a whole vault: registers every note first so
+    /// each link is resolved once.
+    pub fn build<'a>(notes: impl Iterator<Item = (&'a String, &'a Vec<String>)>) -> Self {
+        let notes: Vec<(&String, &Vec<String>)> = notes.collect();
+        let mut adjacency = Self::default();
+        for (path, _) in &notes {
+            adjacency.register(path);
+        }
+        let mut delta = EdgeDelta::default();
+        for (path, links) in notes {
+            let resolved = adjacency.resolve_all(links.iter().cloned());
+            adjacency.replace_links(path, resolved, &mut delta);
+        }
+        adjacency
+    }
+
+    /// The note a wiki link (inner text; alias and subpath allowed) points to.
+    pub fn resolve(&self, link: &str) -> Option<&str> {
+        let key = target_key(link)?;
+        if let Some(path) = self.by_path.get(&key) {
+            return Some(path);
+        }
+        let candidates = self.by_name.get(last_segment(&key))?;
+        best_candidate(candidates.iter().map(String::as_str), &key)
+    }
+
+    pub fn contains(&self, note: &str) -> bool {
+        self.links.contains_key(note)
+    }
+
+    /// Wiki links of `note`, each with the note it resolves to.
+    pub fn links(&self, note: &str) -> &[(String, Option<String>)] {
+        self.links.get(note).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Notes `note` links to, without repeats.
+    pub fn targets(&self, note: &str) -> BTreeSet<&str> {
+        self.links(note).iter().filter_map(|(_, t)| t.as_deref()).collect()
+    }
+
+    /// Notes linking to `note`.
+    pub fn sources(&self, note: &str) -> impl Iterator<Item = &str> {
+        self.incoming.get(note).into_iter().flatten().map(String::as_str)
+    }
+
+    /// Sets the links of `note`, adding the note if it is new. A new note can
+    /// resolve links elsewhere that dangled or pointed to a namesake.
+    pub fn set_note(&mut self, note: &str, links: &[String]) -> EdgeDelta {
+        let mut delta = EdgeDelta::default();
+        if !self.contains(note) {
+            self.register(note);
+            for source in self.wanting(note) {
+                if source != note {
+                    self.reresolve(&source, &mut delta);
+                }
+            }
+        }
+        let resolved = self.resolve_all(links.iter().cloned());
+        self.replace_links(note, resolved, &mut delta);
+        delta
+    }
+
+    /// Drops `note` and re-resolves the links that pointed to it.
+    pub fn remove_note(&mut self, note: &str) -> EdgeDelta {
+        let mut delta = EdgeDelta::default();
+        if !self.contains(note) {
+            return delta;
+        }
+        self.replace_links(note, Vec::new(), &mut delta);
+        self.links.remove(note);
+        let key = note_key(note);
+        if self.by_path.get(&key).is_some_and(|p| p == note) {
+            self.by_path.remove(&key);
+        }
+        let name = last_segment(&key).to_string();
+        if let Some(notes) = self.by_name.get_mut(&name) {
+            notes.remove(note);
+            if notes.is_empty() {
+                self.by_name.remove(&name);
+            }
+        }
+        for source in self.wanting(note) {
+            self.reresolve(&source, &mut delta);
+        }
+        self.incoming.remove(note);
+        delta
+    }
+
+    fn register(&mut self, note: &str) {
+        let key = note_key(note);
+        self.by_name.entry(last_segment(&key).to_string()).or_default().insert(note.to_string());
+        self.by_path.insert(key, note.to_string());
+        self.links.entry(note.to_string()).or_default();
+    }
+
+    /// Notes with a link whose last segment is `note`'s name.
+    fn wanting(&self, note: &str) -> BTreeSet<String> {
+        let key = note_key(note);
+        self.wanted.get(last_segment(&key)).cloned().unwrap_or_default()
+    }
+
+    fn resolve_all(&self, links: impl Iterator<Item = String>) -> Vec<(String, Option<String>)> {
+        links.map(|link| {
+            let target = self.resolve(&link).map(str::to_string);
+            (link, target)
+        }).collect()
+    }
+
+    fn reresolve(&mut self, note: &str, delta: &mut EdgeDelta) {
+        let links: Vec<String> = self.links(note).iter().map(|(l, _)| l.clone()).collect();
+        let resolved = self.resolve_all(links.into_iter());
+        self.replace_links(note, resolved, delta);
+    }
+
+    fn replace_links(&mut self, note: &str, links: Vec<(String, Option<String>)>, delta: &mut EdgeDelta) {
+        let old: BTreeSet<String> = self.targets(note).into_iter().map(str::to_string).collect();
+        let new: BTreeSet<String> = links.iter().filter_map(|(_, t)| t.clone()).collect();
+
+        for (link, _) in self.links.get(note).into_iter().flatten() {
+            if let Some(key) = target_key(link) {
+                let name = last_segment(&key);
+                if let Some(notes) = self.wanted.get_mut(name) {
+                    notes.remove(note);
+                    if notes.is_empty() {
+                        self.wanted.remove(name);
+                    }
+                }
+            }
+        }
+        for (link, _) in &links {
+            if let Some(key) = target_key(link) {
+                self.wanted.entry(last_segment(&key).to_string()).or_default().insert(note.to_string());
+            }
+        }
+
+        for target in old.difference(&new) {
+            if let Some(sources) = self.incoming.get_mut(target) {
+                sources.remove(note);
+            }
+            delta.removed.push(GraphEdge { source: note.to_string(), target: target.clone() });
+        }
+        for target in new.difference(&old) {
+            self.incoming.entry(target.clone()).or_default().insert(note.to_string());
+            delta.added.push(GraphEdge { source: note.to_string(), target: target.clone() });
+        }
+        self.links.insert(note.to_string(), links);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn links(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn edges(delta: &[GraphEdge]) -> Vec<(&str, &str)> {
+        delta.iter().map(|e| (e.source.as_str(), e.target.as_str())).collect()
+    }
+
+    #[test]
+    fn test_new_and_removed_notes_re_resolve_links() {
+        let mut adj = Adjacency::default();
+        adj.set_note("a.md", &links(&["Topic", "b|Bee"]));
+        let delta = adj.set_note("b.md", &links(&[]));
+        assert_eq!(edges(&delta.added), vec![("a.md", "b.md")]);
+        assert!(adj.resolve("Topic").is_none());
+
+        // Creating the missing note resolves the dangling link
+        let delta = adj.set_note("notes/topic.md", &links(&["a#Intro"]));
+        assert_eq!(edges(&delta.added), vec![("a.md", "notes/topic.md"), ("notes/topic.md", "a.md")]);
+        assert_eq!(adj.sources("a.md").collect::<Vec<_>>(), vec!["notes/topic.md"]);
+
+        // A shallower namesake wins by name; a path link keeps its note
+        adj.set_note("c.md", &links(&["notes/topic"]));
+        let delta = adj.set_note("topic.md", &links(&[]));
+        assert_eq!(edges(&delta.removed), vec![("a.md", "notes/topic.md")]);
+        assert_eq!(edges(&delta.added), vec![("a.md", "topic.md")]);
+        assert_eq!(adj.targets("c.md").into_iter().collect::<Vec<_>>(), vec!["notes/topic.md"]);
+
+        let delta = adj.remove_note("topic.md");
+        assert_eq!(edges(&delta.added), vec![("a.md", "notes/topic.md")]);
+        assert!(!adj.contains("topic.md"));
+
+        // Editing links only touches the note's own edges
+        let delta = adj.set_note("a.md", &links(&["c"]));
+        assert_eq!(edges(&delta.added), vec![("a.md", "c.md")]);
+        assert_eq!(delta.removed.len(), 2);
+        assert_eq!(adj.sources("b.md").count(), 0);
+    }
+
+    #[test]
+    fn test_build_matches_incremental() {
+        let notes: HashMap<String, Vec<String>> = [
+            ("a.md", vec!["b", "sub/c"]),
+            ("b.md", vec!["c"]),
+            ("sub/c.md", vec!["A"]),
+        ].into_iter().map(|(p, l)| (p.to_string(), links(&l))).collect();
+        let built = Adjacency::build(notes.iter());
+
+        let mut incremental = Adjacency::default();
+        for path in ["sub/c.md", "b.md", "a.md"] {
+            incremental.set_note(path, &notes[path]);
+        }
+        for path in notes.keys() {
+            assert_eq!(built.targets(path), incremental.targets(path), "{}", path);
+        }
+        assert_eq!(built.sources("sub/c.md").collect::<Vec<_>>(), vec!["a.md", "b.md"]);
+    }
+}
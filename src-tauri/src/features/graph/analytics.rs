@@ -0,0 +1,372 @@
+// Oxidian — Graph Analytics
+// Centrality (PageRank, degree), communities (label propagation), connected
+// components, orphans and dead ends over the link graph. Results are cached
+// and, when the graph changes, recomputed warm-started from the last run.
+
+use super::GraphData;
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+
+const DAMPING: f64 = 0.85;
+const PAGERANK_MAX_ITERATIONS: usize = 100;
+const PAGERANK_TOLERANCE: f64 = 1e-9;
+const PROPAGATION_MAX_ROUNDS: usize = 30;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NodeMetrics {
+    pub id: String,
+    pub pagerank: f64,
+    pub in_degree: usize,
+    pub out_degree: usize,
+    /// (in + out) / (n − 1)
+    pub degree_centrality: f64,
+    /// Index into `GraphAnalytics::communities`
+    pub community: usize,
+    /// Index into `GraphAnalytics::components`
+    pub component: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GraphAnalytics {
+    /// Every node, highest PageRank first
+    pub nodes: Vec<NodeMetrics>,
+    /// Communities, largest first
+    pub communities: Vec<Vec<String>>,
+    /// Weakly connected components, largest first
+    pub components: Vec<Vec<String>>,
+    /// Notes without any links in or out
+    pub orphans: Vec<String>,
+    /// Notes that are linked to but link nowhere themselves
+    pub dead_ends: Vec<String>,
+    /// Node id → position in `nodes`
+    #[serde(skip)]
+    index: HashMap<String, usize>,
+}
+
+impl GraphAnalytics {
+    pub fn node(&self, id: &str) -> Option<&NodeMetrics> {
+        self.index.get(id).map(|&i| &self.nodes[i])
+    }
+
+    /// Node id → community index, for grouping.
+    pub fn community_of(&self) -> HashMap<&str, usize> {
+        self.nodes.iter().map(|n| (n.id.as_str(), n.community)).collect()
+    }
+}
+
+/// Deduplicated directed edges between distinct, known nodes.
+fn edge_list(graph: &GraphData, index: &HashMap<&str, usize>) -> Vec<(usize, usize)> {
+    let mut edges: Vec<(usize, usize)> = graph.edges.iter()
+        .filter_map(|e| Some((*index.get(e.source.as_str())?, *index.get(e.target.as_str())?)))
+        .filter(|(a, b)| a != b)
+        .collect();
+    edges.sort_unstable();
+    edges.dedup();
+    edges
+}
+
+fn pagerank(n: usize, edges: &[(usize, usize)], out_degree: &[usize], start: Vec<f64>) -> Vec<f64> {
+    let mut rank = start;
+    let mut next = vec![0.0; n];
+    for _ in 0..PAGERANK_MAX_ITERATIONS {
+        let dangling: f64 = (0..n).filter(|&i| out_degree[i] == 0).map(|i| rank[i]).sum();
+        let base = (1.0 - DAMPING) / n as f64 + DAMPING * dangling / n as f64;
+        next.iter_mut().for_each(|r| *r = base);
+        for &(a, b) in edges {
+            next[b] += DAMPING * rank[a] / out_degree[a] as f64;
+        }
+        let delta: f64 = rank.iter().zip(&next).map(|(a, b)| (a - b).abs()).sum();
+        std::mem::swap(&mut rank, &mut next);
+        if delta < PAGERANK_TOLERANCE {
+            break;
+        }
+    }
+    rank
+}
+
+/// Label propagation on the undirected graph. Nodes are visited in a fixed
+/// order and adopt the most common label among their neighbours, keeping
+/// their own label on ties, so results are deterministic.
+fn propagate_labels(neighbours: &[Vec<usize>], mut labels: Vec<usize>) -> Vec<usize> {
+    for _ in 0..PROPAGATION_MAX_ROUNDS {
+        let mut changed = false;
+        for i in 0..neighbours.len() {
+            if neighbours[i].is_empty() {
+                continue;
+            }
+            let mut counts: BTreeMap<usize, usize> = BTreeMap::new();
+            for &j in &neighbours[i] {
+                *counts.entry(labels[j]).or_default() += 1;
+            }
+            let best = counts.values().copied().max().unwrap_or(0);
+            if counts.get(&labels[i]).copied().unwrap_or(0) == best {
+                continue;
+            }
+            // Smallest label among the most common ones
+            if let Some((&label, _)) = counts.iter().find(|(_, c)| **c == best) {
+                labels[i] = label;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+    labels
+}
+
+fn find(parent: &mut [usize], mut i: usize) -> usize {
+    while parent[i] != i {
+        parent[i] = parent[parent[i]];
+        i = parent[i];
+    }
+    i
+}
+
+/// Group node indices by key, largest group first (ties by first member),
+/// and return the groups plus each node's group index.
+fn groups(ids: &[&str], keys: &[usize]) -> (Vec<Vec<String>>, Vec<usize>) {
+    let mut by_key: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (i, key) in keys.iter().enumerate() {
+        by_key.entry(*key).or_default().push(i);
+    }
+    let mut members: Vec<Vec<usize>> = by_key.into_values().collect();
+    for m in &mut members {
+        m.sort_by(|a, b| ids[*a].cmp(ids[*b]));
+    }
+    members.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| ids[a[0]].cmp(ids[b[0]])));
+    let mut group_of = vec![0; ids.len()];
+    for (g, m) in members.iter().enumerate() {
+        for &i in m {
+            group_of[i] = g;
+        }
+    }
+    let groups = members.into_iter().map(|m| m.into_iter().map(|i| ids[i].to_string()).collect()).collect();
+    (groups, group_of)
+}
+
+/// Analyse `graph`. With `previous` results, PageRank starts from the old
+/// scores and label propagation from the old communities, so small changes
+/// converge in a few rounds and communities keep their identity.
+pub fn analyze(graph: &GraphData, previous: Option<&GraphAnalytics>) -> GraphAnalytics {
+    let n = graph.nodes.len();
+    if n == 0 {
+        return GraphAnalytics::default();
+    }
+    // Stable node order, independent of the cache's hash map order
+    let mut ids: Vec<&str> = graph.nodes.iter().map(|node| node.id.as_str()).collect();
+    ids.sort_unstable();
+    ids.dedup();
+    let n = ids.len();
+    let index: HashMap<&str, usize> = ids.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+    let edges = edge_list(graph, &index);
+
+    let mut in_degree = vec![0; n];
+    let mut out_degree = vec![0; n];
+    let mut neighbours = vec![Vec::new(); n];
+    for &(a, b) in &edges {
+        out_degree[a] += 1;
+        in_degree[b] += 1;
+        neighbours[a].push(b);
+        neighbours[b].push(a);
+    }
+
+    let old: HashMap<&str, &NodeMetrics> = previous
+        .map(|p| p.nodes.iter().map(|m| (m.id.as_str(), m)).collect())
+        .unwrap_or_default();
+
+    let mut start: Vec<f64> = ids.iter().map(|id| old.get(id).map(|m| m.pagerank).unwrap_or(1.0 / n as f64)).collect();
+    let total: f64 = start.iter().sum();
+    start.iter_mut().for_each(|r| *r /= total);
+    let rank = pagerank(n, &edges, &out_degree, start);
+
+    // Warm start: nodes keep the first member's index of their old community
+    let old_labels: HashMap<usize, usize> = previous
+        .map(|p| {
+            p.communities.iter().enumerate()
+                .filter_map(|(c, members)| members.iter().find_map(|m| index.get(m.as_str())).map(|i| (c, *i)))
+                .collect()
+        })
+        .unwrap_or_default();
+    let labels: Vec<usize> = (0..n)
+        .map(|i| old.get(ids[i]).and_then(|m| old_labels.get(&m.community)).copied().unwrap_or(i))
+        .collect();
+    let labels = propagate_labels(&neighbours, labels);
+
+    let mut parent: Vec<usize> = (0..n).collect();
+    for &(a, b) in &edges {
+        let (ra, rb) = (find(&mut parent, a), find(&mut parent, b));
+        if ra != rb {
+            parent[ra.max(rb)] = ra.min(rb);
+        }
+    }
+    let roots: Vec<usize> = (0..n).map(|i| find(&mut parent, i)).collect();
+    let (components, component_of) = groups(&ids, &roots);
+
+    // A warm-started label can outlive the links that joined its members,
+    // so a community never spans more than one component
+    let mut split: HashMap<(usize, usize), usize> = HashMap::new();
+    let keys: Vec<usize> = (0..n)
+        .map(|i| {
+            let next = split.len();
+            *split.entry((labels[i], roots[i])).or_insert(next)
+        })
+        .collect();
+    let (communities, community_of) = groups(&ids, &keys);
+
+    let denominator = (n.max(2) - 1) as f64;
+    let mut nodes: Vec<NodeMetrics> = (0..n)
+        .map(|i| NodeMetrics {
+            id: ids[i].to_string(),
+            pagerank: rank[i],
+            in_degree: in_degree[i],
+            out_degree: out_degree[i],
+            degree_centrality: (in_degree[i] + out_degree[i]) as f64 / denominator,
+            community: community_of[i],
+            component: component_of[i],
+        })
+        .collect();
+    nodes.sort_by(|a, b| b.pagerank.total_cmp(&a.pagerank).then_with(|| a.id.cmp(&b.id)));
+    let index = nodes.iter().enumerate().map(|(i, m)| (m.id.clone(), i)).collect();
+
+    GraphAnalytics {
+        orphans: (0..n).filter(|&i| in_degree[i] + out_degree[i] == 0).map(|i| ids[i].to_string()).collect(),
+        dead_ends: (0..n).filter(|&i| out_degree[i] == 0 && in_degree[i] > 0).map(|i| ids[i].to_string()).collect(),
+        nodes,
+        communities,
+        components,
+        index,
+    }
+}
+
+/// Set each node's `group` to its community index.
+pub fn apply_communities(graph: &mut GraphData, analytics: &GraphAnalytics) {
+    let community = analytics.community_of();
+    for node in &mut graph.nodes {
+        node.group = community.get(node.id.as_str()).map(|c| c.to_string());
+    }
+}
+
+// ─── Cache ─────────────────────────────────────────────────────────
+
+/// Last analysis plus a fingerprint of the graph it was computed for.
+#[derive(Debug, Default)]
+pub struct AnalyticsCache {
+    signature: Option<u64>,
+    analytics: GraphAnalytics,
+}
+
+fn signature(graph: &GraphData) -> u64 {
+    let mut nodes: Vec<&str> = graph.nodes.iter().map(|n| n.id.as_str()).collect();
+    nodes.sort_unstable();
+    let mut edges: Vec<(&str, &str)> = graph.edges.iter().map(|e| (e.source.as_str(), e.target.as_str())).collect();
+    edges.sort_unstable();
+    let mut hasher = DefaultHasher::new();
+    nodes.hash(&mut hasher);
+    edges.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl AnalyticsCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Analytics for `graph`: the cached result if the graph is unchanged,
+    /// otherwise a warm-started refresh.
+    pub fn get(&mut self, graph: &GraphData) -> &GraphAnalytics {
+        let sig = signature(graph);
+        if self.signature != Some(sig) {
+            let previous = self.signature.map(|_| &self.analytics);
+            self.analytics = analyze(graph, previous);
+            self.signature = Some(sig);
+        }
+        &self.analytics
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::graph::{GraphEdge, GraphNode, NodeKind};
+
+    fn graph(nodes: &[&str], edges: &[(&str, &str)]) -> GraphData {
+        GraphData {
+            nodes: nodes.iter().map(|id| GraphNode { id: id.to_string(), name: id.to_string(), group: None, kind: NodeKind::Note }).collect(),
+            edges: edges.iter().map(|(s, t)| GraphEdge { source: s.to_string(), target: t.to_string() }).collect(),
+        }
+    }
+
+    fn two_clusters() -> GraphData {
+        graph(
+            &["a1", "a2", "a3", "b1", "b2", "b3", "sink", "lonely"],
+            &[("a1", "a2"), ("a2", "a3"), ("a3", "a1"), ("b1", "b2"), ("b2", "b3"), ("b3", "b1"), ("a1", "sink"), ("a2", "sink"), ("b1", "sink")],
+        )
+    }
+
+    #[test]
+    fn test_centrality_orphans_and_components() {
+        let result = analyze(&two_clusters(), None);
+        assert_eq!(result.nodes[0].id, "sink");
+        let total: f64 = result.nodes.iter().map(|n| n.pagerank).sum();
+        assert!((total - 1.0).abs() < 1e-6);
+        let a1 = result.node("a1").unwrap();
+        assert_eq!((a1.in_degree, a1.out_degree), (1, 2));
+        assert!((a1.degree_centrality - 3.0 / 7.0).abs() < 1e-9);
+        assert_eq!(result.orphans, vec!["lonely"]);
+        assert_eq!(result.dead_ends, vec!["sink"]);
+        assert_eq!(result.components.len(), 2);
+        assert_eq!(result.components[1], vec!["lonely"]);
+    }
+
+    #[test]
+    fn test_communities_feed_groups() {
+        let mut g = two_clusters();
+        let result = analyze(&g, None);
+        let a = result.node("a1").unwrap().community;
+        assert_eq!(result.node("a3").unwrap().community, a);
+        assert_ne!(result.node("b2").unwrap().community, a);
+        assert_eq!(result.node("b1").unwrap().community, result.node("b3").unwrap().community);
+
+        apply_communities(&mut g, &result);
+        let group = |id: &str| g.nodes.iter().find(|n| n.id == id).unwrap().group.clone();
+        assert_eq!(group("a2"), Some(a.to_string()));
+    }
+
+    #[test]
+    fn test_cache_refreshes_only_on_change() {
+        let mut cache = AnalyticsCache::new();
+        let g = two_clusters();
+        let first = cache.get(&g).clone();
+        let again = cache.get(&g);
+        assert_eq!(first.nodes[0].pagerank, again.nodes[0].pagerank);
+
+        let mut grown = two_clusters();
+        grown.nodes.push(GraphNode { id: "a4".into(), name: "a4".into(), group: None, kind: NodeKind::Note });
+        grown.edges.push(GraphEdge { source: "a4".into(), target: "a1".into() });
+        let refreshed = cache.get(&grown);
+        assert_eq!(refreshed.node("a4").unwrap().community, refreshed.node("a1").unwrap().community);
+        assert!(refreshed.orphans.contains(&"lonely".to_string()));
+        let cold = analyze(&grown, None);
+        assert!((refreshed.node("sink").unwrap().pagerank - cold.node("sink").unwrap().pagerank).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_warm_start_splits_disconnected_communities() {
+        let joined = graph(&["a", "b", "c", "d"], &[("a", "b"), ("b", "c"), ("c", "d")]);
+        let previous = analyze(&joined, None);
+        assert_eq!(previous.communities.len(), 1);
+
+        let cut = graph(&["a", "b", "c", "d"], &[("a", "b"), ("c", "d")]);
+        let result = analyze(&cut, Some(&previous));
+        assert_eq!(result.components.len(), 2);
+        assert_eq!(result.communities.len(), 2);
+        assert_ne!(result.node("a").unwrap().community, result.node("d").unwrap().community);
+    }
+}
@@ -0,0 +1,379 @@
+// Oxidian — Graph export
+// Writes the link graph with its node attributes (tags, folder, degree,
+// PageRank, community) as GraphML, GEXF or Graphviz DOT.
+
+use super::analytics::GraphAnalytics;
+use super::{GraphData, NodeKind};
+use crate::state::VaultMetaCache;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    GraphMl,
+    Gexf,
+    Dot,
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::GraphMl => "graphml",
+            ExportFormat::Gexf => "gexf",
+            ExportFormat::Dot => "dot",
+        }
+    }
+}
+
+/// A node with everything the exporters write about it.
+#[derive(Debug, Clone)]
+pub struct ExportNode {
+    pub id: String,
+    pub label: String,
+    pub kind: NodeKind,
+    /// Parent folder of a note or attachment, empty at the vault root
+    pub folder: String,
+    pub tags: Vec<String>,
+    pub in_degree: usize,
+    pub out_degree: usize,
+    pub pagerank: Option<f64>,
+    pub community: Option<usize>,
+    /// The node group as shown in the graph view (community or `color_by`)
+    pub group: Option<String>,
+}
+
+fn kind_name(kind: NodeKind) -> &'static str {
+    match kind {
+        NodeKind::Note => "note",
+        NodeKind::Ghost => "ghost",
+        NodeKind::Tag => "tag",
+        NodeKind::Attachment => "attachment",
+    }
+}
+
+/// Attributes for every node of `graph`. Degrees count the edges of `graph`
+/// itself; PageRank and community come from `analytics` when given.
+pub fn export_nodes(graph: &GraphData, cache: &VaultMetaCache, analytics: Option<&GraphAnalytics>) -> Vec<ExportNode> {
+    let mut in_degree: HashMap<&str, usize> = HashMap::new();
+    let mut out_degree: HashMap<&str, usize> = HashMap::new();
+    for edge in &graph.edges {
+        *out_degree.entry(edge.source.as_str()).or_default() += 1;
+        *in_degree.entry(edge.target.as_str()).or_default() += 1;
+    }
+
+    graph.nodes.iter()
+        .map(|node| {
+            let on_disk = matches!(node.kind, NodeKind::Note | NodeKind::Attachment);
+            let folder = if on_disk {
+                node.id.rsplit_once('/').map(|(folder, _)| folder.to_string()).unwrap_or_default()
+            } else {
+                String::new()
+            };
+            let metrics = analytics.and_then(|a| a.node(&node.id));
+            ExportNode {
+                id: node.id.clone(),
+                label: node.name.clone(),
+                kind: node.kind,
+                folder,
+                tags: cache.entries.get(&node.id).map(|(tags, _)| tags.clone()).unwrap_or_default(),
+                in_degree: in_degree.get(node.id.as_str()).copied().unwrap_or(0),
+                out_degree: out_degree.get(node.id.as_str()).copied().unwrap_or(0),
+                pagerank: metrics.map(|m| m.pagerank),
+                community: metrics.map(|m| m.community),
+                group: node.group.clone(),
+            }
+        })
+        .collect()
+}
+
+// ─── Escaping ──────────────────────────────────────────────────────
+
+/// Escapes text for XML attributes and content, dropping characters
+/// XML 1.0 cannot carry at all.
+pub fn escape_xml(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            '\t' => out.push_str("&#9;"),
+            '\n' => out.push_str("&#10;"),
+            '\r' => out.push_str("&#13;"),
+            c if (c as u32) < 0x20 || c == '\u{FFFE}' || c == '\u{FFFF}' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// A double-quoted DOT ID.
+pub fn quote_dot(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => {}
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+// ─── Formats ───────────────────────────────────────────────────────
+
+pub fn to_graphml(graph: &GraphData, nodes: &[ExportNode]) -> String {
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\" xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" xsi:schemaLocation=\"http://graphml.graphdrawing.org/xmlns http://graphml.graphdrawing.org/xmlns/1.0/graphml.xsd\">\n");
+    for (key, ty) in [
+        ("label", "string"), ("kind", "string"), ("folder", "string"), ("tags", "string"), ("group", "string"),
+        ("in_degree", "int"), ("out_degree", "int"), ("pagerank", "double"), ("community", "int"),
+    ] {
+        let _ = writeln!(out, "  <key id=\"{key}\" for=\"node\" attr.name=\"{key}\" attr.type=\"{ty}\"/>");
+    }
+    out.push_str("  <graph id=\"vault\" edgedefault=\"directed\">\n");
+    for node in nodes {
+        let _ = writeln!(out, "    <node id=\"{}\">", escape_xml(&node.id));
+        let mut data = |key: &str, value: String| {
+            let _ = writeln!(out, "      <data key=\"{}\">{}</data>", key, escape_xml(&value));
+        };
+        data("label", node.label.clone());
+        data("kind", kind_name(node.kind).to_string());
+        data("folder", node.folder.clone());
+        data("tags", node.tags.join(","));
+        if let Some(group) = &node.group {
+            data("group", group.clone());
+        }
+        data("in_degree", node.in_degree.to_string());
+        data("out_degree", node.out_degree.to_string());
+        if let Some(pagerank) = node.pagerank {
+            data("pagerank", pagerank.to_string());
+        }
+        if let Some(community) = node.community {
+            data("community", community.to_string());
+        }
+        out.push_str("    </node>\n");
+    }
+    for (i, edge) in graph.edges.iter().enumerate() {
+        let _ = writeln!(out, "    <edge id=\"e{}\" source=\"{}\" target=\"{}\"/>", i, escape_xml(&edge.source), escape_xml(&edge.target));
+    }
+    out.push_str("  </graph>\n</graphml>\n");
+    out
+}
+
+pub fn to_gexf(graph: &GraphData, nodes: &[ExportNode]) -> String {
+    const ATTRIBUTES: [(&str, &str); 8] = [
+        ("kind", "string"), ("folder", "string"), ("tags", "liststring"), ("group", "string"),
+        ("in_degree", "integer"), ("out_degree", "integer"), ("pagerank", "double"), ("community", "integer"),
+    ];
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<gexf xmlns=\"http://gexf.net/1.3\" xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" xsi:schemaLocation=\"http://gexf.net/1.3 http://gexf.net/1.3/gexf.xsd\" version=\"1.3\">\n");
+    out.push_str("  <meta>\n    <creator>Oxidian</creator>\n  </meta>\n");
+    out.push_str("  <graph mode=\"static\" defaultedgetype=\"directed\">\n    <attributes class=\"node\">\n");
+    for (i, (title, ty)) in ATTRIBUTES.iter().enumerate() {
+        let _ = writeln!(out, "      <attribute id=\"{i}\" title=\"{title}\" type=\"{ty}\"/>");
+    }
+    out.push_str("    </attributes>\n    <nodes>\n");
+    for node in nodes {
+        let _ = writeln!(out, "      <node id=\"{}\" label=\"{}\">", escape_xml(&node.id), escape_xml(&node.label));
+        out.push_str("        <attvalues>\n");
+        let values = [
+            Some(kind_name(node.kind).to_string()),
+            Some(node.folder.clone()),
+            Some(node.tags.join("|")),
+            node.group.clone(),
+            Some(node.in_degree.to_string()),
+            Some(node.out_degree.to_string()),
+            node.pagerank.map(|p| p.to_string()),
+            node.community.map(|c| c.to_string()),
+        ];
+        for (i, value) in values.iter().enumerate() {
+            if let Some(value) = value {
+                let _ = writeln!(out, "          <attvalue for=\"{}\" value=\"{}\"/>", i, escape_xml(value));
+            }
+        }
+        out.push_str("        </attvalues>\n      </node>\n");
+    }
+    out.push_str("    </nodes>\n    <edges>\n");
+    for (i, edge) in graph.edges.iter().enumerate() {
+        let _ = writeln!(out, "      <edge id=\"{}\" source=\"{}\" target=\"{}\"/>", i, escape_xml(&edge.source), escape_xml(&edge.target));
+    }
+    out.push_str("    </edges>\n  </graph>\n</gexf>\n");
+    out
+}
+
+pub fn to_dot(graph: &GraphData, nodes: &[ExportNode]) -> String {
+    let mut out = String::from("digraph vault {\n");
+    for node in nodes {
+        let mut attrs = vec![
+            format!("label={}", quote_dot(&node.label)),
+            format!("kind={}", quote_dot(kind_name(node.kind))),
+            format!("folder={}", quote_dot(&node.folder)),
+            format!("tags={}", quote_dot(&node.tags.join(","))),
+            format!("in_degree={}", node.in_degree),
+            format!("out_degree={}", node.out_degree),
+        ];
+        if let Some(group) = &node.group {
+            attrs.push(format!("group={}", quote_dot(group)));
+        }
+        if let Some(pagerank) = node.pagerank {
+            attrs.push(format!("pagerank={}", pagerank));
+        }
+        if let Some(community) = node.community {
+            attrs.push(format!("community={}", community));
+        }
+        if node.kind == NodeKind::Ghost {
+            attrs.push("style=dashed".to_string());
+        }
+        let _ = writeln!(out, "  {} [{}];", quote_dot(&node.id), attrs.join(", "));
+    }
+    for edge in &graph.edges {
+        let _ = writeln!(out, "  {} -> {};", quote_dot(&edge.source), quote_dot(&edge.target));
+    }
+    out.push_str("}\n");
+    out
+}
+
+pub fn render(format: ExportFormat, graph: &GraphData, nodes: &[ExportNode]) -> String {
+    match format {
+        ExportFormat::GraphMl => to_graphml(graph, nodes),
+        ExportFormat::Gexf => to_gexf(graph, nodes),
+        ExportFormat::Dot => to_dot(graph, nodes),
+    }
+}
+
+// ─── Writing ───────────────────────────────────────────────────────
+
+/// Folder inside the vault that exports go to when no file is chosen.
+pub const EXPORT_DIR: &str = "exports";
+
+/// Where an export lands, relative to the vault. `target` may be a file,
+/// a folder (ending in `/` or without an extension) or nothing.
+pub fn export_path(vault_path: &str, target: Option<&str>, format: ExportFormat) -> Result<String, String> {
+    resolve_export_path(vault_path, target, "vault-graph", format.extension())
+}
+
+/// [`export_path`] for any export, named `stem.extension` inside folders. A
+/// file target must carry `extension`, and an existing note is never
+/// overwritten.
+pub fn resolve_export_path(vault_path: &str, target: Option<&str>, stem: &str, extension: &str) -> Result<String, String> {
+    let target = target.map(|t| t.trim().replace('\\', "/")).filter(|t| !t.is_empty());
+    let relative = match target {
+        None => format!("{}/{}.{}", EXPORT_DIR, stem, extension),
+        Some(target) => {
+            if target.starts_with('/') || target.split('/').any(|part| part == "..") || target.contains(':') {
+                return Err("Invalid export path".to_string());
+            }
+            match Path::new(&target).extension().and_then(|e| e.to_str()) {
+                _ if target.ends_with('/') => format!("{}/{}.{}", target.trim_end_matches('/'), stem, extension),
+                None => format!("{}/{}.{}", target, stem, extension),
+                Some(ext) if ext.eq_ignore_ascii_case(extension) => target,
+                Some(_) => return Err(format!("Export path must end in .{}", extension)),
+            }
+        }
+    };
+    if extension.eq_ignore_ascii_case("md") && Path::new(vault_path).join(&relative).exists() {
+        return Err(format!("Refusing to overwrite existing note {}", relative));
+    }
+    Ok(relative)
+}
+
+/// Renders the graph and writes it into the vault. Returns the relative path.
+pub fn export_graph(vault_path: &str, target: Option<&str>, format: ExportFormat, graph: &GraphData, nodes: &[ExportNode]) -> Result<String, String> {
+    let relative = export_path(vault_path, target, format)?;
+    let full = Path::new(vault_path).join(&relative);
+    if let Some(parent) = full.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create export folder: {}", e))?;
+    }
+    fs::write(&full, render(format, graph, nodes)).map_err(|e| format!("Failed to write graph export: {}", e))?;
+    Ok(relative)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::graph::{GraphEdge, GraphNode};
+    use tempfile::TempDir;
+
+    fn sample() -> (GraphData, VaultMetaCache) {
+        let node = |id: &str, name: &str, kind| GraphNode { id: id.into(), name: name.into(), group: Some("0".into()), kind };
+        let graph = GraphData {
+            nodes: vec![
+                node("notes/Q&A \"draft\".md", "Q&A \"draft\"", NodeKind::Note),
+                node("b.md", "b<1>", NodeKind::Note),
+                node("ghost:Missing", "Missing", NodeKind::Ghost),
+            ],
+            edges: vec![
+                GraphEdge { source: "notes/Q&A \"draft\".md".into(), target: "b.md".into() },
+                GraphEdge { source: "b.md".into(), target: "ghost:Missing".into() },
+            ],
+        };
+        let mut cache = VaultMetaCache::new();
+        cache.set_entry("b.md".into(), (vec!["x".into(), "y/z".into()], vec![]));
+        (graph, cache)
+    }
+
+    #[test]
+    fn test_xml_formats_escape_and_carry_attributes() {
+        let (graph, cache) = sample();
+        let nodes = export_nodes(&graph, &cache, None);
+        assert_eq!(nodes[0].folder, "notes");
+        assert_eq!((nodes[1].in_degree, nodes[1].out_degree), (1, 1));
+
+        let graphml = to_graphml(&graph, &nodes);
+        assert!(graphml.contains("<node id=\"notes/Q&amp;A &quot;draft&quot;.md\">"));
+        assert!(graphml.contains("<data key=\"label\">b&lt;1&gt;</data>"));
+        assert!(graphml.contains("<data key=\"tags\">x,y/z</data>"));
+        assert!(graphml.contains("<data key=\"kind\">ghost</data>"));
+        assert!(!graphml.contains("key=\"pagerank\">"));
+        assert_eq!(graphml.matches("<edge ").count(), 2);
+
+        let gexf = to_gexf(&graph, &nodes);
+        assert!(gexf.contains("label=\"Q&amp;A &quot;draft&quot;\""));
+        assert!(gexf.contains("<attvalue for=\"2\" value=\"x|y/z\"/>"));
+        assert_eq!(gexf.matches("<node ").count(), 3);
+
+        assert_eq!(escape_xml("a\u{1}b\tc'"), "ab&#9;c&apos;");
+    }
+
+    #[test]
+    fn test_dot_quotes_ids_and_labels() {
+        let (graph, cache) = sample();
+        let dot = to_dot(&graph, &export_nodes(&graph, &cache, None));
+        assert!(dot.starts_with("digraph vault {\n"));
+        assert!(dot.contains("\"notes/Q&A \\\"draft\\\".md\" -> \"b.md\";"));
+        assert!(dot.contains("\"ghost:Missing\" [label=\"Missing\", kind=\"ghost\""));
+        assert!(dot.contains("style=dashed"));
+        assert_eq!(quote_dot("a\\b\nc"), "\"a\\\\b\\nc\"");
+    }
+
+    #[test]
+    fn test_export_paths() {
+        let dir = TempDir::new().unwrap();
+        let vault = dir.path().to_str().unwrap();
+        let (graph, cache) = sample();
+        let nodes = export_nodes(&graph, &cache, None);
+
+        let written = export_graph(vault, None, ExportFormat::GraphMl, &graph, &nodes).unwrap();
+        assert_eq!(written, "exports/vault-graph.graphml");
+        assert!(dir.path().join(&written).exists());
+        assert_eq!(export_graph(vault, Some("out/"), ExportFormat::Dot, &graph, &nodes).unwrap(), "out/vault-graph.dot");
+        assert_eq!(export_graph(vault, Some("g/links.gexf"), ExportFormat::Gexf, &graph, &nodes).unwrap(), "g/links.gexf");
+        assert!(export_path(vault, Some("../outside.dot"), ExportFormat::Dot).is_err());
+        assert!(export_path(vault, Some("/etc/x.dot"), ExportFormat::Dot).is_err());
+        assert!(export_path(vault, Some("Notes/Index.md"), ExportFormat::Dot).is_err());
+        assert_eq!(export_path(vault, Some("g/Links.DOT"), ExportFormat::Dot).unwrap(), "g/Links.DOT");
+    }
+}
@@ -0,0 +1,416 @@
+// Oxidian — Graph Layout
+// Barnes–Hut force-directed layout computed in Rust, so large vaults do not
+// stall the webview. Positions persist in `.oxidian/graph-layout.json` and seed
+// the next run: when only a few notes changed, only those move noticeably.
+
+use super::GraphData;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+const LAYOUT_FILE: &str = "graph-layout.json";
+/// Quadtree depth at which coincident points stop being split further
+const MAX_DEPTH: usize = 24;
+const GOLDEN_ANGLE: f64 = 2.399_963_229_728_653;
+
+// ─── Options ───────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct LayoutOptions {
+    /// Iteration budget for a layout from scratch. Incremental runs use a
+    /// quarter of it.
+    pub iterations: usize,
+    /// Barnes–Hut opening angle: larger is faster and coarser.
+    pub theta: f64,
+    /// Ideal edge length.
+    pub spring_length: f64,
+    /// Pull towards the origin, keeps disconnected parts from drifting away.
+    pub gravity: f64,
+}
+
+impl Default for LayoutOptions {
+    fn default() -> Self {
+        Self { iterations: 300, theta: 0.9, spring_length: 60.0, gravity: 0.05 }
+    }
+}
+
+// ─── Persistence ───────────────────────────────────────────────────
+
+/// Node positions saved between sessions, keyed by node id.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LayoutStore {
+    pub positions: BTreeMap<String, [f64; 2]>,
+}
+
+impl LayoutStore {
+    fn file(vault_path: &str) -> std::path::PathBuf {
+        Path::new(vault_path).join(".oxidian").join(LAYOUT_FILE)
+    }
+
+    /// Saved positions, or an empty store if there are none yet.
+    pub fn load(vault_path: &str) -> Self {
+        fs::read_to_string(Self::file(vault_path))
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, vault_path: &str) -> Result<(), String> {
+        let file = Self::file(vault_path);
+        if let Some(dir) = file.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("Failed to create .oxidian dir: {}", e))?;
+        }
+        let json = serde_json::to_string(self).map_err(|e| format!("Failed to serialize graph layout: {}", e))?;
+        fs::write(file, json).map_err(|e| format!("Failed to write graph layout: {}", e))
+    }
+}
+
+// ─── Quadtree ──────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy)]
+struct Quad {
+    cx: f64,
+    cy: f64,
+    half: f64,
+    mass: f64,
+    /// Mass-weighted position sums, divided by `mass` for the centre of mass
+    mx: f64,
+    my: f64,
+    body: Option<usize>,
+    children: Option<[usize; 4]>,
+}
+
+impl Quad {
+    fn new(cx: f64, cy: f64, half: f64) -> Self {
+        Self { cx, cy, half, mass: 0.0, mx: 0.0, my: 0.0, body: None, children: None }
+    }
+
+    fn quadrant(&self, p: [f64; 2]) -> usize {
+        (p[0] >= self.cx) as usize + 2 * (p[1] >= self.cy) as usize
+    }
+}
+
+struct QuadTree {
+    quads: Vec<Quad>,
+}
+
+impl QuadTree {
+    fn build(pos: &[[f64; 2]]) -> Self {
+        let (mut min_x, mut min_y, mut max_x, mut max_y) = (f64::MAX, f64::MAX, f64::MIN, f64::MIN);
+        for p in pos {
+            min_x = min_x.min(p[0]);
+            min_y = min_y.min(p[1]);
+            max_x = max_x.max(p[0]);
+            max_y = max_y.max(p[1]);
+        }
+        let half = ((max_x - min_x).max(max_y - min_y) / 2.0).max(1.0) + 1.0;
+        let mut tree = Self { quads: vec![Quad::new((min_x + max_x) / 2.0, (min_y + max_y) / 2.0, half)] };
+        for body in 0..pos.len() {
+            tree.insert(body, pos);
+        }
+        tree
+    }
+
+    fn split(&mut self, q: usize) -> [usize; 4] {
+        let Quad { cx, cy, half, .. } = self.quads[q];
+        let h = half / 2.0;
+        let first = self.quads.len();
+        for (dx, dy) in [(-h, -h), (h, -h), (-h, h), (h, h)] {
+            self.quads.push(Quad::new(cx + dx, cy + dy, h));
+        }
+        let children = [first, first + 1, first + 2, first + 3];
+        self.quads[q].children = Some(children);
+        children
+    }
+
+    fn add_mass(&mut self, q: usize, p: [f64; 2]) {
+        let quad = &mut self.quads[q];
+        quad.mass += 1.0;
+        quad.mx += p[0];
+        quad.my += p[1];
+    }
+
+    fn insert(&mut self, body: usize, pos: &[[f64; 2]]) {
+        let p = pos[body];
+        let mut q = 0;
+        let mut depth = 0;
+        loop {
+            self.add_mass(q, p);
+            if let Some(children) = self.quads[q].children {
+                q = children[self.quads[q].quadrant(p)];
+                depth += 1;
+                continue;
+            }
+            let Some(other) = self.quads[q].body else {
+                self.quads[q].body = Some(body);
+                return;
+            };
+            if depth >= MAX_DEPTH {
+                // Coincident points: keep the mass, the leaf stays with `other`
+                return;
+            }
+            let children = self.split(q);
+            self.quads[q].body = None;
+            let o = pos[other];
+            let oq = children[self.quads[q].quadrant(o)];
+            self.add_mass(oq, o);
+            self.quads[oq].body = Some(other);
+            q = children[self.quads[q].quadrant(p)];
+            depth += 1;
+        }
+    }
+
+    /// Repulsive displacement on `body` from every other body, approximating
+    /// distant cells by their centre of mass.
+    fn repulsion(&self, body: usize, p: [f64; 2], k2: f64, theta: f64) -> [f64; 2] {
+        let mut force = [0.0, 0.0];
+        let mut stack = vec![0];
+        while let Some(q) = stack.pop() {
+            let quad = &self.quads[q];
+            let own = (quad.body == Some(body)) as u8 as f64;
+            let mass = quad.mass - own;
+            if mass <= 0.0 {
+                continue;
+            }
+            let (com_x, com_y) = if own > 0.0 {
+                ((quad.mx - p[0]) / mass, (quad.my - p[1]) / mass)
+            } else {
+                (quad.mx / quad.mass, quad.my / quad.mass)
+            };
+            let (mut dx, mut dy) = (p[0] - com_x, p[1] - com_y);
+            let mut d = (dx * dx + dy * dy).sqrt();
+            match quad.children {
+                Some(children) if 2.0 * quad.half / d.max(1e-9) >= theta => stack.extend(children),
+                _ => {
+                    if d < 1e-3 {
+                        // Nudge coincident points apart in a fixed direction per body
+                        let angle = body as f64 * GOLDEN_ANGLE;
+                        (dx, dy, d) = (angle.cos() * 1e-3, angle.sin() * 1e-3, 1e-3);
+                    }
+                    let f = k2 * mass / (d * d);
+                    force[0] += dx * f;
+                    force[1] += dy * f;
+                }
+            }
+        }
+        force
+    }
+}
+
+// ─── Layout ────────────────────────────────────────────────────────
+
+/// Position every node of `graph`. Nodes found in `previous` start where they
+/// were; new nodes start next to their placed neighbours. When earlier
+/// positions exist the run is incremental: a quarter of the iteration budget,
+/// a lower starting temperature, and only new nodes and their neighbours
+/// moving freely.
+pub fn compute_layout(graph: &GraphData, previous: &BTreeMap<String, [f64; 2]>, opts: &LayoutOptions) -> BTreeMap<String, [f64; 2]> {
+    let n = graph.nodes.len();
+    if n == 0 {
+        return BTreeMap::new();
+    }
+    let index: HashMap<&str, usize> = graph.nodes.iter().enumerate().map(|(i, node)| (node.id.as_str(), i)).collect();
+    let mut edges: Vec<(usize, usize)> = graph.edges.iter()
+        .filter_map(|e| Some((*index.get(e.source.as_str())?, *index.get(e.target.as_str())?)))
+        .filter(|(a, b)| a != b)
+        .map(|(a, b)| (a.min(b), a.max(b)))
+        .collect();
+    edges.sort_unstable();
+    edges.dedup();
+    let mut neighbours = vec![Vec::new(); n];
+    for &(a, b) in &edges {
+        neighbours[a].push(b);
+        neighbours[b].push(a);
+    }
+
+    let k = opts.spring_length.max(1.0);
+    let mut pos = vec![[0.0, 0.0]; n];
+    let mut known = vec![false; n];
+    for (i, node) in graph.nodes.iter().enumerate() {
+        if let Some(p) = previous.get(&node.id).filter(|p| p[0].is_finite() && p[1].is_finite()) {
+            pos[i] = *p;
+            known[i] = true;
+        }
+    }
+    let known_count = known.iter().filter(|k| **k).count();
+    let incremental = known_count > 0;
+
+    // Seed new nodes: beside their placed neighbours, or on a spiral
+    let mut spiral = known_count;
+    for i in 0..n {
+        if known[i] {
+            continue;
+        }
+        let placed: Vec<[f64; 2]> = neighbours[i].iter().filter(|&&j| known[j]).map(|&j| pos[j]).collect();
+        let angle = i as f64 * GOLDEN_ANGLE;
+        pos[i] = if placed.is_empty() {
+            let r = k * (spiral as f64 + 1.0).sqrt();
+            spiral += 1;
+            [r * angle.cos(), r * angle.sin()]
+        } else {
+            let cx = placed.iter().map(|p| p[0]).sum::<f64>() / placed.len() as f64;
+            let cy = placed.iter().map(|p| p[1]).sum::<f64>() / placed.len() as f64;
+            [cx + k * 0.5 * angle.cos(), cy + k * 0.5 * angle.sin()]
+        };
+    }
+
+    let mut mobility = vec![1.0; n];
+    if incremental {
+        let fresh: HashSet<usize> = (0..n).filter(|&i| !known[i]).collect();
+        for i in 0..n {
+            if known[i] && !neighbours[i].iter().any(|j| fresh.contains(j)) {
+                mobility[i] = 0.02;
+            }
+        }
+    }
+
+    let (iterations, start_temp) = if incremental {
+        ((opts.iterations / 4).max(1), k * 0.25)
+    } else {
+        (opts.iterations.max(1), k * (n as f64).sqrt().max(2.0))
+    };
+    let k2 = k * k;
+    let mut disp = vec![[0.0, 0.0]; n];
+    for iteration in 0..iterations {
+        let temp = start_temp * (1.0 - iteration as f64 / iterations as f64);
+        let tree = QuadTree::build(&pos);
+        for i in 0..n {
+            let f = tree.repulsion(i, pos[i], k2, opts.theta);
+            disp[i] = [f[0] - opts.gravity * pos[i][0], f[1] - opts.gravity * pos[i][1]];
+        }
+        for &(a, b) in &edges {
+            let (dx, dy) = (pos[a][0] - pos[b][0], pos[a][1] - pos[b][1]);
+            let d = (dx * dx + dy * dy).sqrt().max(1e-3);
+            // Fruchterman–Reingold attraction d² / k along the edge
+            let f = d / k;
+            disp[a][0] -= dx * f;
+            disp[a][1] -= dy * f;
+            disp[b][0] += dx * f;
+            disp[b][1] += dy * f;
+        }
+        for i in 0..n {
+            let len = (disp[i][0] * disp[i][0] + disp[i][1] * disp[i][1]).sqrt();
+            if len > 0.0 && len.is_finite() {
+                let step = len.min(temp) * mobility[i] / len;
+                pos[i][0] += disp[i][0] * step;
+                pos[i][1] += disp[i][1] * step;
+            }
+        }
+    }
+
+    graph.nodes.iter().zip(pos)
+        .map(|(node, p)| (node.id.clone(), [(p[0] * 100.0).round() / 100.0, (p[1] * 100.0).round() / 100.0]))
+        .collect()
+}
+
+/// Positions for `graph`, starting from those saved in the vault (unless
+/// `reset`). The layout runs when `opts` are given or some node has no
+/// position yet; otherwise saved positions are returned as they are.
+///
+/// Results are merged into the store, so nodes filtered out of this graph
+/// keep their place; entries of deleted files are dropped. The store is only
+/// written when something moved.
+pub fn layout_vault_graph(vault_path: &str, graph: &GraphData, opts: Option<&LayoutOptions>, reset: bool) -> Result<BTreeMap<String, [f64; 2]>, String> {
+    let mut store = LayoutStore::load(vault_path);
+    let empty = BTreeMap::new();
+    let previous = if reset { &empty } else { &store.positions };
+    let all_placed = graph.nodes.iter().all(|n| previous.contains_key(&n.id));
+    let positions = match opts {
+        None if all_placed => {
+            return Ok(graph.nodes.iter().map(|n| (n.id.clone(), previous[&n.id])).collect());
+        }
+        _ => compute_layout(graph, previous, &opts.cloned().unwrap_or_default()),
+    };
+
+    let moved = positions.iter().any(|(id, p)| store.positions.get(id) != Some(p));
+    if moved {
+        store.positions.extend(positions.iter().map(|(id, p)| (id.clone(), *p)));
+        // Ghost and tag nodes are not files; they are cheap to keep
+        store.positions.retain(|id, _| {
+            id.starts_with("ghost:") || id.starts_with("tag:") || Path::new(vault_path).join(id).exists()
+        });
+        store.save(vault_path)?;
+    }
+    Ok(positions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::graph::{GraphEdge, GraphNode, NodeKind};
+
+    fn graph(nodes: &[&str], edges: &[(&str, &str)]) -> GraphData {
+        GraphData {
+            nodes: nodes.iter().map(|id| GraphNode { id: id.to_string(), name: id.to_string(), group: None, kind: NodeKind::Note }).collect(),
+            edges: edges.iter().map(|(s, t)| GraphEdge { source: s.to_string(), target: t.to_string() }).collect(),
+        }
+    }
+
+    fn dist(p: &BTreeMap<String, [f64; 2]>, a: &str, b: &str) -> f64 {
+        let (p, q) = (p[a], p[b]);
+        ((p[0] - q[0]).powi(2) + (p[1] - q[1]).powi(2)).sqrt()
+    }
+
+    #[test]
+    fn test_layout_pulls_linked_nodes_together() {
+        let g = graph(&["a", "b", "c", "d", "e", "f"], &[("a", "b"), ("b", "c"), ("c", "a"), ("d", "e"), ("e", "f"), ("f", "d")]);
+        let opts = LayoutOptions::default();
+        let first = compute_layout(&g, &BTreeMap::new(), &opts);
+        assert_eq!(first, compute_layout(&g, &BTreeMap::new(), &opts));
+        assert!(first.values().all(|p| p[0].is_finite() && p[1].is_finite()));
+        // Triangles stay tight and apart from each other
+        assert!(dist(&first, "a", "b") < dist(&first, "a", "e"));
+        assert!(dist(&first, "d", "f") < dist(&first, "c", "f"));
+    }
+
+    #[test]
+    fn test_incremental_layout_keeps_existing_positions() {
+        let names: Vec<String> = (0..40).map(|i| format!("n{}", i)).collect();
+        let ids: Vec<&str> = names.iter().map(String::as_str).collect();
+        let edges: Vec<(&str, &str)> = (1..40).map(|i| (ids[i], ids[(i - 1) / 2])).collect();
+        let opts = LayoutOptions::default();
+        let before = compute_layout(&graph(&ids, &edges), &BTreeMap::new(), &opts);
+
+        let mut more = ids.clone();
+        more.push("new");
+        let mut more_edges = edges.clone();
+        more_edges.push(("new", "n39"));
+        let after = compute_layout(&graph(&more, &more_edges), &before, &opts);
+
+        // Only the new node's neighbour may move freely
+        let far = ids.iter().filter(|id| **id != "n39")
+            .map(|id| ((after[*id][0] - before[*id][0]).powi(2) + (after[*id][1] - before[*id][1]).powi(2)).sqrt())
+            .fold(0.0, f64::max);
+        assert!(far < opts.spring_length / 4.0, "existing nodes moved {}", far);
+        assert!(dist(&after, "new", "n39") < 3.0 * opts.spring_length);
+    }
+
+    #[test]
+    fn test_layout_store_round_trip() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let vault = dir.path().to_str().unwrap();
+        for name in ["a.md", "b.md", "c.md"] {
+            fs::write(dir.path().join(name), "").unwrap();
+        }
+        let g = graph(&["a.md", "b.md", "tag:x"], &[("a.md", "b.md"), ("b.md", "tag:x")]);
+        let saved = layout_vault_graph(vault, &g, None, false).unwrap();
+        assert_eq!(LayoutStore::load(vault).positions, saved);
+
+        // A filtered view reuses saved positions without writing the store
+        let file = dir.path().join(".oxidian").join(LAYOUT_FILE);
+        let pretty = serde_json::to_string_pretty(&LayoutStore { positions: saved.clone() }).unwrap();
+        fs::write(&file, &pretty).unwrap();
+        let filtered = layout_vault_graph(vault, &graph(&["a.md"], &[]), None, false).unwrap();
+        assert_eq!(filtered.get("a.md"), saved.get("a.md"));
+        assert_eq!(fs::read_to_string(&file).unwrap(), pretty);
+
+        // New nodes are placed and merged in; deleted files drop out
+        fs::remove_file(dir.path().join("b.md")).unwrap();
+        let grown = layout_vault_graph(vault, &graph(&["a.md", "c.md"], &[("a.md", "c.md")]), None, false).unwrap();
+        let stored = LayoutStore::load(vault).positions;
+        assert_eq!(stored.keys().collect::<Vec<_>>(), vec!["a.md", "c.md", "tag:x"]);
+        assert_eq!(stored["c.md"], grown["c.md"]);
+    }
+}
@@ -1,10 +1,36 @@
+// Oxidian — Graph
+// Link graph of the vault: nodes and edges from the metadata cache, plus the
+// server-side layout and analytics.
+
+pub mod adjacency;
+pub mod analytics;
+pub mod export;
+pub mod layout;
+pub mod paths;
+pub mod query;
+pub mod timelapse;
+
 use serde::Serialize;
 
+/// What a graph node stands for. Everything but notes comes from a
+/// [`query::GraphQuery`] that asks for it.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeKind {
+    #[default]
+    Note,
+    /// Target of a link that resolves to no note or file
+    Ghost,
+    Tag,
+    Attachment,
+}
+
 #[derive(Debug, Serialize, Clone)]
 pub struct GraphNode {
     pub id: String,
     pub name: String,
     pub group: Option<String>,
+    pub kind: NodeKind,
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -21,73 +47,31 @@ pub struct GraphData {
 
 /// Compute full graph data from the vault meta cache
 pub fn compute_graph(cache: &crate::state::VaultMetaCache) -> GraphData {
-    let mut nodes = Vec::new();
-    let mut edges = Vec::new();
-
-    let all_files: Vec<(String, String)> = cache.entries.keys()
-        .map(|path| {
-            let name = std::path::Path::new(path)
-                .file_stem()
-                .unwrap_or_default()
-                .to_string_lossy()
-                .to_string();
-            (path.clone(), name)
-        })
-        .collect();
-
-    for (path, name) in &all_files {
-        nodes.push(GraphNode {
-            id: path.clone(),
-            name: name.clone(),
-            group: None,
-        });
-    }
-
-    for (path, _) in &all_files {
-        if let Some((_, links)) = cache.entries.get(path) {
-            for link in links {
-                let target = link.split('|').next().unwrap_or(link);
-                if let Some((target_path, _)) = all_files.iter().find(|(_, name)| {
-                    name == target || target.ends_with(&format!("/{}", name))
-                }) {
-                    edges.push(GraphEdge {
-                        source: path.clone(),
-                        target: target_path.clone(),
-                    });
-                }
-            }
-        }
-    }
-
-    GraphData { nodes, edges }
+    query::query_graph(cache, &query::GraphQuery::default(), &query::QueryInputs::default())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::collections::HashMap;
     use crate::state::VaultMetaCache;
 
     fn create_test_cache() -> VaultMetaCache {
-        let mut cache = VaultMetaCache {
-            entries: HashMap::new(),
-            built_at: None,
-        };
+        let mut cache = VaultMetaCache::new();
         
         // Add some test entries with links
-        cache.entries.insert(
+        cache.set_entry(
             "note1.md".to_string(), 
             (Vec::new(), vec!["note2".to_string(), "note3".to_string()])
         );
-        cache.entries.insert(
+        cache.set_entry(
             "note2.md".to_string(), 
             (Vec::new(), vec!["note1".to_string()])
         );
-        cache.entries.insert(
+        cache.set_entry(
             "note3.md".to_string(), 
             (Vec::new(), Vec::new())
         );
-        cache.entries.insert(
+        cache.set_entry(
             "orphan.md".to_string(), 
             (Vec::new(), Vec::new())
         );
@@ -101,6 +85,7 @@ mod tests {
             id: "test.md".to_string(),
             name: "Test Note".to_string(),
             group: Some("category".to_string()),
+            kind: NodeKind::Note,
         };
         
         assert_eq!(node.id, "test.md");
@@ -114,6 +99,7 @@ mod tests {
             id: "test.md".to_string(),
             name: "Test".to_string(),
             group: None,
+            kind: NodeKind::Note,
         };
         
         assert_eq!(node.id, "test.md");
@@ -138,11 +124,13 @@ mod tests {
                 id: "1".to_string(),
                 name: "Node 1".to_string(),
                 group: None,
+                kind: NodeKind::Note,
             },
             GraphNode {
                 id: "2".to_string(),
                 name: "Node 2".to_string(),
                 group: None,
+                kind: NodeKind::Note,
             },
         ];
         
@@ -209,10 +197,7 @@ mod tests {
 
     #[test]
     fn test_compute_graph_empty_cache() {
-        let empty_cache = VaultMetaCache {
-            entries: HashMap::new(),
-            built_at: None,
-        };
+        let empty_cache = VaultMetaCache::new();
         let graph = compute_graph(&empty_cache);
         
         assert!(graph.nodes.is_empty());
@@ -221,17 +206,14 @@ mod tests {
 
     #[test]
     fn test_compute_graph_link_resolution() {
-        let mut cache = VaultMetaCache {
-            entries: HashMap::new(),
-            built_at: None,
-        };
+        let mut cache = VaultMetaCache::new();
         
         // Add files with specific link patterns
-        cache.entries.insert(
+        cache.set_entry(
             "folder/deep_note.md".to_string(),
             (Vec::new(), vec!["target".to_string(), "deep_note".to_string()]) // Self-reference and target
         );
-        cache.entries.insert(
+        cache.set_entry(
             "target.md".to_string(),
             (Vec::new(), Vec::new())
         );
@@ -247,17 +229,14 @@ mod tests {
 
     #[test]
     fn test_compute_graph_with_aliases() {
-        let mut cache = VaultMetaCache {
-            entries: HashMap::new(),
-            built_at: None,
-        };
+        let mut cache = VaultMetaCache::new();
         
         // Test links with aliases (pipe syntax)
-        cache.entries.insert(
+        cache.set_entry(
             "source.md".to_string(),
             (Vec::new(), vec!["target|Display Name".to_string()])
         );
-        cache.entries.insert(
+        cache.set_entry(
             "target.md".to_string(),
             (Vec::new(), Vec::new())
         );
@@ -273,17 +252,14 @@ mod tests {
 
     #[test]
     fn test_compute_graph_bidirectional_links() {
-        let mut cache = VaultMetaCache {
-            entries: HashMap::new(),
-            built_at: None,
-        };
+        let mut cache = VaultMetaCache::new();
         
         // Create bidirectional links
-        cache.entries.insert(
+        cache.set_entry(
             "a.md".to_string(),
             (Vec::new(), vec!["b".to_string()])
         );
-        cache.entries.insert(
+        cache.set_entry(
             "b.md".to_string(),
             (Vec::new(), vec!["a".to_string()])
         );
@@ -306,6 +282,7 @@ mod tests {
             id: "test.md".to_string(),
             name: "Test".to_string(),
             group: Some("group".to_string()),
+            kind: NodeKind::Note,
         };
         
         let cloned = original.clone();
@@ -334,6 +311,7 @@ mod tests {
                     id: "test.md".to_string(),
                     name: "Test".to_string(),
                     group: None,
+                    kind: NodeKind::Note,
                 }
             ],
             edges: vec![
@@ -0,0 +1,323 @@
+// Oxidian — Note paths
+// Shortest link paths between two notes, optionally across backlinks and
+// shared tags, with the line that makes each hop.
+
+use super::adjacency::Adjacency;
+use crate::engine::vault;
+use crate::encryption;
+use crate::state::VaultMetaCache;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct PathOptions {
+    /// Also follow links backwards (from a note to the notes linking to it).
+    pub undirected: bool,
+    /// Also hop between notes that share a tag.
+    pub via_tags: bool,
+    /// Most shortest paths to return.
+    pub max_paths: usize,
+    /// Give up on paths longer than this.
+    pub max_hops: usize,
+}
+
+impl Default for PathOptions {
+    fn default() -> Self {
+        Self { undirected: false, via_tags: false, max_paths: 5, max_hops: 8 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HopKind {
+    /// `from` links to `to`
+    Link,
+    /// `to` links to `from`
+    Backlink,
+    /// Both notes carry `tag`
+    Tag,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Hop {
+    pub from: String,
+    pub to: String,
+    pub kind: HopKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    /// Note the context line is from: `from` for links and tags, `to` for backlinks
+    pub source: String,
+    /// 1-based line of the link or tag in `source`; 0 when not found
+    pub line: usize,
+    pub context: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NotePath {
+    pub hops: Vec<Hop>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Step {
+    Link,
+    Backlink,
+    Tag(String),
+}
+
+/// Neighbours of notes in the cached link adjacency, plus notes by tag.
+struct Neighbors<'a> {
+    cache: &'a VaultMetaCache,
+    tag_notes: BTreeMap<String, Vec<&'a str>>,
+}
+
+impl<'a> Neighbors<'a> {
+    fn new(cache: &'a VaultMetaCache, options: &PathOptions) -> Self {
+        let mut tag_notes: BTreeMap<String, Vec<&str>> = BTreeMap::new();
+        if options.via_tags {
+            let mut paths: Vec<&String> = cache.entries.keys().collect();
+            paths.sort();
+            for path in paths {
+                for tag in &cache.entries[path].0 {
+                    tag_notes.entry(tag.trim_start_matches('#').to_lowercase()).or_default().push(path);
+                }
+            }
+        }
+        Self { cache, tag_notes }
+    }
+
+    fn of(&self, note: &'a str, options: &PathOptions) -> Vec<(&'a str, Step)> {
+        let adjacency = &self.cache.adjacency;
+        let mut next: Vec<(&str, Step)> = adjacency.targets(note)
+            .into_iter()
+            .filter(|t| *t != note)
+            .map(|t| (t, Step::Link))
+            .collect();
+        if options.undirected {
+            next.extend(adjacency.sources(note).filter(|s| *s != note).map(|s| (s, Step::Backlink)));
+        }
+        if options.via_tags {
+            if let Some((tags, _)) = self.cache.entries.get(note) {
+                for tag in tags {
+                    let tag = tag.trim_start_matches('#').to_lowercase();
+                    for other in self.tag_notes.get(&tag).into_iter().flatten() {
+                        if *other != note {
+                            next.push((*other, Step::Tag(tag.clone())));
+                        }
+                    }
+                }
+            }
+        }
+        next
+    }
+}
+
+/// Up to `options.max_paths` shortest paths from `from` to `to`. Links are
+/// preferred over backlinks and tags when several hops tie.
+pub fn find_paths(vault_path: &str, cache: &VaultMetaCache, from: &str, to: &str, options: &PathOptions, password: Option<&str>) -> Result<Vec<NotePath>, String> {
+    for note in [from, to] {
+        if !cache.entries.contains_key(note) {
+            return Err(format!("Note not found: {}", note));
+        }
+    }
+    if from == to {
+        return Ok(vec![NotePath { hops: Vec::new() }]);
+    }
+
+    let neighbors = Neighbors::new(cache, options);
+    let mut dist: HashMap<&str, usize> = HashMap::from([(from, 0)]);
+    let mut preds: HashMap<&str, Vec<(&str, Step)>> = HashMap::new();
+    let mut frontier = vec![from];
+    let mut depth = 0;
+    while !frontier.is_empty() && !dist.contains_key(to) && depth < options.max_hops {
+        let mut next = Vec::new();
+        for &note in &frontier {
+            for (neighbor, step) in neighbors.of(note, options) {
+                match dist.get(neighbor) {
+                    None => {
+                        dist.insert(neighbor, depth + 1);
+                        preds.entry(neighbor).or_default().push((note, step));
+                        next.push(neighbor);
+                    }
+                    Some(&d) if d == depth + 1 => {
+                        let entry = preds.entry(neighbor).or_default();
+                        if !entry.iter().any(|(p, s)| *p == note && *s == step) {
+                            entry.push((note, step));
+                        }
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+        frontier = next;
+        depth += 1;
+    }
+    if !dist.contains_key(to) {
+        return Ok(Vec::new());
+    }
+
+    // Walk the predecessor lists back from the target
+    let mut routes: Vec<Vec<RawHop>> = Vec::new();
+    let mut stack: Vec<(&str, Vec<RawHop>)> = vec![(to, Vec::new())];
+    while let Some((note, suffix)) = stack.pop() {
+        if routes.len() >= options.max_paths.max(1) {
+            break;
+        }
+        if note == from {
+            routes.push(suffix.into_iter().rev().collect());
+            continue;
+        }
+        let Some(list) = preds.get(note) else { continue };
+        let mut ordered: Vec<&(&str, Step)> = list.iter().collect();
+        ordered.sort_by_key(|(p, s)| (step_rank(s), *p));
+        for (pred, step) in ordered.into_iter().rev() {
+            let mut hops = suffix.clone();
+            hops.push((*pred, note, step));
+            stack.push((pred, hops));
+        }
+    }
+
+    let mut context = ContextReader { vault_path, password, lines: HashMap::new() };
+    Ok(routes.into_iter()
+        .map(|route| NotePath {
+            hops: route.into_iter().map(|(a, b, step)| context.hop(&cache.adjacency, a, b, step)).collect(),
+        })
+        .collect())
+}
+
+/// `(from, to, step)` of a hop before its context is read.
+type RawHop<'a> = (&'a str, &'a str, &'a Step);
+
+fn step_rank(step: &Step) -> u8 {
+    match step {
+        Step::Link => 0,
+        Step::Backlink => 1,
+        Step::Tag(_) => 2,
+    }
+}
+
+// ─── Context ───────────────────────────────────────────────────────
+
+struct ContextReader<'a> {
+    vault_path: &'a str,
+    password: Option<&'a str>,
+    lines: HashMap<String, Vec<String>>,
+}
+
+impl ContextReader<'_> {
+    fn lines(&mut self, note: &str) -> &[String] {
+        let (vault_path, password) = (self.vault_path, self.password);
+        self.lines.entry(note.to_string()).or_insert_with(|| {
+            fs::read_to_string(Path::new(vault_path).join(note))
+                .ok()
+                .and_then(|c| encryption::plaintext_content(c, password))
+                .map(|c| c.lines().map(str::to_string).collect())
+                .unwrap_or_default()
+        })
+    }
+
+    /// First line of `source` matching `is_match`, trimmed.
+    fn find(&mut self, source: &str, is_match: impl Fn(&str) -> bool) -> (usize, String) {
+        self.lines(source)
+            .iter()
+            .enumerate()
+            .find(|(_, line)| is_match(line))
+            .map(|(i, line)| (i + 1, line.trim().to_string()))
+            .unwrap_or((0, String::new()))
+    }
+
+    fn hop(&mut self, adjacency: &Adjacency, from: &str, to: &str, step: &Step) -> Hop {
+        let links_to = |line: &str, target: &str| {
+            vault::extract_wiki_links(line).iter().any(|l| adjacency.resolve(l) == Some(target))
+        };
+        let (kind, tag, source) = match step {
+            Step::Link => (HopKind::Link, None, from),
+            Step::Backlink => (HopKind::Backlink, None, to),
+            Step::Tag(tag) => (HopKind::Tag, Some(tag.clone()), from),
+        };
+        let (line, context) = match step {
+            Step::Link => self.find(from, |l| links_to(l, to)),
+            Step::Backlink => self.find(to, |l| links_to(l, from)),
+            Step::Tag(tag) => {
+                let needle = format!("#{}", tag);
+                self.find(from, |l| l.to_lowercase().contains(&needle))
+            }
+        };
+        Hop { from: from.to_string(), to: to.to_string(), kind, tag, source: source.to_string(), line, context }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn vault(notes: &[(&str, &str)]) -> (TempDir, VaultMetaCache) {
+        let dir = TempDir::new().unwrap();
+        for (path, content) in notes {
+            let full = dir.path().join(path);
+            fs::create_dir_all(full.parent().unwrap()).unwrap();
+            fs::write(full, content).unwrap();
+        }
+        let mut cache = VaultMetaCache::new();
+        cache.rebuild(dir.path().to_str().unwrap());
+        (dir, cache)
+    }
+
+    fn route(path: &NotePath) -> Vec<&str> {
+        let mut notes = vec![path.hops[0].from.as_str()];
+        notes.extend(path.hops.iter().map(|h| h.to.as_str()));
+        notes
+    }
+
+    #[test]
+    fn test_shortest_paths_with_context() {
+        let (dir, cache) = vault(&[
+            ("a.md", "# A\nSee [[b]] for details.\nAlso [[c|the C note]]."),
+            ("b.md", "Leads to [[d#Intro]]."),
+            ("c.md", "Then [[folder/d]]."),
+            ("folder/d.md", "End."),
+            ("e.md", "Points at [[a]]."),
+        ]);
+        let vault_path = dir.path().to_str().unwrap();
+        let options = PathOptions::default();
+
+        let paths = find_paths(vault_path, &cache, "a.md", "folder/d.md", &options, None).unwrap();
+        assert_eq!(paths.len(), 2);
+        assert_eq!(route(&paths[0]), vec!["a.md", "b.md", "folder/d.md"]);
+        assert_eq!(route(&paths[1]), vec!["a.md", "c.md", "folder/d.md"]);
+        let hop = &paths[0].hops[0];
+        assert_eq!((hop.kind, hop.line, hop.context.as_str()), (HopKind::Link, 2, "See [[b]] for details."));
+
+        let limited = PathOptions { max_paths: 1, ..PathOptions::default() };
+        assert_eq!(find_paths(vault_path, &cache, "a.md", "folder/d.md", &limited, None).unwrap().len(), 1);
+
+        // Only reachable against the link direction
+        assert!(find_paths(vault_path, &cache, "a.md", "e.md", &options, None).unwrap().is_empty());
+        let undirected = PathOptions { undirected: true, ..PathOptions::default() };
+        let paths = find_paths(vault_path, &cache, "a.md", "e.md", &undirected, None).unwrap();
+        let hop = &paths[0].hops[0];
+        assert_eq!((hop.kind, hop.source.as_str(), hop.context.as_str()), (HopKind::Backlink, "e.md", "Points at [[a]]."));
+
+        assert!(find_paths(vault_path, &cache, "a.md", "missing.md", &options, None).is_err());
+    }
+
+    #[test]
+    fn test_tag_hops() {
+        let (dir, cache) = vault(&[
+            ("x.md", "Reading list #Books\n"),
+            ("y.md", "Notes on a novel\n#books"),
+        ]);
+        let vault_path = dir.path().to_str().unwrap();
+        assert!(find_paths(vault_path, &cache, "x.md", "y.md", &PathOptions::default(), None).unwrap().is_empty());
+
+        let options = PathOptions { via_tags: true, ..PathOptions::default() };
+        let paths = find_paths(vault_path, &cache, "x.md", "y.md", &options, None).unwrap();
+        let hop = &paths[0].hops[0];
+        assert_eq!(hop.kind, HopKind::Tag);
+        assert_eq!(hop.tag.as_deref(), Some("books"));
+        assert_eq!((hop.line, hop.context.as_str()), (1, "Reading list #Books"));
+    }
+}
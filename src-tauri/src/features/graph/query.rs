@@ -0,0 +1,445 @@
+// Oxidian — Graph queries
+// Filters the vault graph by folder glob, tag or search hits, and adds ghost
+// nodes for unresolved links, tag and attachment nodes, and property colours.
+
+use super::{GraphData, GraphEdge, GraphNode, NodeKind};
+use crate::features::properties::PropertyIndex;
+use crate::state::VaultMetaCache;
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::Path;
+use walkdir::WalkDir;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct GraphQuery {
+    /// Folder globs (`projects/**`, `*/drafts`); a note must match one.
+    /// A plain folder name matches everything inside it.
+    pub include_folders: Vec<String>,
+    pub exclude_folders: Vec<String>,
+    /// Tags with or without `#`; also match nested tags.
+    pub include_tags: Vec<String>,
+    pub exclude_tags: Vec<String>,
+    /// Search query (field filters allowed); only matching notes are kept.
+    pub search: Option<String>,
+    pub exclude_search: Option<String>,
+    /// Unresolved link targets as ghost nodes.
+    pub show_ghosts: bool,
+    /// Tags as nodes, linked from every note carrying them.
+    pub show_tags: bool,
+    /// Linked or embedded non-markdown files as nodes.
+    pub show_attachments: bool,
+    /// Frontmatter property whose value becomes the node group.
+    pub color_by: Option<String>,
+}
+
+impl GraphQuery {
+    /// Whether the query needs the list of non-markdown files in the vault.
+    pub fn needs_files(&self) -> bool {
+        self.show_ghosts || self.show_attachments
+    }
+
+    pub fn search_query(&self) -> Option<&str> {
+        self.search.as_deref().map(str::trim).filter(|q| !q.is_empty())
+    }
+
+    pub fn exclude_search_query(&self) -> Option<&str> {
+        self.exclude_search.as_deref().map(str::trim).filter(|q| !q.is_empty())
+    }
+}
+
+/// Data the query needs from outside the meta cache, gathered by the caller.
+#[derive(Debug, Default)]
+pub struct QueryInputs {
+    /// Notes matching `search`; `None` when the query has no search.
+    pub search_hits: Option<HashSet<String>>,
+    /// Notes matching `exclude_search`.
+    pub excluded_hits: Option<HashSet<String>>,
+    /// Non-markdown files in the vault, relative paths.
+    pub files: Vec<String>,
+    /// Note path → group from the `color_by` property.
+    pub groups: HashMap<String, String>,
+}
+
+// ─── Matching ──────────────────────────────────────────────────────
+
+/// A folder glob: `*` and `?` stay within one path segment, `**` spans
+/// several. Matching a folder matches everything below it.
+enum Glob {
+    Folder(String),
+    Pattern(Regex),
+}
+
+impl Glob {
+    fn new(pattern: &str) -> Option<Glob> {
+        let pattern = pattern.trim().trim_start_matches("./").trim_matches('/');
+        if pattern.is_empty() {
+            return None;
+        }
+        if !pattern.contains(['*', '?']) {
+            return Some(Glob::Folder(pattern.to_lowercase()));
+        }
+        let mut re = String::from("(?i)^");
+        let mut chars = pattern.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '*' if chars.peek() == Some(&'*') => {
+                    chars.next();
+                    if chars.peek() == Some(&'/') {
+                        chars.next();
+                        re.push_str("(?:.*/)?");
+                    } else {
+                        re.push_str(".*");
+                    }
+                }
+                '*' => re.push_str("[^/]*"),
+                '?' => re.push_str("[^/]"),
+                c => re.push_str(&regex::escape(&c.to_string())),
+            }
+        }
+        re.push_str("(?:/.*)?$");
+        Regex::new(&re).ok().map(Glob::Pattern)
+    }
+
+    fn matches(&self, path: &str) -> bool {
+        match self {
+            Glob::Folder(folder) => {
+                let path = path.to_lowercase();
+                path == *folder || path.starts_with(&format!("{}/", folder))
+            }
+            Glob::Pattern(re) => re.is_match(path),
+        }
+    }
+}
+
+fn compile_globs(patterns: &[String]) -> Vec<Glob> {
+    patterns.iter().filter_map(|p| Glob::new(p)).collect()
+}
+
+fn normalize_tag(tag: &str) -> String {
+    tag.trim().trim_start_matches('#').to_lowercase()
+}
+
+/// `project` matches `project` and `project/x`.
+fn tag_matches(tag: &str, wanted: &str) -> bool {
+    let tag = normalize_tag(tag);
+    tag == wanted || tag.starts_with(&format!("{}/", wanted))
+}
+
+struct NoteFilter {
+    include_folders: Vec<Glob>,
+    exclude_folders: Vec<Glob>,
+    include_tags: Vec<String>,
+    exclude_tags: Vec<String>,
+}
+
+impl NoteFilter {
+    fn new(query: &GraphQuery) -> Self {
+        let tags = |tags: &[String]| tags.iter().map(|t| normalize_tag(t)).filter(|t| !t.is_empty()).collect();
+        Self {
+            include_folders: compile_globs(&query.include_folders),
+            exclude_folders: compile_globs(&query.exclude_folders),
+            include_tags: tags(&query.include_tags),
+            exclude_tags: tags(&query.exclude_tags),
+        }
+    }
+
+    fn admits(&self, path: &str, tags: &[String], inputs: &QueryInputs) -> bool {
+        if !self.include_folders.is_empty() && !self.include_folders.iter().any(|g| g.matches(path)) {
+            return false;
+        }
+        if self.exclude_folders.iter().any(|g| g.matches(path)) {
+            return false;
+        }
+        let has = |wanted: &String| tags.iter().any(|t| tag_matches(t, wanted));
+        if !self.include_tags.is_empty() && !self.include_tags.iter().any(has) {
+            return false;
+        }
+        if self.exclude_tags.iter().any(has) {
+            return false;
+        }
+        if inputs.search_hits.as_ref().is_some_and(|hits| !hits.contains(path)) {
+            return false;
+        }
+        !inputs.excluded_hits.as_ref().is_some_and(|hits| hits.contains(path))
+    }
+}
+
+// ─── Link resolution ───────────────────────────────────────────────
+
+/// The best of several same-named files for `target` (lowercase, no `.md`):
+/// one whose path ends with the target, then the shallowest, then by path.
+pub(super) fn best_candidate<'a>(candidates: impl Iterator<Item = &'a str>, target: &str) -> Option<&'a str> {
+    candidates.min_by_key(|p| {
+        let key = p.to_lowercase();
+        (!key.trim_end_matches(".md").ends_with(target), p.matches('/').count(), *p)
+    })
+}
+
+/// Resolves link targets to attachment files, case-insensitively: by full
+/// path first, then by file name.
+struct Lookup<'a> {
+    by_path: HashMap<String, &'a str>,
+    by_name: HashMap<String, Vec<&'a str>>,
+}
+
+impl<'a> Lookup<'a> {
+    fn new(paths: impl Iterator<Item = &'a str>) -> Self {
+        let mut by_path = HashMap::new();
+        let mut by_name: HashMap<String, Vec<&str>> = HashMap::new();
+        for path in paths {
+            let key = path.to_lowercase();
+            let name = key.rsplit('/').next().unwrap_or(&key).to_string();
+            by_path.insert(key, path);
+            by_name.entry(name).or_default().push(path);
+        }
+        Self { by_path, by_name }
+    }
+
+    fn resolve(&self, target: &str) -> Option<&'a str> {
+        if let Some(path) = self.by_path.get(target) {
+            return Some(path);
+        }
+        let name = target.rsplit('/').next().unwrap_or(target);
+        best_candidate(self.by_name.get(name)?.iter().copied(), target)
+    }
+}
+
+/// The note or file part of a wiki link: no alias, heading or block ref.
+pub(super) fn link_target(link: &str) -> &str {
+    let target = link.split('|').next().unwrap_or(link);
+    target.split('#').next().unwrap_or(target).trim()
+}
+
+fn has_extension(target: &str) -> bool {
+    let name = target.rsplit('/').next().unwrap_or(target);
+    name.rsplit_once('.').is_some_and(|(stem, ext)| !stem.is_empty() && !ext.is_empty() && !ext.contains(' '))
+}
+
+fn stem(path: &str) -> String {
+    Path::new(path).file_stem().unwrap_or_default().to_string_lossy().to_string()
+}
+
+fn file_name(path: &str) -> String {
+    Path::new(path).file_name().unwrap_or_default().to_string_lossy().to_string()
+}
+
+// ─── Query ─────────────────────────────────────────────────────────
+
+/// The graph of the notes admitted by `query`. Links to filtered-out notes
+/// are dropped; links to missing notes become ghosts when asked for.
+pub fn query_graph(cache: &VaultMetaCache, query: &GraphQuery, inputs: &QueryInputs) -> GraphData {
+    let filter = NoteFilter::new(query);
+    let mut paths: Vec<&str> = cache.entries.keys().map(String::as_str).collect();
+    paths.sort_unstable();
+    let files = Lookup::new(inputs.files.iter().map(String::as_str));
+
+    let kept: BTreeSet<&str> = paths.iter()
+        .copied()
+        .filter(|p| filter.admits(p, &cache.entries[*p].0, inputs))
+        .collect();
+
+    let mut nodes: Vec<GraphNode> = kept.iter()
+        .map(|p| GraphNode {
+            id: p.to_string(),
+            name: stem(p),
+            group: inputs.groups.get(*p).cloned(),
+            kind: NodeKind::Note,
+        })
+        .collect();
+    let mut extra: BTreeMap<String, GraphNode> = BTreeMap::new();
+    let mut edges = Vec::new();
+    let mut seen: HashSet<(String, String)> = HashSet::new();
+    let mut add_edge = |source: &str, target: &str, edges: &mut Vec<GraphEdge>| {
+        if seen.insert((source.to_string(), target.to_string())) {
+            edges.push(GraphEdge { source: source.to_string(), target: target.to_string() });
+        }
+    };
+
+    for &path in &kept {
+        for (link, note) in cache.adjacency.links(path) {
+            if let Some(note) = note {
+                if kept.contains(note.as_str()) {
+                    add_edge(path, note, &mut edges);
+                }
+                continue;
+            }
+            let target = link_target(link);
+            if target.is_empty() {
+                continue;
+            }
+            let key = target.to_lowercase();
+            if has_extension(&key) && !key.ends_with(".md") {
+                if let Some(file) = files.resolve(&key) {
+                    if query.show_attachments {
+                        extra.entry(file.to_string()).or_insert_with(|| GraphNode {
+                            id: file.to_string(),
+                            name: file_name(file),
+                            group: None,
+                            kind: NodeKind::Attachment,
+                        });
+                        add_edge(path, file, &mut edges);
+                    }
+                    continue;
+                }
+            }
+            if query.show_ghosts {
+                let id = format!("ghost:{}", target);
+                extra.entry(id.clone()).or_insert_with(|| GraphNode {
+                    id: id.clone(),
+                    name: target.to_string(),
+                    group: None,
+                    kind: NodeKind::Ghost,
+                });
+                add_edge(path, &id, &mut edges);
+            }
+        }
+        if query.show_tags {
+            for tag in &cache.entries[path].0 {
+                let tag = tag.trim_start_matches('#');
+                let id = format!("tag:{}", tag.to_lowercase());
+                extra.entry(id.clone()).or_insert_with(|| GraphNode {
+                    id: id.clone(),
+                    name: format!("#{}", tag),
+                    group: None,
+                    kind: NodeKind::Tag,
+                });
+                add_edge(path, &id, &mut edges);
+            }
+        }
+    }
+
+    nodes.extend(extra.into_values());
+    GraphData { nodes, edges }
+}
+
+/// Non-markdown files in the vault, skipping hidden folders.
+pub fn vault_files(vault_path: &str) -> Vec<String> {
+    let root = Path::new(vault_path);
+    let mut files: Vec<String> = WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter(|e| e.path().extension().is_none_or(|ext| ext != "md"))
+        .filter_map(|e| e.path().strip_prefix(root).ok().map(|p| p.to_string_lossy().replace('\\', "/")))
+        .collect();
+    files.sort();
+    files
+}
+
+fn group_label(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(s) => Some(s.trim().to_string()).filter(|s| !s.is_empty()),
+        Value::Array(items) => items.iter().find_map(group_label),
+        Value::Bool(_) | Value::Number(_) => Some(value.to_string()),
+        Value::Object(_) => None,
+    }
+}
+
+/// Group per note from a frontmatter property; the first item of a list.
+pub fn property_groups<'a>(props: &PropertyIndex, paths: impl Iterator<Item = &'a String>, key: &str) -> HashMap<String, String> {
+    paths
+        .filter_map(|path| {
+            let value = props.properties_for_file(path)
+                .into_iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(key))
+                .and_then(|(_, v)| group_label(&v))?;
+            Some((path.clone(), value))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache(entries: &[(&str, &[&str], &[&str])]) -> VaultMetaCache {
+        let mut cache = VaultMetaCache::new();
+        for (p, tags, links) in entries {
+            cache.set_entry(p.to_string(), (tags.iter().map(|t| t.to_string()).collect(), links.iter().map(|l| l.to_string()).collect()));
+        }
+        cache
+    }
+
+    fn ids(graph: &GraphData) -> Vec<&str> {
+        graph.nodes.iter().map(|n| n.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_folder_tag_and_search_filters() {
+        let cache = cache(&[
+            ("projects/alpha.md", &["project/active"], &["projects/beta", "Inbox#Today"]),
+            ("projects/beta.md", &["project"], &[]),
+            ("projects/drafts/gamma.md", &[], &["alpha"]),
+            ("inbox.md", &["todo"], &[]),
+        ]);
+        let all = query_graph(&cache, &GraphQuery::default(), &QueryInputs::default());
+        assert_eq!(all.nodes.len(), 4);
+        assert!(all.edges.iter().any(|e| e.source == "projects/alpha.md" && e.target == "inbox.md"));
+
+        let query = GraphQuery {
+            include_folders: vec!["projects".into()],
+            exclude_folders: vec!["**/drafts/*".into()],
+            ..Default::default()
+        };
+        let graph = query_graph(&cache, &query, &QueryInputs::default());
+        assert_eq!(ids(&graph), vec!["projects/alpha.md", "projects/beta.md"]);
+        assert_eq!(graph.edges.len(), 1);
+
+        let query = GraphQuery { include_tags: vec!["#Project".into()], exclude_tags: vec!["project/active".into()], ..Default::default() };
+        assert_eq!(ids(&query_graph(&cache, &query, &QueryInputs::default())), vec!["projects/beta.md"]);
+
+        let inputs = QueryInputs {
+            search_hits: Some(["inbox.md".to_string(), "projects/alpha.md".to_string()].into()),
+            excluded_hits: Some(["projects/alpha.md".to_string()].into()),
+            ..Default::default()
+        };
+        assert_eq!(ids(&query_graph(&cache, &GraphQuery::default(), &inputs)), vec!["inbox.md"]);
+    }
+
+    #[test]
+    fn test_ghost_tag_and_attachment_nodes() {
+        let cache = cache(&[
+            ("a.md", &["idea"], &["Missing note", "diagram.png", "gone.pdf", "b|Bee"]),
+            ("b.md", &["Idea"], &["Missing note"]),
+        ]);
+        let inputs = QueryInputs { files: vec!["assets/diagram.png".into()], ..Default::default() };
+
+        let plain = query_graph(&cache, &GraphQuery::default(), &inputs);
+        assert_eq!(plain.nodes.len(), 2);
+        assert_eq!(plain.edges.len(), 1);
+
+        let query = GraphQuery { show_ghosts: true, show_tags: true, show_attachments: true, ..Default::default() };
+        let graph = query_graph(&cache, &query, &inputs);
+        let kind = |id: &str| graph.nodes.iter().find(|n| n.id == id).map(|n| n.kind);
+        assert_eq!(kind("assets/diagram.png"), Some(NodeKind::Attachment));
+        assert_eq!(kind("ghost:Missing note"), Some(NodeKind::Ghost));
+        assert_eq!(kind("ghost:gone.pdf"), Some(NodeKind::Ghost));
+        assert_eq!(kind("tag:idea"), Some(NodeKind::Tag));
+        assert_eq!(graph.nodes.len(), 6);
+        assert_eq!(graph.edges.iter().filter(|e| e.target == "ghost:Missing note").count(), 2);
+        assert_eq!(graph.edges.iter().filter(|e| e.target == "tag:idea").count(), 2);
+
+        // A known attachment is never shown as a ghost
+        let query = GraphQuery { show_ghosts: true, ..Default::default() };
+        let graph = query_graph(&cache, &query, &inputs);
+        assert!(!graph.nodes.iter().any(|n| n.name == "diagram.png"));
+    }
+
+    #[test]
+    fn test_glob_patterns() {
+        let glob = |p: &str| Glob::new(p).unwrap();
+        assert!(glob("Projects").matches("projects/a/b.md"));
+        assert!(!glob("proj").matches("projects/a.md"));
+        assert!(glob("*/drafts").matches("work/drafts/x.md"));
+        assert!(!glob("*/drafts").matches("a/b/drafts/x.md"));
+        assert!(glob("**/drafts").matches("a/b/drafts/x.md"));
+        assert!(glob("**/*.md").matches("top.md"));
+        assert!(glob("daily/202?-*").matches("daily/2024-01-01.md"));
+        assert!(Glob::new("  / ").is_none());
+    }
+}
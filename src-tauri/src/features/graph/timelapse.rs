@@ -0,0 +1,254 @@
+// Oxidian — Graph time-lapse
+// When each note came to be, the graph as of a date, and day-by-day growth
+// frames for animating how the vault grew.
+
+use super::{GraphData, GraphEdge, NodeKind};
+use crate::features::file_recovery;
+use crate::features::properties::{parse_date, parse_datetime, PropertyIndex};
+use chrono::{DateTime, Local, NaiveDate};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+/// Frontmatter property read for creation dates unless another is given.
+pub const DEFAULT_CREATED_PROPERTY: &str = "created";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DateSource {
+    /// The `created` frontmatter property
+    Frontmatter,
+    /// The file's birth time or its oldest snapshot, whichever is earlier
+    History,
+    /// Last modification time, when nothing better is known
+    Modified,
+}
+
+/// Dates go to the frontend as `YYYY-MM-DD`, like task dates.
+fn serialize_day<S: serde::Serializer>(date: &NaiveDate, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(&date.format("%Y-%m-%d"))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NoteDate {
+    #[serde(serialize_with = "serialize_day")]
+    pub date: NaiveDate,
+    pub source: DateSource,
+}
+
+fn value_date(value: &Value) -> Option<NaiveDate> {
+    match value {
+        Value::String(s) => parse_date(s).or_else(|| parse_datetime(s).map(|dt| dt.date())),
+        Value::Array(items) => items.iter().find_map(value_date),
+        _ => None,
+    }
+}
+
+fn local_date(time: std::time::SystemTime) -> NaiveDate {
+    DateTime::<Local>::from(time).date_naive()
+}
+
+/// Day of the oldest snapshot (`20240131_101500.123`) of a note.
+fn oldest_snapshot(vault_path: &str, relative_path: &str) -> Option<NaiveDate> {
+    file_recovery::list_snapshots(vault_path, relative_path)
+        .ok()?
+        .iter()
+        .filter_map(|s| NaiveDate::parse_from_str(s.timestamp.get(..8)?, "%Y%m%d").ok())
+        .min()
+}
+
+/// When a note was created: the frontmatter property if it holds a date,
+/// else the earlier of birth time and oldest snapshot, else its mtime.
+pub fn creation_date(vault_path: &str, relative_path: &str, props: &PropertyIndex, property: &str) -> Option<NoteDate> {
+    let frontmatter = props.properties_for_file(relative_path)
+        .into_iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(property))
+        .and_then(|(_, v)| value_date(&v));
+    if let Some(date) = frontmatter {
+        return Some(NoteDate { date, source: DateSource::Frontmatter });
+    }
+    let meta = fs::metadata(Path::new(vault_path).join(relative_path)).ok();
+    let birth = meta.as_ref().and_then(|m| m.created().ok()).map(local_date);
+    let history = [birth, oldest_snapshot(vault_path, relative_path)].into_iter().flatten().min();
+    if let Some(date) = history {
+        return Some(NoteDate { date, source: DateSource::History });
+    }
+    let modified = meta.and_then(|m| m.modified().ok()).map(local_date)?;
+    Some(NoteDate { date: modified, source: DateSource::Modified })
+}
+
+/// Creation dates for every note node of `graph`.
+pub fn creation_dates(vault_path: &str, graph: &GraphData, props: &PropertyIndex, property: &str) -> HashMap<String, NoteDate> {
+    graph.nodes.iter()
+        .filter(|n| n.kind == NodeKind::Note)
+        .filter_map(|n| Some((n.id.clone(), creation_date(vault_path, &n.id, props, property)?)))
+        .collect()
+}
+
+/// The day each node and edge of `graph` appears. Notes take their creation
+/// date; other nodes and edges appear with the first note that brings them.
+/// Undated notes are left out.
+fn appearance<'a>(graph: &'a GraphData, dates: &HashMap<String, NoteDate>) -> (HashMap<String, NaiveDate>, Vec<(NaiveDate, &'a GraphEdge)>) {
+    let mut nodes: HashMap<String, NaiveDate> = graph.nodes.iter()
+        .filter(|n| n.kind == NodeKind::Note)
+        .filter_map(|n| Some((n.id.clone(), dates.get(&n.id)?.date)))
+        .collect();
+    let is_note: HashSet<&str> = graph.nodes.iter().filter(|n| n.kind == NodeKind::Note).map(|n| n.id.as_str()).collect();
+    for edge in &graph.edges {
+        let note_end = [&edge.source, &edge.target].into_iter()
+            .filter(|id| is_note.contains(id.as_str()))
+            .filter_map(|id| nodes.get(id).copied())
+            .min();
+        if let Some(date) = note_end {
+            for id in [&edge.source, &edge.target] {
+                if !is_note.contains(id.as_str()) {
+                    let entry = nodes.entry(id.clone()).or_insert(date);
+                    *entry = (*entry).min(date);
+                }
+            }
+        }
+    }
+    let edges = graph.edges.iter()
+        .filter_map(|e| Some((nodes.get(&e.source)?.max(nodes.get(&e.target)?).to_owned(), e)))
+        .collect();
+    (nodes, edges)
+}
+
+/// `graph` restricted to what existed before `before`.
+pub fn graph_as_of(graph: &GraphData, dates: &HashMap<String, NoteDate>, before: NaiveDate) -> GraphData {
+    let (nodes, edges) = appearance(graph, dates);
+    let exists = |id: &str| nodes.get(id).is_some_and(|d| *d < before);
+    GraphData {
+        nodes: graph.nodes.iter().filter(|n| exists(&n.id)).cloned().collect(),
+        edges: edges.into_iter().filter(|(d, _)| *d < before).map(|(_, e)| e.clone()).collect(),
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GrowthFrame {
+    #[serde(serialize_with = "serialize_day")]
+    pub date: NaiveDate,
+    pub nodes_added: Vec<String>,
+    pub edges_added: Vec<GraphEdge>,
+    /// Totals after this frame
+    pub node_count: usize,
+    pub edge_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Timelapse {
+    /// One frame per day on which something appeared, oldest first
+    pub frames: Vec<GrowthFrame>,
+    pub dates: BTreeMap<String, NoteDate>,
+    /// Notes without any date; they never appear in a frame
+    pub undated: Vec<String>,
+}
+
+/// Day-by-day additions of nodes and edges, for animating growth.
+pub fn growth_frames(graph: &GraphData, dates: &HashMap<String, NoteDate>) -> Timelapse {
+    let (nodes, edges) = appearance(graph, dates);
+    let mut days: BTreeMap<NaiveDate, (Vec<String>, Vec<GraphEdge>)> = BTreeMap::new();
+    for node in &graph.nodes {
+        if let Some(date) = nodes.get(&node.id) {
+            days.entry(*date).or_default().0.push(node.id.clone());
+        }
+    }
+    for (date, edge) in edges {
+        days.entry(date).or_default().1.push(edge.clone());
+    }
+
+    let (mut node_count, mut edge_count) = (0, 0);
+    let frames = days.into_iter()
+        .map(|(date, (mut nodes_added, edges_added))| {
+            nodes_added.sort();
+            node_count += nodes_added.len();
+            edge_count += edges_added.len();
+            GrowthFrame { date, nodes_added, edges_added, node_count, edge_count }
+        })
+        .collect();
+    let mut undated: Vec<String> = graph.nodes.iter()
+        .filter(|n| n.kind == NodeKind::Note && !dates.contains_key(&n.id))
+        .map(|n| n.id.clone())
+        .collect();
+    undated.sort();
+    Timelapse {
+        frames,
+        dates: dates.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+        undated,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::graph::query::{query_graph, GraphQuery, QueryInputs};
+    use crate::state::VaultMetaCache;
+    use tempfile::TempDir;
+
+    fn day(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_creation_dates_and_graph_as_of() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        let vp = root.to_str().unwrap();
+        fs::write(root.join("seed.md"), "---\ncreated: 2024-01-05\n---\nFirst #idea").unwrap();
+        fs::write(root.join("branch.md"), "---\ncreated: 2024-02-10T09:30\n---\nFrom [[seed]] to [[Unwritten]]").unwrap();
+        fs::write(root.join("leaf.md"), "Back to [[branch]]").unwrap();
+        let snapshots = root.join(".oxidian/snapshots/leaf.md");
+        fs::create_dir_all(&snapshots).unwrap();
+        fs::write(snapshots.join("20240301_120000.000.md"), "old").unwrap();
+        fs::write(snapshots.join("20240315_120000.000.md"), "newer").unwrap();
+
+        let mut props = PropertyIndex::new();
+        props.build_from_vault(vp, None);
+        let mut cache = VaultMetaCache::new();
+        cache.rebuild(vp);
+        let query = GraphQuery { show_ghosts: true, show_tags: true, ..Default::default() };
+        let graph = query_graph(&cache, &query, &QueryInputs::default());
+        let dates = creation_dates(vp, &graph, &props, DEFAULT_CREATED_PROPERTY);
+
+        assert_eq!(dates["seed.md"].date, day("2024-01-05"));
+        assert_eq!(dates["seed.md"].source, DateSource::Frontmatter);
+        assert_eq!(dates["branch.md"].date, day("2024-02-10"));
+        assert_eq!((dates["leaf.md"].date, dates["leaf.md"].source), (day("2024-03-01"), DateSource::History));
+
+        let early = graph_as_of(&graph, &dates, day("2024-02-10"));
+        let ids: Vec<&str> = early.nodes.iter().map(|n| n.id.as_str()).collect();
+        assert_eq!(ids, vec!["seed.md", "tag:idea"]);
+        assert_eq!(early.edges.len(), 1);
+
+        let later = graph_as_of(&graph, &dates, day("2024-03-02"));
+        assert_eq!(later.nodes.len(), 5);
+        assert_eq!(later.edges.len(), graph.edges.len());
+    }
+
+    #[test]
+    fn test_growth_frames() {
+        let mut cache = VaultMetaCache::new();
+        cache.set_entry("a.md".into(), (vec![], vec!["b".into()]));
+        cache.set_entry("b.md".into(), (vec![], vec!["a".into()]));
+        cache.set_entry("c.md".into(), (vec![], vec!["a".into()]));
+        cache.set_entry("d.md".into(), (vec![], vec![]));
+        let graph = query_graph(&cache, &GraphQuery::default(), &QueryInputs::default());
+        let dated = |d: &str| NoteDate { date: day(d), source: DateSource::Frontmatter };
+        let dates: HashMap<String, NoteDate> = [
+            ("a.md", dated("2024-01-01")),
+            ("b.md", dated("2024-01-03")),
+            ("c.md", dated("2024-01-03")),
+        ].into_iter().map(|(k, v)| (k.to_string(), v)).collect();
+
+        let timelapse = growth_frames(&graph, &dates);
+        assert_eq!(timelapse.frames.len(), 2);
+        assert_eq!(timelapse.frames[0].nodes_added, vec!["a.md"]);
+        assert!(timelapse.frames[0].edges_added.is_empty());
+        let second = &timelapse.frames[1];
+        assert_eq!(second.nodes_added, vec!["b.md", "c.md"]);
+        assert_eq!((second.edges_added.len(), second.node_count, second.edge_count), (3, 3, 3));
+        assert_eq!(timelapse.undated, vec!["d.md"]);
+    }
+}
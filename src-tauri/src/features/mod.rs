@@ -1,9 +1,15 @@
 pub mod bookmarks;
+pub mod bulk_properties;
 pub mod file_recovery;
 pub mod canvas;
 pub mod daily_notes;
 pub mod graph;
 pub mod nav_history;
+pub mod properties;
+pub mod query;
+pub mod related;
 pub mod remember;
 pub mod tags;
+pub mod tasks;
 pub mod templates;
+pub mod transclusion;
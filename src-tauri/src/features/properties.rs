@@ -0,0 +1,638 @@
+// Oxidian — Properties Feature
+// Typed frontmatter properties: a per-vault type registry (.oxidian/types.json),
+// value validation, and a vault-wide index of property keys and values.
+
+use crate::encryption;
+use crate::engine::frontmatter::{self, Frontmatter};
+use crate::engine::search::{split_field_filters, SearchIndex, SearchResult};
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+// ─── PropertyType ──────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PropertyType {
+    Text,
+    Number,
+    Date,
+    Datetime,
+    List,
+    Checkbox,
+    Link,
+}
+
+impl PropertyType {
+    /// Guess the type of a value that has no registered type.
+    pub fn infer(value: &Value) -> PropertyType {
+        match value {
+            Value::Bool(_) => PropertyType::Checkbox,
+            Value::Number(_) => PropertyType::Number,
+            Value::Array(_) => PropertyType::List,
+            Value::String(s) if parse_date(s).is_some() => PropertyType::Date,
+            Value::String(s) if parse_datetime(s).is_some() => PropertyType::Datetime,
+            Value::String(s) if is_wikilink(s) => PropertyType::Link,
+            _ => PropertyType::Text,
+        }
+    }
+
+    /// Check a value against this type. Empty values (`key:` with nothing after
+    /// it) are always accepted as "unset".
+    pub fn check(&self, value: &Value) -> Result<(), String> {
+        if value.is_null() {
+            return Ok(());
+        }
+        let ok = match self {
+            PropertyType::Text => !value.is_array() && !value.is_object(),
+            PropertyType::Number => value.is_number(),
+            PropertyType::Checkbox => value.is_boolean(),
+            PropertyType::List => value.is_array(),
+            PropertyType::Date => value.as_str().and_then(parse_date).is_some(),
+            PropertyType::Datetime => value.as_str()
+                .map(|s| parse_datetime(s).is_some() || parse_date(s).is_some())
+                .unwrap_or(false),
+            PropertyType::Link => value.as_str().map(is_wikilink).unwrap_or(false),
+        };
+        if ok {
+            Ok(())
+        } else {
+            Err(format!("expected {}, got {}", self.describe(), value))
+        }
+    }
+
+    fn describe(&self) -> &'static str {
+        match self {
+            PropertyType::Text => "text",
+            PropertyType::Number => "a number",
+            PropertyType::Checkbox => "true or false",
+            PropertyType::List => "a list",
+            PropertyType::Date => "a date (YYYY-MM-DD)",
+            PropertyType::Datetime => "a date-time (YYYY-MM-DDTHH:MM[:SS])",
+            PropertyType::Link => "a [[wiki link]]",
+        }
+    }
+}
+
+pub(crate) fn parse_date(s: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d").ok()
+}
+
+pub(crate) fn parse_datetime(s: &str) -> Option<NaiveDateTime> {
+    let s = s.trim();
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Some(dt.naive_local());
+    }
+    ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|fmt| NaiveDateTime::parse_from_str(s, fmt).ok())
+}
+
+fn is_wikilink(s: &str) -> bool {
+    let s = s.trim();
+    s.starts_with("[[") && s.ends_with("]]") && s.len() > 4
+}
+
+// ─── Frontmatter → properties ──────────────────────────────────────
+
+/// Flatten a frontmatter block into `key → value`, skipping unset values
+/// (`None` known fields and empty `tags`/`aliases`).
+pub fn frontmatter_properties(fm: &Frontmatter) -> BTreeMap<String, Value> {
+    let mut props = BTreeMap::new();
+    if let Ok(Value::Object(map)) = serde_json::to_value(fm) {
+        for (key, value) in map {
+            let unset = match &value {
+                Value::Null => !fm.extra.contains_key(&key),
+                Value::Array(items) => items.is_empty() && (key == "tags" || key == "aliases"),
+                _ => false,
+            };
+            if !unset {
+                props.insert(key, value);
+            }
+        }
+    }
+    props
+}
+
+/// Properties of a note: its frontmatter plus inline `key:: value` fields.
+/// A key set in the frontmatter wins over an inline field of the same name.
+pub fn note_properties(content: &str) -> BTreeMap<String, Value> {
+    let mut props = match frontmatter::parse_frontmatter(content) {
+        Ok((Some(fm), _)) => frontmatter_properties(&fm),
+        _ => BTreeMap::new(),
+    };
+    for (key, value) in frontmatter::inline_properties(content) {
+        props.entry(key).or_insert(value);
+    }
+    props
+}
+
+// ─── PropertyRegistry ──────────────────────────────────────────────
+
+/// A value that does not match its registered type.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct PropertyIssue {
+    pub key: String,
+    pub expected: PropertyType,
+    pub message: String,
+}
+
+/// Property name → type, stored per vault in `.oxidian/types.json`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PropertyRegistry {
+    #[serde(default)]
+    pub types: BTreeMap<String, PropertyType>,
+}
+
+fn registry_path(vault_path: &str) -> PathBuf {
+    Path::new(vault_path).join(".oxidian").join("types.json")
+}
+
+impl PropertyRegistry {
+    pub fn load(vault_path: &str) -> Self {
+        fs::read_to_string(registry_path(vault_path))
+            .ok()
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, vault_path: &str) -> Result<(), String> {
+        let path = registry_path(vault_path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create .oxidian dir: {}", e))?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(|e| format!("Failed to serialize property types: {}", e))?;
+        fs::write(&path, json).map_err(|e| format!("Failed to write property types: {}", e))
+    }
+
+    /// Registered type for `key`. `tags` and `aliases` are always lists.
+    pub fn get(&self, key: &str) -> Option<PropertyType> {
+        match key {
+            "tags" | "aliases" => Some(PropertyType::List),
+            _ => self.types.get(key).copied(),
+        }
+    }
+
+    /// Register a type for `key`, or clear it with `None`.
+    pub fn set(&mut self, key: &str, ty: Option<PropertyType>) {
+        match ty {
+            Some(ty) => { self.types.insert(key.to_string(), ty); }
+            None => { self.types.remove(key); }
+        }
+    }
+
+    /// Check every property of a frontmatter block against the registry.
+    pub fn validate(&self, fm: &Frontmatter) -> Vec<PropertyIssue> {
+        frontmatter_properties(fm).iter()
+            .filter_map(|(key, value)| {
+                let expected = self.get(key)?;
+                expected.check(value).err().map(|message| PropertyIssue {
+                    key: key.clone(),
+                    expected,
+                    message,
+                })
+            })
+            .collect()
+    }
+
+    /// Validate the frontmatter of raw note content.
+    pub fn validate_content(&self, content: &str) -> Result<Vec<PropertyIssue>, String> {
+        match frontmatter::parse_frontmatter(content)? {
+            (Some(fm), _) => Ok(self.validate(&fm)),
+            (None, _) => Ok(vec![]),
+        }
+    }
+}
+
+// ─── PropertyIndex ─────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PropertyKeyInfo {
+    pub key: String,
+    /// Type from the registry, or inferred from the most common value kind
+    pub property_type: PropertyType,
+    pub registered: bool,
+    /// Number of notes carrying the key
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PropertyValueCount {
+    pub value: String,
+    pub count: usize,
+}
+
+/// Stringify a value for indexing; list values are indexed item by item.
+fn index_values(value: &Value) -> Vec<String> {
+    match value {
+        Value::Null => vec![],
+        Value::String(s) => vec![s.clone()],
+        Value::Array(items) => items.iter().flat_map(index_values).collect(),
+        other => vec![other.to_string()],
+    }
+}
+
+pub struct PropertyIndex {
+    /// key → value → set of file paths
+    index: BTreeMap<String, BTreeMap<String, BTreeSet<String>>>,
+    /// key → set of file paths (includes notes where the value is empty)
+    key_files: BTreeMap<String, BTreeSet<String>>,
+    /// file → its properties (reverse index for fast updates)
+    file_props: HashMap<String, BTreeMap<String, Value>>,
+}
+
+impl PropertyIndex {
+    pub fn new() -> Self {
+        PropertyIndex {
+            index: BTreeMap::new(),
+            key_files: BTreeMap::new(),
+            file_props: HashMap::new(),
+        }
+    }
+
+    /// Build the full property index from a vault directory, decrypting
+    /// encrypted notes with `password` and skipping them without one.
+    pub fn build_from_vault(&mut self, vault_path: &str, password: Option<&str>) {
+        self.index.clear();
+        self.key_files.clear();
+        self.file_props.clear();
+
+        for entry in WalkDir::new(vault_path)
+            .into_iter()
+            .filter_entry(|e| e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'))
+            .filter_map(|e| e.ok())
+        {
+            let path = entry.path();
+            if path.is_file() && path.extension().map(|e| e == "md").unwrap_or(false) {
+                let content = fs::read_to_string(path).ok()
+                    .and_then(|c| encryption::plaintext_content(c, password));
+                if let Some(content) = content {
+                    let relative = path.strip_prefix(vault_path)
+                        .unwrap_or(path)
+                        .to_string_lossy()
+                        .to_string();
+                    self.index_file(&relative, &content);
+                }
+            }
+        }
+    }
+
+    /// Index a single file's properties.
+    pub fn index_file(&mut self, relative_path: &str, content: &str) {
+        self.index_properties(relative_path, note_properties(content));
+    }
+
+    /// Index an already-parsed set of properties for a file.
+    pub fn index_properties(&mut self, relative_path: &str, props: BTreeMap<String, Value>) {
+        self.remove_file(relative_path);
+        if props.is_empty() {
+            return;
+        }
+        for (key, value) in &props {
+            self.key_files.entry(key.clone()).or_default().insert(relative_path.to_string());
+            for v in index_values(value) {
+                self.index.entry(key.clone())
+                    .or_default()
+                    .entry(v)
+                    .or_default()
+                    .insert(relative_path.to_string());
+            }
+        }
+        self.file_props.insert(relative_path.to_string(), props);
+    }
+
+    /// Remove a file from the index.
+    pub fn remove_file(&mut self, relative_path: &str) {
+        let Some(old) = self.file_props.remove(relative_path) else { return };
+        for (key, value) in old {
+            if let Some(files) = self.key_files.get_mut(&key) {
+                files.remove(relative_path);
+                if files.is_empty() {
+                    self.key_files.remove(&key);
+                }
+            }
+            if let Some(values) = self.index.get_mut(&key) {
+                for v in index_values(&value) {
+                    if let Some(files) = values.get_mut(&v) {
+                        files.remove(relative_path);
+                        if files.is_empty() {
+                            values.remove(&v);
+                        }
+                    }
+                }
+                if values.is_empty() {
+                    self.index.remove(&key);
+                }
+            }
+        }
+    }
+
+    /// Every property key with its (registered or inferred) type and usage count.
+    pub fn keys(&self, registry: &PropertyRegistry) -> Vec<PropertyKeyInfo> {
+        self.key_files.iter().map(|(key, files)| {
+            let registered = registry.get(key);
+            PropertyKeyInfo {
+                key: key.clone(),
+                property_type: registered.unwrap_or_else(|| self.infer_type(key)),
+                registered: registered.is_some(),
+                count: files.len(),
+            }
+        }).collect()
+    }
+
+    /// Most common inferred type among the non-empty values of `key`.
+    fn infer_type(&self, key: &str) -> PropertyType {
+        let mut counts: HashMap<PropertyType, usize> = HashMap::new();
+        for props in self.file_props.values() {
+            if let Some(value) = props.get(key).filter(|v| !v.is_null()) {
+                *counts.entry(PropertyType::infer(value)).or_default() += 1;
+            }
+        }
+        counts.into_iter()
+            .max_by_key(|(ty, n)| (*n, std::cmp::Reverse(*ty as u8)))
+            .map(|(ty, _)| ty)
+            .unwrap_or(PropertyType::Text)
+    }
+
+    /// Distinct values of `key` with how many notes use each, most used first.
+    pub fn values(&self, key: &str) -> Vec<PropertyValueCount> {
+        let mut values: Vec<PropertyValueCount> = self.index.get(key)
+            .map(|values| values.iter()
+                .map(|(value, files)| PropertyValueCount { value: value.clone(), count: files.len() })
+                .collect())
+            .unwrap_or_default();
+        values.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+        values
+    }
+
+    /// Prefix search over the values of `key` for autocomplete.
+    pub fn autocomplete_value(&self, key: &str, prefix: &str) -> Vec<PropertyValueCount> {
+        let lower = prefix.to_lowercase();
+        self.values(key).into_iter()
+            .filter(|v| v.value.to_lowercase().starts_with(&lower))
+            .collect()
+    }
+
+    /// Files carrying `key`, optionally restricted to those where it has `value`
+    /// (for lists: where the list contains `value`).
+    pub fn files_with(&self, key: &str, value: Option<&str>) -> Vec<String> {
+        let files = match value {
+            None => self.key_files.get(key),
+            Some(v) => self.index.get(key).and_then(|values| values.get(v)),
+        };
+        files.map(|s| s.iter().cloned().collect()).unwrap_or_default()
+    }
+
+    /// Files whose `key` has `value` (or, with `None`, any value), ignoring
+    /// case in both key and value.
+    pub fn files_matching(&self, key: &str, value: Option<&str>) -> BTreeSet<String> {
+        let mut files = BTreeSet::new();
+        match value {
+            None => {
+                for (k, paths) in &self.key_files {
+                    if k.eq_ignore_ascii_case(key) {
+                        files.extend(paths.iter().cloned());
+                    }
+                }
+            }
+            Some(value) => {
+                let value = value.to_lowercase();
+                for (k, values) in &self.index {
+                    if !k.eq_ignore_ascii_case(key) {
+                        continue;
+                    }
+                    for (v, paths) in values {
+                        if v.to_lowercase() == value {
+                            files.extend(paths.iter().cloned());
+                        }
+                    }
+                }
+            }
+        }
+        files
+    }
+
+    /// Properties of a single file.
+    pub fn properties_for_file(&self, relative_path: &str) -> BTreeMap<String, Value> {
+        self.file_props.get(relative_path).cloned().unwrap_or_default()
+    }
+
+    /// Get the total number of distinct property keys.
+    pub fn key_count(&self) -> usize {
+        self.key_files.len()
+    }
+}
+
+// ─── Field search ──────────────────────────────────────────────────
+
+/// Full-text search that also understands `key::value` / `[key:: value]`
+/// filters over note properties. A query made only of filters lists the
+/// matching notes by path.
+pub fn search_with_fields(search: &SearchIndex, props: &PropertyIndex, query: &str, limit: usize) -> Result<Vec<SearchResult>, String> {
+    let (filters, text) = split_field_filters(query);
+    if filters.is_empty() {
+        return search.search(query, limit);
+    }
+    let mut allowed: Option<BTreeSet<String>> = None;
+    for filter in &filters {
+        let files = props.files_matching(&filter.key, filter.value.as_deref());
+        allowed = Some(match allowed {
+            None => files,
+            Some(prev) => prev.intersection(&files).cloned().collect(),
+        });
+    }
+    let allowed = allowed.unwrap_or_default();
+
+    if text.is_empty() {
+        let summary = filters.iter()
+            .map(|f| match &f.value {
+                Some(v) => format!("{}: {}", f.key, v),
+                None => f.key.clone(),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        return Ok(allowed.into_iter().take(limit).map(|path| SearchResult {
+            title: Path::new(&path).file_stem().unwrap_or_default().to_string_lossy().to_string(),
+            path,
+            snippet: summary.clone(),
+            score: 1.0,
+        }).collect());
+    }
+    // Over-fetch so that filtering still leaves enough hits
+    let mut results = search.search(&text, limit.max(1) * 10)?;
+    results.retain(|r| allowed.contains(&r.path));
+    results.truncate(limit);
+    Ok(results)
+}
+
+// ─── Tests ─────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    fn setup_vault() -> (TempDir, PropertyIndex) {
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path();
+        fs::write(root.join("a.md"), "---\nstatus: open\ndue: 2025-03-01\nrating: 4\ntags: [work]\n---\nA").unwrap();
+        fs::write(root.join("b.md"), "---\nstatus: done\nrating: 5\nproject: \"[[Apollo]]\"\n---\nB").unwrap();
+        fs::write(root.join("c.md"), "---\nstatus: open\nreviewers: [ann, bob]\ndue:\n---\nC").unwrap();
+        fs::write(root.join("plain.md"), "No frontmatter").unwrap();
+        let mut idx = PropertyIndex::new();
+        idx.build_from_vault(root.to_str().unwrap(), None);
+        (tmp, idx)
+    }
+
+    #[test]
+    fn test_type_checks() {
+        assert!(PropertyType::Number.check(&json!(4)).is_ok());
+        assert!(PropertyType::Number.check(&json!("four")).is_err());
+        assert!(PropertyType::Date.check(&json!("2025-03-01")).is_ok());
+        assert!(PropertyType::Date.check(&json!("tomorrow")).is_err());
+        assert!(PropertyType::Datetime.check(&json!("2025-03-01T09:30")).is_ok());
+        assert!(PropertyType::Datetime.check(&json!("2025-03-01T09:30:00+02:00")).is_ok());
+        assert!(PropertyType::Checkbox.check(&json!(true)).is_ok());
+        assert!(PropertyType::Checkbox.check(&json!("yes")).is_err());
+        assert!(PropertyType::List.check(&json!(["a"])).is_ok());
+        assert!(PropertyType::Link.check(&json!("[[Note]]")).is_ok());
+        assert!(PropertyType::Link.check(&json!("Note")).is_err());
+        assert!(PropertyType::Text.check(&json!(["a"])).is_err());
+        // Empty values are unset, not invalid
+        assert!(PropertyType::Number.check(&Value::Null).is_ok());
+    }
+
+    #[test]
+    fn test_infer_type() {
+        assert_eq!(PropertyType::infer(&json!(3.5)), PropertyType::Number);
+        assert_eq!(PropertyType::infer(&json!("2025-01-01")), PropertyType::Date);
+        assert_eq!(PropertyType::infer(&json!("2025-01-01 10:00")), PropertyType::Datetime);
+        assert_eq!(PropertyType::infer(&json!("[[X]]")), PropertyType::Link);
+        assert_eq!(PropertyType::infer(&json!(false)), PropertyType::Checkbox);
+        assert_eq!(PropertyType::infer(&json!("hello")), PropertyType::Text);
+    }
+
+    #[test]
+    fn test_registry_roundtrip_and_validate() {
+        let tmp = TempDir::new().unwrap();
+        let vp = tmp.path().to_str().unwrap();
+        let mut reg = PropertyRegistry::load(vp);
+        assert!(reg.types.is_empty());
+        reg.set("due", Some(PropertyType::Date));
+        reg.set("rating", Some(PropertyType::Number));
+        reg.save(vp).unwrap();
+
+        let reg = PropertyRegistry::load(vp);
+        assert_eq!(reg.get("due"), Some(PropertyType::Date));
+        assert_eq!(reg.get("tags"), Some(PropertyType::List));
+
+        let issues = reg.validate_content("---\ndue: next week\nrating: 3\n---\nBody").unwrap();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].key, "due");
+        assert_eq!(issues[0].expected, PropertyType::Date);
+        assert!(reg.validate_content("---\ndue: 2025-01-01\n---\n").unwrap().is_empty());
+        assert!(reg.validate_content("no frontmatter").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_frontmatter_properties_skips_unset() {
+        let props = note_properties("---\ntitle: T\nempty:\n---\n");
+        assert_eq!(props.get("title"), Some(&json!("T")));
+        assert!(props.contains_key("empty"));
+        assert!(!props.contains_key("tags"));
+        assert!(!props.contains_key("created"));
+    }
+
+    #[test]
+    fn test_search_with_fields() {
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path();
+        fs::write(root.join("dune.md"), "# Dune\nauthor:: Frank Herbert\nA desert planet [rating:: 5].").unwrap();
+        fs::write(root.join("emma.md"), "---\nauthor: Jane Austen\n---\nA matchmaking story on no planet.").unwrap();
+        let vp = root.to_str().unwrap();
+        let mut search = SearchIndex::in_memory(vp, &Default::default()).unwrap();
+        search.reindex_vault(vp).unwrap();
+        let mut idx = PropertyIndex::new();
+        idx.build_from_vault(vp, None);
+
+        let paths = |q: &str| -> Vec<String> {
+            search_with_fields(&search, &idx, q, 10).unwrap().into_iter().map(|r| r.path).collect()
+        };
+        assert_eq!(paths("[author:: frank herbert]"), vec!["dune.md"]);
+        assert_eq!(paths("planet rating::"), vec!["dune.md"]);
+        assert_eq!(paths("author::"), vec!["dune.md", "emma.md"]);
+        assert!(paths("planet rating::4").is_empty());
+    }
+
+    #[test]
+    fn test_build_from_encrypted_vault() {
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path();
+        let secret = encryption::encrypt_file_content("---\nstatus: secret\n---\nS", "pw").unwrap();
+        fs::write(root.join("s.md"), secret).unwrap();
+        let vp = root.to_str().unwrap();
+
+        let mut idx = PropertyIndex::new();
+        idx.build_from_vault(vp, None);
+        assert_eq!(idx.key_count(), 0);
+        idx.build_from_vault(vp, Some("pw"));
+        assert_eq!(idx.files_with("status", Some("secret")), vec!["s.md"]);
+    }
+
+    #[test]
+    fn test_note_properties_include_inline_fields() {
+        let props = note_properties("---\nstatus: draft\n---\nstatus:: final\nRated [rating:: 4].\n");
+        assert_eq!(props.get("status"), Some(&json!("draft")));
+        assert_eq!(props.get("rating"), Some(&json!(4)));
+    }
+
+    #[test]
+    fn test_index_keys_and_values() {
+        let (_tmp, idx) = setup_vault();
+        let reg = PropertyRegistry::default();
+        let keys = idx.keys(&reg);
+        let status = keys.iter().find(|k| k.key == "status").unwrap();
+        assert_eq!(status.count, 3);
+        assert_eq!(status.property_type, PropertyType::Text);
+        assert!(!status.registered);
+        let rating = keys.iter().find(|k| k.key == "rating").unwrap();
+        assert_eq!(rating.property_type, PropertyType::Number);
+        let due = keys.iter().find(|k| k.key == "due").unwrap();
+        assert_eq!(due.property_type, PropertyType::Date);
+        assert_eq!(due.count, 2);
+
+        let values = idx.values("status");
+        assert_eq!(values[0].value, "open");
+        assert_eq!(values[0].count, 2);
+        let completions = idx.autocomplete_value("status", "D");
+        assert_eq!(completions.len(), 1);
+        assert_eq!(completions[0].value, "done");
+    }
+
+    #[test]
+    fn test_files_with_property() {
+        let (_tmp, idx) = setup_vault();
+        assert_eq!(idx.files_with("status", Some("open")), vec!["a.md", "c.md"]);
+        assert_eq!(idx.files_with("reviewers", Some("bob")), vec!["c.md"]);
+        assert_eq!(idx.files_with("due", None), vec!["a.md", "c.md"]);
+        assert_eq!(idx.files_with("rating", Some("5")), vec!["b.md"]);
+        assert!(idx.files_with("missing", None).is_empty());
+    }
+
+    #[test]
+    fn test_update_and_remove_file() {
+        let (_tmp, mut idx) = setup_vault();
+        idx.index_file("a.md", "---\nstatus: done\n---\n");
+        assert_eq!(idx.files_with("status", Some("open")), vec!["c.md"]);
+        assert_eq!(idx.files_with("rating", None), vec!["b.md"]);
+        idx.remove_file("b.md");
+        assert!(idx.files_with("project", None).is_empty());
+        assert!(idx.keys(&PropertyRegistry::default()).iter().all(|k| k.key != "project"));
+        assert!(idx.properties_for_file("b.md").is_empty());
+    }
+}
@@ -0,0 +1,698 @@
+// Oxidian — Query Evaluation
+// Values, pages built from the metadata cache, and execution of a parsed
+// query (FROM → WHERE/SORT/LIMIT) into list, table or task results.
+
+use super::parser::{BinOp, Clause, Expr, Query, QueryKind, Source, DATE_KEYWORDS};
+use crate::features::properties::{parse_date, parse_datetime, PropertyIndex};
+use crate::features::tasks::{Task, TaskDateField, TaskIndex};
+use crate::state::VaultMetaCache;
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::time::SystemTime;
+
+// ─── Value ─────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    Text(String),
+    Date(NaiveDateTime),
+    Duration(Duration),
+    /// Wiki-link target without alias, e.g. `Projects/Apollo#Goals`.
+    Link(String),
+    List(Vec<Value>),
+    Object(BTreeMap<String, Value>),
+}
+
+/// Lower-cased note name a link (or path) points at, for comparisons.
+fn link_key(target: &str) -> String {
+    let target = target.split('#').next().unwrap_or(target).trim();
+    Path::new(target).file_stem().unwrap_or_default().to_string_lossy().to_lowercase()
+}
+
+fn parse_text_date(s: &str) -> Option<NaiveDateTime> {
+    parse_datetime(s).or_else(|| parse_date(s).and_then(|d| d.and_hms_opt(0, 0, 0)))
+}
+
+impl Value {
+    /// Link value from the inside of `[[...]]`, dropping any alias.
+    pub fn link(raw: &str) -> Value {
+        Value::Link(raw.split('|').next().unwrap_or(raw).trim().to_string())
+    }
+
+    /// Convert a frontmatter value; date strings become dates and
+    /// `"[[Note]]"` strings become links.
+    pub fn from_json(value: &serde_json::Value) -> Value {
+        match value {
+            serde_json::Value::Null => Value::Null,
+            serde_json::Value::Bool(b) => Value::Bool(*b),
+            serde_json::Value::Number(n) => n.as_f64().map(Value::Number).unwrap_or(Value::Null),
+            serde_json::Value::String(s) => {
+                let t = s.trim();
+                if t.starts_with("[[") && t.ends_with("]]") && t.len() > 4 {
+                    Value::link(&t[2..t.len() - 2])
+                } else if let Some(date) = parse_text_date(t) {
+                    Value::Date(date)
+                } else {
+                    Value::Text(s.clone())
+                }
+            }
+            serde_json::Value::Array(items) => Value::List(items.iter().map(Value::from_json).collect()),
+            serde_json::Value::Object(map) => Value::Object(
+                map.iter().map(|(k, v)| (k.clone(), Value::from_json(v))).collect(),
+            ),
+        }
+    }
+
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Value::Null => false,
+            Value::Bool(b) => *b,
+            Value::Number(n) => *n != 0.0,
+            Value::Text(s) => !s.is_empty(),
+            Value::Duration(d) => !d.is_zero(),
+            Value::List(items) => !items.is_empty(),
+            Value::Object(map) => !map.is_empty(),
+            Value::Date(_) | Value::Link(_) => true,
+        }
+    }
+
+    fn rank(&self) -> u8 {
+        match self {
+            Value::Null => 0,
+            Value::Bool(_) => 1,
+            Value::Number(_) => 2,
+            Value::Duration(_) => 3,
+            Value::Date(_) => 4,
+            Value::Text(_) => 5,
+            Value::Link(_) => 6,
+            Value::List(_) => 7,
+            Value::Object(_) => 8,
+        }
+    }
+
+    /// Ordering between comparable values; `None` if the types don't mix.
+    pub fn compare(&self, other: &Value) -> Option<Ordering> {
+        match (self, other) {
+            (Value::Null, Value::Null) => Some(Ordering::Equal),
+            (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+            (Value::Number(a), Value::Number(b)) => a.partial_cmp(b),
+            (Value::Text(a), Value::Text(b)) => Some(a.cmp(b)),
+            (Value::Date(a), Value::Date(b)) => Some(a.cmp(b)),
+            (Value::Duration(a), Value::Duration(b)) => Some(a.cmp(b)),
+            (Value::Date(a), Value::Text(b)) => parse_text_date(b).map(|b| a.cmp(&b)),
+            (Value::Text(a), Value::Date(b)) => parse_text_date(a).map(|a| a.cmp(b)),
+            (Value::Link(a), Value::Link(b)) | (Value::Link(a), Value::Text(b)) | (Value::Text(a), Value::Link(b)) => {
+                Some(link_key(a).cmp(&link_key(b)))
+            }
+            (Value::List(a), Value::List(b)) => {
+                for (x, y) in a.iter().zip(b) {
+                    match x.compare(y)? {
+                        Ordering::Equal => continue,
+                        ord => return Some(ord),
+                    }
+                }
+                Some(a.len().cmp(&b.len()))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn loose_eq(&self, other: &Value) -> bool {
+        self.compare(other) == Some(Ordering::Equal)
+    }
+
+    /// Total order used by SORT: comparable values by value, others by type.
+    pub fn sort_cmp(&self, other: &Value) -> Ordering {
+        self.compare(other).unwrap_or_else(|| self.rank().cmp(&other.rank()))
+    }
+
+    /// Plain-text form, as produced by `string()`.
+    pub fn display(&self) -> String {
+        match self {
+            Value::Null => String::new(),
+            Value::Bool(b) => b.to_string(),
+            Value::Number(n) if n.fract() == 0.0 && n.abs() < 1e15 => format!("{}", *n as i64),
+            Value::Number(n) => n.to_string(),
+            Value::Text(s) => s.clone(),
+            Value::Date(d) if d.time() == chrono::NaiveTime::MIN => d.format("%Y-%m-%d").to_string(),
+            Value::Date(d) => d.format("%Y-%m-%d %H:%M").to_string(),
+            Value::Duration(d) => format_duration(*d),
+            Value::Link(target) => link_display(target),
+            Value::List(items) => items.iter().map(Value::display).collect::<Vec<_>>().join(", "),
+            Value::Object(map) => map.iter()
+                .map(|(k, v)| format!("{}: {}", k, v.display()))
+                .collect::<Vec<_>>()
+                .join(", "),
+        }
+    }
+}
+
+/// Text shown for a link: the note name, or `Note > Heading`.
+pub fn link_display(target: &str) -> String {
+    let (note, heading) = match target.split_once('#') {
+        Some((n, h)) => (n, Some(h)),
+        None => (target, None),
+    };
+    let name = Path::new(note).file_stem().unwrap_or_default().to_string_lossy().to_string();
+    match heading {
+        Some(h) => format!("{} > {}", name, h.trim_start_matches('^')),
+        None => name,
+    }
+}
+
+fn format_duration(d: Duration) -> String {
+    let days = d.num_days();
+    let hours = d.num_hours() - days * 24;
+    let minutes = d.num_minutes() - d.num_hours() * 60;
+    let mut parts = Vec::new();
+    if days != 0 { parts.push(format!("{} days", days)); }
+    if hours != 0 { parts.push(format!("{} hours", hours)); }
+    if minutes != 0 || parts.is_empty() { parts.push(format!("{} minutes", minutes)); }
+    parts.join(", ")
+}
+
+/// Parse durations like `7 days`, `1 week 2 days`, `3h`.
+pub fn parse_duration(s: &str) -> Option<Duration> {
+    let mut total = Duration::zero();
+    let mut rest = s.trim();
+    if rest.is_empty() {
+        return None;
+    }
+    while !rest.is_empty() {
+        let num_end = rest.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(rest.len());
+        let amount: f64 = rest[..num_end].parse().ok()?;
+        rest = rest[num_end..].trim_start();
+        let unit_end = rest.find(|c: char| !c.is_alphabetic()).unwrap_or(rest.len());
+        let seconds = match rest[..unit_end].to_lowercase().as_str() {
+            "s" | "sec" | "secs" | "second" | "seconds" => 1.0,
+            "m" | "min" | "mins" | "minute" | "minutes" => 60.0,
+            "h" | "hr" | "hrs" | "hour" | "hours" => 3600.0,
+            "d" | "day" | "days" => 86400.0,
+            "w" | "wk" | "wks" | "week" | "weeks" => 604800.0,
+            "mo" | "month" | "months" => 30.0 * 86400.0,
+            "y" | "yr" | "yrs" | "year" | "years" => 365.0 * 86400.0,
+            _ => return None,
+        };
+        // Out-of-range amounts (`dur(1000000000 years)`) are not a duration
+        total = total.checked_add(&Duration::try_seconds((amount * seconds) as i64)?)?;
+        rest = rest[unit_end..].trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+    }
+    Some(total)
+}
+
+/// Resolve `today`, `sow`, `eom`, ... relative to `now`.
+fn date_keyword(keyword: &str, now: NaiveDateTime) -> Option<NaiveDateTime> {
+    let today = now.date();
+    let start = |d: NaiveDate| d.and_hms_opt(0, 0, 0);
+    let end = |d: NaiveDate| d.and_hms_opt(23, 59, 59);
+    let first_of_month = |y: i32, m: u32| NaiveDate::from_ymd_opt(y, m, 1);
+    let sow = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+    let som = first_of_month(today.year(), today.month())?;
+    let next_month = if today.month() == 12 {
+        first_of_month(today.year() + 1, 1)?
+    } else {
+        first_of_month(today.year(), today.month() + 1)?
+    };
+    match keyword {
+        "now" => Some(now),
+        "today" => start(today),
+        "tomorrow" => start(today + Duration::days(1)),
+        "yesterday" => start(today - Duration::days(1)),
+        "sow" => start(sow),
+        "eow" => end(sow + Duration::days(6)),
+        "som" => start(som),
+        "eom" => end(next_month - Duration::days(1)),
+        "soy" => start(NaiveDate::from_ymd_opt(today.year(), 1, 1)?),
+        "eoy" => end(NaiveDate::from_ymd_opt(today.year(), 12, 31)?),
+        _ => None,
+    }
+}
+
+// ─── Pages & tasks ─────────────────────────────────────────────────
+
+/// A note as seen by queries.
+#[derive(Debug, Clone, Default)]
+pub struct Page {
+    pub path: String,
+    /// Tags without `#`, inline and frontmatter combined.
+    pub tags: Vec<String>,
+    /// Resolved paths of linked notes (raw target when unresolved).
+    pub outlinks: Vec<String>,
+    pub inlinks: Vec<String>,
+    pub properties: BTreeMap<String, serde_json::Value>,
+    pub size: u64,
+    pub ctime: Option<NaiveDateTime>,
+    pub mtime: Option<NaiveDateTime>,
+}
+
+fn path_link(path: &str) -> Value {
+    Value::Link(path.strip_suffix(".md").unwrap_or(path).to_string())
+}
+
+fn local_time(time: std::io::Result<SystemTime>) -> Option<NaiveDateTime> {
+    time.ok().map(|t| DateTime::<Local>::from(t).naive_local())
+}
+
+impl Page {
+    pub fn name(&self) -> String {
+        Path::new(&self.path).file_stem().unwrap_or_default().to_string_lossy().to_string()
+    }
+
+    pub fn folder(&self) -> String {
+        Path::new(&self.path).parent().map(|p| p.to_string_lossy().to_string()).unwrap_or_default()
+    }
+
+    pub fn link(&self) -> Value {
+        path_link(&self.path)
+    }
+
+    fn file_field(&self, field: &str) -> Value {
+        let day = |t: Option<NaiveDateTime>| t
+            .and_then(|t| t.date().and_hms_opt(0, 0, 0))
+            .map(Value::Date)
+            .unwrap_or(Value::Null);
+        let links = |paths: &[String]| Value::List(paths.iter().map(|p| path_link(p)).collect());
+        match field {
+            "name" => Value::Text(self.name()),
+            "path" => Value::Text(self.path.clone()),
+            "folder" => Value::Text(self.folder()),
+            "link" => self.link(),
+            "ext" => Value::Text(Path::new(&self.path).extension().unwrap_or_default().to_string_lossy().to_string()),
+            "size" => Value::Number(self.size as f64),
+            "ctime" => self.ctime.map(Value::Date).unwrap_or(Value::Null),
+            "mtime" => self.mtime.map(Value::Date).unwrap_or(Value::Null),
+            "cday" => day(self.ctime),
+            "mday" => day(self.mtime),
+            "tags" => Value::List(self.tags.iter().map(|t| Value::Text(format!("#{}", t))).collect()),
+            "outlinks" => links(&self.outlinks),
+            "inlinks" => links(&self.inlinks),
+            "aliases" => self.properties.get("aliases").map(Value::from_json).unwrap_or(Value::List(vec![])),
+            "frontmatter" => Value::Object(
+                self.properties.iter().map(|(k, v)| (k.clone(), Value::from_json(v))).collect(),
+            ),
+            _ => Value::Null,
+        }
+    }
+
+    fn property(&self, key: &str) -> Value {
+        self.properties.get(key)
+            .or_else(|| self.properties.iter().find(|(k, _)| k.eq_ignore_ascii_case(key)).map(|(_, v)| v))
+            .map(Value::from_json)
+            .unwrap_or(Value::Null)
+    }
+}
+
+/// Build query pages from the metadata cache, property index and file stats.
+pub fn collect_pages(vault_path: &str, cache: &VaultMetaCache, properties: &PropertyIndex) -> Vec<Page> {
+    let mut pages: Vec<Page> = cache.entries.iter().map(|(path, (tags, _))| {
+        let props = properties.properties_for_file(path);
+        let mut all_tags: Vec<String> = tags.iter().map(|t| t.trim_start_matches('#').to_string()).collect();
+        if let Some(serde_json::Value::Array(fm_tags)) = props.get("tags") {
+            all_tags.extend(fm_tags.iter().filter_map(|t| t.as_str()).map(|t| t.trim_start_matches('#').to_string()));
+        }
+        all_tags.sort();
+        all_tags.dedup();
+
+        // Resolved links point at the note; unresolved ones keep their target text
+        let mut outlinks: Vec<String> = cache.adjacency.links(path).iter()
+            .map(|(link, note)| note.clone().unwrap_or_else(|| {
+                let target = link.split('|').next().unwrap_or(link);
+                target.split('#').next().unwrap_or(target).trim().to_string()
+            }))
+            .collect();
+        outlinks.sort();
+        outlinks.dedup();
+        let mut inlinks: Vec<String> = cache.adjacency.sources(path).filter(|s| s != path).map(str::to_string).collect();
+        inlinks.sort();
+
+        let meta = fs::metadata(Path::new(vault_path).join(path)).ok();
+        Page {
+            path: path.clone(),
+            tags: all_tags,
+            outlinks,
+            inlinks,
+            properties: props,
+            size: meta.as_ref().map(|m| m.len()).unwrap_or(0),
+            ctime: meta.as_ref().and_then(|m| local_time(m.created()).or_else(|| local_time(m.modified()))),
+            mtime: meta.as_ref().and_then(|m| local_time(m.modified())),
+        }
+    }).collect();
+    pages.sort_by(|a, b| a.path.cmp(&b.path));
+    pages
+}
+
+// ─── Evaluation ────────────────────────────────────────────────────
+
+/// One row under evaluation: a page, or a task within a page.
+#[derive(Clone, Copy)]
+pub struct Row<'a> {
+    pub page: &'a Page,
+    pub task: Option<&'a Task>,
+}
+
+pub struct Evaluator {
+    pub now: NaiveDateTime,
+}
+
+impl Evaluator {
+    pub fn eval(&self, expr: &Expr, row: Row) -> Result<Value, String> {
+        match expr {
+            Expr::Literal(v) => Ok(v.clone()),
+            Expr::Field(path) => Ok(self.field(path, row)),
+            Expr::List(items) => Ok(Value::List(
+                items.iter().map(|e| self.eval(e, row)).collect::<Result<_, _>>()?,
+            )),
+            Expr::Not(inner) => Ok(Value::Bool(!self.eval(inner, row)?.is_truthy())),
+            Expr::Neg(inner) => Ok(match self.eval(inner, row)? {
+                Value::Number(n) => Value::Number(-n),
+                Value::Duration(d) => Value::Duration(-d),
+                _ => Value::Null,
+            }),
+            Expr::Binary(BinOp::And, l, r) => Ok(Value::Bool(
+                self.eval(l, row)?.is_truthy() && self.eval(r, row)?.is_truthy(),
+            )),
+            Expr::Binary(BinOp::Or, l, r) => Ok(Value::Bool(
+                self.eval(l, row)?.is_truthy() || self.eval(r, row)?.is_truthy(),
+            )),
+            Expr::Binary(op, l, r) => Ok(binary(*op, self.eval(l, row)?, self.eval(r, row)?)),
+            Expr::Call(name, args) => {
+                let args = args.iter().map(|a| self.eval(a, row)).collect::<Result<Vec<_>, _>>()?;
+                self.call(name, args)
+            }
+        }
+    }
+
+    fn field(&self, path: &[String], row: Row) -> Value {
+        let head = path[0].as_str();
+        let mut value = if head == "file" {
+            match path.get(1) {
+                Some(field) => {
+                    let v = row.page.file_field(field);
+                    return index_path(v, &path[2..]);
+                }
+                None => Value::Null,
+            }
+        } else if let Some(task) = row.task.and_then(|t| task_field(t, head)) {
+            task
+        } else {
+            row.page.property(head)
+        };
+        if path.len() > 1 {
+            value = index_path(value, &path[1..]);
+        }
+        value
+    }
+
+    fn call(&self, name: &str, args: Vec<Value>) -> Result<Value, String> {
+        let arg = |i: usize| args.get(i).cloned().unwrap_or(Value::Null);
+        let text = |i: usize| match arg(i) {
+            Value::Null => None,
+            v => Some(v.display()),
+        };
+        Ok(match name {
+            "date" => match arg(0) {
+                Value::Date(d) => Value::Date(d),
+                Value::Text(s) => {
+                    let lower = s.trim().to_lowercase();
+                    let date = if DATE_KEYWORDS.contains(&lower.as_str()) {
+                        date_keyword(&lower, self.now)
+                    } else {
+                        parse_text_date(&s)
+                    };
+                    date.map(Value::Date).unwrap_or(Value::Null)
+                }
+                _ => Value::Null,
+            },
+            "dur" => text(0).and_then(|s| parse_duration(&s)).map(Value::Duration).unwrap_or(Value::Null),
+            "contains" | "icontains" => {
+                let fold = name == "icontains";
+                Value::Bool(contains(&arg(0), &arg(1), fold))
+            }
+            "startswith" => Value::Bool(matches!((text(0), text(1)), (Some(a), Some(b)) if a.starts_with(&b))),
+            "endswith" => Value::Bool(matches!((text(0), text(1)), (Some(a), Some(b)) if a.ends_with(&b))),
+            "lower" => text(0).map(|s| Value::Text(s.to_lowercase())).unwrap_or(Value::Null),
+            "upper" => text(0).map(|s| Value::Text(s.to_uppercase())).unwrap_or(Value::Null),
+            "length" => match arg(0) {
+                Value::List(items) => Value::Number(items.len() as f64),
+                Value::Object(map) => Value::Number(map.len() as f64),
+                Value::Text(s) => Value::Number(s.chars().count() as f64),
+                Value::Null => Value::Number(0.0),
+                _ => Value::Null,
+            },
+            "default" => match arg(0) {
+                Value::Null => arg(1),
+                v => v,
+            },
+            "choice" => if arg(0).is_truthy() { arg(1) } else { arg(2) },
+            "number" => match arg(0) {
+                Value::Number(n) => Value::Number(n),
+                v => v.display().trim().parse().map(Value::Number).unwrap_or(Value::Null),
+            },
+            "string" => Value::Text(arg(0).display()),
+            "link" => text(0).map(|s| Value::link(&s)).unwrap_or(Value::Null),
+            _ => return Err(format!("Unknown function '{}'", name)),
+        })
+    }
+}
+
+fn task_field(task: &Task, field: &str) -> Option<Value> {
+    Some(match field {
+        "text" => Value::Text(task.text.clone()),
+        "status" => Value::Text(task.status.to_string()),
+        "completed" => Value::Bool(task.completed()),
+        "checked" => Value::Bool(task.status != ' '),
+        "line" => Value::Number(task.line as f64),
+        "tags" => Value::List(task.tags.iter().map(|t| Value::Text(format!("#{}", t))).collect()),
+        "priority" => Value::Text(format!("{:?}", task.priority).to_lowercase()),
+        "recurrence" | "repeat" => task.recurrence.clone().map(Value::Text).unwrap_or(Value::Null),
+        "due" | "scheduled" | "start" | "created" | "completion" | "done" | "cancelled" => {
+            let field = TaskDateField::from_key(field)?;
+            task.date(field)
+                .and_then(parse_date)
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+                .map(Value::Date)
+                .unwrap_or(Value::Null)
+        }
+        _ => return None,
+    })
+}
+
+fn index_path(mut value: Value, path: &[String]) -> Value {
+    for seg in path {
+        value = match value {
+            Value::Object(mut map) => map.remove(seg.as_str())
+                .or_else(|| {
+                    let key = map.keys().find(|k| k.eq_ignore_ascii_case(seg))?.clone();
+                    map.remove(&key)
+                })
+                .unwrap_or(Value::Null),
+            _ => Value::Null,
+        };
+    }
+    value
+}
+
+fn contains(haystack: &Value, needle: &Value, fold: bool) -> bool {
+    let norm = |s: String| if fold { s.to_lowercase() } else { s };
+    match haystack {
+        Value::List(items) => items.iter().any(|item| match (item, needle) {
+            (Value::Text(a), Value::Text(b)) if fold => a.to_lowercase() == b.to_lowercase(),
+            _ => item.loose_eq(needle),
+        }),
+        Value::Object(map) => map.keys().any(|k| norm(k.clone()) == norm(needle.display())),
+        Value::Null => false,
+        other => norm(other.display()).contains(&norm(needle.display())),
+    }
+}
+
+fn binary(op: BinOp, l: Value, r: Value) -> Value {
+    let cmp = || l.compare(&r);
+    match op {
+        BinOp::Eq => Value::Bool(l.loose_eq(&r)),
+        BinOp::Ne => Value::Bool(!l.loose_eq(&r)),
+        BinOp::Lt => Value::Bool(cmp() == Some(Ordering::Less)),
+        BinOp::Le => Value::Bool(matches!(cmp(), Some(Ordering::Less | Ordering::Equal))),
+        BinOp::Gt => Value::Bool(cmp() == Some(Ordering::Greater)),
+        BinOp::Ge => Value::Bool(matches!(cmp(), Some(Ordering::Greater | Ordering::Equal))),
+        BinOp::Add => match (l, r) {
+            (Value::Number(a), Value::Number(b)) => Value::Number(a + b),
+            (Value::Date(d), Value::Duration(x)) | (Value::Duration(x), Value::Date(d)) => {
+                d.checked_add_signed(x).map_or(Value::Null, Value::Date)
+            }
+            (Value::Duration(a), Value::Duration(b)) => a.checked_add(&b).map_or(Value::Null, Value::Duration),
+            (Value::Text(a), b) => Value::Text(a + &b.display()),
+            _ => Value::Null,
+        },
+        BinOp::Sub => match (l, r) {
+            (Value::Number(a), Value::Number(b)) => Value::Number(a - b),
+            (Value::Date(d), Value::Duration(x)) => d.checked_sub_signed(x).map_or(Value::Null, Value::Date),
+            (Value::Date(a), Value::Date(b)) => Value::Duration(a - b),
+            (Value::Duration(a), Value::Duration(b)) => a.checked_sub(&b).map_or(Value::Null, Value::Duration),
+            _ => Value::Null,
+        },
+        BinOp::Mul => match (l, r) {
+            (Value::Number(a), Value::Number(b)) => Value::Number(a * b),
+            _ => Value::Null,
+        },
+        BinOp::Div => match (l, r) {
+            (Value::Number(a), Value::Number(b)) if b != 0.0 => Value::Number(a / b),
+            _ => Value::Null,
+        },
+        BinOp::And | BinOp::Or => unreachable!("short-circuited in eval"),
+    }
+}
+
+// ─── Execution ─────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryOutput {
+    /// (file link, optional LIST expression value)
+    List(Vec<(Value, Option<Value>)>),
+    Table { headers: Vec<String>, rows: Vec<Vec<Value>> },
+    /// Tasks grouped by the note they live in.
+    Task(Vec<(String, Vec<Task>)>),
+}
+
+fn source_matches(source: &Source, page: &Page, pages: &[Page]) -> bool {
+    match source {
+        Source::Tag(tag) => {
+            let tag = tag.to_lowercase();
+            page.tags.iter().any(|t| {
+                let t = t.to_lowercase();
+                t == tag || t.starts_with(&format!("{}/", tag))
+            })
+        }
+        Source::Folder(folder) => {
+            let folder = folder.trim_matches('/');
+            folder.is_empty()
+                || page.path == folder
+                || page.path.strip_suffix(".md") == Some(folder)
+                || page.path.starts_with(&format!("{}/", folder))
+        }
+        Source::LinksTo(link) => {
+            let key = link_key(link.split('|').next().unwrap_or(link));
+            page.outlinks.iter().any(|o| link_key(o) == key)
+        }
+        Source::OutgoingFrom(link) => {
+            let key = link_key(link.split('|').next().unwrap_or(link));
+            pages.iter()
+                .filter(|p| link_key(&p.path) == key)
+                .any(|p| p.outlinks.contains(&page.path))
+        }
+        Source::And(a, b) => source_matches(a, page, pages) && source_matches(b, page, pages),
+        Source::Or(a, b) => source_matches(a, page, pages) || source_matches(b, page, pages),
+        Source::Not(inner) => !source_matches(inner, page, pages),
+    }
+}
+
+/// Pages picked by the query's FROM (all pages if it has none).
+fn select_pages<'a>(query: &Query, pages: &'a [Page]) -> Vec<&'a Page> {
+    pages.iter()
+        .filter(|p| query.from.as_ref().is_none_or(|s| source_matches(s, p, pages)))
+        .collect()
+}
+
+/// Apply WHERE / SORT / LIMIT in the order they were written.
+fn apply_clauses<'a>(clauses: &[Clause], evaluator: &Evaluator, mut rows: Vec<Row<'a>>) -> Result<Vec<Row<'a>>, String> {
+    for clause in clauses {
+        match clause {
+            Clause::Where(expr) => {
+                let mut kept = Vec::with_capacity(rows.len());
+                for row in rows {
+                    if evaluator.eval(expr, row)?.is_truthy() {
+                        kept.push(row);
+                    }
+                }
+                rows = kept;
+            }
+            Clause::Sort(keys) => {
+                let mut keyed = Vec::with_capacity(rows.len());
+                for row in rows {
+                    let values = keys.iter().map(|k| evaluator.eval(&k.expr, row)).collect::<Result<Vec<_>, _>>()?;
+                    keyed.push((values, row));
+                }
+                keyed.sort_by(|(a, _), (b, _)| {
+                    keys.iter().zip(a.iter().zip(b))
+                        .map(|(key, (x, y))| {
+                            let ord = x.sort_cmp(y);
+                            if key.descending { ord.reverse() } else { ord }
+                        })
+                        .find(|o| *o != Ordering::Equal)
+                        .unwrap_or(Ordering::Equal)
+                });
+                rows = keyed.into_iter().map(|(_, row)| row).collect();
+            }
+            Clause::Limit(n) => rows.truncate(*n),
+        }
+    }
+    Ok(rows)
+}
+
+/// Paths of the notes a query selects, in result order. Task queries are
+/// treated as page queries.
+pub fn matching_paths(query: &Query, pages: &[Page], now: NaiveDateTime) -> Result<Vec<String>, String> {
+    let evaluator = Evaluator { now };
+    let rows = select_pages(query, pages).into_iter().map(|page| Row { page, task: None }).collect();
+    let rows = apply_clauses(&query.clauses, &evaluator, rows)?;
+    Ok(rows.iter().map(|row| row.page.path.clone()).collect())
+}
+
+/// Run a parsed query over `pages`. Task queries take the tasks of matching
+/// notes from `tasks`, which also covers decrypted notes of encrypted vaults.
+pub fn execute(query: &Query, pages: &[Page], tasks: &TaskIndex, now: NaiveDateTime) -> Result<QueryOutput, String> {
+    let evaluator = Evaluator { now };
+    let selected = select_pages(query, pages);
+
+    let rows: Vec<Row> = if query.kind == QueryKind::Task {
+        selected.iter()
+            .flat_map(|page| tasks.file_tasks(&page.path).iter().map(move |t| Row { page, task: Some(t) }))
+            .collect()
+    } else {
+        selected.iter().map(|p| Row { page: p, task: None }).collect()
+    };
+    let rows = apply_clauses(&query.clauses, &evaluator, rows)?;
+
+    Ok(match &query.kind {
+        QueryKind::List(expr) => QueryOutput::List(
+            rows.iter().map(|row| {
+                let value = expr.as_ref().map(|e| evaluator.eval(e, *row)).transpose()?;
+                Ok((row.page.link(), value))
+            }).collect::<Result<_, String>>()?,
+        ),
+        QueryKind::Table { without_id, columns } => {
+            let mut headers: Vec<String> = Vec::new();
+            if !without_id {
+                headers.push("File".to_string());
+            }
+            headers.extend(columns.iter().map(|c| c.header.clone()));
+            let rows = rows.iter().map(|row| {
+                let mut cells = Vec::with_capacity(headers.len());
+                if !without_id {
+                    cells.push(row.page.link());
+                }
+                for column in columns {
+                    cells.push(evaluator.eval(&column.expr, *row)?);
+                }
+                Ok(cells)
+            }).collect::<Result<_, String>>()?;
+            QueryOutput::Table { headers, rows }
+        }
+        QueryKind::Task => {
+            let mut groups: Vec<(String, Vec<Task>)> = Vec::new();
+            for row in &rows {
+                let Some(task) = row.task else { continue };
+                match groups.iter_mut().find(|(path, _)| *path == task.path) {
+                    Some((_, items)) => items.push(task.clone()),
+                    None => groups.push((task.path.clone(), vec![task.clone()])),
+                }
+            }
+            QueryOutput::Task(groups)
+        }
+    })
+}
@@ -0,0 +1,239 @@
+// Oxidian — Query Blocks
+// Dataview-style ```query blocks (LIST / TABLE / TASK with FROM, WHERE, SORT,
+// LIMIT), evaluated against the vault metadata cache and rendered to HTML.
+
+pub mod eval;
+pub mod parser;
+pub mod render;
+
+use crate::engine::markdown as md;
+use crate::features::properties::PropertyIndex;
+use crate::features::tasks::TaskIndex;
+use crate::state::VaultMetaCache;
+use chrono::{Local, NaiveDateTime};
+use eval::{Page, QueryOutput};
+use std::cell::OnceCell;
+
+// ─── QueryContext ──────────────────────────────────────────────────
+
+/// Everything a query needs to run. Pages are collected on first use, so
+/// notes without query blocks cost nothing.
+pub struct QueryContext<'a> {
+    vault_path: &'a str,
+    cache: &'a VaultMetaCache,
+    properties: &'a PropertyIndex,
+    tasks: &'a TaskIndex,
+    now: NaiveDateTime,
+    pages: OnceCell<Vec<Page>>,
+}
+
+impl<'a> QueryContext<'a> {
+    pub fn new(vault_path: &'a str, cache: &'a VaultMetaCache, properties: &'a PropertyIndex, tasks: &'a TaskIndex) -> Self {
+        Self::at(vault_path, cache, properties, tasks, Local::now().naive_local())
+    }
+
+    /// Context with a fixed "now" for `date(today)` and friends.
+    pub fn at(
+        vault_path: &'a str,
+        cache: &'a VaultMetaCache,
+        properties: &'a PropertyIndex,
+        tasks: &'a TaskIndex,
+        now: NaiveDateTime,
+    ) -> Self {
+        QueryContext { vault_path, cache, properties, tasks, now, pages: OnceCell::new() }
+    }
+
+    fn pages(&self) -> &[Page] {
+        self.pages.get_or_init(|| eval::collect_pages(self.vault_path, self.cache, self.properties))
+    }
+}
+
+// ─── Entry points ──────────────────────────────────────────────────
+
+/// Parse and run a query block body.
+pub fn run_query(source: &str, ctx: &QueryContext) -> Result<QueryOutput, String> {
+    let query = parser::parse_query(source)?;
+    eval::execute(&query, ctx.pages(), ctx.tasks, ctx.now)
+}
+
+/// Paths of the notes selected by a query. The leading `LIST` may be left
+/// out, e.g. `FROM #project WHERE status = "active"`.
+pub fn query_paths(source: &str, ctx: &QueryContext) -> Result<Vec<String>, String> {
+    let first = source.split_whitespace().next().unwrap_or("").to_lowercase();
+    let query = if ["list", "table", "task"].contains(&first.as_str()) {
+        parser::parse_query(source)?
+    } else {
+        parser::parse_query(&format!("LIST {}", source))?
+    };
+    eval::matching_paths(&query, ctx.pages(), ctx.now)
+}
+
+/// Query block body → HTML (results, or an inline error message).
+pub fn render_query(source: &str, ctx: &QueryContext) -> String {
+    match run_query(source, ctx) {
+        Ok(output) => render::output_html(&output),
+        Err(e) => render::error_html(&e),
+    }
+}
+
+/// Render a note, evaluating its ```query blocks.
+pub fn render_markdown(content: &str, ctx: &QueryContext) -> String {
+    md::render_markdown_with_queries(content, &|source| render_query(source, ctx))
+}
+
+// ─── Tests ─────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use eval::Value;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn setup() -> (TempDir, VaultMetaCache, PropertyIndex, TaskIndex) {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("Projects")).unwrap();
+        fs::create_dir_all(root.join("Archive")).unwrap();
+        fs::write(root.join("Projects/Apollo.md"),
+            "---\nstatus: active\npriority: 2\ndue: 2025-03-10\ntags: [project]\n---\nSee [[Hub]].\n- [ ] Book venue\n- [x] Draft plan\n").unwrap();
+        fs::write(root.join("Projects/Zephyr.md"),
+            "---\nstatus: done\npriority: 1\ndue: 2025-02-01\n---\n#project #project/infra\n- [ ] Write [[Hub|summary]]\n```\n- [ ] not a task\n```\n").unwrap();
+        fs::write(root.join("Archive/Old.md"), "---\nstatus: active\n---\n#project\n").unwrap();
+        fs::write(root.join("Hub.md"), "Index of [[Apollo]] and [[Zephyr]].\nreviewed:: true").unwrap();
+        let vp = root.to_str().unwrap();
+        let mut cache = VaultMetaCache::new();
+        cache.rebuild(vp);
+        let mut props = PropertyIndex::new();
+        props.build_from_vault(vp, None);
+        let mut tasks = TaskIndex::new();
+        tasks.build_from_vault(vp, None);
+        (dir, cache, props, tasks)
+    }
+
+    fn now() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2025, 3, 5).unwrap().and_hms_opt(12, 0, 0).unwrap()
+    }
+
+    fn list_names(output: QueryOutput) -> Vec<String> {
+        match output {
+            QueryOutput::List(items) => items.into_iter().map(|(link, _)| link.display()).collect(),
+            other => panic!("expected list, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_list_from_tag_and_folder() {
+        let (dir, cache, props, tasks) = setup();
+        let ctx = QueryContext::at(dir.path().to_str().unwrap(), &cache, &props, &tasks, now());
+        assert_eq!(list_names(run_query("LIST FROM #project", &ctx).unwrap()), vec!["Old", "Apollo", "Zephyr"]);
+        assert_eq!(list_names(run_query("LIST FROM #project AND -\"Archive\"", &ctx).unwrap()), vec!["Apollo", "Zephyr"]);
+        assert_eq!(list_names(run_query("LIST FROM #project/infra", &ctx).unwrap()), vec!["Zephyr"]);
+        assert_eq!(list_names(run_query("LIST FROM [[Hub]]", &ctx).unwrap()), vec!["Apollo", "Zephyr"]);
+        assert_eq!(list_names(run_query("LIST FROM outgoing([[Hub]])", &ctx).unwrap()), vec!["Apollo", "Zephyr"]);
+    }
+
+    #[test]
+    fn test_table_where_sort_limit() {
+        let (dir, cache, props, tasks) = setup();
+        let ctx = QueryContext::at(dir.path().to_str().unwrap(), &cache, &props, &tasks, now());
+        let out = run_query(
+            "TABLE status, due AS \"Due\" FROM \"Projects\" WHERE due >= date(today) - dur(60 days) SORT priority ASC LIMIT 5",
+            &ctx,
+        ).unwrap();
+        let QueryOutput::Table { headers, rows } = out else { panic!("expected table") };
+        assert_eq!(headers, vec!["File", "status", "Due"]);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0][0].display(), "Zephyr");
+        assert_eq!(rows[1][1], Value::Text("active".into()));
+        assert_eq!(rows[1][2].display(), "2025-03-10");
+
+        let out = run_query("TABLE WITHOUT ID file.name WHERE status = \"active\" AND file.inlinks SORT file.name DESC LIMIT 1", &ctx).unwrap();
+        let QueryOutput::Table { headers, rows } = out else { panic!("expected table") };
+        assert_eq!(headers, vec!["file.name"]);
+        assert_eq!(rows, vec![vec![Value::Text("Apollo".into())]]);
+    }
+
+    #[test]
+    fn test_date_arithmetic_out_of_range_is_null() {
+        let (dir, cache, props, tasks) = setup();
+        let ctx = QueryContext::at(dir.path().to_str().unwrap(), &cache, &props, &tasks, now());
+        let out = run_query(
+            "TABLE WITHOUT ID date(today) + dur(1000000000 years), date(today) - dur(300000 years), dur(1 day) + dur(9999999999999999 years) LIMIT 1",
+            &ctx,
+        ).unwrap();
+        let QueryOutput::Table { rows, .. } = out else { panic!("expected table") };
+        assert_eq!(rows, vec![vec![Value::Null, Value::Null, Value::Null]]);
+        assert!(query_paths("WHERE due < date(today) + dur(1000000000 years)", &ctx).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_task_query() {
+        let (dir, cache, props, tasks) = setup();
+        let ctx = QueryContext::at(dir.path().to_str().unwrap(), &cache, &props, &tasks, now());
+        let QueryOutput::Task(groups) = run_query("TASK FROM \"Projects\" WHERE !completed", &ctx).unwrap() else {
+            panic!("expected tasks")
+        };
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].0, "Projects/Apollo.md");
+        assert_eq!(groups[0].1.len(), 1);
+        assert_eq!(groups[0].1[0].text, "Book venue");
+        assert_eq!(groups[0].1[0].line, 8);
+        assert_eq!(groups[1].1.len(), 1);
+
+        // Tasks come from the index, so notes encrypted on disk still show up
+        let apollo = dir.path().join("Projects/Apollo.md");
+        let plain = fs::read_to_string(&apollo).unwrap();
+        fs::write(&apollo, crate::encryption::encrypt_file_content(&plain, "pw").unwrap()).unwrap();
+        let QueryOutput::Task(groups) = run_query("TASK FROM \"Projects\" WHERE !completed", &ctx).unwrap() else {
+            panic!("expected tasks")
+        };
+        assert_eq!(groups[0].1[0].text, "Book venue");
+    }
+
+    #[test]
+    fn test_render_query_blocks() {
+        let (dir, cache, props, tasks) = setup();
+        let ctx = QueryContext::at(dir.path().to_str().unwrap(), &cache, &props, &tasks, now());
+        let html = render_markdown("# Dash\n\n```query\nTABLE status FROM #project\n```\n\n```query\nLIST WHERE bogus(1)\n```\n", &ctx);
+        assert!(html.contains(r#"<table class="query-table">"#));
+        assert!(html.contains(r#"data-target="Projects/Apollo""#));
+        assert!(html.contains("<td>active</td>"));
+        assert!(html.contains(r#"<div class="query-error">Query error: Unknown function 'bogus'</div>"#));
+
+        let html = render_markdown("```query\nTASK FROM [[Hub]]\n```", &ctx);
+        assert!(html.contains(r#"data-line="9" checked="""#));
+        assert!(html.contains(r#"class="wiki-link" data-target="Hub""#));
+        let html = render_markdown("```query\nLIST FROM \"Nowhere\"\n```", &ctx);
+        assert!(html.contains("query-empty"));
+    }
+
+    #[test]
+    fn test_query_paths() {
+        let (dir, cache, props, tasks) = setup();
+        let ctx = QueryContext::at(dir.path().to_str().unwrap(), &cache, &props, &tasks, now());
+        assert_eq!(query_paths("FROM #project WHERE status = \"active\"", &ctx).unwrap(), vec!["Archive/Old.md", "Projects/Apollo.md"]);
+        assert_eq!(query_paths("TABLE priority SORT priority DESC LIMIT 1", &ctx).unwrap(), vec!["Projects/Apollo.md"]);
+        // Inline fields are queryable like frontmatter
+        assert_eq!(query_paths("WHERE reviewed", &ctx).unwrap(), vec!["Hub.md"]);
+    }
+
+    #[test]
+    fn test_links_follow_the_resolved_note() {
+        let (dir, mut cache, props, tasks) = setup();
+        let vp = dir.path().to_str().unwrap();
+        fs::write(dir.path().join("Archive/Plan.md"), "Retired").unwrap();
+        fs::write(dir.path().join("Projects/Plan.md"), "Current").unwrap();
+        fs::write(dir.path().join("Linker.md"), "See [[Projects/Plan]]").unwrap();
+        cache.rebuild(vp);
+        let ctx = QueryContext::at(vp, &cache, &props, &tasks, now());
+        assert_eq!(query_paths("FROM outgoing([[Linker]])", &ctx).unwrap(), vec!["Projects/Plan.md"]);
+
+        let pages = eval::collect_pages(vp, &cache, &props);
+        let page = |path: &str| pages.iter().find(|p| p.path == path).unwrap();
+        assert_eq!(page("Linker.md").outlinks, vec!["Projects/Plan.md"]);
+        assert_eq!(page("Projects/Plan.md").inlinks, vec!["Linker.md"]);
+        assert!(page("Archive/Plan.md").inlinks.is_empty());
+    }
+}
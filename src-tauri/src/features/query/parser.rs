@@ -0,0 +1,664 @@
+// Oxidian — Query Parser
+// Tokenizer and recursive-descent parser for ```query blocks:
+//
+//   LIST [expr] | TABLE [WITHOUT ID] expr [AS "Header"], ... | TASK
+//   [FROM #tag | "folder" | [[note]] | outgoing([[note]]) combined with and/or/-]
+//   [WHERE expr] [SORT expr [ASC|DESC], ...] [LIMIT n]
+
+use super::eval::Value;
+
+// ─── AST ───────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    pub kind: QueryKind,
+    pub from: Option<Source>,
+    /// WHERE / SORT / LIMIT, applied in the order they were written.
+    pub clauses: Vec<Clause>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueryKind {
+    List(Option<Expr>),
+    Table { without_id: bool, columns: Vec<Column> },
+    Task,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Column {
+    pub expr: Expr,
+    pub header: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Source {
+    Tag(String),
+    Folder(String),
+    /// Notes linking to the given note.
+    LinksTo(String),
+    /// Notes the given note links to.
+    OutgoingFrom(String),
+    And(Box<Source>, Box<Source>),
+    Or(Box<Source>, Box<Source>),
+    Not(Box<Source>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Clause {
+    Where(Expr),
+    Sort(Vec<SortKey>),
+    Limit(usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SortKey {
+    pub expr: Expr,
+    pub descending: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinOp {
+    Eq, Ne, Lt, Le, Gt, Ge,
+    Add, Sub, Mul, Div,
+    And, Or,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Literal(Value),
+    /// Dotted field path, e.g. `file.name` or `status`.
+    Field(Vec<String>),
+    Call(String, Vec<Expr>),
+    List(Vec<Expr>),
+    Not(Box<Expr>),
+    Neg(Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+}
+
+// ─── Tokenizer ─────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    Ident(String),
+    Str(String),
+    Num(f64),
+    Tag(String),
+    Link(String),
+    Op(&'static str),
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Comma,
+    Dot,
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    tok: Tok,
+    start: usize,
+    end: usize,
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn tokenize(src: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<(usize, char)> = src.char_indices().collect();
+    let byte_at = |i: usize| chars.get(i).map(|(b, _)| *b).unwrap_or(src.len());
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let (start, c) = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        let mut j = i + 1;
+        let tok = match c {
+            '"' | '\'' => {
+                let mut s = String::new();
+                let mut closed = false;
+                while j < chars.len() {
+                    let ch = chars[j].1;
+                    j += 1;
+                    if ch == '\\' && j < chars.len() {
+                        s.push(chars[j].1);
+                        j += 1;
+                    } else if ch == c {
+                        closed = true;
+                        break;
+                    } else {
+                        s.push(ch);
+                    }
+                }
+                if !closed {
+                    return Err(format!("Unterminated string at offset {}", start));
+                }
+                Tok::Str(s)
+            }
+            '[' if chars.get(j).map(|(_, ch)| *ch) == Some('[') => {
+                let rest = &src[byte_at(j + 1)..];
+                let close = rest.find("]]").ok_or_else(|| format!("Unterminated link at offset {}", start))?;
+                let inner = &rest[..close];
+                let end = byte_at(j + 1) + close + 2;
+                while j < chars.len() && chars[j].0 < end {
+                    j += 1;
+                }
+                Tok::Link(inner.to_string())
+            }
+            '#' => {
+                while j < chars.len() && (is_ident_char(chars[j].1) || chars[j].1 == '/' || chars[j].1 == '-') {
+                    j += 1;
+                }
+                if j == i + 1 {
+                    return Err(format!("Empty tag at offset {}", start));
+                }
+                Tok::Tag(src[byte_at(i + 1)..byte_at(j)].to_string())
+            }
+            c if c.is_ascii_digit() => {
+                while j < chars.len() && chars[j].1.is_ascii_digit() {
+                    j += 1;
+                }
+                if chars.get(j).map(|(_, ch)| *ch) == Some('.')
+                    && chars.get(j + 1).is_some_and(|(_, ch)| ch.is_ascii_digit())
+                {
+                    j += 1;
+                    while j < chars.len() && chars[j].1.is_ascii_digit() {
+                        j += 1;
+                    }
+                }
+                let text = &src[start..byte_at(j)];
+                Tok::Num(text.parse().map_err(|_| format!("Invalid number '{}'", text))?)
+            }
+            c if is_ident_char(c) => {
+                while j < chars.len() && is_ident_char(chars[j].1) {
+                    j += 1;
+                }
+                Tok::Ident(src[start..byte_at(j)].to_string())
+            }
+            '(' => Tok::LParen,
+            ')' => Tok::RParen,
+            '[' => Tok::LBracket,
+            ']' => Tok::RBracket,
+            ',' => Tok::Comma,
+            '.' => Tok::Dot,
+            _ => {
+                let next = chars.get(j).map(|(_, ch)| *ch);
+                let (op, len) = match (c, next) {
+                    ('!', Some('=')) => ("!=", 2),
+                    ('<', Some('=')) => ("<=", 2),
+                    ('>', Some('=')) => (">=", 2),
+                    ('=', Some('=')) => ("=", 2),
+                    ('&', Some('&')) => ("&", 2),
+                    ('|', Some('|')) => ("|", 2),
+                    ('=', _) => ("=", 1),
+                    ('<', _) => ("<", 1),
+                    ('>', _) => (">", 1),
+                    ('!', _) => ("!", 1),
+                    ('+', _) => ("+", 1),
+                    ('-', _) => ("-", 1),
+                    ('*', _) => ("*", 1),
+                    ('/', _) => ("/", 1),
+                    ('&', _) => ("&", 1),
+                    ('|', _) => ("|", 1),
+                    _ => return Err(format!("Unexpected character '{}' at offset {}", c, start)),
+                };
+                j = i + len;
+                Tok::Op(op)
+            }
+        };
+        tokens.push(Token { tok, start, end: byte_at(j) });
+        i = j;
+    }
+    Ok(tokens)
+}
+
+// ─── Parser ────────────────────────────────────────────────────────
+
+const CLAUSE_KEYWORDS: &[&str] = &["from", "where", "sort", "limit"];
+
+/// Keywords accepted as the bare argument of `date(...)`.
+pub(crate) const DATE_KEYWORDS: &[&str] = &[
+    "today", "now", "tomorrow", "yesterday", "sow", "eow", "som", "eom", "soy", "eoy",
+];
+
+struct Parser<'a> {
+    src: &'a str,
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+/// Parse the body of a ```query block.
+pub fn parse_query(src: &str) -> Result<Query, String> {
+    let mut p = Parser { src, tokens: tokenize(src)?, pos: 0 };
+    let kind = p.parse_kind()?;
+
+    let mut from = None;
+    let mut clauses = Vec::new();
+    while let Some(keyword) = p.peek_keyword() {
+        p.pos += 1;
+        match keyword.as_str() {
+            "from" => {
+                if from.is_some() {
+                    return Err("FROM may only appear once".to_string());
+                }
+                if !clauses.is_empty() {
+                    return Err("FROM must come before WHERE, SORT and LIMIT".to_string());
+                }
+                from = Some(p.parse_source()?);
+            }
+            "where" => clauses.push(Clause::Where(p.parse_expr()?)),
+            "sort" => clauses.push(Clause::Sort(p.parse_sort_keys()?)),
+            "limit" => match p.next() {
+                Some(Tok::Num(n)) if n >= 0.0 && n.fract() == 0.0 => clauses.push(Clause::Limit(n as usize)),
+                _ => return Err("LIMIT expects a whole number".to_string()),
+            },
+            _ => unreachable!(),
+        }
+    }
+    if let Some(token) = p.tokens.get(p.pos) {
+        return Err(format!("Unexpected '{}'", &src[token.start..token.end]));
+    }
+    Ok(Query { kind, from, clauses })
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Tok> {
+        self.tokens.get(self.pos).map(|t| &t.tok)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&Tok> {
+        self.tokens.get(self.pos + offset).map(|t| &t.tok)
+    }
+
+    fn next(&mut self) -> Option<Tok> {
+        let tok = self.tokens.get(self.pos).map(|t| t.tok.clone());
+        self.pos += 1;
+        tok
+    }
+
+    fn expect(&mut self, expected: Tok, what: &str) -> Result<(), String> {
+        match self.next() {
+            Some(tok) if tok == expected => Ok(()),
+            _ => Err(format!("Expected {}", what)),
+        }
+    }
+
+    fn peek_ident(&self, word: &str) -> bool {
+        matches!(self.peek(), Some(Tok::Ident(s)) if s.eq_ignore_ascii_case(word))
+    }
+
+    fn eat_ident(&mut self, word: &str) -> bool {
+        let found = self.peek_ident(word);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn peek_op(&self, op: &str) -> bool {
+        matches!(self.peek(), Some(Tok::Op(o)) if *o == op)
+    }
+
+    fn peek_keyword(&self) -> Option<String> {
+        match self.peek() {
+            Some(Tok::Ident(s)) => {
+                let lower = s.to_lowercase();
+                CLAUSE_KEYWORDS.contains(&lower.as_str()).then_some(lower)
+            }
+            _ => None,
+        }
+    }
+
+    fn at_clause_end(&self) -> bool {
+        self.peek().is_none() || self.peek_keyword().is_some()
+    }
+
+    fn parse_kind(&mut self) -> Result<QueryKind, String> {
+        match self.next() {
+            Some(Tok::Ident(s)) if s.eq_ignore_ascii_case("list") => {
+                if self.at_clause_end() {
+                    Ok(QueryKind::List(None))
+                } else {
+                    Ok(QueryKind::List(Some(self.parse_expr()?)))
+                }
+            }
+            Some(Tok::Ident(s)) if s.eq_ignore_ascii_case("table") => {
+                let without_id = if self.peek_ident("without") {
+                    self.pos += 1;
+                    if !self.eat_ident("id") {
+                        return Err("Expected ID after WITHOUT".to_string());
+                    }
+                    true
+                } else {
+                    false
+                };
+                let mut columns = Vec::new();
+                while !self.at_clause_end() {
+                    if !columns.is_empty() {
+                        self.expect(Tok::Comma, "',' between columns")?;
+                    }
+                    columns.push(self.parse_column()?);
+                }
+                Ok(QueryKind::Table { without_id, columns })
+            }
+            Some(Tok::Ident(s)) if s.eq_ignore_ascii_case("task") => Ok(QueryKind::Task),
+            _ => Err("Query must start with LIST, TABLE or TASK".to_string()),
+        }
+    }
+
+    fn parse_column(&mut self) -> Result<Column, String> {
+        let start = self.tokens.get(self.pos).map(|t| t.start).unwrap_or(0);
+        let expr = self.parse_expr()?;
+        let end = self.tokens.get(self.pos - 1).map(|t| t.end).unwrap_or(start);
+        let header = if self.eat_ident("as") {
+            match self.next() {
+                Some(Tok::Str(s)) | Some(Tok::Ident(s)) => s,
+                _ => return Err("Expected a column name after AS".to_string()),
+            }
+        } else {
+            self.src[start..end].trim().to_string()
+        };
+        Ok(Column { expr, header })
+    }
+
+    fn parse_sort_keys(&mut self) -> Result<Vec<SortKey>, String> {
+        let mut keys = Vec::new();
+        loop {
+            let expr = self.parse_expr()?;
+            let descending = self.eat_ident("desc") || self.eat_ident("descending");
+            if !descending && !self.eat_ident("asc") {
+                self.eat_ident("ascending");
+            }
+            keys.push(SortKey { expr, descending });
+            if self.peek() != Some(&Tok::Comma) {
+                break;
+            }
+            self.pos += 1;
+        }
+        Ok(keys)
+    }
+
+    // ─── FROM sources ──────────────────────────────────────────────
+
+    fn parse_source(&mut self) -> Result<Source, String> {
+        let mut left = self.parse_source_and()?;
+        while self.eat_ident("or") || self.eat_op("|") {
+            let right = self.parse_source_and()?;
+            left = Source::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_source_and(&mut self) -> Result<Source, String> {
+        let mut left = self.parse_source_unary()?;
+        while self.eat_ident("and") || self.eat_op("&") {
+            let right = self.parse_source_unary()?;
+            left = Source::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_source_unary(&mut self) -> Result<Source, String> {
+        if self.eat_op("-") || self.eat_op("!") {
+            return Ok(Source::Not(Box::new(self.parse_source_unary()?)));
+        }
+        match self.next() {
+            Some(Tok::Tag(tag)) => Ok(Source::Tag(tag)),
+            Some(Tok::Str(folder)) => Ok(Source::Folder(folder)),
+            Some(Tok::Link(link)) => Ok(Source::LinksTo(link)),
+            Some(Tok::LParen) => {
+                let inner = self.parse_source()?;
+                self.expect(Tok::RParen, "')'")?;
+                Ok(inner)
+            }
+            Some(Tok::Ident(s)) if s.eq_ignore_ascii_case("outgoing") => {
+                self.expect(Tok::LParen, "'(' after outgoing")?;
+                let link = match self.next() {
+                    Some(Tok::Link(link)) => link,
+                    _ => return Err("outgoing() expects a [[link]]".to_string()),
+                };
+                self.expect(Tok::RParen, "')'")?;
+                Ok(Source::OutgoingFrom(link))
+            }
+            _ => Err("FROM expects #tag, \"folder\" or [[link]]".to_string()),
+        }
+    }
+
+    fn eat_op(&mut self, op: &str) -> bool {
+        let found = self.peek_op(op);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    // ─── Expressions ───────────────────────────────────────────────
+
+    fn parse_expr(&mut self) -> Result<Expr, String> {
+        let mut left = self.parse_and()?;
+        while self.eat_ident("or") || self.eat_op("|") {
+            let right = self.parse_and()?;
+            left = Expr::Binary(BinOp::Or, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, String> {
+        let mut left = self.parse_not()?;
+        while self.eat_ident("and") || self.eat_op("&") {
+            let right = self.parse_not()?;
+            left = Expr::Binary(BinOp::And, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr, String> {
+        if self.eat_op("!") || self.eat_ident("not") {
+            return Ok(Expr::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_comparison()
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr, String> {
+        let left = self.parse_additive()?;
+        let op = match self.peek() {
+            Some(Tok::Op("=")) => BinOp::Eq,
+            Some(Tok::Op("!=")) => BinOp::Ne,
+            Some(Tok::Op("<")) => BinOp::Lt,
+            Some(Tok::Op("<=")) => BinOp::Le,
+            Some(Tok::Op(">")) => BinOp::Gt,
+            Some(Tok::Op(">=")) => BinOp::Ge,
+            _ => return Ok(left),
+        };
+        self.pos += 1;
+        let right = self.parse_additive()?;
+        Ok(Expr::Binary(op, Box::new(left), Box::new(right)))
+    }
+
+    fn parse_additive(&mut self) -> Result<Expr, String> {
+        let mut left = self.parse_multiplicative()?;
+        loop {
+            let op = if self.eat_op("+") {
+                BinOp::Add
+            } else if self.eat_op("-") {
+                BinOp::Sub
+            } else {
+                return Ok(left);
+            };
+            let right = self.parse_multiplicative()?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+    }
+
+    fn parse_multiplicative(&mut self) -> Result<Expr, String> {
+        let mut left = self.parse_unary()?;
+        loop {
+            let op = if self.eat_op("*") {
+                BinOp::Mul
+            } else if self.eat_op("/") {
+                BinOp::Div
+            } else {
+                return Ok(left);
+            };
+            let right = self.parse_unary()?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, String> {
+        if self.eat_op("-") {
+            return Ok(Expr::Neg(Box::new(self.parse_unary()?)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Tok::Num(n)) => Ok(Expr::Literal(Value::Number(n))),
+            Some(Tok::Str(s)) => Ok(Expr::Literal(Value::Text(s))),
+            Some(Tok::Tag(t)) => Ok(Expr::Literal(Value::Text(format!("#{}", t)))),
+            Some(Tok::Link(l)) => Ok(Expr::Literal(Value::link(&l))),
+            Some(Tok::LParen) => {
+                let inner = self.parse_expr()?;
+                self.expect(Tok::RParen, "')'")?;
+                Ok(inner)
+            }
+            Some(Tok::LBracket) => {
+                let mut items = Vec::new();
+                while self.peek() != Some(&Tok::RBracket) {
+                    if !items.is_empty() {
+                        self.expect(Tok::Comma, "',' in list")?;
+                    }
+                    items.push(self.parse_expr()?);
+                }
+                self.pos += 1;
+                Ok(Expr::List(items))
+            }
+            Some(Tok::Ident(name)) => {
+                match name.to_lowercase().as_str() {
+                    "true" => return Ok(Expr::Literal(Value::Bool(true))),
+                    "false" => return Ok(Expr::Literal(Value::Bool(false))),
+                    "null" => return Ok(Expr::Literal(Value::Null)),
+                    _ => {}
+                }
+                if self.peek() == Some(&Tok::LParen) {
+                    return self.parse_call(name);
+                }
+                let mut path = vec![name];
+                while self.peek() == Some(&Tok::Dot) {
+                    match self.peek_at(1) {
+                        Some(Tok::Ident(seg)) => {
+                            path.push(seg.clone());
+                            self.pos += 2;
+                        }
+                        _ => return Err("Expected a field name after '.'".to_string()),
+                    }
+                }
+                Ok(Expr::Field(path))
+            }
+            Some(_) => {
+                let token = &self.tokens[self.pos - 1];
+                Err(format!("Unexpected '{}'", &self.src[token.start..token.end]))
+            }
+            None => Err("Unexpected end of query".to_string()),
+        }
+    }
+
+    fn parse_call(&mut self, name: String) -> Result<Expr, String> {
+        let lower = name.to_lowercase();
+        self.pos += 1; // '('
+
+        // `date(today)`, `date(2025-01-31)` and `dur(7 days)` take their
+        // argument verbatim rather than as an expression.
+        if (lower == "date" || lower == "dur") && !matches!(self.peek(), Some(Tok::Str(_))) {
+            let close = self.matching_paren()?;
+            let start = self.tokens[self.pos - 1].end;
+            let raw = self.src[start..self.tokens[close].start].trim().to_string();
+            let literal = lower == "dur"
+                || DATE_KEYWORDS.contains(&raw.to_lowercase().as_str())
+                || raw.starts_with(|c: char| c.is_ascii_digit());
+            if literal {
+                self.pos = close + 1;
+                return Ok(Expr::Call(lower, vec![Expr::Literal(Value::Text(raw))]));
+            }
+        }
+
+        let mut args = Vec::new();
+        while self.peek() != Some(&Tok::RParen) {
+            if self.peek().is_none() {
+                return Err(format!("Unclosed call to {}()", name));
+            }
+            if !args.is_empty() {
+                self.expect(Tok::Comma, "',' between arguments")?;
+            }
+            args.push(self.parse_expr()?);
+        }
+        self.pos += 1;
+        Ok(Expr::Call(lower, args))
+    }
+
+    /// Index of the `)` closing the call whose `(` was just consumed.
+    fn matching_paren(&self) -> Result<usize, String> {
+        let mut depth = 0;
+        for (i, token) in self.tokens.iter().enumerate().skip(self.pos) {
+            match token.tok {
+                Tok::LParen => depth += 1,
+                Tok::RParen if depth == 0 => return Ok(i),
+                Tok::RParen => depth -= 1,
+                _ => {}
+            }
+        }
+        Err("Unclosed '('".to_string())
+    }
+}
+
+// ─── Tests ─────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_table_query() {
+        let q = parse_query("TABLE status, file.mtime AS \"Edited\"\nFROM #project and -\"Archive\"\nWHERE status != \"done\"\nSORT file.mtime DESC\nLIMIT 10").unwrap();
+        match &q.kind {
+            QueryKind::Table { without_id, columns } => {
+                assert!(!without_id);
+                assert_eq!(columns[0].header, "status");
+                assert_eq!(columns[1].header, "Edited");
+                assert_eq!(columns[1].expr, Expr::Field(vec!["file".into(), "mtime".into()]));
+            }
+            other => panic!("unexpected kind {:?}", other),
+        }
+        assert_eq!(q.from, Some(Source::And(
+            Box::new(Source::Tag("project".into())),
+            Box::new(Source::Not(Box::new(Source::Folder("Archive".into())))),
+        )));
+        assert_eq!(q.clauses.len(), 3);
+        assert!(matches!(&q.clauses[1], Clause::Sort(keys) if keys[0].descending));
+        assert_eq!(q.clauses[2], Clause::Limit(10));
+    }
+
+    #[test]
+    fn test_parse_date_and_duration_literals() {
+        let q = parse_query("list where file.cday >= date(today) - dur(7 days) and due < date(2025-01-31)").unwrap();
+        let Clause::Where(expr) = &q.clauses[0] else { panic!("expected WHERE") };
+        let text = format!("{:?}", expr);
+        assert!(text.contains("Call(\"date\", [Literal(Text(\"today\"))])"));
+        assert!(text.contains("Call(\"dur\", [Literal(Text(\"7 days\"))])"));
+        assert!(text.contains("Call(\"date\", [Literal(Text(\"2025-01-31\"))])"));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse_query("SELECT *").is_err());
+        assert!(parse_query("LIST FROM").is_err());
+        assert!(parse_query("LIST WHERE (a = 1").is_err());
+        assert!(parse_query("LIST LIMIT many").is_err());
+        assert!(parse_query("TABLE a b").is_err());
+    }
+}
@@ -0,0 +1,105 @@
+// Oxidian — Query Rendering
+// Turns query results into the HTML embedded in rendered notes.
+
+use super::eval::{link_display, QueryOutput, Value};
+use crate::engine::markdown as md;
+
+pub fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn value_html(value: &Value) -> String {
+    match value {
+        Value::Link(target) => md::wiki_link_html(target, &link_display(target)),
+        Value::List(items) => items.iter().map(value_html).collect::<Vec<_>>().join(", "),
+        Value::Bool(b) => format!(
+            r#"<input type="checkbox" disabled=""{}>"#,
+            if *b { r#" checked="""# } else { "" }
+        ),
+        other => escape_html(&other.display()),
+    }
+}
+
+/// Task text rendered as inline markdown (links, emphasis, tags).
+fn inline_markdown(text: &str) -> String {
+    let html = md::render_markdown(text);
+    let trimmed = html.trim_end();
+    trimmed.strip_prefix("<p>")
+        .and_then(|s| s.strip_suffix("</p>"))
+        .unwrap_or(trimmed)
+        .to_string()
+}
+
+pub fn error_html(message: &str) -> String {
+    format!(r#"<div class="query-error">Query error: {}</div>"#, escape_html(message))
+}
+
+pub fn output_html(output: &QueryOutput) -> String {
+    let empty = r#"<div class="query-empty">No results</div>"#.to_string();
+    match output {
+        QueryOutput::List(items) => {
+            if items.is_empty() {
+                return empty;
+            }
+            let mut html = String::from(r#"<ul class="query-list">"#);
+            for (link, value) in items {
+                html.push_str("<li>");
+                html.push_str(&value_html(link));
+                if let Some(value) = value {
+                    html.push_str(": ");
+                    html.push_str(&value_html(value));
+                }
+                html.push_str("</li>");
+            }
+            html.push_str("</ul>");
+            html
+        }
+        QueryOutput::Table { headers, rows } => {
+            if rows.is_empty() {
+                return empty;
+            }
+            let mut html = String::from(r#"<table class="query-table"><thead><tr>"#);
+            for header in headers {
+                html.push_str(&format!("<th>{}</th>", escape_html(header)));
+            }
+            html.push_str("</tr></thead><tbody>");
+            for row in rows {
+                html.push_str("<tr>");
+                for cell in row {
+                    html.push_str(&format!("<td>{}</td>", value_html(cell)));
+                }
+                html.push_str("</tr>");
+            }
+            html.push_str("</tbody></table>");
+            html
+        }
+        QueryOutput::Task(groups) => {
+            if groups.is_empty() {
+                return empty;
+            }
+            let mut html = String::from(r#"<div class="query-tasks">"#);
+            for (path, tasks) in groups {
+                let target = path.strip_suffix(".md").unwrap_or(path);
+                html.push_str(&format!(
+                    r#"<div class="query-task-file">{}</div><ul class="contains-task-list">"#,
+                    md::wiki_link_html(target, &link_display(target))
+                ));
+                for task in tasks {
+                    html.push_str(&format!(
+                        r#"<li class="task-list-item"><input type="checkbox" class="task-checkbox" data-path="{}" data-line="{}"{}> {}</li>"#,
+                        escape_html(path),
+                        task.line,
+                        if task.completed() { r#" checked="""# } else { "" },
+                        inline_markdown(&task.text)
+                    ));
+                }
+                html.push_str("</ul>");
+            }
+            html.push_str("</div>");
+            html
+        }
+    }
+}
@@ -0,0 +1,127 @@
+// Oxidian — Related Notes Feature
+// "Similar notes" by content (more-like-this on the search index), skipping
+// notes that are already linked, plus a vault-wide link-candidate report.
+
+use crate::engine::search::{SearchIndex, SimilarNote};
+use crate::state::VaultMetaCache;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+// ─── LinkCandidate ─────────────────────────────────────────────────
+
+/// A pair of similar notes with no link between them in either direction.
+#[derive(Debug, Clone, Serialize)]
+pub struct LinkCandidate {
+    pub source: String,
+    pub target: String,
+    pub score: f32,
+}
+
+// ─── Linked notes ──────────────────────────────────────────────────
+
+/// All notes that `path` links to or that link to `path`, read from the
+/// cache's link adjacency in both directions.
+pub fn linked_paths(cache: &VaultMetaCache, path: &str) -> HashSet<String> {
+    let adjacency = &cache.adjacency;
+    let mut linked: HashSet<String> = adjacency.targets(path).into_iter().map(str::to_string).collect();
+    linked.extend(adjacency.sources(path).map(str::to_string));
+    linked.remove(path);
+    linked
+}
+
+// ─── Queries ───────────────────────────────────────────────────────
+
+/// Top `limit` notes similar in content to `path` that are not yet linked to it.
+pub fn similar_notes(index: &SearchIndex, cache: &VaultMetaCache, path: &str, limit: usize) -> Result<Vec<SimilarNote>, String> {
+    let exclude = linked_paths(cache, path);
+    index.more_like_this(path, &exclude, limit)
+}
+
+/// Similar-but-unlinked pairs across the whole vault, best first. Each note
+/// contributes up to `per_note` candidates scoring at least `min_score`; a
+/// pair found from both ends is reported once with its higher score.
+pub fn link_candidates(index: &SearchIndex, cache: &VaultMetaCache, per_note: usize, min_score: f32) -> Result<Vec<LinkCandidate>, String> {
+    let mut best: HashMap<(String, String), f32> = HashMap::new();
+
+    let mut paths: Vec<&String> = cache.entries.keys().collect();
+    paths.sort();
+    for path in paths {
+        let exclude = linked_paths(cache, path);
+        for similar in index.more_like_this(path, &exclude, per_note)? {
+            if similar.score < min_score { continue; }
+            let key = if *path < similar.path {
+                (path.clone(), similar.path.clone())
+            } else {
+                (similar.path.clone(), path.clone())
+            };
+            let score = best.entry(key).or_insert(similar.score);
+            if similar.score > *score { *score = similar.score; }
+        }
+    }
+
+    let mut candidates: Vec<LinkCandidate> = best.into_iter()
+        .map(|((source, target), score)| LinkCandidate { source, target, score })
+        .collect();
+    candidates.sort_by(|a, b| {
+        b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.source.cmp(&b.source))
+            .then_with(|| a.target.cmp(&b.target))
+    });
+    Ok(candidates)
+}
+
+// ─── Tests ─────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn setup() -> (TempDir, SearchIndex, VaultMetaCache) {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::write(root.join("a.md"), "Rust ownership borrowing lifetimes borrow checker. See [[b]].").unwrap();
+        fs::write(root.join("b.md"), "The borrow checker enforces ownership and lifetimes in Rust").unwrap();
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::write(root.join("sub/c.md"), "Rust ownership and borrowing explained").unwrap();
+        fs::write(root.join("d.md"), "Tomatoes need sun, water, patience").unwrap();
+        let vp = root.to_str().unwrap();
+        let mut index = SearchIndex::new(vp).unwrap();
+        index.reindex_vault(vp).unwrap();
+        let mut cache = VaultMetaCache::new();
+        cache.rebuild(vp);
+        (dir, index, cache)
+    }
+
+    #[test]
+    fn test_linked_paths_both_directions() {
+        let (_dir, _index, cache) = setup();
+        assert!(linked_paths(&cache, "a.md").contains("b.md"));
+        assert!(linked_paths(&cache, "b.md").contains("a.md"));
+        assert!(linked_paths(&cache, "d.md").is_empty());
+    }
+
+    #[test]
+    fn test_similar_notes_excludes_linked() {
+        let (_dir, index, cache) = setup();
+        let similar = similar_notes(&index, &cache, "a.md", 5).unwrap();
+        assert!(!similar.is_empty());
+        assert!(similar.iter().all(|s| s.path != "b.md"));
+        assert_eq!(similar[0].path, "sub/c.md");
+    }
+
+    #[test]
+    fn test_link_candidates_report() {
+        let (_dir, index, cache) = setup();
+        let report = link_candidates(&index, &cache, 5, 0.0).unwrap();
+        assert!(report.iter().any(|c| c.source == "b.md" && c.target == "sub/c.md"));
+        // Linked pair never reported, each pair reported once
+        assert!(!report.iter().any(|c| c.source == "a.md" && c.target == "b.md"));
+        let mut pairs: Vec<_> = report.iter().map(|c| (&c.source, &c.target)).collect();
+        let len = pairs.len();
+        pairs.dedup();
+        assert_eq!(pairs.len(), len);
+        assert!(report.windows(2).all(|w| w[0].score >= w[1].score));
+    }
+}
@@ -0,0 +1,886 @@
+// Oxidian — Tasks
+// Vault-wide task index: `- [ ]` items with status, tags, dates, priority and
+// recurrence (Tasks-plugin emoji and Dataview `due::` forms), kept current from
+// saves and file system events, plus in-place toggling of a single task line.
+
+use crate::encryption;
+use crate::engine::vault;
+use crate::features::file_recovery;
+use crate::features::properties::parse_date;
+use chrono::{Duration, Months, NaiveDate};
+use notify::{Event, EventKind};
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::LazyLock;
+use walkdir::WalkDir;
+
+// ─── Task ──────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskStatus {
+    Todo,
+    InProgress,
+    Done,
+    Cancelled,
+    /// Any other custom status character.
+    Other,
+}
+
+impl TaskStatus {
+    pub fn from_char(c: char) -> Self {
+        match c {
+            ' ' => TaskStatus::Todo,
+            'x' | 'X' => TaskStatus::Done,
+            '/' => TaskStatus::InProgress,
+            '-' => TaskStatus::Cancelled,
+            _ => TaskStatus::Other,
+        }
+    }
+}
+
+/// Task priority, most urgent first so that sorting ascending puts the
+/// important tasks on top.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskPriority {
+    Highest,
+    High,
+    Medium,
+    #[default]
+    Normal,
+    Low,
+    Lowest,
+}
+
+impl TaskPriority {
+    fn from_emoji(s: &str) -> Option<Self> {
+        Some(match s.trim_end_matches('\u{FE0F}') {
+            "🔺" => TaskPriority::Highest,
+            "⏫" => TaskPriority::High,
+            "🔼" => TaskPriority::Medium,
+            "🔽" => TaskPriority::Low,
+            "⏬" => TaskPriority::Lowest,
+            _ => return None,
+        })
+    }
+
+    fn from_name(s: &str) -> Option<Self> {
+        Some(match s.trim().to_lowercase().as_str() {
+            "highest" => TaskPriority::Highest,
+            "high" => TaskPriority::High,
+            "medium" => TaskPriority::Medium,
+            "normal" | "none" => TaskPriority::Normal,
+            "low" => TaskPriority::Low,
+            "lowest" => TaskPriority::Lowest,
+            _ => return None,
+        })
+    }
+}
+
+/// A task list item. Dates are `YYYY-MM-DD` strings.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Task {
+    pub path: String,
+    /// 1-based line number.
+    pub line: usize,
+    /// Width of the leading whitespace, for nesting.
+    pub indent: usize,
+    /// The character between the brackets.
+    pub status: char,
+    pub status_type: TaskStatus,
+    /// Description with dates, priority, recurrence and block id removed.
+    pub text: String,
+    pub tags: Vec<String>,
+    pub due: Option<String>,
+    pub scheduled: Option<String>,
+    pub start: Option<String>,
+    pub created: Option<String>,
+    pub done: Option<String>,
+    pub cancelled: Option<String>,
+    pub priority: TaskPriority,
+    pub recurrence: Option<String>,
+    pub block_id: Option<String>,
+}
+
+impl Task {
+    pub fn completed(&self) -> bool {
+        self.status_type == TaskStatus::Done
+    }
+
+    pub fn date(&self, field: TaskDateField) -> Option<&str> {
+        match field {
+            TaskDateField::Due => self.due.as_deref(),
+            TaskDateField::Scheduled => self.scheduled.as_deref(),
+            TaskDateField::Start => self.start.as_deref(),
+            TaskDateField::Created => self.created.as_deref(),
+            TaskDateField::Done => self.done.as_deref(),
+            TaskDateField::Cancelled => self.cancelled.as_deref(),
+        }
+    }
+
+    fn date_mut(&mut self, field: TaskDateField) -> &mut Option<String> {
+        match field {
+            TaskDateField::Due => &mut self.due,
+            TaskDateField::Scheduled => &mut self.scheduled,
+            TaskDateField::Start => &mut self.start,
+            TaskDateField::Created => &mut self.created,
+            TaskDateField::Done => &mut self.done,
+            TaskDateField::Cancelled => &mut self.cancelled,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskDateField {
+    Due,
+    Scheduled,
+    Start,
+    Created,
+    Done,
+    Cancelled,
+}
+
+impl TaskDateField {
+    fn from_emoji(s: &str) -> Option<Self> {
+        Some(match s.trim_end_matches('\u{FE0F}') {
+            "📅" | "📆" | "🗓" => TaskDateField::Due,
+            "⏳" | "⌛" => TaskDateField::Scheduled,
+            "🛫" => TaskDateField::Start,
+            "➕" => TaskDateField::Created,
+            "✅" => TaskDateField::Done,
+            "❌" => TaskDateField::Cancelled,
+            _ => return None,
+        })
+    }
+
+    pub(crate) fn from_key(s: &str) -> Option<Self> {
+        Some(match s.trim().to_lowercase().as_str() {
+            "due" => TaskDateField::Due,
+            "scheduled" => TaskDateField::Scheduled,
+            "start" => TaskDateField::Start,
+            "created" => TaskDateField::Created,
+            "completion" | "done" => TaskDateField::Done,
+            "cancelled" => TaskDateField::Cancelled,
+            _ => return None,
+        })
+    }
+
+    /// Dates that move forward when a recurring task spawns its next occurrence.
+    fn recurs(self) -> bool {
+        matches!(self, TaskDateField::Due | TaskDateField::Scheduled | TaskDateField::Start)
+    }
+}
+
+// ─── Parsing ───────────────────────────────────────────────────────
+
+static EMOJI_DATE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(📅|📆|🗓|⏳|⌛|🛫|➕|✅|❌)\x{FE0F}?[ \t]*(\d{4}-\d{2}-\d{2})").unwrap()
+});
+static PRIORITY_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(🔺|⏫|🔼|🔽|⏬)\x{FE0F}?").unwrap()
+});
+static RECURRENCE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"🔁\x{FE0F}?[ \t]*([^📅📆🗓⏳⌛🛫➕✅❌🔺⏫🔼🔽⏬🔁\[\(]*)").unwrap()
+});
+/// `[due:: 2025-01-01]` or `(due:: 2025-01-01)`.
+static FIELD_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"[\[(]([A-Za-z][\w-]*)::[ \t]*([^\]\)]*?)[ \t]*[\])]").unwrap()
+});
+/// Unbracketed `due:: 2025-01-01`.
+static BARE_FIELD_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?:^|[ \t])(due|scheduled|start|created|completion|cancelled)::[ \t]*(\d{4}-\d{2}-\d{2})").unwrap()
+});
+static BLOCK_ID_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?:^|[ \t])\^([A-Za-z0-9-]+)[ \t]*$").unwrap()
+});
+
+/// Split a list item into (indent, byte offset of the status character,
+/// status, body). Returns `None` for anything that is not a task.
+fn split_task_line(line: &str) -> Option<(usize, usize, char, &str)> {
+    let trimmed = line.trim_start();
+    let indent = line.len() - trimmed.len();
+    let rest = if let Some(r) = trimmed.strip_prefix(['-', '*', '+']) {
+        r
+    } else {
+        let digits = trimmed.find(|c: char| !c.is_ascii_digit()).unwrap_or(0);
+        match trimmed[digits..].strip_prefix(['.', ')']) {
+            Some(r) if digits > 0 => r,
+            _ => return None,
+        }
+    };
+    let after = rest.strip_prefix(' ')?.strip_prefix('[')?;
+    let status = after.chars().next()?;
+    let body = after[status.len_utf8()..].strip_prefix(']')?;
+    if !(body.is_empty() || body.starts_with(' ')) {
+        return None;
+    }
+    let status_at = line.len() - after.len();
+    Some((indent, status_at, status, body))
+}
+
+fn normalize_date(s: &str) -> Option<String> {
+    let s = s.trim();
+    let date = parse_date(s.get(..10).unwrap_or(s))?;
+    Some(date.format("%Y-%m-%d").to_string())
+}
+
+/// Parse one line as a task. `line` is 1-based.
+pub fn parse_task(path: &str, line: usize, text: &str) -> Option<Task> {
+    let (indent, _, status, body) = split_task_line(text)?;
+    let mut task = Task {
+        path: path.to_string(),
+        line,
+        indent,
+        status,
+        status_type: TaskStatus::from_char(status),
+        text: String::new(),
+        tags: vault::extract_tags(body),
+        due: None,
+        scheduled: None,
+        start: None,
+        created: None,
+        done: None,
+        cancelled: None,
+        priority: TaskPriority::Normal,
+        recurrence: None,
+        block_id: None,
+    };
+    let mut cuts: Vec<Range<usize>> = Vec::new();
+
+    if let Some(c) = BLOCK_ID_RE.captures(body) {
+        task.block_id = Some(c[1].to_string());
+        cuts.push(c.get(0).unwrap().range());
+    }
+    for c in EMOJI_DATE_RE.captures_iter(body) {
+        if let Some(field) = TaskDateField::from_emoji(&c[1]) {
+            *task.date_mut(field) = normalize_date(&c[2]);
+            cuts.push(c.get(0).unwrap().range());
+        }
+    }
+    for c in PRIORITY_RE.captures_iter(body) {
+        if let Some(p) = TaskPriority::from_emoji(&c[1]) {
+            task.priority = p;
+            cuts.push(c.get(0).unwrap().range());
+        }
+    }
+    if let Some(c) = RECURRENCE_RE.captures(body) {
+        let rule = c[1].trim();
+        if !rule.is_empty() {
+            task.recurrence = Some(rule.to_string());
+        }
+        cuts.push(c.get(0).unwrap().range());
+    }
+    for c in FIELD_RE.captures_iter(body) {
+        let key = c[1].to_lowercase();
+        let value = &c[2];
+        if let Some(field) = TaskDateField::from_key(&key) {
+            *task.date_mut(field) = normalize_date(value);
+        } else if key == "priority" {
+            task.priority = TaskPriority::from_name(value).unwrap_or_default();
+        } else if key == "repeat" || key == "recurrence" {
+            task.recurrence = Some(value.trim().to_string()).filter(|v| !v.is_empty());
+        } else {
+            continue;
+        }
+        cuts.push(c.get(0).unwrap().range());
+    }
+    for c in BARE_FIELD_RE.captures_iter(body) {
+        if let Some(field) = TaskDateField::from_key(&c[1]) {
+            *task.date_mut(field) = normalize_date(&c[2]);
+            cuts.push(c.get(0).unwrap().range());
+        }
+    }
+
+    cuts.sort_by_key(|r| r.start);
+    let mut text = String::new();
+    let mut pos = 0;
+    for cut in cuts {
+        if cut.start > pos {
+            text.push_str(&body[pos..cut.start]);
+        }
+        pos = pos.max(cut.end);
+    }
+    text.push_str(&body[pos.min(body.len())..]);
+    task.text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    Some(task)
+}
+
+/// All tasks of a note, skipping fenced code.
+pub fn parse_tasks(path: &str, content: &str) -> Vec<Task> {
+    let mut tasks = Vec::new();
+    let mut in_code = false;
+    for (i, line) in content.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code = !in_code;
+            continue;
+        }
+        if in_code {
+            continue;
+        }
+        if let Some(task) = parse_task(path, i + 1, line) {
+            tasks.push(task);
+        }
+    }
+    tasks
+}
+
+// ─── TaskQuery ─────────────────────────────────────────────────────
+
+/// Date range on one task date. `from`/`to` are inclusive and accept
+/// `YYYY-MM-DD`, `today`, `tomorrow` or `yesterday`; with neither set the
+/// filter only requires the date to be present.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TaskDateFilter {
+    pub field: TaskDateField,
+    #[serde(default)]
+    pub from: Option<String>,
+    #[serde(default)]
+    pub to: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct TaskQuery {
+    /// Any of these statuses; empty matches all.
+    pub status: Vec<TaskStatus>,
+    pub dates: Vec<TaskDateFilter>,
+    /// Open tasks whose due date is before today.
+    pub overdue: bool,
+    /// Tag without `#`; also matches nested tags (`project` matches `project/x`).
+    pub tag: Option<String>,
+    /// A note path, or a folder prefix.
+    pub path: Option<String>,
+    /// Case-insensitive substring of the task text.
+    pub text: Option<String>,
+    pub limit: Option<usize>,
+}
+
+fn resolve_date(s: &str, today: NaiveDate) -> Option<NaiveDate> {
+    match s.trim().to_lowercase().as_str() {
+        "today" => Some(today),
+        "tomorrow" => Some(today + Duration::days(1)),
+        "yesterday" => Some(today - Duration::days(1)),
+        other => parse_date(other),
+    }
+}
+
+impl TaskQuery {
+    pub fn matches(&self, task: &Task, today: NaiveDate) -> Result<bool, String> {
+        if !self.status.is_empty() && !self.status.contains(&task.status_type) {
+            return Ok(false);
+        }
+        if self.overdue {
+            let open = matches!(task.status_type, TaskStatus::Todo | TaskStatus::InProgress);
+            let late = task.due.as_deref().and_then(parse_date).is_some_and(|d| d < today);
+            if !(open && late) {
+                return Ok(false);
+            }
+        }
+        for filter in &self.dates {
+            let Some(date) = task.date(filter.field).and_then(parse_date) else { return Ok(false) };
+            let bound = |s: &Option<String>| -> Result<Option<NaiveDate>, String> {
+                s.as_deref()
+                    .map(|s| resolve_date(s, today).ok_or_else(|| format!("Invalid date '{}'", s)))
+                    .transpose()
+            };
+            if bound(&filter.from)?.is_some_and(|from| date < from) || bound(&filter.to)?.is_some_and(|to| date > to) {
+                return Ok(false);
+            }
+        }
+        if let Some(tag) = &self.tag {
+            let tag = tag.trim_start_matches('#').to_lowercase();
+            let nested = format!("{}/", tag);
+            if !task.tags.iter().any(|t| {
+                let t = t.to_lowercase();
+                t == tag || t.starts_with(&nested)
+            }) {
+                return Ok(false);
+            }
+        }
+        if let Some(path) = &self.path {
+            let folder = format!("{}/", path.trim_end_matches('/'));
+            if task.path != *path && !task.path.starts_with(&folder) {
+                return Ok(false);
+            }
+        }
+        if let Some(text) = &self.text {
+            if !task.text.to_lowercase().contains(&text.to_lowercase()) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+// ─── TaskIndex ─────────────────────────────────────────────────────
+
+/// Tasks per note, updated file by file.
+#[derive(Default)]
+pub struct TaskIndex {
+    files: HashMap<String, Vec<Task>>,
+}
+
+impl TaskIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Walk the vault once. Encrypted notes are only indexed when `password`
+    /// is available.
+    pub fn build_from_vault(&mut self, vault_path: &str, password: Option<&str>) {
+        self.files.clear();
+        for entry in WalkDir::new(vault_path)
+            .into_iter()
+            .filter_entry(|e| e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'))
+            .filter_map(|e| e.ok())
+        {
+            let path = entry.path();
+            if path.is_file() && path.extension().map(|e| e == "md").unwrap_or(false) {
+                let content = std::fs::read_to_string(path).ok()
+                    .and_then(|c| encryption::plaintext_content(c, password));
+                if let Some(content) = content {
+                    let relative = path.strip_prefix(vault_path)
+                        .unwrap_or(path)
+                        .to_string_lossy()
+                        .to_string();
+                    self.index_file(&relative, &content);
+                }
+            }
+        }
+    }
+
+    /// Re-index a single note.
+    pub fn index_file(&mut self, relative_path: &str, content: &str) {
+        let tasks = parse_tasks(relative_path, content);
+        if tasks.is_empty() {
+            self.files.remove(relative_path);
+        } else {
+            self.files.insert(relative_path.to_string(), tasks);
+        }
+    }
+
+    pub fn remove_file(&mut self, relative_path: &str) {
+        self.files.remove(relative_path);
+    }
+
+    /// Apply a file system event from the vault watcher. Renames arrive as
+    /// modify events on both paths, so every path is simply re-read or dropped
+    /// depending on whether it still exists. Returns true if anything changed.
+    pub fn handle_event(&mut self, vault_path: &str, event: &Event, password: Option<&str>) -> bool {
+        if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)) {
+            return false;
+        }
+        let mut changed = false;
+        for path in &event.paths {
+            let Ok(relative) = path.strip_prefix(vault_path) else { continue };
+            if path.extension().map(|e| e != "md").unwrap_or(true) {
+                continue;
+            }
+            let relative = relative.to_string_lossy().to_string();
+            let content = if path.is_file() {
+                std::fs::read_to_string(path).ok()
+                    .and_then(|c| encryption::plaintext_content(c, password))
+            } else {
+                None
+            };
+            match content {
+                Some(content) => self.index_file(&relative, &content),
+                None => self.remove_file(&relative),
+            }
+            changed = true;
+        }
+        changed
+    }
+
+    pub fn file_tasks(&self, relative_path: &str) -> &[Task] {
+        self.files.get(relative_path).map(|t| t.as_slice()).unwrap_or(&[])
+    }
+
+    pub fn task_count(&self) -> usize {
+        self.files.values().map(|t| t.len()).sum()
+    }
+
+    /// Tasks matching `query`, sorted by due date (undated last), priority,
+    /// then position in the vault.
+    pub fn query(&self, query: &TaskQuery, today: NaiveDate) -> Result<Vec<Task>, String> {
+        let mut out = Vec::new();
+        for task in self.files.values().flatten() {
+            if query.matches(task, today)? {
+                out.push(task.clone());
+            }
+        }
+        out.sort_by(|a, b| {
+            (a.due.is_none(), &a.due, a.priority, &a.path, a.line)
+                .cmp(&(b.due.is_none(), &b.due, b.priority, &b.path, b.line))
+        });
+        if let Some(limit) = query.limit {
+            out.truncate(limit);
+        }
+        Ok(out)
+    }
+}
+
+// ─── Toggling ──────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ToggleResult {
+    /// The toggled task, at its new line.
+    pub task: Task,
+    /// Next occurrence inserted above a completed recurring task.
+    pub next: Option<Task>,
+    #[serde(skip)]
+    pub content: String,
+}
+
+enum Interval {
+    Days(i64),
+    Months(u32),
+}
+
+/// `every day`, `every 3 weeks`, `every other month`, `every year`, with an
+/// optional trailing `when done`. Other rules are not understood.
+fn parse_recurrence(rule: &str) -> Option<(Interval, bool)> {
+    let rule = rule.trim().to_lowercase();
+    let (rule, when_done) = match rule.strip_suffix("when done") {
+        Some(r) => (r.trim().to_string(), true),
+        None => (rule, false),
+    };
+    let words: Vec<&str> = rule.split_whitespace().collect();
+    let (n, unit) = match words.as_slice() {
+        ["every", unit] => (1, *unit),
+        ["every", "other", unit] => (2, *unit),
+        ["every", n, unit] => (n.parse::<u32>().ok()?, *unit),
+        _ => return None,
+    };
+    let interval = match unit.trim_end_matches('s') {
+        "day" => Interval::Days(n as i64),
+        "week" => Interval::Days(7 * n as i64),
+        "month" => Interval::Months(n),
+        "year" => Interval::Months(n.checked_mul(12)?),
+        _ => return None,
+    };
+    Some((interval, when_done))
+}
+
+fn advance(date: NaiveDate, interval: &Interval) -> Option<NaiveDate> {
+    match interval {
+        Interval::Days(d) => date.checked_add_signed(Duration::try_days(*d)?),
+        Interval::Months(m) => date.checked_add_months(Months::new(*m)),
+    }
+}
+
+/// Remove a metadata span along with the whitespace in front of it.
+fn strip_span(s: &str, re: &Regex, keep: impl Fn(&Captures) -> bool) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut pos = 0;
+    for c in re.captures_iter(s) {
+        if keep(&c) {
+            continue;
+        }
+        let m = c.get(0).unwrap();
+        out.push_str(s[pos..m.start()].trim_end_matches([' ', '\t']));
+        pos = m.end();
+    }
+    out.push_str(&s[pos..]);
+    out
+}
+
+/// Insert `extra` at the end of a task line, before a trailing block id.
+fn append_metadata(line: &str, extra: &str) -> String {
+    match BLOCK_ID_RE.find(line) {
+        Some(m) => format!("{} {}{}", line[..m.start()].trim_end(), extra, &line[m.start()..]),
+        None => format!("{} {}", line.trim_end(), extra),
+    }
+}
+
+/// Build the next occurrence of a recurring task line, or `None` if the rule
+/// is not understood or the task has no date to recur from.
+fn next_occurrence(line: &str, task: &Task, today: NaiveDate) -> Option<String> {
+    let (interval, when_done) = parse_recurrence(task.recurrence.as_deref()?)?;
+    let reference = [TaskDateField::Due, TaskDateField::Scheduled, TaskDateField::Start]
+        .into_iter()
+        .find_map(|f| task.date(f).and_then(parse_date))?;
+    let base = advance(if when_done { today } else { reference }, &interval)?;
+    let shift = |date: &str| -> Option<String> {
+        let d = parse_date(date)?;
+        Some(base.checked_add_signed(d - reference)?.format("%Y-%m-%d").to_string())
+    };
+
+    let (_, status_at, status, _) = split_task_line(line)?;
+    let mut next = format!("{} {}", &line[..status_at], &line[status_at + status.len_utf8()..]);
+    // Completion dates and block ids belong to the finished occurrence only
+    next = strip_span(&next, &EMOJI_DATE_RE, |c| {
+        !matches!(TaskDateField::from_emoji(&c[1]), Some(TaskDateField::Done | TaskDateField::Cancelled))
+    });
+    next = strip_span(&next, &FIELD_RE, |c| {
+        !matches!(TaskDateField::from_key(&c[1]), Some(TaskDateField::Done | TaskDateField::Cancelled))
+    });
+    next = strip_span(&next, &BLOCK_ID_RE, |_| false);
+    next = EMOJI_DATE_RE.replace_all(&next, |c: &Captures| {
+        match TaskDateField::from_emoji(&c[1]).filter(|f| f.recurs()).and_then(|_| shift(&c[2])) {
+            Some(date) => c[0].replace(&c[2], &date),
+            None => c[0].to_string(),
+        }
+    }).to_string();
+    next = FIELD_RE.replace_all(&next, |c: &Captures| {
+        match TaskDateField::from_key(&c[1]).filter(|f| f.recurs()).and_then(|_| normalize_date(&c[2])).and_then(|d| shift(&d)) {
+            Some(date) => c[0].replacen(&c[2][..10.min(c[2].len())], &date, 1),
+            None => c[0].to_string(),
+        }
+    }).to_string();
+    next = BARE_FIELD_RE.replace_all(&next, |c: &Captures| {
+        match TaskDateField::from_key(&c[1]).filter(|f| f.recurs()).and_then(|_| shift(&c[2])) {
+            Some(date) => c[0].replace(&c[2], &date),
+            None => c[0].to_string(),
+        }
+    }).to_string();
+    Some(next)
+}
+
+/// Toggle the task on `line` (1-based) of `content`. Completing marks it `[x]`
+/// and, if the task already carries task metadata, records the done date in
+/// the same style (`✅ date` or `[completion:: date]`); recurring tasks get
+/// their next occurrence inserted above. Un-completing resets it to `[ ]` and
+/// drops the done date. No other line is touched.
+pub fn toggle_in_content(content: &str, path: &str, line: usize, today: NaiveDate) -> Result<ToggleResult, String> {
+    let task = parse_tasks(path, content)
+        .into_iter()
+        .find(|t| t.line == line)
+        .ok_or_else(|| format!("Line {} of {} is not a task", line, path))?;
+
+    let mut start = 0;
+    let mut raw_line = "";
+    for (i, l) in content.split_inclusive('\n').enumerate() {
+        if i + 1 == line {
+            raw_line = l;
+            break;
+        }
+        start += l.len();
+    }
+    let text = raw_line.trim_end_matches(['\n', '\r']);
+    let eol = &raw_line[text.len()..];
+    let (_, status_at, status, _) = split_task_line(text)
+        .ok_or_else(|| format!("Line {} of {} is not a task", line, path))?;
+
+    let today_str = today.format("%Y-%m-%d").to_string();
+    let mut replacement = String::new();
+    let new_line = if task.completed() {
+        let reopened = format!("{} {}", &text[..status_at], &text[status_at + status.len_utf8()..]);
+        let reopened = strip_span(&reopened, &EMOJI_DATE_RE, |c| TaskDateField::from_emoji(&c[1]) != Some(TaskDateField::Done));
+        strip_span(&reopened, &FIELD_RE, |c| TaskDateField::from_key(&c[1]) != Some(TaskDateField::Done))
+    } else {
+        if let Some(next) = next_occurrence(text, &task, today) {
+            replacement.push_str(&next);
+            replacement.push_str(if eol.is_empty() { "\n" } else { eol });
+        }
+        let done = format!("{}x{}", &text[..status_at], &text[status_at + status.len_utf8()..]);
+        let uses_fields = FIELD_RE.captures_iter(text).any(|c| {
+            let key = c[1].to_lowercase();
+            TaskDateField::from_key(&key).is_some() || key == "priority" || key == "repeat"
+        });
+        let uses_emoji = EMOJI_DATE_RE.is_match(text) || PRIORITY_RE.is_match(text) || RECURRENCE_RE.is_match(text);
+        if task.done.is_some() {
+            done
+        } else if uses_fields {
+            append_metadata(&done, &format!("[completion:: {}]", today_str))
+        } else if uses_emoji {
+            append_metadata(&done, &format!("✅ {}", today_str))
+        } else {
+            done
+        }
+    };
+    let inserted = !replacement.is_empty();
+    replacement.push_str(&new_line);
+    replacement.push_str(eol);
+
+    let mut updated = String::with_capacity(content.len() + replacement.len());
+    updated.push_str(&content[..start]);
+    updated.push_str(&replacement);
+    updated.push_str(&content[start + raw_line.len()..]);
+
+    let tasks = parse_tasks(path, &updated);
+    let toggled_line = if inserted { line + 1 } else { line };
+    let toggled = tasks.iter().find(|t| t.line == toggled_line).cloned()
+        .ok_or_else(|| format!("Failed to toggle task on line {}", line))?;
+    let next = if inserted { tasks.iter().find(|t| t.line == line).cloned() } else { None };
+    Ok(ToggleResult { task: toggled, next, content: updated })
+}
+
+/// Toggle a task in a note on disk, keeping the note encrypted if it was.
+pub fn toggle_task(vault_path: &str, path: &str, line: usize, password: Option<&str>, today: NaiveDate) -> Result<ToggleResult, String> {
+    let raw = vault::read_note(vault_path, path)?;
+    let encrypted = encryption::is_encrypted_content(&raw);
+    let content = encryption::plaintext_content(raw, password)
+        .ok_or_else(|| "Vault is locked — cannot edit encrypted note".to_string())?;
+    let result = toggle_in_content(&content, path, line, today)?;
+
+    file_recovery::create_snapshot(vault_path, path).ok();
+    let stored = match (encrypted, password) {
+        (true, Some(pwd)) => encryption::encrypt_file_content(&result.content, pwd)?,
+        _ => result.content.clone(),
+    };
+    vault::save_note(vault_path, path, &stored)?;
+    Ok(result)
+}
+
+// ─── Tests ─────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{CreateKind, RemoveKind};
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn day(s: &str) -> NaiveDate {
+        parse_date(s).unwrap()
+    }
+
+    #[test]
+    fn test_parse_emoji_metadata() {
+        let t = parse_task("a.md", 3, "  - [ ] Pay rent #home ⏫ 🔁 every month 📅 2025-03-01 ⏳ 2025-02-27 ➕ 2025-01-15 ^rent").unwrap();
+        assert_eq!(t.indent, 2);
+        assert_eq!(t.status_type, TaskStatus::Todo);
+        assert_eq!(t.text, "Pay rent #home");
+        assert_eq!(t.tags, vec!["home"]);
+        assert_eq!(t.priority, TaskPriority::High);
+        assert_eq!(t.recurrence.as_deref(), Some("every month"));
+        assert_eq!(t.due.as_deref(), Some("2025-03-01"));
+        assert_eq!(t.scheduled.as_deref(), Some("2025-02-27"));
+        assert_eq!(t.created.as_deref(), Some("2025-01-15"));
+        assert_eq!(t.block_id.as_deref(), Some("rent"));
+
+        let t = parse_task("a.md", 1, "1. [x] Ship it ✅ 2025-02-02").unwrap();
+        assert!(t.completed());
+        assert_eq!(t.done.as_deref(), Some("2025-02-02"));
+        assert_eq!(parse_task("a.md", 1, "- [/] Drafting").unwrap().status_type, TaskStatus::InProgress);
+        assert!(parse_task("a.md", 1, "- [ ]no space").is_none());
+        assert!(parse_task("a.md", 1, "- plain item").is_none());
+    }
+
+    #[test]
+    fn test_parse_dataview_fields() {
+        let t = parse_task("a.md", 1, "- [ ] Call Bob [due:: 2025-04-01] (priority:: high) [repeat:: every week] [mood:: ok]").unwrap();
+        assert_eq!(t.text, "Call Bob [mood:: ok]");
+        assert_eq!(t.due.as_deref(), Some("2025-04-01"));
+        assert_eq!(t.priority, TaskPriority::High);
+        assert_eq!(t.recurrence.as_deref(), Some("every week"));
+
+        let t = parse_task("a.md", 1, "- [ ] Renew passport due:: 2025-06-30").unwrap();
+        assert_eq!(t.text, "Renew passport");
+        assert_eq!(t.due.as_deref(), Some("2025-06-30"));
+    }
+
+    #[test]
+    fn test_parse_tasks_skips_code() {
+        let tasks = parse_tasks("a.md", "- [ ] one\n```\n- [ ] code\n```\n* [x] two\n");
+        assert_eq!(tasks.iter().map(|t| t.line).collect::<Vec<_>>(), vec![1, 5]);
+    }
+
+    #[test]
+    fn test_index_query() {
+        let mut index = TaskIndex::new();
+        index.index_file("Work/a.md", "- [ ] Report 📅 2025-03-01 #work\n- [x] Email ✅ 2025-02-20\n- [ ] Plan 🔺 📅 2025-03-01\n");
+        index.index_file("b.md", "- [ ] Someday\n- [-] Dropped 📅 2025-01-01\n");
+        let today = day("2025-03-05");
+
+        let open = TaskQuery { status: vec![TaskStatus::Todo], ..Default::default() };
+        let texts: Vec<String> = index.query(&open, today).unwrap().into_iter().map(|t| t.text).collect();
+        assert_eq!(texts, vec!["Plan", "Report #work", "Someday"]);
+
+        let overdue = TaskQuery { overdue: true, ..Default::default() };
+        assert_eq!(index.query(&overdue, today).unwrap().len(), 2);
+
+        let week = TaskQuery {
+            dates: vec![TaskDateFilter { field: TaskDateField::Done, from: Some("2025-02-15".into()), to: Some("today".into()) }],
+            ..Default::default()
+        };
+        assert_eq!(index.query(&week, today).unwrap()[0].text, "Email");
+
+        let tagged = TaskQuery { tag: Some("#work".into()), path: Some("Work".into()), ..Default::default() };
+        assert_eq!(index.query(&tagged, today).unwrap().len(), 1);
+
+        index.index_file("b.md", "no tasks");
+        assert_eq!(index.task_count(), 3);
+    }
+
+    #[test]
+    fn test_handle_event() {
+        let dir = TempDir::new().unwrap();
+        let vp = dir.path().to_str().unwrap();
+        let file = dir.path().join("todo.md");
+        let mut index = TaskIndex::new();
+
+        fs::write(&file, "- [ ] a\n- [ ] b\n").unwrap();
+        let event = Event::new(EventKind::Create(CreateKind::File)).add_path(file.clone());
+        assert!(index.handle_event(vp, &event, None));
+        assert_eq!(index.file_tasks("todo.md").len(), 2);
+
+        fs::remove_file(&file).unwrap();
+        let event = Event::new(EventKind::Remove(RemoveKind::File)).add_path(file);
+        assert!(index.handle_event(vp, &event, None));
+        assert_eq!(index.task_count(), 0);
+    }
+
+    #[test]
+    fn test_toggle_only_touches_line() {
+        let content = "# Todo\r\n- [ ] Plain\r\n- [ ] Tracked 📅 2025-03-01 ^t1\r\n- [ ] Field [due:: 2025-03-01]\r\nTail";
+        let today = day("2025-03-05");
+
+        let r = toggle_in_content(content, "a.md", 2, today).unwrap();
+        assert_eq!(r.content, content.replacen("- [ ] Plain", "- [x] Plain", 1));
+        assert!(r.task.completed());
+
+        let r = toggle_in_content(content, "a.md", 3, today).unwrap();
+        assert!(r.content.contains("- [x] Tracked 📅 2025-03-01 ✅ 2025-03-05 ^t1\r\n"));
+        let back = toggle_in_content(&r.content, "a.md", 3, today).unwrap();
+        assert_eq!(back.content, content);
+
+        let r = toggle_in_content(content, "a.md", 4, today).unwrap();
+        assert!(r.content.contains("- [x] Field [due:: 2025-03-01] [completion:: 2025-03-05]\r\nTail"));
+
+        assert!(toggle_in_content(content, "a.md", 1, today).is_err());
+    }
+
+    #[test]
+    fn test_toggle_recurring() {
+        let content = "- [ ] Water plants 🔁 every week ⏳ 2025-03-01 📅 2025-03-03\nnext\n";
+        let r = toggle_in_content(content, "a.md", 1, day("2025-03-04")).unwrap();
+        assert_eq!(
+            r.content,
+            "- [ ] Water plants 🔁 every week ⏳ 2025-03-08 📅 2025-03-10\n- [x] Water plants 🔁 every week ⏳ 2025-03-01 📅 2025-03-03 ✅ 2025-03-04\nnext\n"
+        );
+        assert_eq!(r.task.line, 2);
+        assert_eq!(r.next.unwrap().due.as_deref(), Some("2025-03-10"));
+
+        let content = "- [ ] Review [due:: 2025-01-31] [repeat:: every month when done]";
+        let r = toggle_in_content(content, "a.md", 1, day("2025-02-10")).unwrap();
+        assert!(r.content.starts_with("- [ ] Review [due:: 2025-03-10] [repeat:: every month when done]\n"));
+
+        // Rules that land outside the calendar complete without a next occurrence
+        for rule in ["every 4000000000 years", "every 4000000000 days"] {
+            let content = format!("- [ ] Far off 🔁 {} ⏳ 2025-01-01 📅 2025-03-03", rule);
+            let r = toggle_in_content(&content, "a.md", 1, day("2025-03-04")).unwrap();
+            assert!(r.next.is_none(), "{}", rule);
+            assert!(r.task.completed());
+        }
+        let content = "- [ ] Skewed 🔁 every 260000 years 📅 0001-01-01 🛫 9999-01-01";
+        let r = toggle_in_content(content, "a.md", 1, day("2025-03-04")).unwrap();
+        assert!(r.content.contains("🛫 9999-01-01"), "an unshiftable date is left as is");
+    }
+
+    #[test]
+    fn test_toggle_task_on_disk() {
+        let dir = TempDir::new().unwrap();
+        let vp = dir.path().to_str().unwrap();
+        fs::write(dir.path().join("n.md"), "intro\n- [ ] Do it\n").unwrap();
+        let r = toggle_task(vp, "n.md", 2, None, day("2025-03-05")).unwrap();
+        assert!(r.task.completed());
+        assert_eq!(fs::read_to_string(dir.path().join("n.md")).unwrap(), "intro\n- [x] Do it\n");
+    }
+}
@@ -0,0 +1,584 @@
+// Oxidian — Transclusion
+// Resolves `![[...]]` embeds: whole notes, heading ranges (`#H1#H2`), blocks
+// (`#^id`) and typed media. Nested embeds are expanded recursively with loop
+// detection, and every rendered region carries the file and lines it came from.
+
+use crate::encryption;
+use crate::engine::blocks;
+use crate::engine::vault;
+use regex::Regex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Component, Path};
+use std::sync::LazyLock;
+use walkdir::WalkDir;
+
+static EMBED_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"!\[\[([^\]\n]+)\]\]").unwrap());
+static CODE_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?s)```.*?```|`[^`\n]+`").unwrap());
+
+const IMAGE_EXTS: &[&str] = &["png", "jpg", "jpeg", "gif", "svg", "webp", "bmp", "ico", "avif"];
+const AUDIO_EXTS: &[&str] = &["mp3", "wav", "ogg", "m4a", "flac"];
+const VIDEO_EXTS: &[&str] = &["mp4", "webm", "mov", "mkv", "avi", "ogv"];
+
+// ─── Types ─────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmbedKind {
+    Note,
+    Heading,
+    Block,
+    Image,
+    Audio,
+    Video,
+    Pdf,
+    Canvas,
+    /// Any other attachment.
+    File,
+}
+
+impl EmbedKind {
+    fn from_path(path: &str) -> Self {
+        let ext = Path::new(path)
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        match ext.as_str() {
+            "" | "md" => EmbedKind::Note,
+            "pdf" => EmbedKind::Pdf,
+            "canvas" => EmbedKind::Canvas,
+            e if IMAGE_EXTS.contains(&e) => EmbedKind::Image,
+            e if AUDIO_EXTS.contains(&e) => EmbedKind::Audio,
+            e if VIDEO_EXTS.contains(&e) => EmbedKind::Video,
+            _ => EmbedKind::File,
+        }
+    }
+
+    /// Media the renderer draws itself from the path alone.
+    pub fn is_media(self) -> bool {
+        matches!(self, EmbedKind::Image | EmbedKind::Audio | EmbedKind::Video)
+    }
+
+    fn is_markdown(self) -> bool {
+        matches!(self, EmbedKind::Note | EmbedKind::Heading | EmbedKind::Block)
+    }
+}
+
+/// Lines of a vault file (1-based, inclusive) a rendered region came from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceRange {
+    pub path: String,
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Embed {
+    pub full_match: String,
+    /// Resolved vault path, or the raw target when it could not be resolved.
+    pub note_path: String,
+    /// Subpath after `#`: a heading path or `^block-id`.
+    pub heading: Option<String>,
+    pub kind: EmbedKind,
+    /// Expanded markdown (nested embeds replaced by their placeholders).
+    /// Empty for image/audio/video, `None` for other attachments.
+    pub content: Option<String>,
+    pub error: Option<String>,
+    /// The embed chain that loops back on itself, ending with the repeat.
+    pub cycle: Option<Vec<String>>,
+    /// Marker standing in for this embed in `processed` or its parent's
+    /// content. `None` when the original `![[...]]` text was kept.
+    pub placeholder: Option<String>,
+    pub depth: usize,
+    /// Position in the embed list of the embed containing this one.
+    pub parent: Option<usize>,
+    /// Byte offset and 1-based line of the embed within its parent's text.
+    pub index: usize,
+    pub line: usize,
+    pub source: Option<SourceRange>,
+    pub alias: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Transclusion {
+    pub processed: String,
+    /// Embeds in document order, parents before their children.
+    pub embeds: Vec<Embed>,
+}
+
+// ─── Sections ──────────────────────────────────────────────────────
+
+/// A slice of a note together with its 1-based line span.
+struct Section {
+    text: String,
+    start_line: usize,
+    end_line: usize,
+}
+
+/// Index of the first body line after a leading frontmatter block.
+fn body_start(lines: &[&str]) -> usize {
+    if lines.first().map(|l| l.trim_end() == "---").unwrap_or(false) {
+        if let Some(end) = lines.iter().skip(1).position(|l| l.trim_end() == "---") {
+            return end + 2;
+        }
+    }
+    0
+}
+
+fn section(lines: &[&str], start: usize, end: usize) -> Section {
+    // Trim blank lines at both ends so the span covers only real content.
+    let mut s = start;
+    let mut e = end;
+    while s < e && lines[s].trim().is_empty() {
+        s += 1;
+    }
+    while e > s && lines[e - 1].trim().is_empty() {
+        e -= 1;
+    }
+    Section {
+        text: lines[s..e].join("\n"),
+        start_line: s + 1,
+        end_line: e.max(s + 1),
+    }
+}
+
+/// Headings outside code fences as `(line index, level, title)`.
+fn headings(lines: &[&str], from: usize) -> Vec<(usize, usize, String)> {
+    let mut out = Vec::new();
+    let mut in_fence = false;
+    for (i, line) in lines.iter().enumerate().skip(from) {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence || !trimmed.starts_with('#') {
+            continue;
+        }
+        let level = trimmed.chars().take_while(|c| *c == '#').count();
+        let rest = &trimmed[level..];
+        if level <= 6 && (rest.is_empty() || rest.starts_with(' ') || rest.starts_with('\t')) {
+            out.push((i, level, rest.trim().trim_end_matches('#').trim().to_string()));
+        }
+    }
+    out
+}
+
+/// The range of a heading path like `Parent#Child`: each segment is looked
+/// up inside the previous one's section. The heading line itself is included.
+fn heading_section(lines: &[&str], subpath: &str) -> Result<Section, String> {
+    let all = headings(lines, body_start(lines));
+    let mut start = 0;
+    let mut end = lines.len();
+    let mut level = 0;
+    let mut found = false;
+    for segment in subpath.split('#').map(str::trim).filter(|s| !s.is_empty()) {
+        let hit = all.iter().position(|(i, lvl, title)| {
+            *i >= start && *i < end && *lvl > level && title.eq_ignore_ascii_case(segment)
+        });
+        let Some(pos) = hit else {
+            return Err(format!("Heading \"{}\" not found", segment));
+        };
+        let (line, lvl, _) = &all[pos];
+        start = *line;
+        level = *lvl;
+        end = all[pos + 1..]
+            .iter()
+            .find(|(_, l, _)| *l <= level)
+            .map(|(i, _, _)| *i)
+            .unwrap_or(lines.len())
+            .min(end);
+        found = true;
+    }
+    if !found {
+        return Err("Empty heading reference".to_string());
+    }
+    Ok(section(lines, start, end))
+}
+
+fn block_section(path: &str, content: &str, lines: &[&str], id: &str) -> Result<Section, String> {
+    let block = blocks::scan_blocks(path, content)
+        .into_iter()
+        .find(|b| b.block_id == id)
+        .ok_or_else(|| format!("Block ^{} not found", id))?;
+    let (start, end) = blocks::block_span(lines, block.line_number - 1)
+        .ok_or_else(|| format!("Block ^{} is empty", id))?;
+    Ok(Section { text: block.content, start_line: start + 1, end_line: end + 1 })
+}
+
+// ─── Resolver ──────────────────────────────────────────────────────
+
+struct Target {
+    path: String,
+    subpath: Option<String>,
+    alias: Option<String>,
+}
+
+fn parse_target(inner: &str) -> Target {
+    let (link, alias) = match inner.split_once('|') {
+        Some((l, a)) => (l.trim(), Some(a.trim().to_string())),
+        None => (inner.trim(), None),
+    };
+    let (path, subpath) = match link.split_once('#') {
+        Some((p, s)) => (p.trim().to_string(), Some(s.trim().to_string())),
+        None => (link.to_string(), None),
+    };
+    Target { path, subpath, alias }
+}
+
+/// `300` → width, `300x200` → width and height.
+fn parse_size(alias: &str) -> Option<(u32, Option<u32>)> {
+    match alias.split_once('x') {
+        Some((w, h)) => Some((w.trim().parse().ok()?, Some(h.trim().parse().ok()?))),
+        None => Some((alias.trim().parse().ok()?, None)),
+    }
+}
+
+fn in_code(ranges: &[(usize, usize)], pos: usize) -> bool {
+    ranges.iter().any(|(s, e)| pos >= *s && pos < *e)
+}
+
+/// Embed targets come from note text, so only plain paths below the vault
+/// root are followed: no `..`, root or drive prefix.
+fn inside_vault(rel: &str) -> bool {
+    Path::new(rel).components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+}
+
+pub struct Resolver<'a> {
+    vault_path: &'a str,
+    password: Option<&'a str>,
+    max_depth: usize,
+    /// Lowercased file name → vault paths, built on first name lookup.
+    names: Option<HashMap<String, Vec<String>>>,
+    embeds: Vec<Embed>,
+}
+
+impl<'a> Resolver<'a> {
+    pub fn new(vault_path: &'a str, password: Option<&'a str>, max_depth: usize) -> Self {
+        Self { vault_path, password, max_depth, names: None, embeds: Vec::new() }
+    }
+
+    /// Expand every embed in `content`, which belongs to `current_path`
+    /// (empty for unsaved text).
+    pub fn resolve(mut self, content: &str, current_path: &str) -> Transclusion {
+        let mut stack = Vec::new();
+        if !current_path.is_empty() {
+            stack.push(current_path.to_string());
+        }
+        let processed = self.expand(content, current_path, None, 0, &mut stack);
+        Transclusion { processed, embeds: self.embeds }
+    }
+
+    fn exists(&self, rel: &str) -> bool {
+        !rel.is_empty() && inside_vault(rel) && Path::new(self.vault_path).join(rel).is_file()
+    }
+
+    fn names(&mut self) -> &HashMap<String, Vec<String>> {
+        let vault_path = self.vault_path;
+        self.names.get_or_insert_with(|| {
+            let mut names: HashMap<String, Vec<String>> = HashMap::new();
+            for entry in WalkDir::new(vault_path)
+                .into_iter()
+                .filter_entry(|e| e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'))
+                .filter_map(|e| e.ok())
+                .filter(|e| e.file_type().is_file())
+            {
+                let rel = entry.path()
+                    .strip_prefix(vault_path)
+                    .unwrap_or(entry.path())
+                    .to_string_lossy()
+                    .to_string();
+                names.entry(entry.file_name().to_string_lossy().to_lowercase()).or_default().push(rel);
+            }
+            for paths in names.values_mut() {
+                paths.sort_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)));
+            }
+            names
+        })
+    }
+
+    /// Resolve a link target the way wiki links resolve: exact vault path,
+    /// then relative to the embedding note, then by file name anywhere.
+    fn resolve_path(&mut self, target: &str, from: &str) -> Option<String> {
+        if target.is_empty() {
+            return (!from.is_empty()).then(|| from.to_string());
+        }
+        let target = target.trim_start_matches('/');
+        if !inside_vault(target) {
+            return None;
+        }
+        let mut candidates = vec![target.to_string()];
+        if EmbedKind::from_path(target) == EmbedKind::Note && !target.ends_with(".md") {
+            candidates.push(format!("{}.md", target));
+        }
+        let dir = Path::new(from).parent().map(|p| p.to_string_lossy().to_string()).unwrap_or_default();
+        let relative: Vec<String> = if dir.is_empty() {
+            Vec::new()
+        } else {
+            candidates.iter().map(|c| format!("{}/{}", dir, c)).collect()
+        };
+        if let Some(hit) = candidates.iter().chain(relative.iter()).find(|c| self.exists(c)) {
+            return Some(hit.clone());
+        }
+        let names = self.names();
+        candidates.iter().find_map(|c| {
+            let name = Path::new(c).file_name()?.to_string_lossy().to_lowercase();
+            let suffix = format!("/{}", c.to_lowercase());
+            let paths = names.get(&name)?;
+            paths.iter()
+                .find(|p| p.to_lowercase().ends_with(&suffix) || p.eq_ignore_ascii_case(c))
+                .or_else(|| (!c.contains('/')).then(|| paths.first()).flatten())
+                .cloned()
+        })
+    }
+
+    fn read(&self, rel: &str) -> Result<String, String> {
+        if !inside_vault(rel) {
+            return Err(format!("{} is outside the vault", rel));
+        }
+        let raw = vault::read_note(self.vault_path, rel).map_err(|e| format!("Failed to read {}: {}", rel, e))?;
+        encryption::plaintext_content(raw, self.password)
+            .ok_or_else(|| format!("{} is encrypted", rel))
+    }
+
+    fn extract(&self, path: &str, subpath: Option<&str>) -> Result<(EmbedKind, Section), String> {
+        let content = self.read(path)?;
+        let lines: Vec<&str> = content.lines().collect();
+        match subpath {
+            Some(sub) if sub.starts_with('^') => {
+                Ok((EmbedKind::Block, block_section(path, &content, &lines, &sub[1..])?))
+            }
+            Some(sub) if !sub.is_empty() => Ok((EmbedKind::Heading, heading_section(&lines, sub)?)),
+            _ => Ok((EmbedKind::Note, section(&lines, body_start(&lines), lines.len()))),
+        }
+    }
+
+    fn expand(
+        &mut self,
+        text: &str,
+        from: &str,
+        parent: Option<usize>,
+        depth: usize,
+        stack: &mut Vec<String>,
+    ) -> String {
+        if depth >= self.max_depth {
+            return text.to_string();
+        }
+        let code: Vec<(usize, usize)> = CODE_RE.find_iter(text).map(|m| (m.start(), m.end())).collect();
+        let mut out = String::with_capacity(text.len());
+        let mut last = 0;
+        for cap in EMBED_RE.captures_iter(text) {
+            let m = cap.get(0).unwrap();
+            if in_code(&code, m.start()) {
+                continue;
+            }
+            out.push_str(&text[last..m.start()]);
+            last = m.end();
+            out.push_str(&self.embed(&cap[1], m.as_str(), text, m.start(), from, parent, depth, stack));
+        }
+        out.push_str(&text[last..]);
+        out
+    }
+
+    /// Record one embed and return the text standing in for it.
+    #[allow(clippy::too_many_arguments)]
+    fn embed(
+        &mut self,
+        inner: &str,
+        full_match: &str,
+        text: &str,
+        offset: usize,
+        from: &str,
+        parent: Option<usize>,
+        depth: usize,
+        stack: &mut Vec<String>,
+    ) -> String {
+        let target = parse_target(inner);
+        let resolved = self.resolve_path(&target.path, from);
+        let mut kind = EmbedKind::from_path(resolved.as_deref().unwrap_or(&target.path));
+        let (width, height) = match target.alias.as_deref().and_then(parse_size) {
+            Some((w, h)) if kind.is_media() => (Some(w), h),
+            _ => (None, None),
+        };
+        let idx = self.embeds.len();
+        let placeholder = format!("<!--oxidian-embed-{}-->", idx);
+        self.embeds.push(Embed {
+            full_match: full_match.to_string(),
+            note_path: resolved.clone().unwrap_or_else(|| target.path.clone()),
+            heading: target.subpath.clone(),
+            kind,
+            content: None,
+            error: None,
+            cycle: None,
+            placeholder: Some(placeholder.clone()),
+            depth,
+            parent,
+            index: offset,
+            line: text[..offset].matches('\n').count() + 1,
+            source: None,
+            alias: target.alias.clone(),
+            width,
+            height,
+        });
+
+        let Some(path) = resolved else {
+            self.embeds[idx].error = Some(format!("Not found: {}", target.path));
+            return placeholder;
+        };
+        if !kind.is_markdown() {
+            if kind.is_media() {
+                self.embeds[idx].content = Some(String::new());
+                return placeholder;
+            }
+            self.embeds[idx].placeholder = None;
+            return full_match.to_string();
+        }
+
+        let label = match &target.subpath {
+            Some(sub) if !sub.is_empty() => format!("{}#{}", path, sub),
+            _ => path.clone(),
+        };
+        if let Some(pos) = stack.iter().position(|s| s.eq_ignore_ascii_case(&label)) {
+            let mut chain = stack[pos..].to_vec();
+            chain.push(label);
+            self.embeds[idx].error = Some(format!("Embed cycle: {}", chain.join(" → ")));
+            self.embeds[idx].cycle = Some(chain);
+            return placeholder;
+        }
+
+        match self.extract(&path, target.subpath.as_deref()) {
+            Ok((section_kind, section)) => {
+                kind = section_kind;
+                self.embeds[idx].kind = kind;
+                self.embeds[idx].source = Some(SourceRange {
+                    path: path.clone(),
+                    start_line: section.start_line,
+                    end_line: section.end_line,
+                });
+                stack.push(label);
+                let expanded = self.expand(&section.text, &path, Some(idx), depth + 1, stack);
+                stack.pop();
+                self.embeds[idx].content = Some(expanded);
+            }
+            Err(e) => self.embeds[idx].error = Some(e),
+        }
+        placeholder
+    }
+}
+
+/// Expand the embeds of `content` up to `max_depth` levels deep.
+pub fn resolve_embeds(
+    vault_path: &str,
+    content: &str,
+    current_path: &str,
+    max_depth: usize,
+    password: Option<&str>,
+) -> Transclusion {
+    Resolver::new(vault_path, password, max_depth).resolve(content, current_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn vault(files: &[(&str, &str)]) -> TempDir {
+        let dir = TempDir::new().unwrap();
+        for (path, content) in files {
+            let full = dir.path().join(path);
+            std::fs::create_dir_all(full.parent().unwrap()).unwrap();
+            std::fs::write(full, content).unwrap();
+        }
+        dir
+    }
+
+    fn resolve(dir: &TempDir, content: &str, current: &str) -> Transclusion {
+        resolve_embeds(dir.path().to_str().unwrap(), content, current, 5, None)
+    }
+
+    #[test]
+    fn test_heading_and_block_embeds_with_source_lines() {
+        let dir = vault(&[(
+            "notes/Source.md",
+            "---\ntags: [a]\n---\n# Top\nintro\n\n## Part\nfirst\n\n### Deep\ninner\n\n## Other\nrest\n\n- item one ^item\n\n| a | b |\n| - | - |\n^table\n",
+        )]);
+        let out = resolve(&dir, "A ![[Source#Top#Part]] B ![[Source#^item]] ![[Source#^table]]", "Main.md");
+        assert_eq!(out.embeds.len(), 3);
+
+        let part = &out.embeds[0];
+        assert_eq!(part.kind, EmbedKind::Heading);
+        assert_eq!(part.note_path, "notes/Source.md");
+        assert_eq!(part.content.as_deref(), Some("## Part\nfirst\n\n### Deep\ninner"));
+        assert_eq!(part.source, Some(SourceRange { path: "notes/Source.md".into(), start_line: 7, end_line: 11 }));
+
+        let item = &out.embeds[1];
+        assert_eq!(item.kind, EmbedKind::Block);
+        assert_eq!(item.content.as_deref(), Some("- item one"));
+        assert_eq!(item.source.as_ref().map(|s| (s.start_line, s.end_line)), Some((16, 16)));
+
+        let table = &out.embeds[2];
+        assert_eq!(table.content.as_deref(), Some("| a | b |\n| - | - |"));
+        assert_eq!(table.source.as_ref().map(|s| (s.start_line, s.end_line)), Some((18, 19)));
+
+        assert_eq!(out.processed, "A <!--oxidian-embed-0--> B <!--oxidian-embed-1--> <!--oxidian-embed-2-->");
+    }
+
+    #[test]
+    fn test_nested_embeds_and_cycle_report() {
+        let dir = vault(&[
+            ("A.md", "a text\n![[B]]"),
+            ("B.md", "b text\n![[C]]"),
+            ("C.md", "c text\n![[A]]"),
+        ]);
+        let out = resolve(&dir, "![[B]]", "A.md");
+        assert_eq!(out.embeds.len(), 3);
+        assert_eq!(out.embeds[0].content.as_deref(), Some("b text\n<!--oxidian-embed-1-->"));
+        assert_eq!(out.embeds[1].parent, Some(0));
+        assert_eq!(out.embeds[1].line, 2);
+        assert_eq!(out.embeds[1].depth, 1);
+        let loop_embed = &out.embeds[2];
+        assert_eq!(loop_embed.cycle.as_deref(), Some(&["A.md".to_string(), "B.md".into(), "C.md".into(), "A.md".into()][..]));
+        assert_eq!(loop_embed.error.as_deref(), Some("Embed cycle: A.md → B.md → C.md → A.md"));
+    }
+
+    #[test]
+    fn test_embeds_never_leave_the_vault() {
+        let dir = vault(&[("secret.md", "password"), ("vault/Note.md", "inside"), ("vault/sub/Child.md", "child")]);
+        let vault_path = dir.path().join("vault");
+        let content = "![[../secret]] ![[/../secret.md]] ![[sub/../../secret]] ![[Note]]";
+        let out = resolve_embeds(vault_path.to_str().unwrap(), content, "sub/Child.md", 5, None);
+        assert_eq!(out.embeds.len(), 4);
+        for embed in &out.embeds[..3] {
+            assert!(embed.content.is_none() && embed.error.is_some(), "{}", embed.full_match);
+        }
+        assert_eq!(out.embeds[3].content.as_deref(), Some("inside"));
+    }
+
+    #[test]
+    fn test_media_typing_depth_and_code() {
+        let dir = vault(&[
+            ("assets/pic.png", "x"),
+            ("Board.canvas", "{}"),
+            ("Deep.md", "![[Deeper]]"),
+            ("Deeper.md", "bottom"),
+        ]);
+        let content = "![[pic.png|300x200]] ![[Board.canvas]] ![[missing]] `![[Deep]]`\n```\n![[Deep]]\n```";
+        let out = resolve(&dir, content, "");
+        assert_eq!(out.embeds.len(), 3);
+        assert_eq!(out.embeds[0].kind, EmbedKind::Image);
+        assert_eq!(out.embeds[0].note_path, "assets/pic.png");
+        assert_eq!((out.embeds[0].width, out.embeds[0].height), (Some(300), Some(200)));
+        assert_eq!(out.embeds[1].kind, EmbedKind::Canvas);
+        assert!(out.embeds[1].placeholder.is_none());
+        assert!(out.processed.contains("![[Board.canvas]]"));
+        assert_eq!(out.embeds[2].error.as_deref(), Some("Not found: missing"));
+
+        let shallow = resolve_embeds(dir.path().to_str().unwrap(), "![[Deep]]", "", 1, None);
+        assert_eq!(shallow.embeds.len(), 1);
+        assert_eq!(shallow.embeds[0].content.as_deref(), Some("![[Deeper]]"));
+    }
+}
@@ -8,15 +8,20 @@ mod state;
 mod encryption;
 mod updater;
 
+use engine::blocks::BlockIndex;
+use features::graph::analytics::AnalyticsCache;
 use engine::search::SearchIndex;
 use engine::settings;
 use engine::vault;
 use features::bookmarks::BookmarkManager;
 use features::nav_history::NavHistory;
+use features::properties::PropertyIndex;
 use features::tags::TagIndex;
+use features::tasks::TaskIndex;
 use state::{AppState, VaultMetaCache};
+use std::collections::HashMap;
 use std::sync::Mutex;
-use tauri::Manager;
+use tauri::{Emitter, Manager};
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -74,6 +79,7 @@ pub fn run() {
             commands::parse_markdown,
             commands::render_markdown_html,
             commands::parse_frontmatter,
+            commands::parse_inline_fields,
             commands::stringify_frontmatter,
             commands::get_field,
             commands::set_field,
@@ -81,12 +87,17 @@ pub fn run() {
             commands::parse_all_links,
             commands::resolve_link,
             commands::update_links_on_rename,
+            commands::rename_with_links,
+            commands::preview_link_updates,
+            commands::commit_link_updates,
             // ── Core: Block References ──
             commands::generate_block_id,
             commands::find_block,
             commands::get_block_content,
             commands::list_block_ids,
             commands::list_all_block_ids,
+            commands::search_blocks,
+            commands::assign_block_id,
             // ── Core: Search ──
             commands::search_vault,
             commands::fuzzy_search,
@@ -106,12 +117,22 @@ pub fn run() {
             // ── Features: Graph ──
             commands::get_graph_data,
             commands::compute_graph,
+            commands::get_graph_analytics,
+            commands::export_graph,
+            commands::find_note_paths,
+            commands::get_graph_as_of,
+            commands::get_graph_timelapse,
             // ── Features: Canvas ──
             commands::load_canvas,
             commands::save_canvas,
+            commands::validate_canvas,
             commands::canvas_add_node,
             commands::canvas_move_node,
             commands::canvas_delete_node,
+            commands::canvas_undo,
+            commands::canvas_redo,
+            commands::canvas_layout,
+            commands::export_canvas,
             // ── Features: Bookmarks ──
             commands::list_bookmarks,
             commands::add_bookmark,
@@ -124,6 +145,14 @@ pub fn run() {
             // ── Features: Tags ──
             commands::get_all_tags,
             commands::search_tags,
+            // ── Features: Properties ──
+            commands::get_property_types,
+            commands::set_property_type,
+            commands::validate_properties,
+            commands::list_property_keys,
+            commands::list_property_values,
+            commands::files_with_property,
+            commands::bulk_edit_properties,
             // ── Features: File Recovery ──
             commands::create_file_snapshot,
             commands::list_file_snapshots,
@@ -132,6 +161,13 @@ pub fn run() {
             commands::list_all_snapshot_files,
             // ── Features: Local Graph ──
             commands::get_local_graph,
+            // ── Features: Related Notes ──
+            commands::find_similar_notes,
+            commands::link_candidate_report,
+            // ── Features: Tasks ──
+            commands::query_tasks,
+            commands::get_note_tasks,
+            commands::toggle_task,
             // ── Features: Nav History ──
             commands::nav_push,
             commands::nav_go_back,
@@ -222,7 +258,10 @@ pub fn run() {
             std::fs::create_dir_all(&vault_path).ok();
             std::fs::create_dir_all(format!("{}/daily", vault_path)).ok();
 
-            let search_index = SearchIndex::new(&vault_path)
+            let loaded_settings = settings::load_settings(&vault_path);
+            let vault_locked = loaded_settings.vault.encryption_enabled;
+
+            let search_index = SearchIndex::for_vault(&vault_path, &loaded_settings)
                 .unwrap_or_else(|e| {
                     log::error!("Failed to initialize search index: {}", e);
                     panic!("Search index init failed: {}", e);
@@ -233,9 +272,6 @@ pub fn run() {
                 log::error!("Failed to index vault: {}", e);
             }
 
-            let loaded_settings = settings::load_settings(&vault_path);
-            let vault_locked = loaded_settings.vault.encryption_enabled;
-
             let mut meta_cache = VaultMetaCache::new();
             meta_cache.rebuild(&vault_path);
 
@@ -245,6 +281,27 @@ pub fn run() {
             let mut tag_index = TagIndex::new();
             tag_index.build_from_vault(&vault_path);
 
+            let mut property_index = PropertyIndex::new();
+            property_index.build_from_vault(&vault_path, None);
+
+            let mut task_index = TaskIndex::new();
+            task_index.build_from_vault(&vault_path, None);
+
+            let block_index = BlockIndex::load_or_build(&vault_path, None, !loaded_settings.vault.encryption_enabled);
+
+            // Feed external edits (sync, other editors) into the incremental indexes
+            let handle = app.handle().clone();
+            let watcher = vault::watch_vault(&vault_path, move |event| {
+                if let Some(state) = handle.try_state::<AppState>() {
+                    let changes = state.handle_fs_event(&event);
+                    if !changes.is_empty() {
+                        handle.emit("vault-meta-changed", &changes).ok();
+                    }
+                }
+            })
+            .map_err(|e| log::warn!("Vault watcher unavailable: {}", e))
+            .ok();
+
             let state = AppState {
                 search_index: Mutex::new(idx),
                 vault_path: Mutex::new(vault_path),
@@ -254,6 +311,12 @@ pub fn run() {
                 nav_history: Mutex::new(nav_history),
                 bookmarks: Mutex::new(bookmarks),
                 tag_index: Mutex::new(tag_index),
+                property_index: Mutex::new(property_index),
+                task_index: Mutex::new(task_index),
+                block_index: Mutex::new(block_index),
+                graph_analytics: Mutex::new(AnalyticsCache::new()),
+                link_baselines: Mutex::new(HashMap::new()),
+                watcher: Mutex::new(watcher),
             };
 
             app.manage(state);
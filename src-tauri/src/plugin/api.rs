@@ -395,7 +395,14 @@ impl PluginApiDispatcher {
                     .map_err(|v| PluginError::PermissionDenied(v.to_string()))?;
                 let content = std::fs::read_to_string(&full_path)
                     .map_err(|e| PluginError::Io(format!("reading {path}: {e}")))?;
-                let frontmatter = extract_frontmatter(&content);
+                let mut frontmatter = extract_frontmatter(&content);
+                // Inline `key:: value` fields are part of the note's metadata;
+                // frontmatter keys take precedence
+                if let serde_json::Value::Object(map) = &mut frontmatter {
+                    for (key, value) in crate::engine::frontmatter::inline_properties(&content) {
+                        map.entry(key).or_insert(value);
+                    }
+                }
                 Ok(PluginApiResponse::Json(frontmatter))
             }
 
@@ -758,6 +765,37 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_dispatcher_frontmatter_includes_inline_fields() {
+        let vault = tempfile::TempDir::new().unwrap();
+        std::fs::write(
+            vault.path().join("book.md"),
+            "---\nauthor: Jane\n---\nauthor:: Someone else\nMy [rating:: 4] for this.\n",
+        ).unwrap();
+
+        let sandbox_mgr = Arc::new(SandboxManager::new(vault.path()));
+        let registry = Arc::new(PluginRegistry::new(vault.path()));
+        sandbox_mgr.create_sandbox("test-plugin").unwrap();
+
+        let dispatcher = PluginApiDispatcher::new(
+            &vault.path().to_string_lossy(),
+            sandbox_mgr,
+            registry,
+        );
+
+        let result = dispatcher
+            .dispatch("test-plugin", PluginApiCall::MetadataGetFrontmatter { path: "book.md".into() })
+            .unwrap();
+
+        match result {
+            PluginApiResponse::Json(v) => {
+                assert_eq!(v["author"], "Jane");
+                assert_eq!(v["rating"], 4);
+            }
+            _ => panic!("expected Json response"),
+        }
+    }
+
     #[test]
     fn test_dispatcher_vault_write() {
         let vault = tempfile::TempDir::new().unwrap();
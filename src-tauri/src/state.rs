@@ -1,34 +1,102 @@
+use crate::encryption;
+use crate::engine::blocks::BlockIndex;
 use crate::engine::search::SearchIndex;
 use crate::engine::vault;
 use crate::features::bookmarks::BookmarkManager;
+use crate::features::graph::adjacency::{Adjacency, EdgeDelta};
+use crate::features::graph::analytics::AnalyticsCache;
+use crate::features::graph::GraphEdge;
 use crate::features::nav_history::NavHistory;
+use crate::features::properties::PropertyIndex;
 use crate::features::tags::TagIndex;
+use crate::features::tasks::TaskIndex;
+use notify::event::{ModifyKind, RenameMode};
+use notify::{Event, EventKind, RecommendedWatcher};
+use serde::Serialize;
 use std::collections::HashMap;
+use std::path::Path;
 use std::sync::Mutex;
 use std::time::Instant;
 
-/// Cached vault metadata (tags + wiki-links per file) to avoid full vault walks on every request
+/// What happened to a note, as reported to the frontend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Created,
+    Modified,
+    Renamed,
+    Removed,
+}
+
+/// One note's change to the metadata cache: its tags and the link graph edges
+/// that came and went, including edges of other notes whose links now resolve
+/// differently.
+#[derive(Debug, Clone, Serialize)]
+pub struct MetaChange {
+    pub kind: ChangeKind,
+    pub path: String,
+    /// Previous path of a renamed note
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    pub tags_added: Vec<String>,
+    pub tags_removed: Vec<String>,
+    pub edges_added: Vec<GraphEdge>,
+    pub edges_removed: Vec<GraphEdge>,
+}
+
+impl MetaChange {
+    fn new(kind: ChangeKind, path: &str, old_tags: &[String], new_tags: &[String], delta: EdgeDelta) -> Self {
+        Self {
+            kind,
+            path: path.to_string(),
+            from: None,
+            tags_added: new_tags.iter().filter(|t| !old_tags.contains(t)).cloned().collect(),
+            tags_removed: old_tags.iter().filter(|t| !new_tags.contains(t)).cloned().collect(),
+            edges_added: delta.added,
+            edges_removed: delta.removed,
+        }
+    }
+
+    /// A modification that changed no tags or links.
+    pub fn is_empty(&self) -> bool {
+        self.kind == ChangeKind::Modified
+            && self.tags_added.is_empty()
+            && self.tags_removed.is_empty()
+            && self.edges_added.is_empty()
+            && self.edges_removed.is_empty()
+    }
+}
+
+/// Cached vault metadata (tags + wiki-links per file) to avoid full vault walks on every request.
+/// Built once per vault, then kept current by saves and file system events.
 pub struct VaultMetaCache {
-    /// file_path → (tags, wiki_links)
+    /// file_path → (tags, wiki_links); change through `set_entry`, `update_file` or
+    /// `remove_file` so the adjacency follows
     pub entries: HashMap<String, (Vec<String>, Vec<String>)>,
+    /// Resolved links between the notes in `entries`
+    pub adjacency: Adjacency,
     pub built_at: Option<Instant>,
 }
 
 impl VaultMetaCache {
     pub fn new() -> Self {
-        Self { entries: HashMap::new(), built_at: None }
+        Self { entries: HashMap::new(), adjacency: Adjacency::default(), built_at: None }
     }
 
-    /// Returns true if cache is older than `max_age` seconds or empty
-    pub fn is_stale(&self, max_age_secs: u64) -> bool {
-        match self.built_at {
-            None => true,
-            Some(t) => t.elapsed().as_secs() > max_age_secs,
+    /// Builds the cache if it has never been built
+    pub fn ensure_built(&mut self, vault_path: &str) {
+        if self.built_at.is_none() {
+            self.rebuild(vault_path);
         }
     }
 
     /// Rebuild cache by walking the entire vault once
     pub fn rebuild(&mut self, vault_path: &str) {
+        self.rebuild_with_password(vault_path, None);
+    }
+
+    /// Rebuild cache, decrypting encrypted notes with `password`
+    pub fn rebuild_with_password(&mut self, vault_path: &str, password: Option<&str>) {
         self.entries.clear();
         for entry in walkdir::WalkDir::new(vault_path).into_iter()
             .filter_entry(|e| {
@@ -40,7 +108,9 @@ impl VaultMetaCache {
         {
             let path = entry.path();
             if path.extension().map(|e| e == "md").unwrap_or(false) {
-                if let Ok(content) = std::fs::read_to_string(path) {
+                let content = std::fs::read_to_string(path).ok()
+                    .and_then(|c| encryption::plaintext_content(c, password));
+                if let Some(content) = content {
                     let relative = path.strip_prefix(vault_path)
                         .unwrap_or(path)
                         .to_string_lossy()
@@ -51,19 +121,117 @@ impl VaultMetaCache {
                 }
             }
         }
+        self.adjacency = Adjacency::build(self.entries.iter().map(|(path, (_, links))| (path, links)));
         self.built_at = Some(Instant::now());
     }
 
+    /// Set the tags and links of a file, adding it if new
+    pub fn set_entry(&mut self, relative_path: String, entry: (Vec<String>, Vec<String>)) -> MetaChange {
+        let delta = self.adjacency.set_note(&relative_path, &entry.1);
+        let old = self.entries.insert(relative_path.clone(), entry);
+        let new_tags = &self.entries[&relative_path].0;
+        match old {
+            Some((old_tags, _)) => MetaChange::new(ChangeKind::Modified, &relative_path, &old_tags, new_tags, delta),
+            None => MetaChange::new(ChangeKind::Created, &relative_path, &[], new_tags, delta),
+        }
+    }
+
     /// Update a single file entry (called on save)
-    pub fn update_file(&mut self, relative_path: &str, content: &str) {
+    pub fn update_file(&mut self, relative_path: &str, content: &str) -> MetaChange {
         let tags = vault::extract_tags(content);
         let links = vault::extract_wiki_links(content);
-        self.entries.insert(relative_path.to_string(), (tags, links));
+        self.set_entry(relative_path.to_string(), (tags, links))
     }
 
     /// Remove a file entry (called on delete)
-    pub fn remove_file(&mut self, relative_path: &str) {
-        self.entries.remove(relative_path);
+    pub fn remove_file(&mut self, relative_path: &str) -> Option<MetaChange> {
+        let (tags, _) = self.entries.remove(relative_path)?;
+        let delta = self.adjacency.remove_note(relative_path);
+        Some(MetaChange::new(ChangeKind::Removed, relative_path, &tags, &[], delta))
+    }
+
+    /// Move an entry to a new path, re-reading it when it can be read.
+    fn rename_file(&mut self, vault_path: &str, from: &str, to: &str, password: Option<&str>) -> Option<MetaChange> {
+        let entry = match read_note(vault_path, to, password) {
+            Some(content) => (vault::extract_tags(&content), vault::extract_wiki_links(&content)),
+            None => self.entries.get(from)?.clone(),
+        };
+        let removed = self.remove_file(from)?;
+        let added = self.set_entry(to.to_string(), entry);
+        let mut change = MetaChange::new(ChangeKind::Renamed, to, &removed.tags_removed, &added.tags_added, EdgeDelta {
+            added: [removed.edges_added, added.edges_added].concat(),
+            removed: [removed.edges_removed, added.edges_removed].concat(),
+        });
+        change.from = Some(from.to_string());
+        Some(change)
+    }
+
+    /// Re-read one note, or drop it when it is gone. Notes that exist but
+    /// cannot be decrypted keep their entry.
+    fn refresh_note(&mut self, vault_path: &str, relative: &str, password: Option<&str>) -> Option<MetaChange> {
+        if !Path::new(vault_path).join(relative).is_file() {
+            return self.remove_file(relative);
+        }
+        let content = read_note(vault_path, relative, password)?;
+        Some(self.update_file(relative, &content)).filter(|c| !c.is_empty())
+    }
+
+    /// Refresh whatever `relative` now is: a note, a folder of notes, or a
+    /// deleted folder whose notes are dropped.
+    fn refresh_path(&mut self, vault_path: &str, relative: &str, password: Option<&str>) -> Vec<MetaChange> {
+        let full = Path::new(vault_path).join(relative);
+        if full.is_dir() {
+            return notes_under(vault_path, relative)
+                .into_iter()
+                .filter_map(|note| self.refresh_note(vault_path, &note, password))
+                .collect();
+        }
+        if is_note(relative) {
+            return self.refresh_note(vault_path, relative, password).into_iter().collect();
+        }
+        if full.exists() {
+            return Vec::new();
+        }
+        let prefix = format!("{}{}", relative, std::path::MAIN_SEPARATOR);
+        let gone: Vec<String> = self.entries.keys().filter(|p| p.starts_with(&prefix)).cloned().collect();
+        gone.iter().filter_map(|p| self.remove_file(p)).collect()
+    }
+
+    /// Apply a file system event from the vault watcher and report what
+    /// changed. Renames of notes and folders keep their entries; anything else
+    /// is re-read or dropped depending on whether it still exists.
+    pub fn apply_event(&mut self, vault_path: &str, event: &Event, password: Option<&str>) -> Vec<MetaChange> {
+        let relative = |p: &Path| p.strip_prefix(vault_path).ok().map(|r| r.to_string_lossy().to_string());
+        match event.kind {
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if event.paths.len() == 2 => {
+                let (Some(from), Some(to)) = (relative(&event.paths[0]), relative(&event.paths[1])) else {
+                    return Vec::new();
+                };
+                if event.paths[1].is_dir() {
+                    let prefix = format!("{}{}", from, std::path::MAIN_SEPARATOR);
+                    let moved: Vec<String> = self.entries.keys().filter(|p| p.starts_with(&prefix)).cloned().collect();
+                    let mut changes: Vec<MetaChange> = moved.iter()
+                        .filter_map(|old| {
+                            let new = format!("{}{}{}", to, std::path::MAIN_SEPARATOR, &old[prefix.len()..]);
+                            self.rename_file(vault_path, old, &new, password)
+                        })
+                        .collect();
+                    changes.extend(self.refresh_path(vault_path, &to, password));
+                    changes
+                } else if is_note(&to) && self.entries.contains_key(&from) {
+                    self.rename_file(vault_path, &from, &to, password).into_iter().collect()
+                } else {
+                    let mut changes = self.refresh_path(vault_path, &from, password);
+                    changes.extend(self.refresh_path(vault_path, &to, password));
+                    changes
+                }
+            }
+            EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_) => event.paths.iter()
+                .filter_map(|p| relative(p))
+                .flat_map(|r| self.refresh_path(vault_path, &r, password))
+                .collect(),
+            _ => Vec::new(),
+        }
     }
 
     /// Get all unique tags across the vault
@@ -76,25 +244,32 @@ impl VaultMetaCache {
         tags
     }
 
-    /// Find all files that link to a given note name
+    /// Find all files that link to a given note
     pub fn find_backlinks(&self, target_note: &str) -> Vec<String> {
-        let target_name = std::path::Path::new(target_note)
-            .file_stem()
-            .unwrap_or_default()
-            .to_string_lossy()
-            .to_string();
-
-        self.entries.iter()
-            .filter(|(_, (_, links))| {
-                links.iter().any(|link| {
-                    link == &target_name || link.ends_with(&format!("/{}", target_name))
-                })
-            })
-            .map(|(path, _)| path.clone())
-            .collect()
+        self.adjacency.sources(target_note).map(str::to_string).collect()
     }
 }
 
+fn is_note(relative: &str) -> bool {
+    Path::new(relative).extension().map(|e| e == "md").unwrap_or(false)
+}
+
+fn read_note(vault_path: &str, relative: &str, password: Option<&str>) -> Option<String> {
+    std::fs::read_to_string(Path::new(vault_path).join(relative)).ok()
+        .and_then(|c| encryption::plaintext_content(c, password))
+}
+
+/// Notes below a folder of the vault, skipping hidden folders.
+fn notes_under(vault_path: &str, folder: &str) -> Vec<String> {
+    walkdir::WalkDir::new(Path::new(vault_path).join(folder)).into_iter()
+        .filter_entry(|e| e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| e.path().strip_prefix(vault_path).ok().map(|r| r.to_string_lossy().to_string()))
+        .filter(|r| is_note(r))
+        .collect()
+}
+
 pub struct AppState {
     pub search_index: Mutex<SearchIndex>,
     pub vault_path: Mutex<String>,
@@ -104,4 +279,225 @@ pub struct AppState {
     pub nav_history: Mutex<NavHistory>,
     pub bookmarks: Mutex<BookmarkManager>,
     pub tag_index: Mutex<TagIndex>,
+    pub property_index: Mutex<PropertyIndex>,
+    pub task_index: Mutex<TaskIndex>,
+    pub block_index: Mutex<BlockIndex>,
+    pub graph_analytics: Mutex<AnalyticsCache>,
+    /// Note content before the edits not yet committed for link updates,
+    /// keyed by note path (see `commit_link_updates`).
+    pub link_baselines: Mutex<HashMap<String, String>>,
+    /// Vault file system watcher; kept alive here and re-pointed on vault switch.
+    pub watcher: Mutex<Option<RecommendedWatcher>>,
+}
+
+impl AppState {
+    /// Bring every index up to date with a note that was just written. The
+    /// caller holds the vault path (and password) locks.
+    pub fn reindex_note(&self, vault_path: &str, path: &str, content: &str) -> Result<(), String> {
+        let mut search = self.search_index.lock().map_err(|e| format!("Lock poisoned: {}", e))?;
+        search.index_note(vault_path, path, content)?;
+
+        if let Ok(mut cache) = self.meta_cache.lock() {
+            cache.update_file(path, content);
+        }
+
+        if let Ok(mut props) = self.property_index.lock() {
+            props.index_file(path, content);
+        }
+
+        if let Ok(mut tasks) = self.task_index.lock() {
+            tasks.index_file(path, content);
+        }
+
+        if let Ok(mut blocks) = self.block_index.lock() {
+            blocks.index_file(vault_path, path, content);
+        }
+        Ok(())
+    }
+
+    /// Drop a deleted or moved-away note from every index.
+    pub fn forget_note(&self, vault_path: &str, path: &str) {
+        if let Ok(mut search) = self.search_index.lock() {
+            let _ = search.delete_path(path);
+        }
+        if let Ok(mut cache) = self.meta_cache.lock() {
+            cache.remove_file(path);
+        }
+        if let Ok(mut props) = self.property_index.lock() {
+            props.remove_file(path);
+        }
+        if let Ok(mut tasks) = self.task_index.lock() {
+            tasks.remove_file(path);
+        }
+        if let Ok(mut blocks) = self.block_index.lock() {
+            blocks.remove_file(vault_path, path);
+        }
+    }
+
+    /// Apply a file system event from the vault watcher to the indexes and
+    /// return the metadata changes. Every note the event touches goes through
+    /// [`Self::reindex_note`] or [`Self::forget_note`], as an in-app edit
+    /// would. Events from outside the current vault or in its hidden folders
+    /// are ignored.
+    pub fn handle_fs_event(&self, event: &notify::Event) -> Vec<MetaChange> {
+        let Ok(vault_path) = self.vault_path.lock() else { return Vec::new() };
+        if event.paths.iter().all(|p| vault::is_hidden_path(&vault_path, p)) {
+            return Vec::new();
+        }
+        let Ok(password) = self.vault_password.lock() else { return Vec::new() };
+        // The metadata cache reports renames as such, so it sees the event first
+        let changes = match self.meta_cache.lock() {
+            Ok(mut cache) => cache.apply_event(&vault_path, event, password.as_deref()),
+            Err(_) => Vec::new(),
+        };
+
+        let mut gone: Vec<String> = changes.iter()
+            .filter_map(|c| match c.kind {
+                ChangeKind::Removed => Some(c.path.clone()),
+                ChangeKind::Renamed => c.from.clone(),
+                _ => None,
+            })
+            .collect();
+        let mut present = Vec::new();
+        for path in event.paths.iter().filter(|p| !vault::is_hidden_path(&vault_path, p)) {
+            let Ok(relative) = path.strip_prefix(&*vault_path) else { continue };
+            let relative = relative.to_string_lossy().to_string();
+            if path.is_dir() {
+                present.extend(notes_under(&vault_path, &relative));
+            } else if !is_note(&relative) {
+                continue;
+            } else if path.is_file() {
+                present.push(relative);
+            } else {
+                gone.push(relative);
+            }
+        }
+        gone.sort();
+        gone.dedup();
+        for path in gone.iter().filter(|p| !present.contains(p)) {
+            self.forget_note(&vault_path, path);
+        }
+        present.sort();
+        present.dedup();
+        for path in &present {
+            if let Some(content) = read_note(&vault_path, path, password.as_deref()) {
+                let _ = self.reindex_note(&vault_path, path, &content);
+            }
+        }
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{CreateKind, RemoveKind};
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn event(kind: EventKind, paths: &[&Path]) -> Event {
+        paths.iter().fold(Event::new(kind), |e, p| e.add_path(p.to_path_buf()))
+    }
+
+    #[test]
+    fn test_apply_event_tracks_notes_and_edges() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        let vp = root.to_str().unwrap();
+        fs::write(root.join("a.md"), "# A\n#alpha").unwrap();
+        let mut cache = VaultMetaCache::new();
+        cache.ensure_built(vp);
+        assert_eq!(cache.entries.len(), 1);
+
+        fs::write(root.join("b.md"), "See [[a]] and [[c]]").unwrap();
+        let changes = cache.apply_event(vp, &event(EventKind::Create(CreateKind::File), &[&root.join("b.md")]), None);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].kind, ChangeKind::Created);
+        assert_eq!(changes[0].edges_added.len(), 1);
+        assert_eq!(cache.find_backlinks("a.md"), vec!["b.md"]);
+
+        // Renaming b to c resolves its own dangling [[c]] link
+        fs::rename(root.join("b.md"), root.join("c.md")).unwrap();
+        let rename = EventKind::Modify(ModifyKind::Name(RenameMode::Both));
+        let changes = cache.apply_event(vp, &event(rename, &[&root.join("b.md"), &root.join("c.md")]), None);
+        assert_eq!(changes.len(), 1);
+        assert_eq!((changes[0].kind, changes[0].from.as_deref()), (ChangeKind::Renamed, Some("b.md")));
+        assert!(cache.adjacency.targets("c.md").contains("c.md"));
+        assert_eq!(cache.find_backlinks("a.md"), vec!["c.md"]);
+
+        // A save that changes nothing in the metadata is not reported
+        fs::write(root.join("c.md"), "See [[a]] and [[c]]!").unwrap();
+        let modify = EventKind::Modify(ModifyKind::Any);
+        assert!(cache.apply_event(vp, &event(modify, &[&root.join("c.md")]), None).is_empty());
+
+        fs::create_dir(root.join("sub")).unwrap();
+        fs::write(root.join("sub/d.md"), "[[a]]").unwrap();
+        cache.apply_event(vp, &event(EventKind::Create(CreateKind::Folder), &[&root.join("sub")]), None);
+        assert_eq!(cache.find_backlinks("a.md").len(), 2);
+
+        fs::remove_dir_all(root.join("sub")).unwrap();
+        fs::remove_file(root.join("a.md")).unwrap();
+        let remove = EventKind::Remove(RemoveKind::Any);
+        let changes = cache.apply_event(vp, &event(remove, &[&root.join("sub"), &root.join("a.md")]), None);
+        assert_eq!(changes.len(), 2);
+        let a = changes.iter().find(|c| c.path == "a.md").unwrap();
+        assert_eq!(a.tags_removed, vec!["alpha"]);
+        assert_eq!(a.edges_removed.len(), 1);
+        assert_eq!(cache.entries.keys().collect::<Vec<_>>(), vec!["c.md"]);
+    }
+
+    fn app_state(vault_path: &str) -> AppState {
+        AppState {
+            search_index: Mutex::new(SearchIndex::new(vault_path).unwrap()),
+            vault_path: Mutex::new(vault_path.to_string()),
+            vault_password: Mutex::new(None),
+            vault_locked: Mutex::new(false),
+            meta_cache: Mutex::new(VaultMetaCache::new()),
+            nav_history: Mutex::new(NavHistory::new(10)),
+            bookmarks: Mutex::new(BookmarkManager::new(vault_path)),
+            tag_index: Mutex::new(TagIndex::new()),
+            property_index: Mutex::new(PropertyIndex::new()),
+            task_index: Mutex::new(TaskIndex::new()),
+            block_index: Mutex::new(BlockIndex::new()),
+            graph_analytics: Mutex::new(AnalyticsCache::new()),
+            link_baselines: Mutex::new(HashMap::new()),
+            watcher: Mutex::new(None),
+        }
+    }
+
+    #[test]
+    fn test_fs_events_reach_search_and_properties() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        let state = app_state(root.to_str().unwrap());
+        let found = |word: &str| -> Vec<String> {
+            state.search_index.lock().unwrap().search(word, 10).unwrap().into_iter().map(|r| r.path).collect()
+        };
+        let with_status = |value: &str| state.property_index.lock().unwrap().files_with("status", Some(value));
+
+        fs::write(root.join("a.md"), "---\nstatus: draft\n---\nzebra").unwrap();
+        state.handle_fs_event(&event(EventKind::Create(CreateKind::File), &[&root.join("a.md")]));
+        assert_eq!(found("zebra"), vec!["a.md"]);
+        assert_eq!(with_status("draft"), vec!["a.md"]);
+
+        // Edits that leave tags and links alone still reach the other indexes
+        fs::write(root.join("a.md"), "---\nstatus: done\n---\nquokka\n- [ ] call").unwrap();
+        let changes = state.handle_fs_event(&event(EventKind::Modify(ModifyKind::Any), &[&root.join("a.md")]));
+        assert!(changes.is_empty());
+        assert_eq!(found("quokka"), vec!["a.md"]);
+        assert!(found("zebra").is_empty());
+        assert_eq!(with_status("done"), vec!["a.md"]);
+        assert_eq!(state.task_index.lock().unwrap().file_tasks("a.md").len(), 1);
+
+        fs::rename(root.join("a.md"), root.join("b.md")).unwrap();
+        let rename = EventKind::Modify(ModifyKind::Name(RenameMode::Both));
+        state.handle_fs_event(&event(rename, &[&root.join("a.md"), &root.join("b.md")]));
+        assert_eq!(found("quokka"), vec!["b.md"]);
+        assert_eq!(with_status("done"), vec!["b.md"]);
+
+        fs::remove_file(root.join("b.md")).unwrap();
+        state.handle_fs_event(&event(EventKind::Remove(RemoveKind::File), &[&root.join("b.md")]));
+        assert!(found("quokka").is_empty());
+        assert!(with_status("done").is_empty());
+    }
 }
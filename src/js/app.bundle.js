@@ -39,6 +39,7 @@
   var tauri_bridge_exports = {};
   __export(tauri_bridge_exports, {
     invoke: () => invoke,
+    listen: () => listen,
     whenReady: () => whenReady
   });
   function _checkReady() {
@@ -63,6 +64,10 @@
     if (_ready) return Promise.resolve();
     return new Promise((resolve) => _waiters.push(() => resolve()));
   }
+  async function listen(event, handler) {
+    await whenReady();
+    return window.__TAURI__.event.listen(event, handler);
+  }
   var _invoke, _ready, _waiters;
   var init_tauri_bridge = __esm({
     "src/js/tauri-bridge.js"() {
@@ -2601,6 +2606,7 @@
       this.mode = options.mode || "global";
       this.localDepth = options.depth || 1;
       this.centerNote = options.centerNote || null;
+      this.query = options.query || null;
       this._toolbar = document.createElement("div");
       this._toolbar.style.cssText = "position:absolute;top:8px;left:8px;z-index:10;display:flex;gap:6px;align-items:center;";
       this._toolbar.innerHTML = `
@@ -2645,6 +2651,18 @@
       this.resize();
       this.bindEvents();
       this.load();
+      this._unlisten = null;
+      listen("vault-meta-changed", (event) => {
+        const changes = event.payload || [];
+        const relevant = changes.some((c) => c.kind !== "modified" || c.edges_added.length || c.edges_removed.length);
+        if (!relevant && !this.query?.show_tags) return;
+        clearTimeout(this._reloadTimer);
+        this._reloadTimer = setTimeout(() => this.load(), 300);
+      }).then((unlisten) => {
+        if (this._destroyed) unlisten();
+        else this._unlisten = unlisten;
+      }).catch(() => {
+      });
     }
     resize() {
       const rect = this.container.getBoundingClientRect();
@@ -2670,10 +2688,12 @@
       this.canvas.addEventListener("dblclick", (e) => this.onDblClick(e));
     }
     /**
-     * Load graph data with pre-computed layout from Rust (Fruchterman-Reingold).
-     * Returns { nodes: [{id, label, x, y, radius, color, tags}], edges: [{source, target}] }
+     * Load graph data with pre-computed layout from Rust (Barnes–Hut force-directed).
+     * Returns { nodes: [{id, name, kind, group, x, y}], edges: [{source, target}] }
+     * @param {object} [query] - replaces the current graph query when given
      */
-    async load(filter) {
+    async load(query) {
+      if (query !== void 0) this.query = query;
       try {
         let data;
         if (this.mode === "local") {
@@ -2682,12 +2702,16 @@
             console.warn("No current file for local graph");
             return;
           }
-          data = await invoke("get_local_graph", { path: centerPath, depth: this.localDepth });
+          data = await invoke("get_local_graph", { path: centerPath, depth: this.localDepth, query: this.query });
           this._centerNodeId = data.center_node;
+          this.panX = 0;
+          this.panY = 0;
         } else {
           const vaultPath = this.app.vaultPath || "";
-          data = await invoke("compute_graph", { vaultPath, filter: filter || null });
+          data = await invoke("compute_graph", { vaultPath, query: this.query });
           this._centerNodeId = null;
+          this.panX = this.width / 2;
+          this.panY = this.height / 2;
         }
         this.setGraphData(data);
         this._applyForceLayout();
@@ -2726,6 +2750,22 @@
         target: this.nodeMap[typeof e.target === "string" ? e.target : e.target.id]
       })).filter((e) => e.source && e.target);
     }
+    /**
+     * Color for a community/group id from Rust; ungrouped nodes use the accent.
+     */
+    _groupColor(group) {
+      if (group == null) return "#7f6df2";
+      const palette = ["#7f6df2", "#f38ba8", "#a6e3a1", "#fab387", "#89b4fa", "#f9e2af", "#94e2d5", "#cba6f7"];
+      const n = Number(group);
+      const idx = Number.isInteger(n) ? n : [...String(group)].reduce((h, c) => h * 31 + c.charCodeAt(0) >>> 0, 0);
+      return palette[idx % palette.length];
+    }
+    /**
+     * Fixed colors for non-note nodes (ghosts, tags, attachments).
+     */
+    _kindColor(kind) {
+      return { ghost: "#6c7086", tag: "#a6e3a1", attachment: "#89b4fa" }[kind] || null;
+    }
     /**
      * Set pre-positioned graph data from Rust. No JS layout computation.
      */
@@ -2735,8 +2775,9 @@
         label: n.label || n.name,
         x: n.x,
         y: n.y,
-        radius: n.radius || 6,
-        color: n.color || "#7f6df2",
+        kind: n.kind || "note",
+        radius: n.radius || (n.kind && n.kind !== "note" ? 4 : 6),
+        color: n.color || this._kindColor(n.kind) || this._groupColor(n.group),
         tags: n.tags || []
       }));
       this.nodeMap = {};
@@ -2860,13 +2901,16 @@
       const mx = e.clientX - rect.left;
       const my = e.clientY - rect.top;
       const node = this.getNodeAt(mx, my);
-      if (node) {
+      if (node && (node.kind === "note" || node.kind === "attachment")) {
         this.app.openFile(node.id);
       }
     }
     destroy() {
       if (this.animId) cancelAnimationFrame(this.animId);
       if (this._resizeObserver) this._resizeObserver.disconnect();
+      this._destroyed = true;
+      if (this._unlisten) this._unlisten();
+      clearTimeout(this._reloadTimer);
     }
   };
 
@@ -12600,19 +12644,12 @@ body.hm-dragging { cursor: grabbing !important; user-select: none; }
         return content;
       }
       const pdfRegex = /!\[\[([^\]]+?\.pdf)(?:#([^\]]+?))?\]\]/gi;
-      const blockRefRegex = /!\[\[([^\]]*?)#(\^[^\]]+?)\]\]/g;
       let pdfMatch;
       while ((pdfMatch = pdfRegex.exec(content)) !== null) {
         const embed = { notePath: pdfMatch[1].trim(), heading: pdfMatch[2]?.trim(), fullMatch: pdfMatch[0] };
         const pdfHtml = this.renderPdfEmbed(embed);
         content = content.replace(pdfMatch[0], pdfHtml);
       }
-      let blockMatch;
-      while ((blockMatch = blockRefRegex.exec(content)) !== null) {
-        const embed = { notePath: blockMatch[1].trim(), heading: blockMatch[2].trim(), fullMatch: blockMatch[0] };
-        const blockHtml = await this.renderBlockEmbed(embed, embedDepth);
-        content = content.replace(blockMatch[0], blockHtml);
-      }
       try {
         const result = await invoke("resolve_embeds", {
           content,
@@ -12834,7 +12871,7 @@ body.hm-dragging { cursor: grabbing !important; user-select: none; }
       const videoExts = /\.(mp4|webm|mov|mkv|avi)$/i;
       const pipeIdx = notePath.indexOf("|");
       const cleanPath = pipeIdx >= 0 ? notePath.substring(0, pipeIdx).trim() : notePath;
-      const sizePart = pipeIdx >= 0 ? notePath.substring(pipeIdx + 1).trim() : "";
+      const sizePart = pipeIdx >= 0 ? notePath.substring(pipeIdx + 1).trim() : embed.width ? embed.height ? `${embed.width}x${embed.height}` : `${embed.width}` : "";
       if (imageExts.test(cleanPath)) {
         let style = "";
         if (sizePart) {
@@ -12857,9 +12894,10 @@ body.hm-dragging { cursor: grabbing !important; user-select: none; }
         return this.renderEmbedError(embed, "No content found");
       }
       const depthClass = `embed-depth-${Math.min(embedDepth, 2)}`;
+      const sourceAttrs = embed.source ? ` data-source-path="${this.escapeHtml(embed.source.path)}" data-start-line="${embed.source.startLine}" data-end-line="${embed.source.endLine}"` : "";
       const sourceInfo = embed.heading ? `${embed.notePath}#${embed.heading}` : embed.notePath;
       return `
-<div class="embedded-content ${depthClass}" data-source="${this.escapeHtml(sourceInfo)}">
+<div class="embedded-content ${depthClass}" data-source="${this.escapeHtml(sourceInfo)}"${sourceAttrs}>
     <div class="embed-header">
         <span class="embed-source">${this.escapeHtml(sourceInfo)}</span>
         <button class="embed-open-btn" onclick="window.navigateToNote('${embed.notePath}')" title="Open in new tab">
@@ -19903,6 +19941,7 @@ A snapshot of the current version will be created first.`)) return;
       }
     }
     onAllTabsClosed() {
+      this.commitLinkUpdates(this.currentFile);
       this.currentFile = null;
       this.isDirty = false;
       this.showWelcome();
@@ -19922,6 +19961,7 @@ A snapshot of the current version will be created first.`)) return;
         if (this.isDirty && this.currentFile) {
           await this.saveCurrentFile();
         }
+        if (this.currentFile !== path) this.commitLinkUpdates(this.currentFile);
         let content;
         try {
           content = await invoke("read_note", { path });
@@ -19979,6 +20019,7 @@ A snapshot of the current version will be created first.`)) return;
       if (this.isDirty && this.currentFile) {
         await this.saveCurrentFile();
       }
+      this.commitLinkUpdates(this.currentFile);
       try {
         const content = await invoke("read_note", { path });
         this.currentFile = path;
@@ -20017,6 +20058,16 @@ A snapshot of the current version will be created first.`)) return;
         });
       }
     }
+    /**
+     * Rewrite links to headings renamed (and blocks moved) in a note once the
+     * user leaves it, rather than on every autosave while a heading is half typed.
+     */
+    commitLinkUpdates(path) {
+      if (!path || !path.endsWith(".md")) return;
+      invoke("commit_link_updates", { path }).then((changes) => {
+        if (changes.length > 0) this.backlinksManager?.invalidate();
+      }).catch((err) => console.error("Failed to update links:", err));
+    }
     async saveCurrentFile() {
       if (!this.currentFile || !this.isDirty) return;
       return new Promise((resolve, reject) => {
@@ -497,6 +497,7 @@ class OxidianApp {
     }
 
     onAllTabsClosed() {
+        this.commitLinkUpdates(this.currentFile);
         this.currentFile = null;
         this.isDirty = false;
         this.showWelcome();
@@ -523,6 +524,7 @@ class OxidianApp {
             if (this.isDirty && this.currentFile) {
                 await this.saveCurrentFile();
             }
+            if (this.currentFile !== path) this.commitLinkUpdates(this.currentFile);
 
             // Read file content BEFORE setting currentFile (race condition fix)
             let content;
@@ -596,6 +598,7 @@ class OxidianApp {
         if (this.isDirty && this.currentFile) {
             await this.saveCurrentFile();
         }
+        this.commitLinkUpdates(this.currentFile);
         try {
             const content = await invoke('read_note', { path });
             this.currentFile = path;
@@ -639,6 +642,17 @@ class OxidianApp {
         }
     }
 
+    /**
+     * Rewrite links to headings renamed (and blocks moved) in a note once the
+     * user leaves it, rather than on every autosave while a heading is half typed.
+     */
+    commitLinkUpdates(path) {
+        if (!path || !path.endsWith('.md')) return;
+        invoke('commit_link_updates', { path }).then(changes => {
+            if (changes.length > 0) this.backlinksManager?.invalidate();
+        }).catch(err => console.error('Failed to update links:', err));
+    }
+
     async saveCurrentFile() {
         if (!this.currentFile || !this.isDirty) return;
 
@@ -26,9 +26,8 @@ export class EmbedProcessor {
             return content;
         }
 
-        // Pre-process: handle PDF embeds and block reference embeds before Rust
+        // Pre-process: handle PDF embeds before Rust
         const pdfRegex = /!\[\[([^\]]+?\.pdf)(?:#([^\]]+?))?\]\]/gi;
-        const blockRefRegex = /!\[\[([^\]]*?)#(\^[^\]]+?)\]\]/g;
         
         // Handle PDF embeds inline
         let pdfMatch;
@@ -38,18 +37,12 @@ export class EmbedProcessor {
             content = content.replace(pdfMatch[0], pdfHtml);
         }
 
-        // Handle block reference embeds
-        let blockMatch;
-        while ((blockMatch = blockRefRegex.exec(content)) !== null) {
-            const embed = { notePath: blockMatch[1].trim(), heading: blockMatch[2].trim(), fullMatch: blockMatch[0] };
-            const blockHtml = await this.renderBlockEmbed(embed, embedDepth);
-            content = content.replace(blockMatch[0], blockHtml);
-        }
-
         try {
             // Rust resolves all embeds: finds ![[...]] patterns, reads files,
-            // extracts sections, strips frontmatter, handles recursion.
-            // Returns: { processed: string, embeds: [{ fullMatch, notePath, heading, content, error }] }
+            // extracts heading/block sections, strips frontmatter, handles recursion
+            // and cycles. Nested embeds appear as placeholders inside their parent's content.
+            // Returns: { processed: string, embeds: [{ fullMatch, notePath, heading, kind,
+            //            content, error, placeholder, depth, source: { path, startLine, endLine } }] }
             const result = await invoke('resolve_embeds', {
                 content,
                 currentPath: currentPath || '',
@@ -325,7 +318,9 @@ export class EmbedProcessor {
         // Parse size from notePath (e.g., "image.png|300" or "image.png|300x200")
         const pipeIdx = notePath.indexOf('|');
         const cleanPath = pipeIdx >= 0 ? notePath.substring(0, pipeIdx).trim() : notePath;
-        const sizePart = pipeIdx >= 0 ? notePath.substring(pipeIdx + 1).trim() : '';
+        const sizePart = pipeIdx >= 0
+            ? notePath.substring(pipeIdx + 1).trim()
+            : (embed.width ? (embed.height ? `${embed.width}x${embed.height}` : `${embed.width}`) : '');
 
         if (imageExts.test(cleanPath)) {
             let style = '';
@@ -353,12 +348,16 @@ export class EmbedProcessor {
         }
 
         const depthClass = `embed-depth-${Math.min(embedDepth, 2)}`;
+        // Source mapping: lets the editor jump from a rendered region to its origin
+        const sourceAttrs = embed.source
+            ? ` data-source-path="${this.escapeHtml(embed.source.path)}" data-start-line="${embed.source.startLine}" data-end-line="${embed.source.endLine}"`
+            : '';
         const sourceInfo = embed.heading
             ? `${embed.notePath}#${embed.heading}`
             : embed.notePath;
 
         return `
-<div class="embedded-content ${depthClass}" data-source="${this.escapeHtml(sourceInfo)}">
+<div class="embedded-content ${depthClass}" data-source="${this.escapeHtml(sourceInfo)}"${sourceAttrs}>
     <div class="embed-header">
         <span class="embed-source">${this.escapeHtml(sourceInfo)}</span>
         <button class="embed-open-btn" onclick="window.navigateToNote('${embed.notePath}')" title="Open in new tab">
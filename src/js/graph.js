@@ -1,5 +1,5 @@
 // Oxidian — Graph View (Canvas-based, layout computed in Rust)
-import { invoke } from './tauri-bridge.js';
+import { invoke, listen } from './tauri-bridge.js';
 
 export class GraphView {
     constructor(app, container, options = {}) {
@@ -8,6 +8,8 @@ export class GraphView {
         this.mode = options.mode || 'global'; // 'global' | 'local'
         this.localDepth = options.depth || 1;
         this.centerNote = options.centerNote || null;
+        // Graph query for Rust: folder/tag/search filters, ghosts, tags, attachments, color_by
+        this.query = options.query || null;
 
         // Add toolbar
         this._toolbar = document.createElement('div');
@@ -58,6 +60,19 @@ export class GraphView {
         this.resize();
         this.bindEvents();
         this.load();
+
+        // Reload when notes gain or lose links or tags outside the editor
+        this._unlisten = null;
+        listen('vault-meta-changed', (event) => {
+            const changes = event.payload || [];
+            const relevant = changes.some(c => c.kind !== 'modified' || c.edges_added.length || c.edges_removed.length);
+            if (!relevant && !this.query?.show_tags) return;
+            clearTimeout(this._reloadTimer);
+            this._reloadTimer = setTimeout(() => this.load(), 300);
+        }).then(unlisten => {
+            if (this._destroyed) unlisten();
+            else this._unlisten = unlisten;
+        }).catch(() => {});
     }
 
     resize() {
@@ -87,10 +102,12 @@ export class GraphView {
     }
 
     /**
-     * Load graph data with pre-computed layout from Rust (Fruchterman-Reingold).
-     * Returns { nodes: [{id, label, x, y, radius, color, tags}], edges: [{source, target}] }
+     * Load graph data with pre-computed layout from Rust (Barnes–Hut force-directed).
+     * Returns { nodes: [{id, name, kind, group, x, y}], edges: [{source, target}] }
+     * @param {object} [query] - replaces the current graph query when given
      */
-    async load(filter) {
+    async load(query) {
+        if (query !== undefined) this.query = query;
         try {
             let data;
             if (this.mode === 'local') {
@@ -99,12 +116,17 @@ export class GraphView {
                     console.warn('No current file for local graph');
                     return;
                 }
-                data = await invoke('get_local_graph', { path: centerPath, depth: this.localDepth });
+                data = await invoke('get_local_graph', { path: centerPath, depth: this.localDepth, query: this.query });
                 this._centerNodeId = data.center_node;
+                this.panX = 0;
+                this.panY = 0;
             } else {
                 const vaultPath = this.app.vaultPath || '';
-                data = await invoke('compute_graph', { vaultPath, filter: filter || null });
+                data = await invoke('compute_graph', { vaultPath, query: this.query });
                 this._centerNodeId = null;
+                // Rust layout is centred on the origin
+                this.panX = this.width / 2;
+                this.panY = this.height / 2;
             }
             this.setGraphData(data);
             this._applyForceLayout();
@@ -154,6 +176,24 @@ export class GraphView {
         })).filter(e => e.source && e.target);
     }
 
+    /**
+     * Color for a community/group id from Rust; ungrouped nodes use the accent.
+     */
+    _groupColor(group) {
+        if (group == null) return '#7f6df2';
+        const palette = ['#7f6df2', '#f38ba8', '#a6e3a1', '#fab387', '#89b4fa', '#f9e2af', '#94e2d5', '#cba6f7'];
+        const n = Number(group);
+        const idx = Number.isInteger(n) ? n : [...String(group)].reduce((h, c) => (h * 31 + c.charCodeAt(0)) >>> 0, 0);
+        return palette[idx % palette.length];
+    }
+
+    /**
+     * Fixed colors for non-note nodes (ghosts, tags, attachments).
+     */
+    _kindColor(kind) {
+        return { ghost: '#6c7086', tag: '#a6e3a1', attachment: '#89b4fa' }[kind] || null;
+    }
+
     /**
      * Set pre-positioned graph data from Rust. No JS layout computation.
      */
@@ -163,8 +203,9 @@ export class GraphView {
             label: n.label || n.name,
             x: n.x,
             y: n.y,
-            radius: n.radius || 6,
-            color: n.color || '#7f6df2',
+            kind: n.kind || 'note',
+            radius: n.radius || (n.kind && n.kind !== 'note' ? 4 : 6),
+            color: n.color || this._kindColor(n.kind) || this._groupColor(n.group),
             tags: n.tags || [],
         }));
 
@@ -308,7 +349,7 @@ export class GraphView {
         const mx = e.clientX - rect.left;
         const my = e.clientY - rect.top;
         const node = this.getNodeAt(mx, my);
-        if (node) {
+        if (node && (node.kind === 'note' || node.kind === 'attachment')) {
             this.app.openFile(node.id);
         }
     }
@@ -316,5 +357,8 @@ export class GraphView {
     destroy() {
         if (this.animId) cancelAnimationFrame(this.animId);
         if (this._resizeObserver) this._resizeObserver.disconnect();
+        this._destroyed = true;
+        if (this._unlisten) this._unlisten();
+        clearTimeout(this._reloadTimer);
     }
 }
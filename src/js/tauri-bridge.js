@@ -47,3 +47,12 @@ export function whenReady() {
     if (_ready) return Promise.resolve();
     return new Promise(resolve => _waiters.push(() => resolve()));
 }
+
+/**
+ * Subscribe to a Tauri event once the bridge is ready.
+ * Resolves to the unlisten function.
+ */
+export async function listen(event, handler) {
+    await whenReady();
+    return window.__TAURI__.event.listen(event, handler);
+}